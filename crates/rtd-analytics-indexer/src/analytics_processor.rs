@@ -23,6 +23,7 @@ use rtd_types::messages_checkpoint::CheckpointSequenceNumber;
 
 use crate::analytics_metrics::AnalyticsMetrics;
 use crate::handlers::AnalyticsHandler;
+use crate::table::TableLog;
 use crate::writers::AnalyticsWriter;
 use crate::{
     EPOCH_DIR_PREFIX, FileMetadata, MaxCheckpointReader, ParquetSchema, TaskContext, join_paths,
@@ -31,6 +32,7 @@ use crate::{
 struct State<S: Serialize + ParquetSchema + Send + Sync> {
     current_epoch: u64,
     current_checkpoint_range: Range<u64>,
+    current_partition: Option<String>,
     last_commit_instant: Instant,
     num_checkpoint_iterations: u64,
     writer: Arc<Mutex<Box<dyn AnalyticsWriter<S>>>>,
@@ -67,6 +69,20 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> Worker for AnalyticsP
             self.reset(&mut state)?;
         }
 
+        // Files written to a table must not span partitions.
+        if let Some(table) = &self.task_context.config.table {
+            let partition = table.partition_by.partition(epoch, timestamp);
+            if state
+                .current_partition
+                .as_ref()
+                .is_some_and(|current| *current != partition)
+            {
+                self.cut(&mut state).await?;
+                self.reset(&mut state)?;
+            }
+            state.current_partition = Some(partition);
+        }
+
         assert_eq!(epoch, state.current_epoch);
         assert_eq!(checkpoint_num, state.current_checkpoint_range.end);
 
@@ -128,6 +144,10 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
         };
         let local_object_store = local_store_config.make()?;
         let remote_object_store = task_context.job_config.remote_store_config.make()?;
+        let table = task_context
+            .config
+            .open_table(&task_context.job_config.remote_store_config)
+            .await?;
         let (kill_sender, kill_receiver) = oneshot::channel();
         let (sender, receiver) = mpsc::channel::<FileMetadata>(100);
         let name = handler.name().to_string();
@@ -138,6 +158,7 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
             local_object_store.clone(),
             checkpoint_dir.to_path_buf(),
             task_context.config.remote_store_path_prefix()?,
+            table,
             receiver,
             kill_receiver,
             cloned_metrics,
@@ -157,6 +178,7 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
         let state = State {
             current_epoch: 0,
             current_checkpoint_range: next_checkpoint_seq_num..next_checkpoint_seq_num,
+            current_partition: None,
             last_commit_instant: Instant::now(),
             num_checkpoint_iterations: 0,
             writer: Arc::new(Mutex::new(writer)),
@@ -199,6 +221,7 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
                 self.task_context.config.file_format,
                 state.current_epoch,
                 state.current_checkpoint_range.clone(),
+                state.current_partition.clone(),
             );
             self.emit_file_size_metric(&file_metadata)?;
 
@@ -273,6 +296,7 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
         local_object_store: Arc<DynObjectStore>,
         local_staging_root_dir: PathBuf,
         remote_store_path_prefix: Option<Path>,
+        mut table: Option<TableLog>,
         mut file_recv: mpsc::Receiver<FileMetadata>,
         mut recv: oneshot::Receiver<()>,
         metrics: AnalyticsMetrics,
@@ -285,15 +309,29 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
                     if let Some(file_metadata) = file {
                         info!("Received {name} file with checkpoints: {:?}", &file_metadata.checkpoint_seq_range);
                         let checkpoint_seq_num = file_metadata.checkpoint_seq_range.end;
-                        Self::sync_file_to_remote(
-                                local_staging_root_dir.clone(),
-                                file_metadata.file_path(),
-                                remote_store_path_prefix.clone(),
-                                local_object_store.clone(),
-                                remote_object_store.clone()
-                            )
-                            .await
-                            .expect("Syncing checkpoint should not fail");
+                        if let Some(table) = &mut table {
+                            // The processor fails to send further files once this task stops.
+                            if let Err(err) = Self::commit_file_to_table(
+                                    local_staging_root_dir.clone(),
+                                    &file_metadata,
+                                    table,
+                                )
+                                .await
+                            {
+                                error!("Failed to commit {name} file to table: {err:?}");
+                                return Err(err);
+                            }
+                        } else {
+                            Self::sync_file_to_remote(
+                                    local_staging_root_dir.clone(),
+                                    file_metadata.file_path(),
+                                    remote_store_path_prefix.clone(),
+                                    local_object_store.clone(),
+                                    remote_object_store.clone()
+                                )
+                                .await
+                                .expect("Syncing checkpoint should not fail");
+                        }
                         metrics.last_uploaded_checkpoint.with_label_values(&[&name]).set(checkpoint_seq_num as i64);
                     } else {
                         info!("Terminating upload sync loop");
@@ -345,4 +383,29 @@ impl<S: Serialize + ParquetSchema + Send + Sync + 'static> AnalyticsProcessor<S>
         fs::remove_file(path_to_filesystem(dir, &path)?)?;
         Ok(())
     }
+
+    async fn commit_file_to_table(
+        dir: PathBuf,
+        file_metadata: &FileMetadata,
+        table: &mut TableLog,
+    ) -> Result<()> {
+        let partition = file_metadata
+            .partition
+            .as_ref()
+            .context("File written for a table has no partition")?;
+        let local_path = path_to_filesystem(dir, &file_metadata.file_path())?;
+        let version = table
+            .append(
+                &local_path,
+                partition,
+                file_metadata.checkpoint_seq_range.clone(),
+            )
+            .await?;
+        info!(
+            "Committed checkpoints {:?} to table partition {partition} at version {version}",
+            file_metadata.checkpoint_seq_range
+        );
+        fs::remove_file(local_path)?;
+        Ok(())
+    }
 }
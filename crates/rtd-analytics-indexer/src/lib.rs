@@ -46,6 +46,7 @@ use crate::handlers::package_handler::PackageHandler;
 use crate::handlers::transaction_handler::TransactionHandler;
 use crate::handlers::transaction_objects_handler::TransactionObjectsHandler;
 use crate::handlers::wrapped_object_handler::WrappedObjectHandler;
+use crate::table::{TableConfig, TableLog};
 use crate::tables::{InputObjectKind, ObjectStatus, OwnerType};
 use crate::writers::AnalyticsWriter;
 use crate::writers::csv_writer::CSVWriter;
@@ -57,6 +58,7 @@ pub mod analytics_processor;
 pub mod errors;
 mod handlers;
 pub mod package_store;
+pub mod table;
 pub mod tables;
 mod writers;
 
@@ -285,6 +287,10 @@ pub struct TaskConfig {
     #[serde(default)]
     pub report_sf_max_table_checkpoint: bool,
     pub package_id_filter: Option<String>,
+    /// Write data files as a partitioned table with a commit log, instead of as one directory of
+    /// files per epoch. Requires the parquet file format.
+    #[serde(default)]
+    pub table: Option<TableConfig>,
 }
 
 impl TaskConfig {
@@ -294,6 +300,30 @@ impl TaskConfig {
             .map(|pb| Ok(Path::from(pb.as_str())))
             .transpose()
     }
+
+    /// Open the table this task writes to, if it is configured to write a table.
+    pub async fn open_table(
+        &self,
+        remote_store_config: &ObjectStoreConfig,
+    ) -> Result<Option<TableLog>> {
+        let Some(table) = &self.table else {
+            return Ok(None);
+        };
+
+        if self.file_format != FileFormat::PARQUET {
+            return Err(anyhow!(
+                "Task '{}' writes a table, which requires the parquet file format",
+                self.task_name
+            ));
+        }
+
+        let root = join_paths(
+            self.remote_store_path_prefix()?.as_ref(),
+            &self.file_type.dir_prefix(),
+        );
+        let log = TableLog::open(remote_store_config.make()?, root, table.partition_by).await?;
+        Ok(Some(log))
+    }
 }
 
 pub struct TaskContext {
@@ -412,12 +442,21 @@ impl TaskContext {
     }
 
     async fn get_starting_checkpoint_seq_num(&self) -> Result<u64> {
-        let remote_latest = read_store_for_checkpoint(
-            &self.job_config.remote_store_config,
-            self.config.file_type,
-            self.config.remote_store_path_prefix()?.as_ref(),
-        )
-        .await?;
+        let remote_latest = match self
+            .config
+            .open_table(&self.job_config.remote_store_config)
+            .await?
+        {
+            Some(table) => table.snapshot().next_checkpoint(),
+            None => {
+                read_store_for_checkpoint(
+                    &self.job_config.remote_store_config,
+                    self.config.file_type,
+                    self.config.remote_store_path_prefix()?.as_ref(),
+                )
+                .await?
+            }
+        };
 
         Ok(self
             .config
@@ -823,6 +862,9 @@ pub struct FileMetadata {
     pub file_format: FileFormat,
     pub epoch_num: u64,
     pub checkpoint_seq_range: Range<u64>,
+    /// The table partition the file belongs to, if the task writes a table.
+    #[serde(default)]
+    pub partition: Option<String>,
 }
 
 impl FileMetadata {
@@ -831,12 +873,14 @@ impl FileMetadata {
         file_format: FileFormat,
         epoch_num: u64,
         checkpoint_seq_range: Range<u64>,
+        partition: Option<String>,
     ) -> FileMetadata {
         FileMetadata {
             file_type,
             file_format,
             epoch_num,
             checkpoint_seq_range,
+            partition,
         }
     }
 
@@ -0,0 +1,664 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Table layout for analytics output.
+//!
+//! A table is a directory on the remote store that holds parquet data files, grouped into
+//! hive-style partition directories (`epoch=12/` or `date=2024-05-01/`), alongside a `_log/`
+//! directory that records which data files belong to the table. Every data file that is uploaded
+//! is added to the table by a commit, an immutable JSON file named after its version
+//! (`_log/00000000000000000007.json`). Readers replay the commits in version order, starting
+//! from the latest snapshot (`_log/00000000000000000100.snapshot.json`), to get the set of files
+//! that make up a consistent view of the table, and can ignore any other files in the data
+//! directories, which may belong to an upload that was interrupted before it was committed.
+//!
+//! The log also tracks the table's schema. When a data file has columns that the table does not
+//! have yet (because a column was added to one of the rows in `tables.rs`), its commit evolves
+//! the schema by appending those columns. Columns are matched by name, so they can be added
+//! anywhere in a row. Files written before the change simply lack the new columns, which readers
+//! treat as null. Removing a column or changing its type is rejected.
+
+use std::fs::File;
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use arrow::datatypes::DataType;
+use bytes::Bytes;
+use chrono::DateTime;
+use object_store::path::Path;
+use object_store::{DynObjectStore, PutMode, PutPayload};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use rtd_storage::object_store::util::put;
+use rtd_types::base_types::EpochId;
+
+/// Directory under the table root that contains the commit log.
+pub const LOG_DIR: &str = "_log";
+
+const COMMIT_SUFFIX: &str = ".json";
+const SNAPSHOT_SUFFIX: &str = ".snapshot.json";
+
+/// A snapshot of the whole table is written after this many commits, so that readers don't have
+/// to replay the log from the beginning.
+const SNAPSHOT_INTERVAL: u64 = 100;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionBy {
+    /// One partition per epoch, e.g. `epoch=12`.
+    #[default]
+    Epoch,
+    /// One partition per UTC day of the checkpoint timestamp, e.g. `date=2024-05-01`.
+    Date,
+}
+
+impl PartitionBy {
+    /// The partition that a checkpoint from `epoch`, with timestamp `timestamp_ms`, belongs to.
+    pub fn partition(&self, epoch: EpochId, timestamp_ms: u64) -> String {
+        match self {
+            PartitionBy::Epoch => format!("epoch={epoch}"),
+            PartitionBy::Date => {
+                let date = DateTime::from_timestamp_millis(timestamp_ms as i64)
+                    .unwrap_or_default()
+                    .date_naive();
+                format!("date={}", date.format("%Y-%m-%d"))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TableConfig {
+    /// How to partition the table's data files.
+    #[serde(default)]
+    pub partition_by: PartitionBy,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    U64,
+    I64,
+    Bool,
+    String,
+}
+
+impl TryFrom<&DataType> for ColumnType {
+    type Error = anyhow::Error;
+
+    fn try_from(data_type: &DataType) -> Result<Self> {
+        Ok(match data_type {
+            DataType::UInt64 => ColumnType::U64,
+            DataType::Int64 => ColumnType::I64,
+            DataType::Boolean => ColumnType::Bool,
+            DataType::Utf8 => ColumnType::String,
+            other => bail!("Unsupported column type: {other}"),
+        })
+    }
+}
+
+/// A column of the table. All columns are nullable.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: ColumnType,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TableSchema {
+    /// Incremented every time the schema changes.
+    pub schema_id: u64,
+    pub partition_by: PartitionBy,
+    pub columns: Vec<Column>,
+}
+
+impl TableSchema {
+    /// The schema the table needs to have to include a data file with `columns`, or `None` if
+    /// this schema can already accommodate it. Columns are matched by name, and columns that the
+    /// table does not have yet are appended to the schema, wherever they are in the file: files
+    /// that are missing columns, or that have columns of a different type, are rejected.
+    pub fn evolve(&self, columns: &[Column]) -> Result<Option<TableSchema>> {
+        for column in &self.columns {
+            let Some(other) = columns.iter().find(|other| other.name == column.name) else {
+                bail!("Column '{}' is missing from data file", column.name);
+            };
+
+            ensure!(
+                other.type_ == column.type_,
+                "Column '{}' of data file has type {:?}, expected {:?}",
+                column.name,
+                other.type_,
+                column.type_,
+            );
+        }
+
+        let added = columns
+            .iter()
+            .filter(|column| !self.columns.iter().any(|c| c.name == column.name));
+
+        let mut evolved = self.columns.clone();
+        evolved.extend(added.cloned());
+        if evolved.len() == self.columns.len() {
+            return Ok(None);
+        }
+
+        Ok(Some(TableSchema {
+            schema_id: self.schema_id + 1,
+            partition_by: self.partition_by,
+            columns: evolved,
+        }))
+    }
+}
+
+/// A data file that belongs to the table.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DataFile {
+    /// Path of the file, relative to the table root.
+    pub path: String,
+    pub partition: String,
+    /// Checkpoints whose rows are in the file.
+    pub checkpoint_range: Range<u64>,
+    pub rows: u64,
+    pub size_bytes: u64,
+    /// The schema the file was written with. Columns that were added to the table later are
+    /// missing from it.
+    pub schema_id: u64,
+}
+
+/// An entry in the commit log.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Commit {
+    pub version: u64,
+    pub timestamp_ms: u64,
+    /// The table's new schema, if this commit changes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<TableSchema>,
+    /// Data files added to the table.
+    pub add: Vec<DataFile>,
+}
+
+/// The state of the table after applying a prefix of the commit log.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Version of the next commit, i.e. the number of commits included in this snapshot.
+    pub next_version: u64,
+    pub schema: Option<TableSchema>,
+    pub files: Vec<DataFile>,
+}
+
+impl Snapshot {
+    pub fn apply(&mut self, commit: Commit) -> Result<()> {
+        ensure!(
+            commit.version == self.next_version,
+            "Expected commit {}, got {}",
+            self.next_version,
+            commit.version,
+        );
+
+        if let Some(schema) = commit.schema {
+            self.schema = Some(schema);
+        }
+
+        ensure!(
+            commit.add.is_empty() || self.schema.is_some(),
+            "Commit {} adds files to a table without a schema",
+            commit.version,
+        );
+
+        self.files.extend(commit.add);
+        self.next_version += 1;
+        Ok(())
+    }
+
+    /// The checkpoint after the last one that has been committed to the table.
+    pub fn next_checkpoint(&self) -> u64 {
+        self.files
+            .iter()
+            .map(|f| f.checkpoint_range.end)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Reads and appends to a table's commit log. There must only be one writer per table: commits
+/// are created exclusively, so a second writer fails instead of overwriting the log.
+pub struct TableLog {
+    store: Arc<DynObjectStore>,
+    root: Path,
+    partition_by: PartitionBy,
+    snapshot: Snapshot,
+}
+
+impl TableLog {
+    /// Load the table rooted at `root`, which is empty if it has never been written to.
+    pub async fn open(
+        store: Arc<DynObjectStore>,
+        root: Path,
+        partition_by: PartitionBy,
+    ) -> Result<Self> {
+        let log_dir = root.child(LOG_DIR);
+        let objects = store
+            .list_with_delimiter(Some(&log_dir))
+            .await
+            .with_context(|| format!("Failed to list table log {log_dir}"))?
+            .objects;
+
+        let mut commits = vec![];
+        let mut snapshot_version = None;
+        for object in objects {
+            let Some(name) = object.location.filename() else {
+                continue;
+            };
+
+            if let Some(version) = parse_version(name, SNAPSHOT_SUFFIX) {
+                snapshot_version = snapshot_version.max(Some(version));
+            } else if let Some(version) = parse_version(name, COMMIT_SUFFIX) {
+                commits.push(version);
+            }
+        }
+
+        let mut snapshot = match snapshot_version {
+            Some(version) => {
+                let bytes = get_bytes(&store, &snapshot_path(&root, version)).await?;
+                serde_json::from_slice(&bytes)
+                    .with_context(|| format!("Failed to parse snapshot {version}"))?
+            }
+            None => Snapshot::default(),
+        };
+
+        commits.sort();
+        for version in commits {
+            if version < snapshot.next_version {
+                continue;
+            }
+
+            let bytes = get_bytes(&store, &commit_path(&root, version)).await?;
+            let commit: Commit = serde_json::from_slice(&bytes)
+                .with_context(|| format!("Failed to parse commit {version}"))?;
+            snapshot.apply(commit)?;
+        }
+
+        if let Some(schema) = &snapshot.schema {
+            ensure!(
+                schema.partition_by == partition_by,
+                "Table {root} is partitioned by {:?}, but {partition_by:?} was requested",
+                schema.partition_by,
+            );
+        }
+
+        info!(
+            "Loaded table {root} at version {} with {} files",
+            snapshot.next_version,
+            snapshot.files.len()
+        );
+
+        Ok(Self {
+            store,
+            root,
+            partition_by,
+            snapshot,
+        })
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// Upload the parquet file at `local_path`, which contains the rows for `checkpoint_range`,
+    /// to `partition`, and commit it to the table. Returns the version of the commit.
+    pub async fn append(
+        &mut self,
+        local_path: &std::path::Path,
+        partition: &str,
+        checkpoint_range: Range<u64>,
+    ) -> Result<u64> {
+        let bytes = Bytes::from(std::fs::read(local_path)?);
+        let (rows, columns) = read_parquet_metadata(local_path)?;
+
+        let schema = match &self.snapshot.schema {
+            Some(schema) => schema.evolve(&columns)?,
+            None => Some(TableSchema {
+                schema_id: 0,
+                partition_by: self.partition_by,
+                columns,
+            }),
+        };
+
+        if let (Some(old), Some(new)) = (&self.snapshot.schema, &schema) {
+            let added: Vec<_> = new.columns[old.columns.len()..]
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect();
+            info!(
+                "Evolving schema of table {} to {}, adding columns {added:?}",
+                self.root, new.schema_id
+            );
+        }
+
+        let schema_id = schema
+            .as_ref()
+            .or(self.snapshot.schema.as_ref())
+            .map_or(0, |s| s.schema_id);
+
+        let file_name = format!(
+            "{}_{}.parquet",
+            checkpoint_range.start, checkpoint_range.end
+        );
+        let data_path = self.root.child(partition).child(file_name.as_str());
+
+        let file = DataFile {
+            path: format!("{partition}/{file_name}"),
+            partition: partition.to_string(),
+            checkpoint_range,
+            rows,
+            size_bytes: bytes.len() as u64,
+            schema_id,
+        };
+
+        put(&self.store, &data_path, bytes).await?;
+
+        let version = self.snapshot.next_version;
+        let commit = Commit {
+            version,
+            timestamp_ms: chrono::Utc::now().timestamp_millis() as u64,
+            schema,
+            add: vec![file],
+        };
+
+        self.create(
+            &commit_path(&self.root, version),
+            serde_json::to_vec(&commit)?,
+        )
+        .await?;
+        self.snapshot.apply(commit)?;
+
+        if self.snapshot.next_version % SNAPSHOT_INTERVAL == 0 {
+            self.write_snapshot().await?;
+        }
+
+        Ok(version)
+    }
+
+    /// Write the current state of the table, so that readers can start from it.
+    pub async fn write_snapshot(&self) -> Result<()> {
+        let path = snapshot_path(&self.root, self.snapshot.next_version);
+        let bytes = Bytes::from(serde_json::to_vec(&self.snapshot)?);
+        put(&self.store, &path, bytes).await
+    }
+
+    /// Write `bytes` to `path`, failing if it already exists.
+    async fn create(&self, path: &Path, bytes: Vec<u8>) -> Result<()> {
+        let payload = PutPayload::from(bytes);
+        match self
+            .store
+            .put_opts(path, payload.clone(), PutMode::Create.into())
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(object_store::Error::AlreadyExists { .. }) => Err(anyhow!(
+                "Commit {path} already exists, is another process writing to this table?"
+            )),
+            // Not every store supports conditional writes, so fall back to checking first.
+            Err(object_store::Error::NotImplemented) => {
+                warn!("Object store does not support conditional writes, committing {path} anyway");
+                ensure!(
+                    self.store.head(path).await.is_err(),
+                    "Commit {path} already exists, is another process writing to this table?"
+                );
+                self.store.put(path, payload).await?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Number of rows and columns of the parquet file at `path`.
+pub fn read_parquet_metadata(path: &std::path::Path) -> Result<(u64, Vec<Column>)> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?;
+    let rows = builder.metadata().file_metadata().num_rows() as u64;
+    let columns = builder
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| {
+            Ok(Column {
+                name: field.name().clone(),
+                type_: field
+                    .data_type()
+                    .try_into()
+                    .with_context(|| format!("Column '{}'", field.name()))?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((rows, columns))
+}
+
+fn commit_path(root: &Path, version: u64) -> Path {
+    root.child(LOG_DIR)
+        .child(format!("{version:020}{COMMIT_SUFFIX}"))
+}
+
+fn snapshot_path(root: &Path, version: u64) -> Path {
+    root.child(LOG_DIR)
+        .child(format!("{version:020}{SNAPSHOT_SUFFIX}"))
+}
+
+fn parse_version(name: &str, suffix: &str) -> Option<u64> {
+    name.strip_suffix(suffix)?.parse().ok()
+}
+
+async fn get_bytes(store: &Arc<DynObjectStore>, path: &Path) -> Result<Bytes> {
+    Ok(store
+        .get(path)
+        .await
+        .with_context(|| format!("Failed to read {path}"))?
+        .bytes()
+        .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::{ArrayRef, BooleanArray, RecordBatch, StringArray, UInt64Array};
+    use object_store::local::LocalFileSystem;
+    use parquet::arrow::ArrowWriter;
+
+    fn column(name: &str, type_: ColumnType) -> Column {
+        Column {
+            name: name.to_string(),
+            type_,
+        }
+    }
+
+    fn write_parquet(path: &std::path::Path, columns: Vec<(&str, ArrayRef)>) {
+        let batch = RecordBatch::try_from_iter(columns).unwrap();
+        let mut writer =
+            ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn test_partition() {
+        assert_eq!(PartitionBy::Epoch.partition(12, 0), "epoch=12");
+        assert_eq!(
+            PartitionBy::Date.partition(12, 1_714_608_000_000),
+            "date=2024-05-02"
+        );
+        assert_eq!(
+            PartitionBy::Date.partition(12, 1_714_607_999_999),
+            "date=2024-05-01"
+        );
+    }
+
+    #[test]
+    fn test_schema_evolution() {
+        let schema = TableSchema {
+            schema_id: 3,
+            partition_by: PartitionBy::Epoch,
+            columns: vec![
+                column("checkpoint", ColumnType::U64),
+                column("digest", ColumnType::String),
+            ],
+        };
+
+        assert_eq!(schema.evolve(&schema.columns).unwrap(), None);
+
+        let mut added = schema.columns.clone();
+        added.push(column("is_system", ColumnType::Bool));
+        let evolved = schema.evolve(&added).unwrap().unwrap();
+        assert_eq!(evolved.schema_id, 4);
+        assert_eq!(evolved.columns, added);
+
+        // Columns are matched by name, and new columns are appended wherever they are in the file.
+        assert_eq!(
+            schema
+                .evolve(&[
+                    column("digest", ColumnType::String),
+                    column("checkpoint", ColumnType::U64),
+                ])
+                .unwrap(),
+            None
+        );
+        let evolved = schema
+            .evolve(&[
+                column("checkpoint", ColumnType::U64),
+                column("is_system", ColumnType::Bool),
+                column("digest", ColumnType::String),
+            ])
+            .unwrap()
+            .unwrap();
+        assert_eq!(evolved.columns, added);
+
+        // Removed, renamed, and retyped columns are all rejected.
+        assert!(schema.evolve(&schema.columns[..1]).is_err());
+        assert!(
+            schema
+                .evolve(&[
+                    column("checkpoint", ColumnType::U64),
+                    column("hash", ColumnType::String),
+                ])
+                .is_err()
+        );
+        assert!(
+            schema
+                .evolve(&[
+                    column("checkpoint", ColumnType::I64),
+                    column("digest", ColumnType::String),
+                ])
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_table_log() {
+        let remote = tempfile::tempdir().unwrap();
+        let staging = tempfile::tempdir().unwrap();
+        let store: Arc<DynObjectStore> =
+            Arc::new(LocalFileSystem::new_with_prefix(remote.path()).unwrap());
+        let root = Path::from("checkpoints");
+
+        let mut log = TableLog::open(store.clone(), root.clone(), PartitionBy::Epoch)
+            .await
+            .unwrap();
+        assert_eq!(log.snapshot(), &Snapshot::default());
+
+        let first = staging.path().join("first.parquet");
+        write_parquet(
+            &first,
+            vec![(
+                "checkpoint",
+                Arc::new(UInt64Array::from(vec![0, 1, 2])) as _,
+            )],
+        );
+        assert_eq!(log.append(&first, "epoch=0", 0..3).await.unwrap(), 0);
+
+        // A column is added to the rows written to the table.
+        let second = staging.path().join("second.parquet");
+        write_parquet(
+            &second,
+            vec![
+                ("checkpoint", Arc::new(UInt64Array::from(vec![3, 4])) as _),
+                (
+                    "digest",
+                    Arc::new(StringArray::from(vec![Some("a"), None])) as _,
+                ),
+            ],
+        );
+        assert_eq!(log.append(&second, "epoch=1", 3..5).await.unwrap(), 1);
+
+        // Files with columns of the wrong type are not committed.
+        let bad = staging.path().join("bad.parquet");
+        write_parquet(
+            &bad,
+            vec![("checkpoint", Arc::new(BooleanArray::from(vec![true])) as _)],
+        );
+        assert!(log.append(&bad, "epoch=1", 5..6).await.is_err());
+
+        let snapshot = log.snapshot().clone();
+        assert_eq!(snapshot.next_version, 2);
+        assert_eq!(snapshot.next_checkpoint(), 5);
+        assert_eq!(snapshot.schema.as_ref().unwrap().schema_id, 1);
+        assert_eq!(
+            snapshot.files.iter().map(|f| f.rows).collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+        assert_eq!(snapshot.files[1].path, "epoch=1/3_5.parquet");
+        assert!(
+            remote
+                .path()
+                .join("checkpoints/epoch=1/3_5.parquet")
+                .exists()
+        );
+
+        // The table is reloaded from the log.
+        let reopened = TableLog::open(store.clone(), root.clone(), PartitionBy::Epoch)
+            .await
+            .unwrap();
+        assert_eq!(reopened.snapshot(), &snapshot);
+
+        // ...and from a snapshot, plus the commits after it.
+        log.write_snapshot().await.unwrap();
+        let third = staging.path().join("third.parquet");
+        write_parquet(
+            &third,
+            vec![
+                ("checkpoint", Arc::new(UInt64Array::from(vec![5])) as _),
+                ("digest", Arc::new(StringArray::from(vec!["b"])) as _),
+            ],
+        );
+        log.append(&third, "epoch=1", 5..6).await.unwrap();
+        std::fs::remove_file(
+            remote
+                .path()
+                .join("checkpoints/_log/00000000000000000000.json"),
+        )
+        .unwrap();
+
+        let reopened = TableLog::open(store.clone(), root.clone(), PartitionBy::Epoch)
+            .await
+            .unwrap();
+        assert_eq!(reopened.snapshot(), log.snapshot());
+        assert_eq!(reopened.snapshot().next_checkpoint(), 6);
+
+        // Only one writer can commit a given version.
+        let mut stale = TableLog::open(store.clone(), root.clone(), PartitionBy::Epoch)
+            .await
+            .unwrap();
+        log.append(&third, "epoch=1", 6..7).await.unwrap();
+        assert!(stale.append(&third, "epoch=1", 6..7).await.is_err());
+
+        assert!(
+            TableLog::open(store, root, PartitionBy::Date)
+                .await
+                .is_err()
+        );
+    }
+}
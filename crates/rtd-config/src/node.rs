@@ -139,6 +139,16 @@ pub struct NodeConfig {
     #[serde(default)]
     pub transaction_deny_config: TransactionDenyConfig,
 
+    /// If set, the transaction deny config is loaded from this file instead of
+    /// `transaction-deny-config`, and reloaded whenever the file changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_deny_config_path: Option<PathBuf>,
+
+    /// Number of recently signed transactions to keep, to dry run transaction deny config
+    /// updates against. Disabled by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_deny_history_capacity: Option<usize>,
+
     #[serde(default)]
     pub certificate_deny_config: CertificateDenyConfig,
 
@@ -7,6 +7,7 @@ use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use rtd_types::base_types::{ObjectID, RtdAddress};
 
+use crate::Config;
 use crate::dynamic_transaction_signing_checks::{
     DynamicCheckRunnerContext, DynamicCheckRunnerError,
 };
@@ -90,6 +91,8 @@ pub struct TransactionDenyConfig {
     // TODO: We could also consider disable more types of commands, such as transfer, split and etc.
 }
 
+impl Config for TransactionDenyConfig {}
+
 impl TransactionDenyConfig {
     pub fn get_object_deny_set(&self) -> &HashSet<ObjectID> {
         self.object_deny_set
@@ -23,6 +23,7 @@ use crate::transaction_outputs::TransactionOutputs;
 use crate::verify_indexes::{fix_indexes, verify_indexes};
 use arc_swap::{ArcSwap, Guard};
 use async_trait::async_trait;
+use rtd_transaction_checks::deny::reload::ReloadableTransactionDenyConfig;
 use authority_per_epoch_store::CertLockGuard;
 use fastcrypto::encoding::Base58;
 use fastcrypto::encoding::Encoding;
@@ -954,6 +955,10 @@ pub struct AuthorityState {
 
     pub config: NodeConfig,
 
+    /// The transaction deny config in use, initially `config.transaction_deny_config`.
+    /// It can be replaced at runtime, see `ReloadableTransactionDenyConfig`.
+    pub transaction_deny_config: Arc<ReloadableTransactionDenyConfig>,
+
     /// Current overload status in this authority. Updated periodically.
     pub overload_info: AuthorityOverloadInfo,
 
@@ -1022,12 +1027,12 @@ impl AuthorityState {
         // Note: the deny checks may do redundant package loads but:
         // - they only load packages when there is an active package deny map
         // - the loads are cached anyway
-        rtd_transaction_checks::deny::check_transaction_for_signing(
+        self.transaction_deny_config.record_transaction(transaction);
+        self.transaction_deny_config.check_transaction_for_signing(
             tx_data,
             transaction.tx_signatures(),
             &input_object_kinds,
             &receiving_objects_refs,
-            &self.config.transaction_deny_config,
             self.get_backing_package_store().as_ref(),
         )?;
 
@@ -2359,12 +2364,11 @@ impl AuthorityState {
         let input_object_kinds = transaction.input_objects()?;
         let receiving_object_refs = transaction.receiving_objects();
 
-        rtd_transaction_checks::deny::check_transaction_for_signing(
+        self.transaction_deny_config.check_transaction_for_signing(
             &transaction,
             &[],
             &input_object_kinds,
             &receiving_object_refs,
-            &self.config.transaction_deny_config,
             self.get_backing_package_store().as_ref(),
         )?;
 
@@ -2567,12 +2571,11 @@ impl AuthorityState {
         let input_object_kinds = transaction.input_objects()?;
         let receiving_object_refs = transaction.receiving_objects();
 
-        rtd_transaction_checks::deny::check_transaction_for_signing(
+        self.transaction_deny_config.check_transaction_for_signing(
             &transaction,
             &[],
             &input_object_kinds,
             &receiving_object_refs,
-            &self.config.transaction_deny_config,
             self.get_backing_package_store().as_ref(),
         )?;
 
@@ -2791,12 +2794,11 @@ impl AuthorityState {
         let input_object_kinds = transaction.input_objects()?;
         let receiving_object_refs = transaction.receiving_objects();
 
-        rtd_transaction_checks::deny::check_transaction_for_signing(
+        self.transaction_deny_config.check_transaction_for_signing(
             &transaction,
             &[],
             &input_object_kinds,
             &receiving_object_refs,
-            &self.config.transaction_deny_config,
             self.get_backing_package_store().as_ref(),
         )?;
 
@@ -3700,6 +3702,17 @@ impl AuthorityState {
             _pruner,
             _authority_per_epoch_pruner,
             db_checkpoint_config: db_checkpoint_config.clone(),
+            transaction_deny_config: Arc::new(match &config.transaction_deny_config_path {
+                Some(path) => ReloadableTransactionDenyConfig::from_file(
+                    path,
+                    config.transaction_deny_history_capacity.unwrap_or(0),
+                )
+                .expect("failed to load transaction deny config"),
+                None => ReloadableTransactionDenyConfig::new(
+                    config.transaction_deny_config.clone(),
+                    config.transaction_deny_history_capacity.unwrap_or(0),
+                ),
+            }),
             config,
             overload_info: AuthorityOverloadInfo::default(),
             validator_tx_finalizer,
@@ -54,6 +54,7 @@ pub struct TestAuthorityBuilder<'a> {
     store_base_path: Option<PathBuf>,
     store: Option<Arc<AuthorityStore>>,
     transaction_deny_config: Option<TransactionDenyConfig>,
+    transaction_deny_history_capacity: Option<usize>,
     certificate_deny_config: Option<CertificateDenyConfig>,
     protocol_config: Option<ProtocolConfig>,
     reference_gas_price: Option<u64>,
@@ -95,6 +96,15 @@ impl<'a> TestAuthorityBuilder<'a> {
         self
     }
 
+    pub fn with_transaction_deny_history_capacity(mut self, capacity: usize) -> Self {
+        assert!(
+            self.transaction_deny_history_capacity
+                .replace(capacity)
+                .is_none()
+        );
+        self
+    }
+
     pub fn with_certificate_deny_config(mut self, config: CertificateDenyConfig) -> Self {
         assert!(self.certificate_deny_config.replace(config).is_none());
         self
@@ -353,6 +363,7 @@ impl<'a> TestAuthorityBuilder<'a> {
         }
 
         config.transaction_deny_config = transaction_deny_config;
+        config.transaction_deny_history_capacity = self.transaction_deny_history_capacity;
         config.certificate_deny_config = certificate_deny_config;
         config.authority_overload_config = authority_overload_config;
         config.authority_store_pruning_config = pruning_config;
@@ -2195,6 +2195,7 @@ impl ValidatorService {
     fn handle_traffic_resp<T>(
         &self,
         client: Option<IpAddr>,
+        method: &str,
         wrapped_response: WrappedServiceResponse<T>,
     ) -> Result<tonic::Response<T>, tonic::Status> {
         let (error, spam_weight, unwrapped_response) = match wrapped_response {
@@ -2216,6 +2217,7 @@ impl ValidatorService {
                     (error_weight, error_type)
                 }),
                 spam_weight,
+                method: Some(method.to_string()),
                 timestamp: SystemTime::now(),
             })
         }
@@ -2266,9 +2268,13 @@ macro_rules! handle_with_decoration {
         // check if either IP is blocked, in which case return early
         $self.handle_traffic_req(client.clone()).await?;
 
-        // handle traffic tallying
+        // handle traffic tallying, naming the request kind after the handler
         let wrapped_response = $self.$func_name($request).await;
-        $self.handle_traffic_resp(client, wrapped_response)
+        $self.handle_traffic_resp(
+            client,
+            stringify!($func_name).trim_end_matches("_impl"),
+            wrapped_response,
+        )
     }};
 }
 
@@ -37,9 +37,16 @@ pub struct MockCheckpointBuilder {
 
 impl MockCheckpointBuilder {
     pub fn new(previous_checkpoint: VerifiedCheckpoint) -> Self {
-        let epoch_rolling_gas_cost_summary =
-            previous_checkpoint.epoch_rolling_gas_cost_summary.clone();
-        let epoch = previous_checkpoint.epoch;
+        // The checkpoint after the last checkpoint of an epoch is the first of the next epoch.
+        let (epoch, epoch_rolling_gas_cost_summary) =
+            if previous_checkpoint.end_of_epoch_data.is_some() {
+                (previous_checkpoint.epoch + 1, GasCostSummary::default())
+            } else {
+                (
+                    previous_checkpoint.epoch,
+                    previous_checkpoint.epoch_rolling_gas_cost_summary.clone(),
+                )
+            };
 
         Self {
             previous_checkpoint: Some(previous_checkpoint),
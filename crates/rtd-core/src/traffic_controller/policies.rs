@@ -2,9 +2,14 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashMap, net::IpAddr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::Arc,
+};
 
 use count_min_sketch::CountMinSketch32;
+use futures::future::BoxFuture;
 use linku_metrics::spawn_monitored_task;
 use parking_lot::RwLock;
 use std::cmp::Reverse;
@@ -13,13 +18,22 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::time::Duration;
 use std::time::{Instant, SystemTime};
-use rtd_types::traffic_control::{FreqThresholdConfig, PolicyConfig, PolicyType, Weight};
+use rtd_types::traffic_control::{
+    CompositeConfig, FreqThresholdConfig, PolicyConfig, PolicyType, SlidingWindowConfig,
+    TokenBucketConfig, Weight,
+};
 use tracing::{info, trace};
 
+use super::parse_ip;
+use linku_common::fatal;
+
 const HIGHEST_RATES_CAPACITY: usize = 20;
+/// How often per-client state of idle clients is dropped by the token bucket
+/// and sliding window policies.
+const CLIENT_STATE_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
 
 /// The type of request client.
-#[derive(Hash, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
 enum ClientType {
     Direct,
     ThroughFullnode,
@@ -234,6 +248,8 @@ pub struct TrafficTally {
     pub through_fullnode: Option<IpAddr>,
     pub error_info: Option<(Weight, String)>,
     pub spam_weight: Weight,
+    /// The RPC method or request kind that was served, if known.
+    pub method: Option<String>,
     pub timestamp: SystemTime,
 }
 
@@ -249,9 +265,15 @@ impl TrafficTally {
             through_fullnode,
             error_info,
             spam_weight,
+            method: None,
             timestamp: SystemTime::now(),
         }
     }
+
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = Some(method.into());
+        self
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -271,6 +293,9 @@ pub trait Policy {
 // not object safe, so we can't use a trait object instead
 pub enum TrafficControlPolicy {
     FreqThreshold(FreqThresholdPolicy),
+    TokenBucket(TokenBucketPolicy),
+    SlidingWindow(SlidingWindowPolicy),
+    Composite(CompositePolicy),
     NoOp(NoOpPolicy),
     // Test policies below this point
     TestNConnIP(TestNConnIPPolicy),
@@ -282,6 +307,9 @@ impl Policy for TrafficControlPolicy {
         match self {
             TrafficControlPolicy::NoOp(policy) => policy.handle_tally(tally),
             TrafficControlPolicy::FreqThreshold(policy) => policy.handle_tally(tally),
+            TrafficControlPolicy::TokenBucket(policy) => policy.handle_tally(tally),
+            TrafficControlPolicy::SlidingWindow(policy) => policy.handle_tally(tally),
+            TrafficControlPolicy::Composite(policy) => policy.handle_tally(tally),
             TrafficControlPolicy::TestNConnIP(policy) => policy.handle_tally(tally),
             TrafficControlPolicy::TestPanicOnInvocation(policy) => policy.handle_tally(tally),
         }
@@ -291,6 +319,9 @@ impl Policy for TrafficControlPolicy {
         match self {
             TrafficControlPolicy::NoOp(policy) => policy.policy_config(),
             TrafficControlPolicy::FreqThreshold(policy) => policy.policy_config(),
+            TrafficControlPolicy::TokenBucket(policy) => policy.policy_config(),
+            TrafficControlPolicy::SlidingWindow(policy) => policy.policy_config(),
+            TrafficControlPolicy::Composite(policy) => policy.policy_config(),
             TrafficControlPolicy::TestNConnIP(policy) => policy.policy_config(),
             TrafficControlPolicy::TestPanicOnInvocation(policy) => policy.policy_config(),
         }
@@ -310,6 +341,15 @@ impl TrafficControlPolicy {
             PolicyType::FreqThreshold(freq_threshold_config) => Self::FreqThreshold(
                 FreqThresholdPolicy::new(policy_config, freq_threshold_config),
             ),
+            PolicyType::TokenBucket(token_bucket_config) => {
+                Self::TokenBucket(TokenBucketPolicy::new(policy_config, token_bucket_config))
+            }
+            PolicyType::SlidingWindow(sliding_window_config) => Self::SlidingWindow(
+                SlidingWindowPolicy::new(policy_config, sliding_window_config),
+            ),
+            PolicyType::Composite(composite_config) => {
+                Self::Composite(CompositePolicy::new(policy_config, composite_config).await)
+            }
             PolicyType::TestNConnIP(n) => {
                 Self::TestNConnIP(TestNConnIPPolicy::new(policy_config, n).await)
             }
@@ -420,6 +460,325 @@ impl FreqThresholdPolicy {
     }
 }
 
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(burst: u64, now: Instant) -> Self {
+        Self {
+            tokens: burst as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, rate: f64, burst: u64, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Takes a token if one is available, returning whether it was.
+    fn try_take(&mut self, rate: f64, burst: u64, now: Instant) -> bool {
+        self.refill(rate, burst, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub struct TokenBucketPolicy {
+    pub config: PolicyConfig,
+    pub bucket_config: TokenBucketConfig,
+    buckets: HashMap<(ClientType, IpAddr), TokenBucket>,
+    last_cleanup: Instant,
+}
+
+impl TokenBucketPolicy {
+    pub fn new(config: PolicyConfig, bucket_config: TokenBucketConfig) -> Self {
+        Self {
+            config,
+            bucket_config,
+            buckets: HashMap::new(),
+            last_cleanup: Instant::now(),
+        }
+    }
+
+    fn limits(bucket_config: &TokenBucketConfig, client_type: ClientType) -> (f64, u64) {
+        match client_type {
+            ClientType::Direct => (bucket_config.client_rate, bucket_config.client_burst),
+            ClientType::ThroughFullnode => (
+                bucket_config.proxied_client_rate,
+                bucket_config.proxied_client_burst,
+            ),
+        }
+    }
+
+    fn take(
+        &mut self,
+        client: Option<IpAddr>,
+        client_type: ClientType,
+        now: Instant,
+    ) -> Option<IpAddr> {
+        let client = client?;
+        let (rate, burst) = Self::limits(&self.bucket_config, client_type);
+        let allowed = self
+            .buckets
+            .entry((client_type, client))
+            .or_insert_with(|| TokenBucket::full(burst, now))
+            .try_take(rate, burst, now);
+        trace!(
+            "TokenBucketPolicy handling tally -- client: {:?}, client_type: {:?}, allowed: {:?}",
+            client, client_type, allowed,
+        );
+        (!allowed).then_some(client)
+    }
+
+    /// Drops the buckets of clients that have been idle long enough for their bucket to
+    /// refill, as they are equivalent to new buckets.
+    fn cleanup(&mut self, now: Instant) {
+        if now.duration_since(self.last_cleanup) < CLIENT_STATE_CLEANUP_INTERVAL {
+            return;
+        }
+        let bucket_config = &self.bucket_config;
+        self.buckets.retain(|(client_type, _), bucket| {
+            let (rate, burst) = Self::limits(bucket_config, *client_type);
+            bucket.refill(rate, burst, now);
+            bucket.tokens < burst as f64
+        });
+        self.last_cleanup = now;
+    }
+
+    pub fn handle_tally(&mut self, tally: TrafficTally) -> PolicyResponse {
+        let now = Instant::now();
+        self.cleanup(now);
+        PolicyResponse {
+            block_client: self.take(tally.direct, ClientType::Direct, now),
+            block_proxied_client: self.take(
+                tally.through_fullnode,
+                ClientType::ThroughFullnode,
+                now,
+            ),
+        }
+    }
+
+    fn policy_config(&self) -> &PolicyConfig {
+        &self.config
+    }
+}
+
+/// Approximates the number of requests in a sliding window from the counts of the
+/// current and previous fixed windows, weighting the previous count by how much of
+/// the previous window the sliding window still covers.
+struct WindowCounter {
+    window_start: Instant,
+    previous: u64,
+    current: u64,
+}
+
+impl WindowCounter {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            previous: 0,
+            current: 0,
+        }
+    }
+
+    fn rotate(&mut self, window: Duration, now: Instant) {
+        let elapsed = now.duration_since(self.window_start);
+        if elapsed >= window * 2 {
+            self.previous = 0;
+            self.current = 0;
+            self.window_start = now;
+        } else if elapsed >= window {
+            self.previous = self.current;
+            self.current = 0;
+            self.window_start += window;
+        }
+    }
+
+    /// Counts a request, returning the estimated number of requests in the window
+    /// ending now.
+    fn increment(&mut self, window: Duration, now: Instant) -> f64 {
+        self.rotate(window, now);
+        self.current += 1;
+        let elapsed = now.duration_since(self.window_start).as_secs_f64();
+        let previous_weight = 1.0 - elapsed / window.as_secs_f64();
+        self.previous as f64 * previous_weight + self.current as f64
+    }
+}
+
+pub struct SlidingWindowPolicy {
+    pub config: PolicyConfig,
+    pub window_config: SlidingWindowConfig,
+    window: Duration,
+    /// Counters keyed by client and method. Methods without a limit of their own
+    /// share the `None` counter.
+    counters: HashMap<(ClientType, IpAddr, Option<String>), WindowCounter>,
+    last_cleanup: Instant,
+}
+
+impl SlidingWindowPolicy {
+    pub fn new(config: PolicyConfig, window_config: SlidingWindowConfig) -> Self {
+        assert!(
+            window_config.window_size_secs > 0,
+            "Sliding window size must be positive"
+        );
+        Self {
+            config,
+            window: Duration::from_secs(window_config.window_size_secs),
+            window_config,
+            counters: HashMap::new(),
+            last_cleanup: Instant::now(),
+        }
+    }
+
+    fn count(
+        &mut self,
+        client: Option<IpAddr>,
+        client_type: ClientType,
+        method: &Option<String>,
+        now: Instant,
+    ) -> Option<IpAddr> {
+        let client = client?;
+        let (method, limit) = match method
+            .as_ref()
+            .and_then(|m| Some((m, *self.window_config.method_limits.get(m)?)))
+        {
+            Some((method, limit)) => (Some(method.clone()), limit),
+            None => (None, self.window_config.default_limit?),
+        };
+        let count = self
+            .counters
+            .entry((client_type, client, method))
+            .or_insert_with(|| WindowCounter::new(now))
+            .increment(self.window, now);
+        trace!(
+            "SlidingWindowPolicy handling tally -- count: {:?}, limit: {:?}, client: {:?}",
+            count, limit, client,
+        );
+        (count > limit as f64).then_some(client)
+    }
+
+    /// Drops the counters of clients that made no requests in the last two windows.
+    fn cleanup(&mut self, now: Instant) {
+        if now.duration_since(self.last_cleanup) < CLIENT_STATE_CLEANUP_INTERVAL {
+            return;
+        }
+        let window = self.window;
+        self.counters
+            .retain(|_, counter| now.duration_since(counter.window_start) < window * 2);
+        self.last_cleanup = now;
+    }
+
+    pub fn handle_tally(&mut self, tally: TrafficTally) -> PolicyResponse {
+        let now = Instant::now();
+        self.cleanup(now);
+        PolicyResponse {
+            block_client: self.count(tally.direct, ClientType::Direct, &tally.method, now),
+            block_proxied_client: self.count(
+                tally.through_fullnode,
+                ClientType::ThroughFullnode,
+                &tally.method,
+                now,
+            ),
+        }
+    }
+
+    fn policy_config(&self) -> &PolicyConfig {
+        &self.config
+    }
+}
+
+pub struct CompositePolicy {
+    pub config: PolicyConfig,
+    policies: Vec<TrafficControlPolicy>,
+    allow_list: HashSet<IpAddr>,
+    allowed_methods: HashSet<String>,
+}
+
+impl CompositePolicy {
+    pub async fn new(
+        config: PolicyConfig,
+        CompositeConfig {
+            policies,
+            allow_list,
+            allowed_methods,
+        }: CompositeConfig,
+    ) -> Self {
+        let allow_list = allow_list
+            .into_iter()
+            .map(|ip_str| {
+                parse_ip(&ip_str).unwrap_or_else(|| {
+                    fatal!(
+                        "Failed to parse composite policy allowlist IP address: {:?}",
+                        ip_str
+                    )
+                })
+            })
+            .collect();
+        let mut inner = Vec::with_capacity(policies.len());
+        for policy_type in policies {
+            inner.push(boxed_policy_from_config(policy_type, config.clone()).await);
+        }
+        Self {
+            config,
+            policies: inner,
+            allow_list,
+            allowed_methods: allowed_methods.into_iter().collect(),
+        }
+    }
+
+    pub fn handle_tally(&mut self, tally: TrafficTally) -> PolicyResponse {
+        if tally
+            .method
+            .as_ref()
+            .is_some_and(|method| self.allowed_methods.contains(method))
+        {
+            return PolicyResponse::default();
+        }
+        let mut response = PolicyResponse::default();
+        // Every policy sees every tally, so that their state stays accurate even once
+        // one of them blocks the client.
+        for policy in &mut self.policies {
+            let PolicyResponse {
+                block_client,
+                block_proxied_client,
+            } = policy.handle_tally(tally.clone());
+            response.block_client = response.block_client.or(block_client);
+            response.block_proxied_client = response.block_proxied_client.or(block_proxied_client);
+        }
+        response.block_client = response
+            .block_client
+            .filter(|client| !self.allow_list.contains(client));
+        response.block_proxied_client = response
+            .block_proxied_client
+            .filter(|client| !self.allow_list.contains(client));
+        response
+    }
+
+    fn policy_config(&self) -> &PolicyConfig {
+        &self.config
+    }
+}
+
+// Composite policies contain policies built by `TrafficControlPolicy::from_config`, so the
+// recursion has to go through a boxed future.
+fn boxed_policy_from_config(
+    policy_type: PolicyType,
+    policy_config: PolicyConfig,
+) -> BoxFuture<'static, TrafficControlPolicy> {
+    Box::pin(TrafficControlPolicy::from_config(
+        policy_type,
+        policy_config,
+    ))
+}
+
 ////////////// *** Test policies below this point *** //////////////
 
 #[derive(Clone)]
@@ -547,6 +906,7 @@ mod tests {
             through_fullnode: Some(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))),
             error_info: None,
             spam_weight: Weight::one(),
+            method: None,
             timestamp: SystemTime::now(),
         };
         let bob = TrafficTally {
@@ -554,6 +914,7 @@ mod tests {
             through_fullnode: Some(IpAddr::V4(Ipv4Addr::new(4, 3, 2, 1))),
             error_info: None,
             spam_weight: Weight::one(),
+            method: None,
             timestamp: SystemTime::now(),
         };
         let charlie = TrafficTally {
@@ -561,6 +922,7 @@ mod tests {
             through_fullnode: Some(IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8))),
             error_info: None,
             spam_weight: Weight::one(),
+            method: None,
             timestamp: SystemTime::now(),
         };
 
@@ -678,6 +1040,165 @@ mod tests {
         assert_eq!(proxied_rate, 1);
     }
 
+    #[sim_test]
+    async fn test_token_bucket_policy() {
+        let mut policy = TokenBucketPolicy::new(
+            PolicyConfig::default(),
+            TokenBucketConfig {
+                client_rate: 100.0,
+                client_burst: 100,
+                proxied_client_rate: 1.0,
+                proxied_client_burst: 3,
+            },
+        );
+        let alice = TrafficTally::new(
+            Some(IpAddr::V4(Ipv4Addr::new(8, 7, 6, 5))),
+            Some(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))),
+            None,
+            Weight::one(),
+        );
+        let bob = TrafficTally::new(
+            Some(IpAddr::V4(Ipv4Addr::new(8, 7, 6, 5))),
+            Some(IpAddr::V4(Ipv4Addr::new(4, 3, 2, 1))),
+            None,
+            Weight::one(),
+        );
+
+        // alice may burst 3 requests, then is blocked
+        for _ in 0..3 {
+            let response = policy.handle_tally(alice.clone());
+            assert_eq!(response.block_client, None);
+            assert_eq!(response.block_proxied_client, None);
+        }
+        let response = policy.handle_tally(alice.clone());
+        assert_eq!(response.block_client, None);
+        assert_eq!(response.block_proxied_client, alice.through_fullnode);
+
+        // bob has a bucket of his own
+        let response = policy.handle_tally(bob.clone());
+        assert_eq!(response.block_proxied_client, None);
+
+        // after a second, alice has been refilled one token
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let response = policy.handle_tally(alice.clone());
+        assert_eq!(response.block_proxied_client, None);
+        let response = policy.handle_tally(alice.clone());
+        assert_eq!(response.block_proxied_client, alice.through_fullnode);
+    }
+
+    #[sim_test]
+    async fn test_sliding_window_policy() {
+        let mut policy = SlidingWindowPolicy::new(
+            PolicyConfig::default(),
+            SlidingWindowConfig {
+                window_size_secs: 10,
+                default_limit: Some(10),
+                method_limits: [("rtd_dryRunTransactionBlock".to_string(), 2)]
+                    .into_iter()
+                    .collect(),
+            },
+        );
+        let client = Some(IpAddr::V4(Ipv4Addr::new(8, 7, 6, 5)));
+        let dry_run = TrafficTally::new(client, None, None, Weight::one())
+            .with_method("rtd_dryRunTransactionBlock");
+        let read =
+            TrafficTally::new(client, None, None, Weight::one()).with_method("rtd_getObject");
+
+        for _ in 0..2 {
+            assert_eq!(policy.handle_tally(dry_run.clone()).block_client, None);
+        }
+        assert_eq!(policy.handle_tally(dry_run.clone()).block_client, client);
+
+        // reads are counted separately, against the default limit
+        for _ in 0..10 {
+            assert_eq!(policy.handle_tally(read.clone()).block_client, None);
+        }
+        assert_eq!(policy.handle_tally(read.clone()).block_client, client);
+
+        // once the requests have slid out of the window, the client is allowed again
+        tokio::time::sleep(tokio::time::Duration::from_secs(20)).await;
+        assert_eq!(policy.handle_tally(dry_run.clone()).block_client, None);
+    }
+
+    #[sim_test]
+    async fn test_composite_policy() {
+        let mut policy = CompositePolicy::new(
+            PolicyConfig::default(),
+            CompositeConfig {
+                policies: vec![
+                    PolicyType::TokenBucket(TokenBucketConfig {
+                        client_rate: 1.0,
+                        client_burst: 5,
+                        ..Default::default()
+                    }),
+                    PolicyType::SlidingWindow(SlidingWindowConfig {
+                        method_limits: [("rtd_dryRunTransactionBlock".to_string(), 1)]
+                            .into_iter()
+                            .collect(),
+                        ..Default::default()
+                    }),
+                ],
+                allow_list: vec!["1.2.3.4".to_string()],
+                allowed_methods: vec!["rtd_getObject".to_string()],
+            },
+        )
+        .await;
+        let client = Some(IpAddr::V4(Ipv4Addr::new(8, 7, 6, 5)));
+        let trusted = Some(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)));
+        let tally = |client: Option<IpAddr>, method: &str| {
+            TrafficTally::new(client, None, None, Weight::one()).with_method(method)
+        };
+
+        // the sliding window blocks the second dry run
+        assert_eq!(
+            policy
+                .handle_tally(tally(client, "rtd_dryRunTransactionBlock"))
+                .block_client,
+            None
+        );
+        assert_eq!(
+            policy
+                .handle_tally(tally(client, "rtd_dryRunTransactionBlock"))
+                .block_client,
+            client
+        );
+
+        // allowed methods are not counted, so they never exhaust the token bucket
+        for _ in 0..10 {
+            assert_eq!(
+                policy
+                    .handle_tally(tally(client, "rtd_getObject"))
+                    .block_client,
+                None
+            );
+        }
+        // the token bucket has 3 tokens left for other methods
+        for _ in 0..3 {
+            assert_eq!(
+                policy
+                    .handle_tally(tally(client, "rtd_getCoins"))
+                    .block_client,
+                None
+            );
+        }
+        assert_eq!(
+            policy
+                .handle_tally(tally(client, "rtd_getCoins"))
+                .block_client,
+            client
+        );
+
+        // allowlisted clients are never blocked
+        for _ in 0..10 {
+            assert_eq!(
+                policy
+                    .handle_tally(tally(trusted, "rtd_dryRunTransactionBlock"))
+                    .block_client,
+                None
+            );
+        }
+    }
+
     #[sim_test]
     async fn test_traffic_sketch_mem_estimate() {
         // Test for getting a rough estimate of memory usage for the traffic sketch
@@ -17,6 +17,7 @@ use rtd_config::transaction_deny_config::{TransactionDenyConfig, TransactionDeny
 use rtd_swarm_config::genesis_config::{AccountConfig, DEFAULT_GAS_AMOUNT};
 use rtd_swarm_config::network_config::NetworkConfig;
 use rtd_test_transaction_builder::TestTransactionBuilder;
+use rtd_transaction_checks::deny::DenyRule;
 use rtd_types::base_types::{ObjectID, ObjectRef, RtdAddress};
 use rtd_types::effects::TransactionEffectsAPI;
 use rtd_types::error::{RtdErrorKind, RtdResult, UserInputError};
@@ -35,6 +36,15 @@ const ACCOUNT_NUM: usize = 5;
 const GAS_OBJECT_COUNT: usize = 15;
 
 async fn setup_test(deny_config: TransactionDenyConfig) -> (NetworkConfig, Arc<AuthorityState>) {
+    setup_test_with_history(deny_config, 0).await
+}
+
+/// Like `setup_test`, keeping the last `history_capacity` signed transactions to dry run deny
+/// configs against.
+async fn setup_test_with_history(
+    deny_config: TransactionDenyConfig,
+    history_capacity: usize,
+) -> (NetworkConfig, Arc<AuthorityState>) {
     let network_config =
         rtd_swarm_config::network_config_builder::ConfigBuilder::new_with_temp_dir()
             .with_accounts(vec![
@@ -47,6 +57,7 @@ async fn setup_test(deny_config: TransactionDenyConfig) -> (NetworkConfig, Arc<A
             .build();
     let state = TestAuthorityBuilder::new()
         .with_transaction_deny_config(deny_config)
+        .with_transaction_deny_history_capacity(history_capacity)
         .with_network_config(&network_config, 0)
         .build()
         .await;
@@ -246,6 +257,65 @@ async fn test_signer_denied() {
     assert_denied(&transfer_with_account(&accounts[2], &accounts[1], &state).await);
 }
 
+#[tokio::test]
+async fn test_reload_deny_config() {
+    let (network_config, state) =
+        setup_test_with_history(TransactionDenyConfigBuilder::new().build(), 10).await;
+    let accounts = get_accounts_and_coins(&network_config, &state);
+    let deny_config = &state.transaction_deny_config;
+    assert_eq!(deny_config.load().version, 1);
+    assert!(
+        transfer_with_account(&accounts[0], &accounts[0], &state)
+            .await
+            .is_ok()
+    );
+
+    // A dry run reports what the new config would deny, without installing it.
+    let new_config = TransactionDenyConfigBuilder::new()
+        .add_denied_address(accounts[0].0)
+        .build();
+    let report = deny_config.dry_run(&new_config, state.get_backing_package_store().as_ref());
+    assert_eq!(report.current_version, 1);
+    assert_eq!(report.transactions_checked, 1);
+    assert_eq!(report.errors, 0);
+    assert_eq!(report.denied.len(), 1);
+    assert_eq!(report.denied[0].1, DenyRule::DeniedAddress(accounts[0].0));
+    assert_eq!(deny_config.load().version, 1);
+
+    assert_eq!(deny_config.update(new_config), 2);
+    assert_denied(&transfer_with_account(&accounts[0], &accounts[0], &state).await);
+    assert!(
+        transfer_with_account(&accounts[1], &accounts[1], &state)
+            .await
+            .is_ok()
+    );
+
+    // Dry runs are checked against the config in use, and their denials are recorded too.
+    let rgp = state.reference_gas_price_for_testing().unwrap();
+    let data = TransactionData::new_transfer_rtd(
+        accounts[0].0,
+        accounts[0].0,
+        None,
+        accounts[0].2[1],
+        TEST_ONLY_GAS_UNIT_FOR_TRANSFER * rgp,
+        rgp,
+    );
+    let digest = data.digest();
+    assert!(
+        state
+            .dry_exec_transaction_for_benchmark(data, digest)
+            .is_err()
+    );
+
+    let denials = deny_config.recent_denials();
+    assert_eq!(denials.len(), 2);
+    for denial in &denials {
+        assert_eq!(denial.rule, DenyRule::DeniedAddress(accounts[0].0));
+        assert_eq!(denial.version, 2);
+    }
+    assert_eq!(denials[1].digest, digest);
+}
+
 #[tokio::test]
 async fn test_shared_object_transaction_disabled() {
     let (network_config, state) = setup_test(
@@ -23,6 +23,13 @@ pub fn verify_indexes(store: &dyn GlobalStateHashStore, indexes: Arc<IndexStore>
     let mut owner_index = BTreeMap::new();
     let mut coin_index = BTreeMap::new();
 
+    // Read both indexes from one snapshot, so that they are checked against a coherent view even
+    // if the indexes are being written to concurrently.
+    let tables = indexes.tables();
+    let snapshot = tables.owner_index().snapshot()?;
+    let owner_index_snapshot = tables.owner_index().at_snapshot(&snapshot)?;
+    let coin_index_snapshot = tables.coin_index().at_snapshot(&snapshot)?;
+
     tracing::info!("Reading live objects set");
     for object in store.iter_live_object_set(false) {
         let LiveObject::Normal(object) = object else {
@@ -51,7 +58,7 @@ pub fn verify_indexes(store: &dyn GlobalStateHashStore, indexes: Arc<IndexStore>
     tracing::info!("Live objects set is prepared, about to verify indexes");
 
     // Verify Owner Index
-    for item in indexes.tables().owner_index().safe_iter() {
+    for item in owner_index_snapshot.safe_iter() {
         let (key, info) = item?;
         let calculated_info = owner_index.remove(&key).ok_or_else(|| {
             anyhow!(
@@ -73,7 +80,7 @@ pub fn verify_indexes(store: &dyn GlobalStateHashStore, indexes: Arc<IndexStore>
     tracing::info!("Owner index is good");
 
     // Verify Coin Index
-    for item in indexes.tables().coin_index().safe_iter() {
+    for item in coin_index_snapshot.safe_iter() {
         let (key, info) = item?;
         let calculated_info = coin_index.remove(&key).ok_or_else(|| {
             anyhow!(
@@ -5,9 +5,9 @@ use crate::Worker;
 use crate::progress_store::{
     ExecutorProgress, ProgressStore, ProgressStoreWrapper, ShimProgressStore,
 };
-use crate::reader::CheckpointReader;
+use crate::reader::{CheckpointReader, Delivery};
 use crate::worker_pool::WorkerPool;
-use crate::{DataIngestionMetrics, ReaderOptions};
+use crate::{CheckpointFilter, DataIngestionMetrics, ReaderOptions};
 use anyhow::Result;
 use futures::Future;
 use linku_metrics::spawn_monitored_task;
@@ -15,8 +15,6 @@ use once_cell::sync::Lazy;
 use prometheus::Registry;
 use std::path::PathBuf;
 use std::pin::Pin;
-use std::sync::Arc;
-use rtd_types::full_checkpoint_content::CheckpointData;
 use rtd_types::messages_checkpoint::CheckpointSequenceNumber;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
@@ -57,7 +55,9 @@ pub static MAX_CHECKPOINTS_IN_PROGRESS: Lazy<usize> = Lazy::new(|| {
 
 pub struct IndexerExecutor<P> {
     pools: Vec<Pin<Box<dyn Future<Output = ()> + Send>>>,
-    pool_senders: Vec<mpsc::Sender<Arc<CheckpointData>>>,
+    pool_senders: Vec<mpsc::Sender<Delivery>>,
+    /// The filter of each registered pool's worker.
+    pool_filters: Vec<Option<CheckpointFilter>>,
     progress_store: ProgressStoreWrapper<P>,
     pool_progress_sender: mpsc::Sender<(String, CheckpointSequenceNumber)>,
     pool_progress_receiver: mpsc::Receiver<(String, CheckpointSequenceNumber)>,
@@ -71,6 +71,7 @@ impl<P: ProgressStore> IndexerExecutor<P> {
         Self {
             pools: vec![],
             pool_senders: vec![],
+            pool_filters: vec![],
             progress_store: ProgressStoreWrapper::new(progress_store),
             pool_progress_sender,
             pool_progress_receiver,
@@ -82,10 +83,12 @@ impl<P: ProgressStore> IndexerExecutor<P> {
     pub async fn register<W: Worker + 'static>(&mut self, pool: WorkerPool<W>) -> Result<()> {
         let checkpoint_number = self.progress_store.load(pool.task_name.clone()).await?;
         let (sender, receiver) = mpsc::channel(*MAX_CHECKPOINTS_IN_PROGRESS);
-        self.pools.push(Box::pin(pool.run(
+        self.pool_filters.push(pool.filter());
+        self.pools.push(Box::pin(pool.run_filtered(
             checkpoint_number,
             receiver,
             self.pool_progress_sender.clone(),
+            self.metrics.clone(),
         )));
         self.pool_senders.push(sender);
         Ok(())
@@ -102,13 +105,30 @@ impl<P: ProgressStore> IndexerExecutor<P> {
     ) -> Result<ExecutorProgress> {
         let mut reader_checkpoint_number = self.progress_store.min_watermark()?;
         let upper_limit = reader_options.upper_limit;
+        // The reader can only skip checkpoints that no pool is interested in, so it needs every
+        // pool to have a filter.
+        let reader_filter = if self.pool_filters.is_empty() {
+            None
+        } else {
+            self.pool_filters
+                .iter()
+                .cloned()
+                .collect::<Option<Vec<_>>>()
+                .map(|filters| {
+                    filters
+                        .iter()
+                        .fold(CheckpointFilter::new(), |acc, filter| acc.union(filter))
+                })
+        };
+
         let (checkpoint_reader, mut checkpoint_recv, gc_sender, _exit_sender) =
-            CheckpointReader::initialize(
+            CheckpointReader::initialize_with_filter(
                 path,
                 reader_checkpoint_number,
                 remote_store_url,
                 remote_store_options,
                 reader_options,
+                reader_filter,
             );
         spawn_monitored_task!(checkpoint_reader.run());
 
@@ -132,6 +152,9 @@ impl<P: ProgressStore> IndexerExecutor<P> {
                         }
                 }
                 Some(checkpoint) = checkpoint_recv.recv() => {
+                    if let Delivery::Skipped(_) = checkpoint {
+                        self.metrics.data_ingestion_checkpoints_prefiltered.inc();
+                    }
                     for sender in &self.pool_senders {
                         sender.send(checkpoint.clone()).await?;
                     }
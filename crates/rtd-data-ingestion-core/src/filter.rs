@@ -0,0 +1,191 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeSet;
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use rtd_storage::blob::BlobEncoding;
+use rtd_types::base_types::{ObjectID, RtdAddress};
+use rtd_types::full_checkpoint_content::{CheckpointData, CheckpointTransaction};
+use rtd_types::transaction::TransactionDataAPI;
+
+/// Describes the checkpoints that a worker is interested in. A checkpoint matches the filter if
+/// any of its transactions:
+///
+/// - calls a function in, or emits an event from, one of the filter's packages,
+/// - emits an event of one of the filter's event types,
+/// - reads or writes an object of one of the filter's object types,
+/// - was sent by one of the filter's senders.
+///
+/// Types without type parameters match every instantiation of that type (e.g. `0x2::coin::Coin`
+/// matches `0x2::coin::Coin<0x2::rtd::RTD>`). An empty filter does not match any checkpoint.
+#[derive(Clone, Debug, Default)]
+pub struct CheckpointFilter {
+    packages: BTreeSet<ObjectID>,
+    event_types: BTreeSet<StructTag>,
+    object_types: BTreeSet<StructTag>,
+    senders: BTreeSet<RtdAddress>,
+}
+
+impl CheckpointFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_package(mut self, package: ObjectID) -> Self {
+        self.packages.insert(package);
+        self
+    }
+
+    pub fn with_event_type(mut self, type_: StructTag) -> Self {
+        self.event_types.insert(type_);
+        self
+    }
+
+    pub fn with_object_type(mut self, type_: StructTag) -> Self {
+        self.object_types.insert(type_);
+        self
+    }
+
+    pub fn with_sender(mut self, sender: RtdAddress) -> Self {
+        self.senders.insert(sender);
+        self
+    }
+
+    /// A filter that matches every checkpoint that either `self` or `other` matches.
+    pub fn union(mut self, other: &CheckpointFilter) -> Self {
+        self.packages.extend(other.packages.iter().copied());
+        self.event_types.extend(other.event_types.iter().cloned());
+        self.object_types.extend(other.object_types.iter().cloned());
+        self.senders.extend(other.senders.iter().copied());
+        self
+    }
+
+    /// Whether any transaction in `checkpoint` matches this filter.
+    pub fn matches(&self, checkpoint: &CheckpointData) -> bool {
+        checkpoint
+            .transactions
+            .iter()
+            .any(|tx| self.matches_transaction(tx))
+    }
+
+    fn matches_transaction(&self, tx: &CheckpointTransaction) -> bool {
+        let data = tx.transaction.transaction_data();
+        if self.senders.contains(&data.sender()) {
+            return true;
+        }
+
+        if !self.packages.is_empty()
+            && data
+                .move_calls()
+                .into_iter()
+                .any(|(package, _, _)| self.packages.contains(package))
+        {
+            return true;
+        }
+
+        if let Some(events) = &tx.events
+            && events.data.iter().any(|event| {
+                self.packages.contains(&event.package_id)
+                    || self.packages.contains(&ObjectID::from(event.type_.address))
+                    || matches_type(&self.event_types, &event.type_)
+            })
+        {
+            return true;
+        }
+
+        if self.object_types.is_empty() {
+            return false;
+        }
+
+        tx.input_objects
+            .iter()
+            .chain(tx.output_objects.iter())
+            .filter_map(|object| object.struct_tag())
+            .any(|tag| matches_type(&self.object_types, &tag))
+    }
+
+    /// A cheap check on the serialized `blob` of a checkpoint, before it is deserialized: returns
+    /// `false` only if the checkpoint cannot possibly match this filter.
+    ///
+    /// Every package, sender, and type that the filter looks for involves some address, which must
+    /// appear verbatim in the checkpoint's BCS representation if the checkpoint matches. This does
+    /// not hold for system addresses (and types defined at them), because some system types are
+    /// serialized without their addresses, so filters that involve system addresses always pass
+    /// this check.
+    pub(crate) fn may_match_blob(&self, blob: &[u8]) -> bool {
+        if blob.first() != Some(&(BlobEncoding::Bcs as u8)) {
+            return true;
+        }
+
+        let mut needles = BTreeSet::new();
+        let packages = self.packages.iter().map(|p| Some(AccountAddress::from(*p)));
+        let senders = self.senders.iter().map(|s| Some(AccountAddress::from(*s)));
+        let types = self
+            .event_types
+            .iter()
+            .chain(self.object_types.iter())
+            .map(user_address);
+
+        for address in packages.chain(senders).chain(types) {
+            match address {
+                Some(address) if !is_system_address(&address) => {
+                    needles.insert(address);
+                }
+                _ => return true,
+            }
+        }
+
+        let blob = &blob[1..];
+        needles.iter().any(|needle| {
+            blob.windows(AccountAddress::LENGTH)
+                .any(|window| window == needle.as_ref())
+        })
+    }
+}
+
+/// Whether `tag` is one of the `types`, or an instantiation of one of them.
+fn matches_type(types: &BTreeSet<StructTag>, tag: &StructTag) -> bool {
+    if types.is_empty() {
+        return false;
+    }
+
+    if types.contains(tag) {
+        return true;
+    }
+
+    if tag.type_params.is_empty() {
+        return false;
+    }
+
+    types.contains(&StructTag {
+        type_params: vec![],
+        ..tag.clone()
+    })
+}
+
+/// An address mentioned by `tag` that is not a system address, if there is one. Any value of this
+/// type must mention this address when serialized.
+fn user_address(tag: &StructTag) -> Option<AccountAddress> {
+    if !is_system_address(&tag.address) {
+        return Some(tag.address);
+    }
+
+    tag.type_params.iter().find_map(|param| match param {
+        TypeTag::Struct(tag) => user_address(tag),
+        TypeTag::Vector(inner) => match inner.as_ref() {
+            TypeTag::Struct(tag) => user_address(tag),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// System packages and objects live at short addresses, and some of their types have a compact
+/// serialized representation that does not include the address.
+fn is_system_address(address: &AccountAddress) -> bool {
+    address.as_ref()[..AccountAddress::LENGTH - 2]
+        .iter()
+        .all(|b| *b == 0)
+}
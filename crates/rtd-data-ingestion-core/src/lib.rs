@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod executor;
+mod filter;
 mod metrics;
 mod progress_store;
 mod reader;
@@ -19,11 +20,12 @@ pub use executor::{
     IndexerExecutor, MAX_CHECKPOINTS_IN_PROGRESS, setup_single_workflow,
     setup_single_workflow_with_options,
 };
+pub use filter::CheckpointFilter;
 pub use metrics::DataIngestionMetrics;
 pub use progress_store::{
     ExecutorProgress, FileProgressStore, ProgressStore, ShimIndexerProgressStore, ShimProgressStore,
 };
-pub use reader::{CheckpointReader, ReaderOptions};
+pub use reader::{CheckpointReader, Delivery, ReaderOptions};
 use rtd_types::full_checkpoint_content::CheckpointData;
 pub use util::{create_remote_store_client, end_of_epoch_data};
 pub use worker_pool::WorkerPool;
@@ -47,6 +49,13 @@ pub trait Worker: Send + Sync {
     fn preprocess_hook(&self, _: &CheckpointData) -> Result<()> {
         Ok(())
     }
+
+    /// The checkpoints this worker is interested in, or `None` (the default) for all of them.
+    /// Checkpoints that do not match the filter are not passed to the worker at all (including to
+    /// `preprocess_hook`), but its watermark still advances past them.
+    fn filter(&self) -> Option<CheckpointFilter> {
+        None
+    }
 }
 
 #[async_trait]
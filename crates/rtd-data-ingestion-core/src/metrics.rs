@@ -1,11 +1,17 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use prometheus::{IntGaugeVec, Registry, register_int_gauge_vec_with_registry};
+use prometheus::{
+    IntCounter, IntCounterVec, IntGaugeVec, Registry, register_int_counter_vec_with_registry,
+    register_int_counter_with_registry, register_int_gauge_vec_with_registry,
+};
 
 #[derive(Clone)]
 pub struct DataIngestionMetrics {
     pub data_ingestion_checkpoint: IntGaugeVec,
+    pub data_ingestion_checkpoints_delivered: IntCounterVec,
+    pub data_ingestion_checkpoints_skipped: IntCounterVec,
+    pub data_ingestion_checkpoints_prefiltered: IntCounter,
 }
 
 impl DataIngestionMetrics {
@@ -18,6 +24,27 @@ impl DataIngestionMetrics {
                 registry,
             )
             .unwrap(),
+            data_ingestion_checkpoints_delivered: register_int_counter_vec_with_registry!(
+                "data_ingestion_checkpoints_delivered",
+                "Number of checkpoints passed to a task's workers.",
+                &["task"],
+                registry,
+            )
+            .unwrap(),
+            data_ingestion_checkpoints_skipped: register_int_counter_vec_with_registry!(
+                "data_ingestion_checkpoints_skipped",
+                "Number of checkpoints skipped by a task because they did not match its filter.",
+                &["task"],
+                registry,
+            )
+            .unwrap(),
+            data_ingestion_checkpoints_prefiltered: register_int_counter_with_registry!(
+                "data_ingestion_checkpoints_prefiltered",
+                "Number of checkpoints the reader did not deserialize, because they could not \
+                match any task's filter.",
+                registry,
+            )
+            .unwrap(),
         }
     }
 }
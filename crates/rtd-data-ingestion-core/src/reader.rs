@@ -3,6 +3,7 @@
 
 use crate::create_remote_store_client;
 use crate::executor::MAX_CHECKPOINTS_IN_PROGRESS;
+use crate::filter::CheckpointFilter;
 use anyhow::Result;
 use backoff::backoff::Backoff;
 use futures::StreamExt;
@@ -35,13 +36,32 @@ pub struct CheckpointReader {
     remote_store_options: Vec<(String, String)>,
     current_checkpoint_number: CheckpointSequenceNumber,
     last_pruned_watermark: CheckpointSequenceNumber,
-    checkpoint_sender: mpsc::Sender<Arc<CheckpointData>>,
+    checkpoint_sender: CheckpointSender,
     processed_receiver: mpsc::Receiver<CheckpointSequenceNumber>,
-    #[allow(clippy::type_complexity)]
-    remote_fetcher_receiver: Option<mpsc::Receiver<Result<(Arc<CheckpointData>, usize)>>>,
+    remote_fetcher_receiver: Option<mpsc::Receiver<Result<(Delivery, usize)>>>,
     exit_receiver: oneshot::Receiver<()>,
     options: ReaderOptions,
     data_limiter: DataLimiter,
+    /// If set, checkpoints are checked against this filter before they are deserialized, and
+    /// checkpoints that cannot match it are skipped.
+    filter: Option<Arc<CheckpointFilter>>,
+}
+
+/// A checkpoint, as the reader passes it on to worker pools.
+#[derive(Clone)]
+pub enum Delivery {
+    /// A deserialized checkpoint, which may match the reader's filter.
+    Checkpoint(Arc<CheckpointData>),
+    /// A checkpoint that was not deserialized, because it could not match the reader's filter. It
+    /// is still passed on, so that watermarks advance past it.
+    Skipped(CheckpointSequenceNumber),
+}
+
+/// Where the reader sends the checkpoints it reads.
+enum CheckpointSender {
+    /// Receives every checkpoint. Readers that send here have no filter, so never skip any.
+    Checkpoints(mpsc::Sender<Arc<CheckpointData>>),
+    Deliveries(mpsc::Sender<Delivery>),
 }
 
 #[derive(Clone)]
@@ -78,7 +98,7 @@ enum RemoteStore {
 impl CheckpointReader {
     /// Represents a single iteration of the reader.
     /// Reads files in a local directory, validates them, and forwards `CheckpointData` to the executor.
-    async fn read_local_files(&self) -> Result<Vec<Arc<CheckpointData>>> {
+    async fn read_local_files(&self) -> Result<Vec<Delivery>> {
         let mut checkpoints = vec![];
         for offset in 0..*MAX_CHECKPOINTS_IN_PROGRESS {
             let sequence_number = self.current_checkpoint_number + offset as u64;
@@ -86,7 +106,11 @@ impl CheckpointReader {
                 break;
             }
             match fs::read(self.path.join(format!("{}.chk", sequence_number))) {
-                Ok(bytes) => checkpoints.push(Blob::from_bytes::<Arc<CheckpointData>>(&bytes)?),
+                Ok(bytes) => checkpoints.push(Self::decode(
+                    &bytes,
+                    sequence_number,
+                    self.filter.as_deref(),
+                )?),
                 Err(err) => match err.kind() {
                     std::io::ErrorKind::NotFound => break,
                     _ => Err(err)?,
@@ -114,6 +138,35 @@ impl CheckpointReader {
         ))
     }
 
+    async fn fetch_delivery_from_object_store(
+        store: &dyn ObjectStore,
+        checkpoint_number: CheckpointSequenceNumber,
+        filter: Option<&CheckpointFilter>,
+    ) -> Result<(Delivery, usize)> {
+        let path = Path::from(format!("{}.chk", checkpoint_number));
+        let response = store.get(&path).await?;
+        let bytes = response.bytes().await?;
+        Ok((
+            Self::decode(&bytes, checkpoint_number, filter)?,
+            bytes.len(),
+        ))
+    }
+
+    /// Deserialize the checkpoint blob in `bytes`, unless it cannot match `filter`.
+    fn decode(
+        bytes: &[u8],
+        checkpoint_number: CheckpointSequenceNumber,
+        filter: Option<&CheckpointFilter>,
+    ) -> Result<Delivery> {
+        if filter.is_some_and(|f| !f.may_match_blob(bytes)) {
+            return Ok(Delivery::Skipped(checkpoint_number));
+        }
+
+        Ok(Delivery::Checkpoint(
+            Blob::from_bytes::<Arc<CheckpointData>>(bytes)?,
+        ))
+    }
+
     async fn fetch_from_full_node(
         client: &Client,
         checkpoint_number: CheckpointSequenceNumber,
@@ -129,18 +182,25 @@ impl CheckpointReader {
     async fn remote_fetch_checkpoint_internal(
         store: &RemoteStore,
         checkpoint_number: CheckpointSequenceNumber,
-    ) -> Result<(Arc<CheckpointData>, usize)> {
+        filter: Option<&CheckpointFilter>,
+    ) -> Result<(Delivery, usize)> {
+        // Checkpoints fetched from a full node arrive deserialized, so there is nothing to gain
+        // from filtering them here.
+        let from_full_node = |(checkpoint, size)| (Delivery::Checkpoint(checkpoint), size);
         match store {
             RemoteStore::ObjectStore(store) => {
-                Self::fetch_from_object_store(store, checkpoint_number).await
-            }
-            RemoteStore::Rest(client) => {
-                Self::fetch_from_full_node(client, checkpoint_number).await
+                Self::fetch_delivery_from_object_store(store, checkpoint_number, filter).await
             }
+            RemoteStore::Rest(client) => Self::fetch_from_full_node(client, checkpoint_number)
+                .await
+                .map(from_full_node),
             RemoteStore::Hybrid(store, client) => {
                 match Self::fetch_from_full_node(client, checkpoint_number).await {
-                    Ok(result) => Ok(result),
-                    Err(_) => Self::fetch_from_object_store(store, checkpoint_number).await,
+                    Ok(result) => Ok(from_full_node(result)),
+                    Err(_) => {
+                        Self::fetch_delivery_from_object_store(store, checkpoint_number, filter)
+                            .await
+                    }
                 }
             }
         }
@@ -149,7 +209,8 @@ impl CheckpointReader {
     async fn remote_fetch_checkpoint(
         store: &RemoteStore,
         checkpoint_number: CheckpointSequenceNumber,
-    ) -> Result<(Arc<CheckpointData>, usize)> {
+        filter: Option<&CheckpointFilter>,
+    ) -> Result<(Delivery, usize)> {
         let mut backoff = backoff::ExponentialBackoff::default();
         let max_elapsed_time = Duration::from_secs(60);
         backoff.max_elapsed_time = Some(max_elapsed_time);
@@ -159,7 +220,7 @@ impl CheckpointReader {
         loop {
             match tokio::time::timeout(
                 max_elapsed_time,
-                Self::remote_fetch_checkpoint_internal(store, checkpoint_number),
+                Self::remote_fetch_checkpoint_internal(store, checkpoint_number, filter),
             )
             .await
             {
@@ -185,8 +246,9 @@ impl CheckpointReader {
         }
     }
 
-    fn start_remote_fetcher(&mut self) -> mpsc::Receiver<Result<(Arc<CheckpointData>, usize)>> {
+    fn start_remote_fetcher(&mut self) -> mpsc::Receiver<Result<(Delivery, usize)>> {
         let batch_size = self.options.batch_size;
+        let filter = self.filter.clone();
         let start_checkpoint = self.current_checkpoint_number;
         let (sender, receiver) = mpsc::channel(batch_size);
         let url = self
@@ -215,7 +277,9 @@ impl CheckpointReader {
 
         spawn_monitored_task!(async move {
             let mut checkpoint_stream = (start_checkpoint..u64::MAX)
-                .map(|checkpoint_number| Self::remote_fetch_checkpoint(&store, checkpoint_number))
+                .map(|checkpoint_number| {
+                    Self::remote_fetch_checkpoint(&store, checkpoint_number, filter.as_deref())
+                })
                 .pipe(futures::stream::iter)
                 .buffered(batch_size);
 
@@ -229,7 +293,7 @@ impl CheckpointReader {
         receiver
     }
 
-    fn remote_fetch(&mut self) -> Vec<Arc<CheckpointData>> {
+    fn remote_fetch(&mut self) -> Vec<Delivery> {
         let mut checkpoints = vec![];
         if self.remote_fetcher_receiver.is_none() {
             self.remote_fetcher_receiver = Some(self.start_remote_fetcher());
@@ -237,7 +301,7 @@ impl CheckpointReader {
         while !self.exceeds_capacity(self.current_checkpoint_number + checkpoints.len() as u64) {
             match self.remote_fetcher_receiver.as_mut().unwrap().try_recv() {
                 Ok(Ok((checkpoint, size))) => {
-                    self.data_limiter.add(&checkpoint, size);
+                    self.data_limiter.add(checkpoint.sequence_number(), size);
                     checkpoints.push(checkpoint);
                 }
                 Ok(Err(err)) => {
@@ -269,8 +333,7 @@ impl CheckpointReader {
         let mut read_source: &str = "local";
         if self.remote_store_url.is_some()
             && (checkpoints.is_empty()
-                || checkpoints[0].checkpoint_summary.sequence_number
-                    > self.current_checkpoint_number)
+                || checkpoints[0].sequence_number() > self.current_checkpoint_number)
         {
             checkpoints = self.remote_fetch();
             read_source = "remote";
@@ -288,14 +351,11 @@ impl CheckpointReader {
         );
         for checkpoint in checkpoints {
             if read_source == "local"
-                && checkpoint.checkpoint_summary.sequence_number > self.current_checkpoint_number
+                && checkpoint.sequence_number() > self.current_checkpoint_number
             {
                 break;
             }
-            assert_eq!(
-                checkpoint.checkpoint_summary.sequence_number,
-                self.current_checkpoint_number
-            );
+            assert_eq!(checkpoint.sequence_number(), self.current_checkpoint_number);
             self.checkpoint_sender.send(checkpoint).await?;
             self.current_checkpoint_number += 1;
         }
@@ -342,6 +402,60 @@ impl CheckpointReader {
         oneshot::Sender<()>,
     ) {
         let (checkpoint_sender, checkpoint_recv) = mpsc::channel(*MAX_CHECKPOINTS_IN_PROGRESS);
+        let (reader, processed_sender, exit_sender) = Self::new(
+            path,
+            starting_checkpoint_number,
+            remote_store_url,
+            remote_store_options,
+            options,
+            CheckpointSender::Checkpoints(checkpoint_sender),
+            None,
+        );
+        (reader, checkpoint_recv, processed_sender, exit_sender)
+    }
+
+    /// Like `initialize`, but skips deserializing checkpoints that cannot match `filter`. Skipped
+    /// checkpoints are still delivered, as `Delivery::Skipped`, so that watermarks advance past
+    /// them.
+    pub fn initialize_with_filter(
+        path: PathBuf,
+        starting_checkpoint_number: CheckpointSequenceNumber,
+        remote_store_url: Option<String>,
+        remote_store_options: Vec<(String, String)>,
+        options: ReaderOptions,
+        filter: Option<CheckpointFilter>,
+    ) -> (
+        Self,
+        mpsc::Receiver<Delivery>,
+        mpsc::Sender<CheckpointSequenceNumber>,
+        oneshot::Sender<()>,
+    ) {
+        let (checkpoint_sender, checkpoint_recv) = mpsc::channel(*MAX_CHECKPOINTS_IN_PROGRESS);
+        let (reader, processed_sender, exit_sender) = Self::new(
+            path,
+            starting_checkpoint_number,
+            remote_store_url,
+            remote_store_options,
+            options,
+            CheckpointSender::Deliveries(checkpoint_sender),
+            filter,
+        );
+        (reader, checkpoint_recv, processed_sender, exit_sender)
+    }
+
+    fn new(
+        path: PathBuf,
+        starting_checkpoint_number: CheckpointSequenceNumber,
+        remote_store_url: Option<String>,
+        remote_store_options: Vec<(String, String)>,
+        options: ReaderOptions,
+        checkpoint_sender: CheckpointSender,
+        filter: Option<CheckpointFilter>,
+    ) -> (
+        Self,
+        mpsc::Sender<CheckpointSequenceNumber>,
+        oneshot::Sender<()>,
+    ) {
         let (processed_sender, processed_receiver) = mpsc::channel(*MAX_CHECKPOINTS_IN_PROGRESS);
         let (exit_sender, exit_receiver) = oneshot::channel();
         let reader = Self {
@@ -356,8 +470,9 @@ impl CheckpointReader {
             exit_receiver,
             data_limiter: DataLimiter::new(options.data_limit),
             options,
+            filter: filter.map(Arc::new),
         };
-        (reader, checkpoint_recv, processed_sender, exit_sender)
+        (reader, processed_sender, exit_sender)
     }
 
     #[cfg(not(target_os = "macos"))]
@@ -406,6 +521,30 @@ impl CheckpointReader {
     }
 }
 
+impl CheckpointSender {
+    async fn send(&self, delivery: Delivery) -> Result<()> {
+        match (self, delivery) {
+            (Self::Checkpoints(sender), Delivery::Checkpoint(checkpoint)) => {
+                sender.send(checkpoint).await?
+            }
+            (Self::Checkpoints(_), Delivery::Skipped(_)) => {
+                unreachable!("readers without a filter never skip checkpoints")
+            }
+            (Self::Deliveries(sender), delivery) => sender.send(delivery).await?,
+        }
+        Ok(())
+    }
+}
+
+impl Delivery {
+    pub fn sequence_number(&self) -> CheckpointSequenceNumber {
+        match self {
+            Delivery::Checkpoint(checkpoint) => checkpoint.checkpoint_summary.sequence_number,
+            Delivery::Skipped(sequence_number) => *sequence_number,
+        }
+    }
+}
+
 pub struct DataLimiter {
     limit: usize,
     queue: BTreeMap<CheckpointSequenceNumber, usize>,
@@ -425,13 +564,12 @@ impl DataLimiter {
         self.limit > 0 && self.in_progress >= self.limit
     }
 
-    fn add(&mut self, checkpoint: &CheckpointData, size: usize) {
+    fn add(&mut self, checkpoint_number: CheckpointSequenceNumber, size: usize) {
         if self.limit == 0 {
             return;
         }
         self.in_progress += size;
-        self.queue
-            .insert(checkpoint.checkpoint_summary.sequence_number, size);
+        self.queue.insert(checkpoint_number, size);
     }
 
     fn gc(&mut self, watermark: CheckpointSequenceNumber) {
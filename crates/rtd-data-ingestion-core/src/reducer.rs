@@ -13,7 +13,7 @@ use tokio_stream::wrappers::ReceiverStream;
 pub(crate) async fn reduce<W: Worker>(
     task_name: String,
     mut current_checkpoint_number: CheckpointSequenceNumber,
-    progress_receiver: mpsc::Receiver<(CheckpointSequenceNumber, W::Result)>,
+    progress_receiver: mpsc::Receiver<(CheckpointSequenceNumber, Option<W::Result>)>,
     executor_progress_sender: mpsc::Sender<(String, CheckpointSequenceNumber)>,
     reducer: Option<Box<dyn Reducer<W::Result>>>,
 ) -> Result<()> {
@@ -29,6 +29,11 @@ pub(crate) async fn reduce<W: Worker>(
             unprocessed.insert(checkpoint_number, message);
         }
         while let Some(message) = unprocessed.remove(&current_checkpoint_number) {
+            // Checkpoints that were skipped by the worker pool's filter have no result.
+            let Some(message) = message else {
+                current_checkpoint_number += 1;
+                continue;
+            };
             if let Some(ref reducer) = reducer {
                 if reducer.should_close_batch(&batch, Some(&message)) {
                     commit_with_retry(reducer, std::mem::take(&mut batch)).await?;
@@ -45,6 +50,9 @@ pub(crate) async fn reduce<W: Worker>(
                 if reducer.should_close_batch(&batch, None) {
                     commit_with_retry(reducer, std::mem::take(&mut batch)).await?;
                     progress_update = Some(current_checkpoint_number);
+                } else if batch.is_empty() {
+                    // Everything up to here has been committed, or skipped.
+                    progress_update = Some(current_checkpoint_number);
                 }
             }
             None => progress_update = Some(current_checkpoint_number),
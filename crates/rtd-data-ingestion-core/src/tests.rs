@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::progress_store::ExecutorProgress;
-use crate::{DataIngestionMetrics, FileProgressStore, IndexerExecutor, WorkerPool};
+use crate::{CheckpointFilter, DataIngestionMetrics, FileProgressStore, IndexerExecutor, WorkerPool};
 use crate::{ReaderOptions, Worker};
 use anyhow::Result;
 use async_trait::async_trait;
@@ -10,9 +10,12 @@ use prometheus::Registry;
 use rand::SeedableRng;
 use rand::prelude::StdRng;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use rtd_protocol_config::ProtocolConfig;
 use rtd_storage::blob::{Blob, BlobEncoding};
+use rtd_types::base_types::ObjectID;
 use rtd_types::crypto::KeypairTraits;
 use rtd_types::full_checkpoint_content::CheckpointData;
 use rtd_types::gas::GasCostSummary;
@@ -20,6 +23,7 @@ use rtd_types::messages_checkpoint::{
     CertifiedCheckpointSummary, CheckpointContents, CheckpointSequenceNumber, CheckpointSummary,
     SignedCheckpointSummary,
 };
+use rtd_types::test_checkpoint_data_builder::TestCheckpointBuilder;
 use rtd_types::utils::make_committee_key;
 use tempfile::NamedTempFile;
 use tokio::sync::oneshot;
@@ -80,6 +84,27 @@ impl Worker for TestWorker {
     }
 }
 
+/// A worker that is only interested in checkpoints that touch a particular package, and counts the
+/// checkpoints it is given.
+#[derive(Clone)]
+struct FilteredWorker {
+    package: ObjectID,
+    processed: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Worker for FilteredWorker {
+    type Result = ();
+    async fn process_checkpoint(&self, _checkpoint: &CheckpointData) -> Result<()> {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn filter(&self) -> Option<CheckpointFilter> {
+        Some(CheckpointFilter::new().with_package(self.package))
+    }
+}
+
 #[tokio::test]
 async fn empty_pools() {
     let bundle = create_executor_bundle();
@@ -106,6 +131,147 @@ async fn basic_flow() {
     assert_eq!(result.unwrap().get("test"), Some(&20));
 }
 
+#[tokio::test]
+async fn filtered_flow() {
+    let progress_file = NamedTempFile::new().unwrap();
+    std::fs::write(progress_file.path(), "{}").unwrap();
+    let metrics = DataIngestionMetrics::new(&Registry::new());
+    let mut executor = IndexerExecutor::new(
+        FileProgressStore::new(progress_file.path().to_path_buf()),
+        1,
+        metrics.clone(),
+    );
+
+    let processed = Arc::new(AtomicUsize::new(0));
+    let worker = FilteredWorker {
+        package: ObjectID::from_hex_literal("0x1234abcd").unwrap(),
+        processed: processed.clone(),
+    };
+    add_worker_pool(&mut executor, worker, 5).await.unwrap();
+
+    let path = temp_dir();
+    for checkpoint_number in 0..20 {
+        let bytes = mock_checkpoint_data_bytes(checkpoint_number);
+        std::fs::write(path.join(format!("{}.chk", checkpoint_number)), bytes).unwrap();
+    }
+
+    // None of the checkpoints mention the package, so they are all skipped before being
+    // deserialized, but the watermark still advances past them.
+    let result = run(executor, Some(path), Some(Duration::from_secs(1))).await;
+    assert_eq!(result.unwrap().get("test"), Some(&20));
+    assert_eq!(processed.load(Ordering::Relaxed), 0);
+    assert_eq!(metrics.data_ingestion_checkpoints_prefiltered.get(), 20);
+
+    let labels = &["test"];
+    assert_eq!(
+        metrics
+            .data_ingestion_checkpoints_skipped
+            .with_label_values(labels)
+            .get(),
+        20
+    );
+    assert_eq!(
+        metrics
+            .data_ingestion_checkpoints_delivered
+            .with_label_values(labels)
+            .get(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn filtered_flow_delivers_matches() {
+    let progress_file = NamedTempFile::new().unwrap();
+    std::fs::write(progress_file.path(), "{}").unwrap();
+    let metrics = DataIngestionMetrics::new(&Registry::new());
+    let mut executor = IndexerExecutor::new(
+        FileProgressStore::new(progress_file.path().to_path_buf()),
+        1,
+        metrics.clone(),
+    );
+
+    let package = ObjectID::from_hex_literal("0x1234abcd").unwrap();
+    let processed = Arc::new(AtomicUsize::new(0));
+    let worker = FilteredWorker {
+        package,
+        processed: processed.clone(),
+    };
+    add_worker_pool(&mut executor, worker, 5).await.unwrap();
+
+    // Only checkpoints 5 and 12 call into the package.
+    let path = temp_dir();
+    let mut builder = TestCheckpointBuilder::new(0);
+    for checkpoint_number in 0..20 {
+        if checkpoint_number == 5 || checkpoint_number == 12 {
+            builder = builder
+                .start_transaction(0)
+                .add_move_call(package, "module", "function")
+                .finish_transaction();
+        }
+
+        let checkpoint = CheckpointData::from(builder.build_checkpoint());
+        assert_eq!(
+            CheckpointFilter::new()
+                .with_package(package)
+                .matches(&checkpoint),
+            checkpoint_number == 5 || checkpoint_number == 12
+        );
+
+        let bytes = Blob::encode(&checkpoint, BlobEncoding::Bcs)
+            .unwrap()
+            .to_bytes();
+        std::fs::write(path.join(format!("{}.chk", checkpoint_number)), bytes).unwrap();
+    }
+
+    let result = run(executor, Some(path), Some(Duration::from_secs(1))).await;
+    assert_eq!(result.unwrap().get("test"), Some(&20));
+    assert_eq!(processed.load(Ordering::Relaxed), 2);
+
+    let labels = &["test"];
+    assert_eq!(
+        metrics
+            .data_ingestion_checkpoints_skipped
+            .with_label_values(labels)
+            .get(),
+        18
+    );
+    assert_eq!(
+        metrics
+            .data_ingestion_checkpoints_delivered
+            .with_label_values(labels)
+            .get(),
+        2
+    );
+}
+
+#[test]
+fn filter_blob_precheck() {
+    let bytes = mock_checkpoint_data_bytes(0);
+    let absent = ObjectID::from_hex_literal("0x1234abcd").unwrap();
+    assert!(
+        !CheckpointFilter::new()
+            .with_package(absent)
+            .may_match_blob(&bytes)
+    );
+
+    // System addresses can be serialized implicitly, so they always pass the check.
+    assert!(
+        CheckpointFilter::new()
+            .with_package(absent)
+            .with_package(ObjectID::from_hex_literal("0x2").unwrap())
+            .may_match_blob(&bytes)
+    );
+
+    // Addresses that do appear in the checkpoint pass the check.
+    let mut present = [0u8; 32];
+    present.copy_from_slice(&bytes[bytes.len() - 32..]);
+    assert!(
+        CheckpointFilter::new()
+            .with_package(ObjectID::new(present))
+            .may_match_blob(&bytes)
+    );
+}
+
 fn temp_dir() -> std::path::PathBuf {
     tempfile::tempdir()
         .expect("Failed to open temporary directory")
@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::executor::MAX_CHECKPOINTS_IN_PROGRESS;
+use crate::reader::Delivery;
 use crate::reducer::reduce;
-use crate::{Reducer, Worker};
+use crate::{CheckpointFilter, DataIngestionMetrics, Reducer, Worker};
 use linku_metrics::spawn_monitored_task;
+use prometheus::Registry;
 use std::collections::{BTreeSet, VecDeque};
 use std::sync::Arc;
 use std::time::Instant;
@@ -44,11 +46,49 @@ impl<W: Worker + 'static> WorkerPool<W> {
         }
     }
 
+    /// The filter for checkpoints that this pool's worker is interested in.
+    pub(crate) fn filter(&self) -> Option<CheckpointFilter> {
+        self.worker.filter()
+    }
+
     pub async fn run(
-        mut self,
+        self,
         watermark: CheckpointSequenceNumber,
         mut checkpoint_receiver: mpsc::Receiver<Arc<CheckpointData>>,
         executor_progress_sender: mpsc::Sender<(String, CheckpointSequenceNumber)>,
+    ) {
+        let (delivery_sender, delivery_receiver) = mpsc::channel(*MAX_CHECKPOINTS_IN_PROGRESS);
+        let forward = async move {
+            while let Some(checkpoint) = checkpoint_receiver.recv().await {
+                if delivery_sender
+                    .send(Delivery::Checkpoint(checkpoint))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        };
+        let metrics = DataIngestionMetrics::new(&Registry::new());
+        tokio::join!(
+            forward,
+            self.run_filtered(
+                watermark,
+                delivery_receiver,
+                executor_progress_sender,
+                metrics
+            )
+        );
+    }
+
+    /// Like `run`, but receives checkpoints from a reader that may have skipped some of them, and
+    /// counts the checkpoints this pool's worker processes and skips in `metrics`.
+    pub async fn run_filtered(
+        mut self,
+        watermark: CheckpointSequenceNumber,
+        mut checkpoint_receiver: mpsc::Receiver<Delivery>,
+        executor_progress_sender: mpsc::Sender<(String, CheckpointSequenceNumber)>,
+        metrics: DataIngestionMetrics,
     ) {
         info!(
             "Starting indexing pipeline {} with concurrency {}. Current watermark is {}.",
@@ -59,6 +99,13 @@ impl<W: Worker + 'static> WorkerPool<W> {
         let mut workers = vec![];
         let mut idle: BTreeSet<_> = (0..self.concurrency).collect();
         let mut checkpoints = VecDeque::new();
+        let filter = self.worker.filter();
+        let delivered = metrics
+            .data_ingestion_checkpoints_delivered
+            .with_label_values(&[&self.task_name]);
+        let skipped = metrics
+            .data_ingestion_checkpoints_skipped
+            .with_label_values(&[&self.task_name]);
 
         let mut join_handles = vec![];
 
@@ -94,7 +141,7 @@ impl<W: Worker + 'static> WorkerPool<W> {
                             .await
                             .expect("checkpoint processing failed for checkpoint");
                             info!("finished checkpoint processing {} for workflow {} in {:?}", sequence_number, task_name, start_time.elapsed());
-                            if cloned_progress_sender.send((worker_id, sequence_number, result)).await.is_err() {
+                            if cloned_progress_sender.send((worker_id, sequence_number, Some(result))).await.is_err() {
                                 // The progress channel closing is a sign we need to exit this loop.
                                 break;
                             }
@@ -134,11 +181,28 @@ impl<W: Worker + 'static> WorkerPool<W> {
                     if maybe_checkpoint.is_none() {
                         break;
                     }
-                    let checkpoint = maybe_checkpoint.expect("invariant's checked");
-                    let sequence_number = checkpoint.checkpoint_summary.sequence_number;
+                    let delivery = maybe_checkpoint.expect("invariant's checked");
+                    let sequence_number = delivery.sequence_number();
                     if sequence_number < watermark {
                         continue;
                     }
+                    let checkpoint = match delivery {
+                        Delivery::Checkpoint(checkpoint)
+                            if filter.as_ref().is_none_or(|f| f.matches(&checkpoint)) =>
+                        {
+                            checkpoint
+                        }
+                        // Skipped checkpoints bypass the workers, but still go to the reducer, so
+                        // that the watermark can advance past them.
+                        _ => {
+                            skipped.inc();
+                            if reducer_sender.send((sequence_number, None)).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+                    delivered.inc();
                     self.worker.preprocess_hook(&checkpoint).expect("failed to preprocess task");
                     if idle.is_empty() {
                         checkpoints.push_back(checkpoint);
@@ -20,6 +20,7 @@
 //! - [`stores::InMemoryStore`] - Unbounded in-memory cache
 //! - [`stores::LruMemoryStore`] - Bounded LRU cache
 //! - [`stores::ReadThroughStore`] - Composable two-tier caching pattern
+//! - [`stores::BundleStore`] - Read-only store over a self-contained replay bundle
 //!
 //! ## Composition
 //!
@@ -0,0 +1,534 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay bundles: everything needed to replay a transaction, in a single portable file.
+//!
+//! A bundle holds the transaction data and effects, the epoch data (from which the protocol
+//! config is derived), and every object read while replaying the transaction: input objects
+//! at their versions, packages and their dependencies, and dynamic field children, along with
+//! the version mappings for the `RootVersion` and `AtCheckpoint` queries that were made.
+//!
+//! Bundles are typically exported from an `InMemoryStore` that recorded a replay
+//! (see `InMemoryStore::export_bundle`), and either replayed directly through a `BundleStore`,
+//! which needs no network access, or imported into a writable store such as the
+//! `FileSystemStore`.
+//!
+//! # File Format
+//!
+//! A bundle file is the BCS encoding of `ReplayBundle`. The first field is the format version,
+//! which is checked when the bundle is read.
+
+use crate::{
+    EpochData, EpochStore, ObjectKey, ObjectStore, ReadWriteDataStore, SetupStore, StoreSummary,
+    TransactionInfo, TransactionStore, VersionQuery, node::Node,
+};
+use anyhow::{Context, Error, Result, bail};
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+use rtd_types::{
+    base_types::ObjectID, committee::ProtocolVersion, effects::TransactionEffects, object::Object,
+    supported_protocol_versions::ProtocolConfig, transaction::TransactionData,
+};
+
+/// Version of the bundle file format written by this crate.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Serializable transaction data in a bundle.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BundleTransaction {
+    pub digest: String,
+    pub data: TransactionData,
+    pub effects: TransactionEffects,
+    pub checkpoint: u64,
+}
+
+/// Serializable epoch data in a bundle.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BundleEpoch {
+    pub epoch_id: u64,
+    pub protocol_version: u64,
+    pub rgp: u64,
+    pub start_timestamp: u64,
+}
+
+/// A version mapping recorded for a `RootVersion` or `AtCheckpoint` query.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BundleVersionMapping {
+    pub object_id: ObjectID,
+    /// The root version bound or the checkpoint queried.
+    pub query: u64,
+    pub actual_version: u64,
+}
+
+/// The content of a replay bundle file.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ReplayBundle {
+    pub format_version: u32,
+    /// The transaction the bundle was captured for.
+    pub tx_digest: String,
+    /// Network name of the node the data was captured from (see `Node::network_name`).
+    pub network: String,
+    /// Chain identifier of the network, if known.
+    pub chain_id: Option<String>,
+    pub transactions: Vec<BundleTransaction>,
+    pub epochs: Vec<BundleEpoch>,
+    pub objects: Vec<Object>,
+    pub root_versions: Vec<BundleVersionMapping>,
+    pub checkpoint_versions: Vec<BundleVersionMapping>,
+}
+
+impl ReplayBundle {
+    /// Read a bundle file.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let bytes = fs::read(path)
+            .with_context(|| format!("Failed to read bundle file: {}", path.display()))?;
+        let bundle: Self = bcs::from_bytes(&bytes)
+            .with_context(|| format!("Failed to deserialize bundle file: {}", path.display()))?;
+        if bundle.format_version != BUNDLE_FORMAT_VERSION {
+            bail!(
+                "Unsupported bundle format version {} in {} (expected {})",
+                bundle.format_version,
+                path.display(),
+                BUNDLE_FORMAT_VERSION
+            );
+        }
+        Ok(bundle)
+    }
+
+    /// Write the bundle to `path`, creating parent directories as needed.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let bytes = bcs::to_bytes(self)
+            .with_context(|| format!("Failed to serialize bundle for: {}", path.display()))?;
+        fs::write(path, bytes)
+            .with_context(|| format!("Failed to write bundle file: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The node the bundle was captured from.
+    pub fn node(&self) -> Node {
+        // Parsing a node never fails: unknown names are custom nodes.
+        Node::from_str(&self.network).unwrap_or_else(|_| Node::Custom(self.network.clone()))
+    }
+
+    /// Write the content of the bundle into `store`, so that the transaction can be replayed
+    /// from it (e.g. from the `FileSystemStore` in `fs-only` mode).
+    pub fn import_into<S: ReadWriteDataStore + SetupStore>(&self, store: &S) -> Result<(), Error> {
+        if let Some(chain_id) = &self.chain_id {
+            store.setup(Some(chain_id.clone()))?;
+        }
+        for tx in &self.transactions {
+            store.write_transaction(
+                &tx.digest,
+                TransactionInfo {
+                    data: tx.data.clone(),
+                    effects: tx.effects.clone(),
+                    checkpoint: tx.checkpoint,
+                },
+            )?;
+        }
+        for epoch in &self.epochs {
+            store.write_epoch_info(epoch.epoch_id, epoch.clone().into())?;
+        }
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| ((object.id(), object.version().value()), object))
+            .collect::<BTreeMap<_, _>>();
+        for ((object_id, version), object) in &objects {
+            let key = ObjectKey {
+                object_id: *object_id,
+                version_query: VersionQuery::Version(*version),
+            };
+            store.write_object(&key, (*object).clone(), *version)?;
+        }
+        let mappings = self
+            .root_versions
+            .iter()
+            .map(|m| (m, VersionQuery::RootVersion(m.query)))
+            .chain(
+                self.checkpoint_versions
+                    .iter()
+                    .map(|m| (m, VersionQuery::AtCheckpoint(m.query))),
+            );
+        for (mapping, version_query) in mappings {
+            let Some(object) = objects.get(&(mapping.object_id, mapping.actual_version)) else {
+                bail!(
+                    "Bundle maps {} to version {}, which it does not contain",
+                    mapping.object_id,
+                    mapping.actual_version
+                );
+            };
+            let key = ObjectKey {
+                object_id: mapping.object_id,
+                version_query,
+            };
+            store.write_object(&key, (*object).clone(), mapping.actual_version)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<BundleEpoch> for EpochData {
+    fn from(epoch: BundleEpoch) -> Self {
+        EpochData {
+            epoch_id: epoch.epoch_id,
+            protocol_version: epoch.protocol_version,
+            rgp: epoch.rgp,
+            start_timestamp: epoch.start_timestamp,
+        }
+    }
+}
+
+impl From<EpochData> for BundleEpoch {
+    fn from(epoch_data: EpochData) -> Self {
+        BundleEpoch {
+            epoch_id: epoch_data.epoch_id,
+            protocol_version: epoch_data.protocol_version,
+            rgp: epoch_data.rgp,
+            start_timestamp: epoch_data.start_timestamp,
+        }
+    }
+}
+
+/// A read-only store over a replay bundle.
+///
+/// The store only answers the queries that were recorded in the bundle: objects at an exact
+/// version it holds, and `RootVersion` and `AtCheckpoint` queries that were made during the
+/// capture. Anything else is reported as missing.
+pub struct BundleStore {
+    node: Node,
+    tx_digest: String,
+    chain_id: Option<String>,
+    transactions: BTreeMap<String, TransactionInfo>,
+    epochs: BTreeMap<u64, EpochData>,
+    objects: BTreeMap<ObjectID, BTreeMap<u64, Object>>,
+    root_versions: BTreeMap<(ObjectID, u64), u64>,
+    checkpoint_versions: BTreeMap<(ObjectID, u64), u64>,
+}
+
+impl BundleStore {
+    pub fn new(bundle: ReplayBundle) -> Self {
+        let node = bundle.node();
+        let mut objects: BTreeMap<ObjectID, BTreeMap<u64, Object>> = BTreeMap::new();
+        for object in bundle.objects {
+            objects
+                .entry(object.id())
+                .or_default()
+                .insert(object.version().value(), object);
+        }
+        let version_map = |mappings: Vec<BundleVersionMapping>| {
+            mappings
+                .into_iter()
+                .map(|m| ((m.object_id, m.query), m.actual_version))
+                .collect()
+        };
+        Self {
+            node,
+            tx_digest: bundle.tx_digest,
+            chain_id: bundle.chain_id,
+            transactions: bundle
+                .transactions
+                .into_iter()
+                .map(|tx| {
+                    (
+                        tx.digest,
+                        TransactionInfo {
+                            data: tx.data,
+                            effects: tx.effects,
+                            checkpoint: tx.checkpoint,
+                        },
+                    )
+                })
+                .collect(),
+            epochs: bundle
+                .epochs
+                .into_iter()
+                .map(|epoch| (epoch.epoch_id, epoch.into()))
+                .collect(),
+            objects,
+            root_versions: version_map(bundle.root_versions),
+            checkpoint_versions: version_map(bundle.checkpoint_versions),
+        }
+    }
+
+    /// Read a bundle file into a store.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Ok(Self::new(ReplayBundle::read(path)?))
+    }
+
+    /// The node the bundle was captured from.
+    pub fn node(&self) -> Node {
+        self.node.clone()
+    }
+
+    /// The transaction the bundle was captured for.
+    pub fn tx_digest(&self) -> &str {
+        &self.tx_digest
+    }
+
+    fn object_at(&self, object_id: &ObjectID, version: u64) -> Option<(Object, u64)> {
+        self.objects
+            .get(object_id)
+            .and_then(|versions| versions.get(&version))
+            .map(|object| (object.clone(), version))
+    }
+}
+
+impl TransactionStore for BundleStore {
+    fn transaction_data_and_effects(
+        &self,
+        tx_digest: &str,
+    ) -> Result<Option<TransactionInfo>, Error> {
+        Ok(self.transactions.get(tx_digest).cloned())
+    }
+}
+
+impl EpochStore for BundleStore {
+    fn epoch_info(&self, epoch: u64) -> Result<Option<EpochData>, Error> {
+        Ok(self.epochs.get(&epoch).cloned())
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<Option<ProtocolConfig>, Error> {
+        Ok(self.epochs.get(&epoch).map(|epoch_data| {
+            ProtocolConfig::get_for_version(
+                ProtocolVersion::new(epoch_data.protocol_version),
+                self.node.chain(),
+            )
+        }))
+    }
+}
+
+impl ObjectStore for BundleStore {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<(Object, u64)>>, Error> {
+        Ok(keys
+            .iter()
+            .map(|key| {
+                let version = match &key.version_query {
+                    VersionQuery::Version(version) => Some(*version),
+                    VersionQuery::RootVersion(max_version) => self
+                        .root_versions
+                        .get(&(key.object_id, *max_version))
+                        .copied(),
+                    VersionQuery::AtCheckpoint(checkpoint) => self
+                        .checkpoint_versions
+                        .get(&(key.object_id, *checkpoint))
+                        .copied(),
+                };
+                version.and_then(|version| self.object_at(&key.object_id, version))
+            })
+            .collect())
+    }
+}
+
+impl SetupStore for BundleStore {
+    fn setup(&self, _chain_id: Option<String>) -> Result<Option<String>, Error> {
+        Ok(self.chain_id.clone())
+    }
+}
+
+impl StoreSummary for BundleStore {
+    fn summary<W: std::io::Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "BundleStore summary")?;
+        writeln!(w, "  Node: {:?}", self.node)?;
+        writeln!(w, "  Transaction: {}", self.tx_digest)?;
+        writeln!(w, "  Contents:")?;
+        writeln!(w, "    Transactions: {} entries", self.transactions.len())?;
+        writeln!(w, "    Epochs: {} entries", self.epochs.len())?;
+        writeln!(
+            w,
+            "    Objects: {} versions",
+            self.objects.values().map(|v| v.len()).sum::<usize>()
+        )?;
+        writeln!(w, "    Root map: {} entries", self.root_versions.len())?;
+        writeln!(
+            w,
+            "    Checkpoint map: {} entries",
+            self.checkpoint_versions.len()
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EpochStoreWriter, ObjectStoreWriter, TransactionStoreWriter, stores::InMemoryStore};
+    use rtd_types::{
+        base_types::SequenceNumber, full_checkpoint_content::CheckpointData, object::Owner,
+        test_checkpoint_data_builder::TestCheckpointBuilder,
+    };
+
+    fn id(id: &str) -> ObjectID {
+        ObjectID::from_hex_literal(id).unwrap()
+    }
+
+    fn key(object_id: ObjectID, version_query: VersionQuery) -> ObjectKey {
+        ObjectKey {
+            object_id,
+            version_query,
+        }
+    }
+
+    fn object(object_id: ObjectID, version: u64) -> Object {
+        Object::with_id_owner_version_for_testing(
+            object_id,
+            SequenceNumber::from_u64(version),
+            Owner::Immutable,
+        )
+    }
+
+    /// A store holding what the replay of a transaction read: `0xa` at version 1 directly and at
+    /// version 3 as of checkpoint 10, and `0xb` at version 2 under root version 5.
+    fn recorded_store() -> (InMemoryStore, String, TransactionEffects) {
+        let store = InMemoryStore::new(Node::Testnet);
+        let mut builder = TestCheckpointBuilder::new(0)
+            .start_transaction(0)
+            .finish_transaction();
+        let tx = CheckpointData::from(builder.build_checkpoint())
+            .transactions
+            .remove(0);
+        let digest = tx.transaction.digest().to_string();
+        let info = TransactionInfo {
+            data: tx.transaction.transaction_data().clone(),
+            effects: tx.effects.clone(),
+            checkpoint: 7,
+        };
+        store.write_transaction(&digest, info).unwrap();
+        let epoch = EpochData {
+            epoch_id: 0,
+            protocol_version: ProtocolVersion::MAX.as_u64(),
+            rgp: 1000,
+            start_timestamp: 0,
+        };
+        store.write_epoch_info(0, epoch).unwrap();
+
+        let (a, b) = (id("0xa"), id("0xb"));
+        let writes = [
+            (key(a, VersionQuery::Version(1)), object(a, 1), 1),
+            (key(a, VersionQuery::AtCheckpoint(10)), object(a, 3), 3),
+            (key(b, VersionQuery::RootVersion(5)), object(b, 2), 2),
+        ];
+        for (key, object, version) in writes {
+            store.write_object(&key, object, version).unwrap();
+        }
+        (store, digest, tx.effects)
+    }
+
+    /// The ID and version of the objects `store` returns for a fixed set of keys.
+    fn lookups<S: ObjectStore>(store: &S) -> Vec<Option<(ObjectID, u64)>> {
+        let (a, b) = (id("0xa"), id("0xb"));
+        store
+            .get_objects(&[
+                key(a, VersionQuery::Version(1)),
+                key(a, VersionQuery::Version(3)),
+                key(a, VersionQuery::AtCheckpoint(10)),
+                key(b, VersionQuery::RootVersion(5)),
+                key(b, VersionQuery::Version(1)),
+                key(b, VersionQuery::RootVersion(6)),
+                key(a, VersionQuery::AtCheckpoint(11)),
+            ])
+            .unwrap()
+            .into_iter()
+            .map(|object| object.map(|(object, version)| (object.id(), version)))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (store, digest, effects) = recorded_store();
+        let bundle = store.export_bundle(&digest, Some("4c78adac".to_string()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundles").join("tx.bundle");
+        bundle.write(&path).unwrap();
+        let store = BundleStore::load(&path).unwrap();
+
+        assert_eq!(store.node().network_name(), "testnet");
+        assert_eq!(store.tx_digest(), digest);
+        assert_eq!(store.setup(None).unwrap(), Some("4c78adac".to_string()));
+
+        let info = store
+            .transaction_data_and_effects(&digest)
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.effects, effects);
+        assert_eq!(info.checkpoint, 7);
+        assert!(
+            store
+                .transaction_data_and_effects("other")
+                .unwrap()
+                .is_none()
+        );
+
+        let config = store.protocol_config(0).unwrap().unwrap();
+        assert_eq!(config.version, ProtocolVersion::MAX);
+        assert!(store.epoch_info(1).unwrap().is_none());
+
+        let (a, b) = (id("0xa"), id("0xb"));
+        assert_eq!(
+            lookups(&store),
+            vec![
+                Some((a, 1)),
+                Some((a, 3)),
+                Some((a, 3)),
+                Some((b, 2)),
+                None,
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_into() {
+        let (store, digest, _) = recorded_store();
+        let bundle = store.export_bundle(&digest, None);
+
+        let imported = InMemoryStore::new(Node::Testnet);
+        bundle.import_into(&imported).unwrap();
+        assert_eq!(lookups(&imported), lookups(&store));
+        assert!(
+            imported
+                .transaction_data_and_effects(&digest)
+                .unwrap()
+                .is_some()
+        );
+
+        // A mapping to a version that is not in the bundle cannot be imported.
+        let mut bundle = bundle;
+        bundle.root_versions[0].actual_version = 4;
+        let err = bundle
+            .import_into(&InMemoryStore::new(Node::Testnet))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("which it does not contain"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_read_rejects_other_formats() {
+        let (store, digest, _) = recorded_store();
+        let mut bundle = store.export_bundle(&digest, None);
+        bundle.format_version = BUNDLE_FORMAT_VERSION + 1;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tx.bundle");
+        bundle.write(&path).unwrap();
+        let err = ReplayBundle::read(&path).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Unsupported bundle format version"),
+            "{err}"
+        );
+        assert!(BundleStore::load(&path).is_err());
+
+        fs::write(&path, b"not a bundle").unwrap();
+        assert!(ReplayBundle::read(&path).is_err());
+    }
+}
@@ -27,6 +27,9 @@ use crate::{
     EpochData, EpochStore, EpochStoreWriter, ObjectKey, ObjectStore, ObjectStoreWriter, SetupStore,
     StoreSummary, TransactionInfo, TransactionStore, TransactionStoreWriter, VersionQuery,
     node::Node,
+    stores::bundle::{
+        BUNDLE_FORMAT_VERSION, BundleTransaction, BundleVersionMapping, ReplayBundle,
+    },
 };
 use anyhow::{Error, Result};
 use std::{
@@ -179,6 +182,55 @@ impl InMemoryStore {
     }
 }
 
+impl InMemoryStore {
+    /// Export the content of the store as a replay bundle for `tx_digest`.
+    ///
+    /// When the store was used as the primary of a `ReadThroughStore` for the replay of a
+    /// transaction, it holds exactly the data that replay read, so the bundle is self-contained.
+    pub fn export_bundle(&self, tx_digest: &str, chain_id: Option<String>) -> ReplayBundle {
+        let inner = self.0.read().unwrap();
+        let version_mappings = |map: &BTreeMap<(ObjectID, u64), u64>| {
+            map.iter()
+                .map(
+                    |((object_id, query), actual_version)| BundleVersionMapping {
+                        object_id: *object_id,
+                        query: *query,
+                        actual_version: *actual_version,
+                    },
+                )
+                .collect()
+        };
+        ReplayBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            tx_digest: tx_digest.to_string(),
+            network: inner.node.network_name(),
+            chain_id,
+            transactions: inner
+                .transaction_cache
+                .iter()
+                .map(|(digest, info)| BundleTransaction {
+                    digest: digest.clone(),
+                    data: info.data.clone(),
+                    effects: info.effects.clone(),
+                    checkpoint: info.checkpoint,
+                })
+                .collect(),
+            epochs: inner
+                .epoch_data_cache
+                .values()
+                .map(|epoch_data| epoch_data.clone().into())
+                .collect(),
+            objects: inner
+                .object_cache
+                .values()
+                .flat_map(|versions| versions.values().cloned())
+                .collect(),
+            root_versions: version_mappings(&inner.root_version_cache),
+            checkpoint_versions: version_mappings(&inner.checkpoint_cache),
+        }
+    }
+}
+
 /// Statistics about cache usage
 #[derive(Debug, Clone)]
 pub struct CacheStats {
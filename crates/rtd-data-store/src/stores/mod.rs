@@ -6,12 +6,17 @@
 //! This module provides various store implementations for caching and retrieving
 //! Rtd blockchain data.
 
+mod bundle;
 mod filesystem;
 mod graphql;
 mod in_memory;
 mod in_memory_lru;
 mod read_through;
 
+pub use bundle::{
+    BUNDLE_FORMAT_VERSION, BundleEpoch, BundleStore, BundleTransaction, BundleVersionMapping,
+    ReplayBundle,
+};
 pub use filesystem::{
     CHECKPOINT_VERSIONS_FILE, DATA_STORE_DIR, EPOCH_DIR, FileSystemStore, NODE_MAPPING_FILE,
     OBJECTS_DIR, ROOT_VERSIONS_FILE, TRANSACTION_DIR,
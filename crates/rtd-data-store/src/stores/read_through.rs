@@ -30,6 +30,11 @@ where
     pub fn new(primary: P, secondary: S) -> Self {
         Self { primary, secondary }
     }
+
+    /// The primary (cache) store
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
 }
 
 impl<P, S> TransactionStore for ReadThroughStore<P, S>
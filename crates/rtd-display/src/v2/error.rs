@@ -50,6 +50,9 @@ pub enum FormatError {
     #[error("BCS error: {0}")]
     Bcs(#[from] bcs::Error),
 
+    #[error("Invalid condition: {0}")]
+    ConditionInvalid(&'static str),
+
     #[error("Hex {0} contains invalid character")]
     InvalidHexCharacter(OwnedLexeme),
 
@@ -59,6 +62,9 @@ pub enum FormatError {
     #[error("Invalid {what}: {err}")]
     InvalidNumber { what: &'static str, err: String },
 
+    #[error("Invalid timestamp format {0}")]
+    InvalidTimestampFormat(OwnedLexeme),
+
     #[error("Odd number of characters in hex {0}")]
     OddHexLiteral(OwnedLexeme),
 
@@ -87,7 +87,8 @@ impl<'s, S: Store<'s>> Interpreter<'s, S> {
                         return Ok(serde_json::Value::Null);
                     };
 
-                    v.format(transform.unwrap_or_default(), &mut writer)?;
+                    let default = P::Transform::default();
+                    v.format(transform.as_ref().unwrap_or(&default), &mut writer)?;
                 }
             }
         }
@@ -95,18 +96,43 @@ impl<'s, S: Store<'s>> Interpreter<'s, S> {
         Ok(serde_json::Value::String(writer.finish()))
     }
 
-    /// Evaluate each `chain` in turn until one succeeds (produces a non-`None` value).
+    /// Evaluate each alternate in turn until one succeeds (produces a non-`None` value that is
+    /// not empty). Alternates guarded by a condition are skipped unless their condition evaluates
+    /// to `true`.
     ///
-    /// Returns the result from the first chain that produces a value, or `Ok(None)` if none do.
-    /// Propagates any errors encountered during evaluation.
-    async fn eval_alts(&self, alts: &'s [P::Chain<'s>]) -> Result<Option<Value<'s>>, FormatError> {
-        for chain in alts {
-            if let Some(v) = self.eval_chain(chain).await? {
-                return Ok(Some(v));
+    /// Returns the result from the first alternate that produces a non-empty value. If all the
+    /// alternates that produced a value produced an empty value, the first of those is returned,
+    /// and if none produced a value, returns `Ok(None)`. Propagates any errors encountered during
+    /// evaluation.
+    async fn eval_alts(
+        &self,
+        alts: &'s [P::Alternate<'s>],
+    ) -> Result<Option<Value<'s>>, FormatError> {
+        let mut empty = None;
+        for alt in alts {
+            if let Some(condition) = &alt.condition {
+                let Some(condition) = self.eval_chain(condition).await? else {
+                    continue;
+                };
+
+                if !condition
+                    .as_bool()
+                    .ok_or(FormatError::ConditionInvalid("expected bool"))?
+                {
+                    continue;
+                }
+            }
+
+            match self.eval_chain(&alt.value).await? {
+                Some(v) if v.is_empty() => {
+                    empty.get_or_insert(v);
+                }
+                Some(v) => return Ok(Some(v)),
+                None => {}
             }
         }
 
-        Ok(None)
+        Ok(empty)
     }
 
     /// Evaluate a chain of field accesses against a root expression.
@@ -72,6 +72,8 @@ pub(crate) enum Token {
     Pipe,
     /// '#'
     Pound,
+    /// '?'
+    Question,
     /// '>'
     RAngle,
     /// '}'
@@ -181,6 +183,8 @@ impl<'s> Lexer<'s> {
 
             b'#' => self.take(ws, T::Pound, 1),
 
+            b'?' => self.take(ws, T::Question, 1),
+
             b'>' => self.take(ws, T::RAngle, 1),
 
             b'}' => {
@@ -313,6 +317,7 @@ impl fmt::Display for OwnedLexeme {
             L(_, T::NumHex, _, s) => write!(f, "hexadecimal number {s:?}"),
             L(_, T::Pipe, _, _) => write!(f, "'|'"),
             L(_, T::Pound, _, _) => write!(f, "'#'"),
+            L(_, T::Question, _, _) => write!(f, "'?'"),
             L(_, T::RAngle, _, _) => write!(f, "'>'"),
             L(_, T::RBrace, _, _) => write!(f, "'}}'"),
             L(_, T::RBracket, _, _) => write!(f, "']'"),
@@ -362,6 +367,7 @@ impl fmt::Display for Token {
             T::NumHex => write!(f, "a hexadecimal number"),
             T::Pipe => write!(f, "'|'"),
             T::Pound => write!(f, "'#'"),
+            T::Question => write!(f, "'?'"),
             T::RAngle => write!(f, "'>'"),
             T::RBrace => write!(f, "'}}'"),
             T::RBracket => write!(f, "']'"),
@@ -576,6 +582,21 @@ mod tests {
         "###);
     }
 
+    /// Question marks separate a condition from the value it guards, within an alternate.
+    #[test]
+    fn test_conditions() {
+        assert_snapshot!(lexemes(r#"foo ? {bar ? baz | qux}"#), @r###"
+        L(false, Text, 0, "foo ? ")
+        L(false, LBrace, 6, "{")
+        L(false, Ident, 7, "bar")
+        L(true, Question, 11, "?")
+        L(true, Ident, 13, "baz")
+        L(true, Pipe, 17, "|")
+        L(true, Ident, 19, "qux")
+        L(false, RBrace, 22, "}")
+        "###);
+    }
+
     // Display supports three kinds of index -- `foo[i]`, `bar->[j]`, and `baz=>[k]`, representing
     // vector/VecMap, dynamic field, and dynamic object field access respectively.
     #[test]
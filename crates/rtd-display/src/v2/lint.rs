@@ -0,0 +1,718 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline checks for Display formats, against the layout of the type being displayed. These catch
+//! field accesses that cannot succeed and transforms that cannot apply for any object of that type,
+//! before the Display is published, rather than when an object is rendered.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use move_core_types::annotated_value::MoveTypeLayout;
+use move_core_types::language_storage::TypeTag;
+use rtd_types::base_types::move_ascii_str_layout;
+use rtd_types::base_types::move_utf8_str_layout;
+use rtd_types::base_types::url_layout;
+use rtd_types::id::ID;
+use rtd_types::id::UID;
+
+use super::Format;
+use super::error::FormatError;
+use super::parser::Accessor;
+use super::parser::Chain;
+use super::parser::Expr;
+use super::parser::Fields;
+use super::parser::Literal;
+use super::parser::Strand;
+use super::parser::Transform;
+use super::visitor::option::is_option;
+use super::visitor::vec_map::is_vec_map;
+
+/// A problem with one of the fields in a Display format.
+#[derive(Debug)]
+pub struct Lint {
+    /// The source of the name of the field that the problem was found in.
+    pub field: String,
+
+    /// Whether the problem was found in the field's name or its value.
+    pub part: Part,
+
+    /// The field access chain that the problem was found in, if it is specific to one.
+    pub chain: Option<String>,
+
+    pub problem: Problem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Name,
+    Value,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Problem {
+    #[error("Cannot access {accessor} on {type_}")]
+    AccessInvalid { accessor: String, type_: String },
+
+    #[error("Condition has type {type_}, expected bool")]
+    ConditionInvalid { type_: String },
+
+    #[error("Cannot access dynamic fields on {type_}, expected an object, UID, ID, or address")]
+    DynamicFieldInvalid { type_: String },
+
+    #[error("No field {field:?} in {type_}")]
+    FieldNotFound { field: String, type_: String },
+
+    #[error(transparent)]
+    Format(FormatError),
+
+    #[error("Duplicate name {0:?}")]
+    NameDuplicate(String),
+
+    #[error("Cannot apply '{transform}' transform to {type_}")]
+    TransformInvalid {
+        transform: &'static str,
+        type_: String,
+    },
+}
+
+/// Statically checks the strands of a single format string against the layout of the root object.
+struct Linter<'l> {
+    root: &'l MoveTypeLayout,
+    problems: Vec<(Option<String>, Problem)>,
+}
+
+impl Format<'_> {
+    /// Check this format against the `layout` of the type it displays, without rendering any
+    /// objects, returning every problem found. An empty result means that every field parsed, and
+    /// that every field access and transform in it is consistent with `layout`.
+    ///
+    /// Accesses that go through dynamic fields, or start from a literal, are not checked beyond
+    /// that point, because their types depend on the objects being displayed. Metering limits that
+    /// apply to the display as a whole are checked by [Format::parse].
+    pub fn lint(&self, layout: &MoveTypeLayout) -> Vec<Lint> {
+        let mut lints = vec![];
+        let mut names = BTreeSet::new();
+
+        for field in &self.fields {
+            let src = field.key.src;
+            for (part, sourced) in [(Part::Name, &field.key), (Part::Value, &field.val)] {
+                let problems = match &sourced.val {
+                    Err(e) => vec![(None, Problem::Format(e.clone()))],
+                    Ok(strands) => {
+                        let mut linter = Linter::new(layout);
+                        linter.strands(strands);
+                        linter.problems
+                    }
+                };
+
+                lints.extend(problems.into_iter().map(|(chain, problem)| Lint {
+                    field: src.to_owned(),
+                    part,
+                    chain,
+                    problem,
+                }));
+            }
+
+            // Names that only contain text are known up-front, so duplicates can be detected
+            // without rendering anything.
+            if let Ok(strands) = &field.key.val
+                && let Some(name) = text(strands)
+                && !names.insert(name.clone())
+            {
+                lints.push(Lint {
+                    field: src.to_owned(),
+                    part: Part::Name,
+                    chain: None,
+                    problem: Problem::NameDuplicate(name),
+                });
+            }
+        }
+
+        lints
+    }
+}
+
+impl<'l> Linter<'l> {
+    fn new(root: &'l MoveTypeLayout) -> Self {
+        Self {
+            root,
+            problems: vec![],
+        }
+    }
+
+    fn strands(&mut self, strands: &[Strand<'_>]) {
+        // A JSON transform is only supported if it is the only strand in the format string.
+        let multiple = strands.len() > 1;
+        let default = Transform::default();
+
+        for strand in strands {
+            let Strand::Expr(Expr {
+                alternates,
+                transform,
+            }) = strand
+            else {
+                continue;
+            };
+
+            let transform = transform.as_ref().unwrap_or(&default);
+            if multiple && *transform == Transform::Json {
+                self.problems.push((
+                    None,
+                    Problem::Format(FormatError::TransformInvalid("unexpected 'json' in string")),
+                ));
+            }
+
+            for alt in alternates {
+                if let Some(condition) = &alt.condition
+                    && let Some(layout) = self.chain(condition)
+                    && *layout != MoveTypeLayout::Bool
+                {
+                    self.report(
+                        condition,
+                        Problem::ConditionInvalid {
+                            type_: type_name(layout),
+                        },
+                    );
+                }
+
+                if let Some(layout) = self.chain(&alt.value)
+                    && !supports(transform, layout)
+                {
+                    self.report(
+                        &alt.value,
+                        Problem::TransformInvalid {
+                            transform: transform_name(transform),
+                            type_: type_name(layout),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Find the layout of the value that `chain` evaluates to, reporting any accesses that cannot
+    /// succeed along the way. Returns `None` if the layout is not known statically (because the
+    /// chain goes through a literal or a dynamic field), or if a problem was reported.
+    fn chain(&mut self, chain: &Chain<'_>) -> Option<&'l MoveTypeLayout> {
+        use Accessor as A;
+        use MoveTypeLayout as L;
+
+        // Chains nested inside accessors and literals are evaluated against the root object,
+        // regardless of what happens to this chain.
+        for accessor in &chain.accessors {
+            if let A::Index(c) | A::DFIndex(c) | A::DOFIndex(c) = accessor {
+                self.chain(c);
+            }
+        }
+
+        if let Some(literal) = &chain.root {
+            self.literal(literal);
+            return None;
+        }
+
+        let mut layout = self.root;
+        for accessor in &chain.accessors {
+            layout = match (layout, accessor) {
+                (L::Address, A::DFIndex(_) | A::DOFIndex(_)) => return None,
+
+                (L::Struct(s), A::DFIndex(_) | A::DOFIndex(_)) => {
+                    let uid = UID::layout();
+                    let has_id = s.as_ref() == &uid
+                        || s.as_ref() == &ID::layout()
+                        || s.fields.first().is_some_and(|f| {
+                            f.name.as_str() == "id"
+                                && matches!(&f.layout, L::Struct(id) if id.as_ref() == &uid)
+                        });
+
+                    if has_id {
+                        return None;
+                    }
+
+                    self.report(
+                        chain,
+                        Problem::DynamicFieldInvalid {
+                            type_: type_name(layout),
+                        },
+                    );
+                    return None;
+                }
+
+                (_, A::DFIndex(_) | A::DOFIndex(_)) => {
+                    self.report(
+                        chain,
+                        Problem::DynamicFieldInvalid {
+                            type_: type_name(layout),
+                        },
+                    );
+                    return None;
+                }
+
+                (L::Vector(element), A::Index(_)) => element.as_ref(),
+
+                // VecMaps are indexed by key, to get the value of the corresponding entry.
+                (L::Struct(s), A::Index(_)) if is_vec_map(&s.type_) => {
+                    let value = s
+                        .fields
+                        .first()
+                        .filter(|f| f.name.as_str() == "contents")
+                        .and_then(|f| match &f.layout {
+                            L::Vector(entry) => Some(entry.as_ref()),
+                            _ => None,
+                        })
+                        .and_then(|entry| match entry {
+                            L::Struct(e) => e.fields.iter().find(|f| f.name.as_str() == "value"),
+                            _ => None,
+                        });
+
+                    // The layout of the VecMap itself is malformed, so it is not possible to
+                    // index into it.
+                    let Some(value) = value else {
+                        self.invalid_access(chain, accessor, layout);
+                        return None;
+                    };
+
+                    &value.layout
+                }
+
+                (L::Struct(s), A::Field(_) | A::Positional(_)) => {
+                    let name = field_name(accessor);
+                    match s.fields.iter().find(|f| f.name.as_str() == name) {
+                        Some(f) => &f.layout,
+                        None => {
+                            self.report(
+                                chain,
+                                Problem::FieldNotFound {
+                                    field: name,
+                                    type_: type_name(layout),
+                                },
+                            );
+                            return None;
+                        }
+                    }
+                }
+
+                // The variant is only known at runtime, so accept a field from any variant.
+                (L::Enum(e), A::Field(_) | A::Positional(_)) => {
+                    let name = field_name(accessor);
+                    let field = e
+                        .variants
+                        .values()
+                        .flatten()
+                        .find(|f| f.name.as_str() == name);
+
+                    match field {
+                        Some(f) => &f.layout,
+                        None => {
+                            self.report(
+                                chain,
+                                Problem::FieldNotFound {
+                                    field: name,
+                                    type_: type_name(layout),
+                                },
+                            );
+                            return None;
+                        }
+                    }
+                }
+
+                (_, _) => {
+                    self.invalid_access(chain, accessor, layout);
+                    return None;
+                }
+            };
+        }
+
+        // Optional values are unwrapped at the end of a chain.
+        if let L::Struct(s) = layout
+            && is_option(s)
+            && let Some(L::Vector(inner)) = s.fields.first().map(|f| &f.layout)
+        {
+            layout = inner.as_ref();
+        }
+
+        Some(layout)
+    }
+
+    /// Check the chains nested inside a literal.
+    fn literal(&mut self, literal: &Literal<'_>) {
+        let fields = match literal {
+            Literal::Vector(v) => {
+                for element in &v.elements {
+                    self.chain(element);
+                }
+                return;
+            }
+
+            Literal::Struct(s) => &s.fields,
+            Literal::Enum(e) => &e.fields,
+
+            Literal::Address(_)
+            | Literal::Bool(_)
+            | Literal::U8(_)
+            | Literal::U16(_)
+            | Literal::U32(_)
+            | Literal::U64(_)
+            | Literal::U128(_)
+            | Literal::U256(_)
+            | Literal::ByteArray(_)
+            | Literal::String(_) => return,
+        };
+
+        match fields {
+            Fields::Positional(chains) => {
+                for chain in chains {
+                    self.chain(chain);
+                }
+            }
+
+            Fields::Named(fields) => {
+                for (_, chain) in fields {
+                    self.chain(chain);
+                }
+            }
+        }
+    }
+
+    fn invalid_access(
+        &mut self,
+        chain: &Chain<'_>,
+        accessor: &Accessor<'_>,
+        layout: &MoveTypeLayout,
+    ) {
+        let accessor = match accessor {
+            Accessor::Field(name) => format!(".{name}"),
+            Accessor::Positional(index) => format!(".{index}"),
+            Accessor::Index(c) => format!("[{c:?}]"),
+            Accessor::DFIndex(c) => format!("->[{c:?}]"),
+            Accessor::DOFIndex(c) => format!("=>[{c:?}]"),
+        };
+
+        self.report(
+            chain,
+            Problem::AccessInvalid {
+                accessor,
+                type_: type_name(layout),
+            },
+        );
+    }
+
+    fn report(&mut self, chain: &Chain<'_>, problem: Problem) {
+        self.problems.push((Some(format!("{chain:?}")), problem));
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.part {
+            Part::Name => write!(f, "name {:?}", self.field)?,
+            Part::Value => write!(f, "value of {:?}", self.field)?,
+        }
+
+        if let Some(chain) = &self.chain {
+            write!(f, ", in {{{chain}}}")?;
+        }
+
+        write!(f, ": {}", self.problem)
+    }
+}
+
+/// Whether values with this layout can be formatted using `transform`. This mirrors the coercion
+/// of values into atoms that happens during rendering.
+fn supports(transform: &Transform<'_>, layout: &MoveTypeLayout) -> bool {
+    use MoveTypeLayout as L;
+
+    match transform {
+        Transform::Bcs(_) | Transform::Json => true,
+
+        Transform::Decimal(_) | Transform::Timestamp(_) => {
+            matches!(layout, L::U8 | L::U16 | L::U32 | L::U64 | L::U128 | L::U256)
+        }
+
+        Transform::Base64(_)
+        | Transform::Hex
+        | Transform::Str
+        | Transform::Truncate { .. }
+        | Transform::Url => match layout {
+            L::Address | L::Bool | L::U8 | L::U16 | L::U32 | L::U64 | L::U128 | L::U256 => true,
+            L::Vector(element) => element.as_ref() == &L::U8,
+            L::Struct(s) => [
+                move_ascii_str_layout(),
+                move_utf8_str_layout(),
+                url_layout(),
+                UID::layout(),
+                ID::layout(),
+            ]
+            .contains(s.as_ref()),
+            L::Signer | L::Enum(_) => false,
+        },
+    }
+}
+
+fn transform_name(transform: &Transform<'_>) -> &'static str {
+    match transform {
+        Transform::Base64(_) => "base64",
+        Transform::Bcs(_) => "bcs",
+        Transform::Decimal(_) => "dec",
+        Transform::Hex => "hex",
+        Transform::Json => "json",
+        Transform::Str => "str",
+        Transform::Timestamp(_) => "ts",
+        Transform::Truncate { .. } => "trunc",
+        Transform::Url => "url",
+    }
+}
+
+/// The name of the struct or enum field that `accessor` refers to (positional fields are named
+/// `pos0`, `pos1`, etc.).
+fn field_name(accessor: &Accessor<'_>) -> String {
+    match accessor {
+        Accessor::Field(name) => name.to_string(),
+        Accessor::Positional(index) => format!("pos{index}"),
+        Accessor::Index(_) | Accessor::DFIndex(_) | Accessor::DOFIndex(_) => String::new(),
+    }
+}
+
+fn type_name(layout: &MoveTypeLayout) -> String {
+    TypeTag::from(layout).to_canonical_string(true)
+}
+
+/// The contents of a format string, if it only contains text.
+fn text(strands: &[Strand<'_>]) -> Option<String> {
+    strands
+        .iter()
+        .map(|s| match s {
+            Strand::Text(t) => Some(t.as_ref()),
+            Strand::Expr(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use move_core_types::annotated_value::MoveTypeLayout as T;
+    use rtd_types::collection_types::Entry;
+    use rtd_types::collection_types::VecMap;
+
+    use crate::v2::meter::Limits;
+    use crate::v2::value::tests::enum_;
+    use crate::v2::value::tests::optional_;
+    use crate::v2::value::tests::struct_;
+    use crate::v2::value::tests::vector_;
+
+    use super::*;
+
+    /// Helper to parse display fields and lint them against `layout`.
+    fn lint(layout: &MoveTypeLayout, fields: &[(&str, &str)]) -> Vec<Lint> {
+        let display = VecMap {
+            contents: fields
+                .iter()
+                .map(|(key, value)| Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        };
+
+        Format::parse(Limits::default(), &display)
+            .unwrap()
+            .lint(layout)
+    }
+
+    /// Where each lint was found: its field, part, and chain.
+    fn locations(lints: &[Lint]) -> Vec<(&str, Part, Option<&str>)> {
+        lints
+            .iter()
+            .map(|l| (l.field.as_str(), l.part, l.chain.as_deref()))
+            .collect()
+    }
+
+    fn nft() -> MoveTypeLayout {
+        struct_(
+            "0x1::nft::NFT",
+            vec![
+                ("id", T::Struct(Box::new(UID::layout()))),
+                ("name", T::Struct(Box::new(move_utf8_str_layout()))),
+                (
+                    "tags",
+                    vector_(T::Struct(Box::new(move_ascii_str_layout()))),
+                ),
+                ("level", T::U64),
+                ("shiny", T::Bool),
+                ("image", optional_(T::Struct(Box::new(url_layout())))),
+                (
+                    "attrs",
+                    struct_(
+                        "0x2::vec_map::VecMap<u8, 0x1::string::String>",
+                        vec![(
+                            "contents",
+                            vector_(struct_(
+                                "0x2::vec_map::Entry<u8, 0x1::string::String>",
+                                vec![
+                                    ("key", T::U8),
+                                    ("value", T::Struct(Box::new(move_utf8_str_layout()))),
+                                ],
+                            )),
+                        )],
+                    ),
+                ),
+                (
+                    "rarity",
+                    enum_(
+                        "0x1::nft::Rarity",
+                        vec![
+                            ("Common", vec![]),
+                            ("Rare", vec![("pos0", T::U8), ("since", T::U64)]),
+                        ],
+                    ),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_valid() {
+        let lints = lint(
+            &nft(),
+            &[
+                ("name", "{name} #{level}"),
+                ("tag", "{tags[0u64]}"),
+                ("image", "{image:url}"),
+                ("attr", "{attrs[1u8] | 'none'}"),
+                ("rarity", "{rarity.0}, since {rarity.since:ts}"),
+                ("level", "{shiny ? level:dec(2) | 'unknown'}"),
+                ("json", "{rarity:json}"),
+                ("df", "{id->['key'].whatever}"),
+                ("lit", "{@0x42=>[name].whatever:hex}"),
+            ],
+        );
+
+        assert!(lints.is_empty(), "{lints:#?}");
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let lints = lint(
+            &nft(),
+            &[
+                ("name", "{nmae}"),
+                ("{name.bytes}", "{level.value}"),
+                ("tag", "{tags.first}"),
+                ("attr", "{attrs[name.missing]}"),
+                ("rarity", "{rarity.1}"),
+                ("df", "{level->[1u8]}"),
+            ],
+        );
+
+        assert_eq!(
+            locations(&lints),
+            vec![
+                ("name", Part::Value, Some("nmae")),
+                ("{name.bytes}", Part::Value, Some("level.value")),
+                ("tag", Part::Value, Some("tags.first")),
+                ("attr", Part::Value, Some("name.missing")),
+                ("rarity", Part::Value, Some("rarity.1")),
+                ("df", Part::Value, Some("level->[1u8]")),
+            ]
+        );
+
+        let [nmae, value, first, missing, pos1, df] = &lints[..] else {
+            panic!("{lints:#?}");
+        };
+
+        assert!(matches!(&nmae.problem, Problem::FieldNotFound { field, .. } if field == "nmae"));
+        assert!(matches!(
+            &value.problem,
+            Problem::AccessInvalid { accessor, type_ } if accessor == ".value" && type_ == "u64"
+        ));
+        assert!(matches!(
+            &first.problem,
+            Problem::AccessInvalid { accessor, .. } if accessor == ".first"
+        ));
+        assert!(matches!(
+            &missing.problem,
+            Problem::FieldNotFound { field, .. } if field == "missing"
+        ));
+        assert!(matches!(&pos1.problem, Problem::FieldNotFound { field, .. } if field == "pos1"));
+        assert!(matches!(
+            &df.problem,
+            Problem::DynamicFieldInvalid { type_ } if type_ == "u64"
+        ));
+    }
+
+    #[test]
+    fn test_conditions_and_transforms() {
+        let lints = lint(
+            &nft(),
+            &[
+                ("cond", "{level ? name}"),
+                ("dec", "{name:dec(2)}"),
+                ("str", "{attrs}"),
+                ("ts", "{shiny:ts}"),
+                ("json", "name: {name:json}"),
+            ],
+        );
+
+        assert_eq!(
+            locations(&lints),
+            vec![
+                ("cond", Part::Value, Some("level")),
+                ("dec", Part::Value, Some("name")),
+                ("str", Part::Value, Some("attrs")),
+                ("ts", Part::Value, Some("shiny")),
+                ("json", Part::Value, None),
+            ]
+        );
+
+        let [cond, dec, str_, ts, json] = &lints[..] else {
+            panic!("{lints:#?}");
+        };
+
+        assert!(matches!(&cond.problem, Problem::ConditionInvalid { type_ } if type_ == "u64"));
+        assert!(matches!(
+            &dec.problem,
+            Problem::TransformInvalid {
+                transform: "dec",
+                ..
+            }
+        ));
+        assert!(matches!(
+            &str_.problem,
+            Problem::TransformInvalid {
+                transform: "str",
+                ..
+            }
+        ));
+        assert!(matches!(
+            &ts.problem,
+            Problem::TransformInvalid { transform: "ts", type_ } if type_ == "bool"
+        ));
+        assert!(matches!(
+            &json.problem,
+            Problem::Format(FormatError::TransformInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors_and_duplicates() {
+        let lints = lint(
+            &nft(),
+            &[
+                ("name", "{name"),
+                ("name", "{level}"),
+                ("{name}", "{level}"),
+            ],
+        );
+
+        assert_eq!(
+            locations(&lints),
+            vec![("name", Part::Value, None), ("name", Part::Name, None)]
+        );
+
+        assert!(matches!(
+            &lints[0].problem,
+            Problem::Format(FormatError::UnexpectedEos { .. })
+        ));
+        assert!(matches!(&lints[1].problem, Problem::NameDuplicate(n) if n == "name"));
+    }
+}
@@ -23,6 +23,7 @@ use self::value::Store;
 pub mod error;
 pub(crate) mod interpreter;
 pub mod lexer;
+pub mod lint;
 pub mod meter;
 pub(crate) mod parser;
 pub(crate) mod peek;
@@ -723,6 +724,114 @@ mod tests {
         "###);
     }
 
+    #[tokio::test]
+    async fn test_alternate_empty() {
+        let bytes = bcs::to_bytes(&("", "name", Vec::<u64>::new())).unwrap();
+        let layout = struct_(
+            "0x1::m::S",
+            vec![
+                ("empty", T::Struct(Box::new(move_utf8_str_layout()))),
+                ("name", T::Struct(Box::new(move_utf8_str_layout()))),
+                ("nums", vector_(T::U64)),
+            ],
+        );
+
+        let formats = [
+            ("skipped", "{empty | name}"),
+            ("fallback", "{empty | 'default'}"),
+            ("vector", "{nums | 'none'}"),
+            ("all_empty", "{empty | ''}"),
+            ("only", "{empty}"),
+        ];
+
+        let output = format(
+            &MockStore::default(),
+            Limits::default(),
+            &bytes,
+            &layout,
+            usize::MAX,
+            ONE_MB,
+            formats,
+        )
+        .await
+        .unwrap();
+
+        assert_debug_snapshot!(output, @r###"
+        {
+            "skipped": Ok(
+                String("name"),
+            ),
+            "fallback": Ok(
+                String("default"),
+            ),
+            "vector": Ok(
+                String("none"),
+            ),
+            "all_empty": Ok(
+                String(""),
+            ),
+            "only": Ok(
+                String(""),
+            ),
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_conditions() {
+        let bytes = bcs::to_bytes(&("name", true, false)).unwrap();
+        let layout = struct_(
+            "0x1::m::S",
+            vec![
+                ("name", T::Struct(Box::new(move_utf8_str_layout()))),
+                ("yes", T::Bool),
+                ("no", T::Bool),
+            ],
+        );
+
+        let formats = [
+            ("true", "{yes ? name | 'hidden'}"),
+            ("false", "{no ? name | 'hidden'}"),
+            ("missing", "{maybe ? name | 'hidden'}"),
+            ("literal", "{false ? name}"),
+            ("invalid", "{name ? name}"),
+        ];
+
+        let output = format(
+            &MockStore::default(),
+            Limits::default(),
+            &bytes,
+            &layout,
+            usize::MAX,
+            ONE_MB,
+            formats,
+        )
+        .await
+        .unwrap();
+
+        assert_debug_snapshot!(output, @r###"
+        {
+            "true": Ok(
+                String("name"),
+            ),
+            "false": Ok(
+                String("hidden"),
+            ),
+            "missing": Ok(
+                String("hidden"),
+            ),
+            "literal": Ok(
+                Null,
+            ),
+            "invalid": Err(
+                ConditionInvalid(
+                    "expected bool",
+                ),
+            ),
+        }
+        "###);
+    }
+
     #[tokio::test]
     async fn test_dynamic_fields() {
         let parent = AccountAddress::from_str("0x1000").unwrap();
@@ -1065,6 +1174,172 @@ mod tests {
         "###);
     }
 
+    #[tokio::test]
+    async fn test_timestamp_format() {
+        let bytes = bcs::to_bytes(&1681318800000u64).unwrap();
+        let layout = struct_("0x1::m::S", vec![("timestamp", T::U64)]);
+
+        let formats = [
+            ("date", "{timestamp:ts('%Y-%m-%d')}"),
+            ("datetime", "{timestamp:ts('%b %e, %Y %H:%M UTC')}"),
+            ("seconds", "{timestamp:ts('%s')}"),
+            ("toobig", "{1681318800000000000u128:ts('%Y')}"),
+        ];
+
+        let output = format(
+            &MockStore::default(),
+            Limits::default(),
+            &bytes,
+            &layout,
+            usize::MAX,
+            ONE_MB,
+            formats,
+        )
+        .await
+        .unwrap();
+
+        assert_debug_snapshot!(output, @r###"
+        {
+            "date": Ok(
+                String("2023-04-12"),
+            ),
+            "datetime": Ok(
+                String("Apr 12, 2023 17:00 UTC"),
+            ),
+            "seconds": Ok(
+                String("1681318800"),
+            ),
+            "toobig": Err(
+                TransformInvalid(
+                    "expected unix timestamp in milliseconds",
+                ),
+            ),
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_decimal() {
+        let bytes = bcs::to_bytes(&(1_234_567_890_123u64, 5u8, 0u64)).unwrap();
+        let layout = struct_(
+            "0x1::m::S",
+            vec![("balance", T::U64), ("small", T::U8), ("zero", T::U64)],
+        );
+
+        let formats = [
+            ("full", "{balance:dec(9)}"),
+            ("precision", "{balance:dec(9, 2)}"),
+            ("padded", "{small:dec(2, 4)}"),
+            ("separated", "{balance:dec(3, sep)}"),
+            ("integer", "{balance:sep}"),
+            ("zero", "{zero:dec(9)}"),
+            ("literal", "{1000000000u64:dec(9, 1)}"),
+            (
+                "large",
+                "{123456789012345678901234567890u256:dec(18, 0, sep)}",
+            ),
+            ("invalid", "{'foo':dec(9)}"),
+        ];
+
+        let output = format(
+            &MockStore::default(),
+            Limits::default(),
+            &bytes,
+            &layout,
+            usize::MAX,
+            ONE_MB,
+            formats,
+        )
+        .await
+        .unwrap();
+
+        assert_debug_snapshot!(output, @r###"
+        {
+            "full": Ok(
+                String("1234.567890123"),
+            ),
+            "precision": Ok(
+                String("1234.56"),
+            ),
+            "padded": Ok(
+                String("0.0500"),
+            ),
+            "separated": Ok(
+                String("1,234,567,890.123"),
+            ),
+            "integer": Ok(
+                String("1,234,567,890,123"),
+            ),
+            "zero": Ok(
+                String("0"),
+            ),
+            "literal": Ok(
+                String("1.0"),
+            ),
+            "large": Ok(
+                String("123,456,789,012"),
+            ),
+            "invalid": Err(
+                TransformInvalid(
+                    "expected unsigned integer",
+                ),
+            ),
+        }
+        "###);
+    }
+
+    #[tokio::test]
+    async fn test_truncate() {
+        let bytes = bcs::to_bytes(&("Hello, World!", "héllo wörld")).unwrap();
+        let layout = struct_(
+            "0x1::m::S",
+            vec![
+                ("greeting", T::Struct(Box::new(move_utf8_str_layout()))),
+                ("accented", T::Struct(Box::new(move_utf8_str_layout()))),
+            ],
+        );
+
+        let formats = [
+            ("short", "{greeting:trunc(5)}"),
+            ("suffix", "{greeting:trunc(5, '...')}"),
+            ("exact", "{greeting:trunc(13, '...')}"),
+            ("unicode", "{accented:trunc(4, '…')}"),
+            ("number", "{12345u64:trunc(3)}"),
+        ];
+
+        let output = format(
+            &MockStore::default(),
+            Limits::default(),
+            &bytes,
+            &layout,
+            usize::MAX,
+            ONE_MB,
+            formats,
+        )
+        .await
+        .unwrap();
+
+        assert_debug_snapshot!(output, @r###"
+        {
+            "short": Ok(
+                String("Hello"),
+            ),
+            "suffix": Ok(
+                String("Hello..."),
+            ),
+            "exact": Ok(
+                String("Hello, World!"),
+            ),
+            "unicode": Ok(
+                String("héll…"),
+            ),
+            "number": Ok(
+                String("123"),
+            ),
+        }
+        "###);
+    }
+
     #[tokio::test]
     async fn test_hex() {
         let bytes = bcs::to_bytes(&(
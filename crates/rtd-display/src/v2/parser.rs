@@ -10,6 +10,8 @@ use base64::engine::general_purpose::STANDARD;
 use base64::engine::general_purpose::STANDARD_NO_PAD;
 use base64::engine::general_purpose::URL_SAFE;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::format::Item;
+use chrono::format::StrftimeItems;
 use move_core_types::account_address::AccountAddress;
 use move_core_types::identifier::IdentStr;
 use move_core_types::identifier::Identifier;
@@ -40,12 +42,20 @@ pub enum Strand<'s> {
 }
 
 /// Expressions are composed of a number of alternates and an optional transform. During
-/// evaluation, each alternate is evaluated in turn until the first one succeeds, and if a
-/// transform is provided, it is applied to the result to convert it to a string.
+/// evaluation, each alternate is evaluated in turn until the first one succeeds with a non-empty
+/// value, and if a transform is provided, it is applied to the result to convert it to a string.
 #[derive(PartialEq, Eq)]
 pub struct Expr<'s> {
-    pub(crate) alternates: Vec<Chain<'s>>,
-    pub(crate) transform: Option<Transform>,
+    pub(crate) alternates: Vec<Alternate<'s>>,
+    pub(crate) transform: Option<Transform<'s>>,
+}
+
+/// A single alternate in an expression, optionally guarded by a condition. If the condition is
+/// provided, the alternate only succeeds if the condition evaluates to `true`.
+#[derive(PartialEq, Eq)]
+pub struct Alternate<'s> {
+    pub(crate) condition: Option<Chain<'s>>,
+    pub(crate) value: Chain<'s>,
 }
 
 /// Chains are a sequence of nested field accesses.
@@ -131,21 +141,44 @@ pub enum Fields<'s> {
 }
 
 /// Ways to modify a value before displaying it.
-#[derive(Default, Copy, Clone, PartialEq, Eq)]
-pub enum Transform {
+#[derive(Default, Clone, PartialEq, Eq)]
+pub enum Transform<'s> {
     Base64(Base64Modifier),
     Bcs(Base64Modifier),
+    Decimal(DecimalFormat),
     Hex,
     Json,
     #[default]
     Str,
-    Timestamp,
+    /// Timestamps are formatted as ISO8601 by default, or using a custom `strftime`-style format
+    /// string, which is validated during parsing.
+    Timestamp(Option<Cow<'s, str>>),
+    /// Truncate the string representation of the value to at most `len` characters, followed by
+    /// `suffix` if any characters were removed.
+    Truncate {
+        len: u32,
+        suffix: Option<Cow<'s, str>>,
+    },
     Url,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct Base64Modifier(u8);
 
+/// Format an unsigned integer as a fixed-point decimal number.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct DecimalFormat {
+    /// The number of digits in the integer that come after the decimal point.
+    pub(crate) decimals: u8,
+
+    /// The exact number of fractional digits to output. Excess digits are truncated, and missing
+    /// digits are padded with zeroes. If not provided, trailing zeroes are trimmed.
+    pub(crate) precision: Option<u8>,
+
+    /// Whether to separate thousands in the integer part with commas.
+    pub(crate) separators: bool,
+}
+
 pub(crate) struct Parser<'s> {
     lexer: Peekable2<Lexer<'s>>,
 }
@@ -227,7 +260,9 @@ macro_rules! match_token_opt {
 ///
 ///   part     ::= TEXT | '{{' | '}}'
 ///
-///   expr     ::= '{' chain ('|' chain)* (':' xform)? '}'
+///   expr     ::= '{' alt ('|' alt)* (':' xform)? '}'
+///
+///   alt      ::= chain ('?' chain)?
 ///
 ///   chain    ::= (literal | IDENT) accessor*
 ///
@@ -271,8 +306,11 @@ macro_rules! match_token_opt {
 ///              | 'hex'
 ///              | 'base64' xmod?
 ///              | 'bcs' xmod?
+///              | 'dec' '(' NUM_DEC (',' NUM_DEC)? (',' 'sep')? ','? ')'
+///              | 'sep'
 ///              | 'json'
-///              | 'timestamp'
+///              | 'ts' ('(' STRING ')')?
+///              | 'trunc' '(' NUM_DEC (',' STRING)? ','? ')'
 ///              | 'url'
 ///
 ///  xmod      ::= '(' b64mod (',' b64mod)* ','? )'
@@ -332,7 +370,7 @@ impl<'s> Parser<'s> {
 
     fn parse_expr<'b>(&mut self, meter: &mut Meter<'b>) -> Result<Expr<'s>, FormatError> {
         match_token! { self.lexer; Tok(_, T::LBrace, _, _) => self.lexer.next() };
-        let mut alternates = vec![self.parse_chain(meter)?];
+        let mut alternates = vec![self.parse_alternate(meter)?];
         let mut transform = None;
 
         loop {
@@ -344,7 +382,7 @@ impl<'s> Parser<'s> {
 
                 Tok(_, T::Colon, _, _) => {
                     self.lexer.next();
-                    transform = Some(self.parse_xform()?);
+                    transform = Some(self.parse_xform(meter)?);
                     match_token! { self.lexer; Tok(_, T::RBrace, _, _) => {
                         self.lexer.next()
                     }};
@@ -353,7 +391,7 @@ impl<'s> Parser<'s> {
 
                 Tok(_, T::Pipe, _, _) => {
                     self.lexer.next();
-                    alternates.push(self.parse_chain(meter)?);
+                    alternates.push(self.parse_alternate(meter)?);
                 }
             }
         }
@@ -365,6 +403,26 @@ impl<'s> Parser<'s> {
         })
     }
 
+    fn parse_alternate<'b>(&mut self, meter: &mut Meter<'b>) -> Result<Alternate<'s>, FormatError> {
+        let chain = self.parse_chain(meter)?;
+
+        let guarded = match_token_opt! { self.lexer;
+            Tok(_, T::Question, _, _) => { self.lexer.next(); }
+        };
+
+        Ok(match guarded {
+            Match::Found(_) => Alternate {
+                condition: Some(chain),
+                value: self.parse_chain(meter)?,
+            },
+
+            Match::Tried(_, _) => Alternate {
+                condition: None,
+                value: chain,
+            },
+        })
+    }
+
     fn parse_chain<'b>(&mut self, meter: &mut Meter<'b>) -> Result<Chain<'s>, FormatError> {
         let meter = &mut meter.nest()?;
         let mut accessors = vec![];
@@ -932,7 +990,7 @@ impl<'s> Parser<'s> {
         })
     }
 
-    fn parse_xform(&mut self) -> Result<Transform, FormatError> {
+    fn parse_xform<'b>(&mut self, meter: &mut Meter<'b>) -> Result<Transform<'s>, FormatError> {
         Ok(match_token! { self.lexer;
             Lit(_, T::Ident, _, "base64") => {
                 self.lexer.next();
@@ -944,6 +1002,20 @@ impl<'s> Parser<'s> {
                 Transform::Bcs(self.parse_xmod()?)
             },
 
+            Lit(_, T::Ident, _, "dec") => {
+                self.lexer.next();
+                Transform::Decimal(self.parse_decimal_format()?)
+            },
+
+            Lit(_, T::Ident, _, "sep") => {
+                self.lexer.next();
+                Transform::Decimal(DecimalFormat {
+                    decimals: 0,
+                    precision: None,
+                    separators: true,
+                })
+            },
+
             Lit(_, T::Ident, _, "hex") => {
                 self.lexer.next();
                 Transform::Hex
@@ -961,7 +1033,12 @@ impl<'s> Parser<'s> {
 
             Lit(_, T::Ident, _, "ts") => {
                 self.lexer.next();
-                Transform::Timestamp
+                Transform::Timestamp(self.parse_timestamp_format(meter)?)
+            },
+
+            Lit(_, T::Ident, _, "trunc") => {
+                self.lexer.next();
+                self.parse_truncate(meter)?
             },
 
             Lit(_, T::Ident, _, "url") => {
@@ -971,6 +1048,114 @@ impl<'s> Parser<'s> {
         })
     }
 
+    fn parse_decimal_format(&mut self) -> Result<DecimalFormat, FormatError> {
+        match_token! { self.lexer; Tok(_, T::LParen, _, _) => self.lexer.next() };
+        let mut format = match_token! { self.lexer; Tok(_, T::NumDec, _, n) => {
+            self.lexer.next();
+            DecimalFormat {
+                decimals: read_u8(n, 10, "decimal places")?,
+                precision: None,
+                separators: false,
+            }
+        }};
+
+        loop {
+            let delimited = match_token_opt! { self.lexer;
+                Tok(_, T::Comma, _, _) => { self.lexer.next(); }
+            };
+
+            let terminated = match_token_opt! { self.lexer;
+                Tok(_, T::RParen, _, _) => { self.lexer.next(); }
+            };
+
+            match (delimited, terminated) {
+                (_, Match::Found(_)) => break,
+                (Match::Found(_), _) => {}
+                (Match::Tried(_, delimited), Match::Tried(_, terminated)) => {
+                    return Err(delimited.union(terminated).into_error(self.lexer.peek()));
+                }
+            }
+
+            // The precision, if provided, must come before the separator flag.
+            match_token! { self.lexer;
+                Tok(_, T::NumDec, _, n) if format.precision.is_none() && !format.separators => {
+                    self.lexer.next();
+                    format.precision = Some(read_u8(n, 10, "precision")?);
+                },
+
+                Lit(_, T::Ident, _, "sep") if !format.separators => {
+                    self.lexer.next();
+                    format.separators = true;
+                },
+            }
+        }
+
+        Ok(format)
+    }
+
+    fn parse_timestamp_format<'b>(
+        &mut self,
+        meter: &mut Meter<'b>,
+    ) -> Result<Option<Cow<'s, str>>, FormatError> {
+        if match_token_opt! { self.lexer; Tok(_, T::LParen, _, _) => { self.lexer.next(); } }
+            .is_not_found()
+        {
+            return Ok(None);
+        }
+
+        let format = match_token! { self.lexer; Tok(_, T::String, _, slice) @ lex => {
+            let format = read_string_literal(slice);
+            if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+                return Err(FormatError::InvalidTimestampFormat(lex.detach()));
+            }
+
+            self.lexer.next();
+            format
+        }};
+
+        match_token! { self.lexer; Tok(_, T::RParen, _, _) => self.lexer.next() };
+
+        meter.alloc()?;
+        Ok(Some(format))
+    }
+
+    fn parse_truncate<'b>(&mut self, meter: &mut Meter<'b>) -> Result<Transform<'s>, FormatError> {
+        match_token! { self.lexer; Tok(_, T::LParen, _, _) => self.lexer.next() };
+        let len = match_token! { self.lexer; Tok(_, T::NumDec, _, n) => {
+            self.lexer.next();
+            read_u32(n, 10, "truncation length")?
+        }};
+
+        let mut suffix = None;
+        loop {
+            let delimited = match_token_opt! { self.lexer;
+                Tok(_, T::Comma, _, _) => { self.lexer.next(); }
+            };
+
+            let terminated = match_token_opt! { self.lexer;
+                Tok(_, T::RParen, _, _) => { self.lexer.next(); }
+            };
+
+            match (delimited, terminated) {
+                (_, Match::Found(_)) => break,
+                (Match::Found(_), _) => {}
+                (Match::Tried(_, delimited), Match::Tried(_, terminated)) => {
+                    return Err(delimited.union(terminated).into_error(self.lexer.peek()));
+                }
+            }
+
+            match_token! { self.lexer;
+                Tok(_, T::String, _, slice) if suffix.is_none() => {
+                    self.lexer.next();
+                    meter.alloc()?;
+                    suffix = Some(read_string_literal(slice));
+                },
+            }
+        }
+
+        Ok(Transform::Truncate { len, suffix })
+    }
+
     fn parse_xmod(&mut self) -> Result<Base64Modifier, FormatError> {
         let mut xmod = Base64Modifier::EMPTY;
         if match_token_opt! { self.lexer; Tok(_, T::LParen, _, _) => { self.lexer.next(); } }
@@ -1076,7 +1261,7 @@ impl fmt::Debug for Expr<'_> {
             writeln!(f)?;
         }
 
-        if let Some(transform) = self.transform {
+        if let Some(transform) = &self.transform {
             write!(f, ": {transform:?}")?;
         }
 
@@ -1084,6 +1269,16 @@ impl fmt::Debug for Expr<'_> {
     }
 }
 
+impl fmt::Debug for Alternate<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(condition) = &self.condition {
+            write!(f, "{condition:?} ? ")?;
+        }
+
+        self.value.fmt(f)
+    }
+}
+
 impl fmt::Debug for Chain<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut accessors = self.accessors.iter();
@@ -1206,15 +1401,22 @@ impl fmt::Debug for Enum<'_> {
     }
 }
 
-impl fmt::Debug for Transform {
+impl fmt::Debug for Transform<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Transform::Base64(xmod) => write!(f, "base64{xmod:?}"),
             Transform::Bcs(xmod) => write!(f, "bcs{xmod:?}"),
+            Transform::Decimal(format) => write!(f, "dec{format:?}"),
             Transform::Hex => write!(f, "hex"),
             Transform::Json => write!(f, "json"),
             Transform::Str => write!(f, "str"),
-            Transform::Timestamp => write!(f, "ts"),
+            Transform::Timestamp(None) => write!(f, "ts"),
+            Transform::Timestamp(Some(format)) => write!(f, "ts({format:?})"),
+            Transform::Truncate { len, suffix: None } => write!(f, "trunc({len})"),
+            Transform::Truncate {
+                len,
+                suffix: Some(suffix),
+            } => write!(f, "trunc({len}, {suffix:?})"),
             Transform::Url => write!(f, "url"),
         }
     }
@@ -1243,6 +1445,22 @@ impl fmt::Debug for Base64Modifier {
     }
 }
 
+impl fmt::Debug for DecimalFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}", self.decimals)?;
+
+        if let Some(precision) = self.precision {
+            write!(f, ", {precision}")?;
+        }
+
+        if self.separators {
+            write!(f, ", sep")?;
+        }
+
+        write!(f, ")")
+    }
+}
+
 fn read_u8(slice: &str, radix: u32, what: &'static str) -> Result<u8, FormatError> {
     u8::from_str_radix(&slice.replace('_', ""), radix).map_err(|err| FormatError::InvalidNumber {
         what,
@@ -1329,7 +1547,10 @@ mod tests {
 
     use crate::v2::meter::Limits;
 
-    use super::{Accessor as A, Chain as C, Expr as E, Literal as L, Parser, Strand as S, *};
+    use super::{
+        Accessor as A, Alternate as Alt, Chain as C, Expr as E, Literal as L, Parser, Strand as S,
+        *,
+    };
 
     fn strands(src: &str) -> String {
         let limits = Limits::default();
@@ -1404,9 +1625,12 @@ mod tests {
         assert_eq!(
             strands,
             vec![S::Expr(E {
-                alternates: vec![C {
-                    root: None,
-                    accessors: vec![A::Field(ident_str!("foo"))],
+                alternates: vec![Alt {
+                    condition: None,
+                    value: C {
+                        root: None,
+                        accessors: vec![A::Field(ident_str!("foo"))],
+                    }
                 }],
                 transform: None,
             })]
@@ -1421,9 +1645,12 @@ mod tests {
         assert_eq!(
             strands,
             vec![S::Expr(E {
-                alternates: vec![C {
-                    root: None,
-                    accessors: vec![A::Field(ident_str!("foo"))],
+                alternates: vec![Alt {
+                    condition: None,
+                    value: C {
+                        root: None,
+                        accessors: vec![A::Field(ident_str!("foo"))],
+                    }
                 }],
                 transform: Some(Transform::Str),
             })]
@@ -1438,9 +1665,12 @@ mod tests {
         assert_eq!(
             strands,
             vec![S::Expr(E {
-                alternates: vec![C {
-                    root: None,
-                    accessors: vec![A::Field(ident_str!("foo")), A::Field(ident_str!("bar"))],
+                alternates: vec![Alt {
+                    condition: None,
+                    value: C {
+                        root: None,
+                        accessors: vec![A::Field(ident_str!("foo")), A::Field(ident_str!("bar"))],
+                    }
                 }],
                 transform: None,
             })]
@@ -1457,9 +1687,12 @@ mod tests {
             vec![
                 S::Text("foo ".into()),
                 S::Expr(E {
-                    alternates: vec![C {
-                        root: None,
-                        accessors: vec![A::Field(ident_str!("bar"))],
+                    alternates: vec![Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("bar"))],
+                        }
                     }],
                     transform: None,
                 }),
@@ -1477,18 +1710,27 @@ mod tests {
             strands,
             vec![S::Expr(E {
                 alternates: vec![
-                    C {
-                        root: None,
-                        accessors: vec![A::Field(ident_str!("foo"))],
-                    },
-                    C {
-                        root: None,
-                        accessors: vec![A::Field(ident_str!("bar"))],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("foo"))],
+                        }
                     },
-                    C {
-                        root: None,
-                        accessors: vec![A::Field(ident_str!("baz"))],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("bar"))],
+                        }
                     },
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("baz"))],
+                        }
+                    }
                 ],
                 transform: None,
             })]
@@ -1496,67 +1738,150 @@ mod tests {
     }
 
     #[test]
-    fn test_metering_indexed_access() {
-        let (nodes, loads, strands) = nodes_and_loads("{foo[bar]->[baz]}");
-        assert_eq!(nodes, 9);
-        assert_eq!(loads, 1);
+    fn test_metering_conditions() {
+        let (nodes, loads, strands) = nodes_and_loads("{foo ? bar | baz}");
+        assert_eq!(nodes, 7);
+        assert_eq!(loads, 0);
         assert_eq!(
             strands,
             vec![S::Expr(E {
-                alternates: vec![C {
-                    root: None,
-                    accessors: vec![
-                        A::Field(ident_str!("foo")),
-                        A::Index(C {
+                alternates: vec![
+                    Alt {
+                        condition: Some(C {
                             root: None,
-                            accessors: vec![A::Field(ident_str!("bar"))],
+                            accessors: vec![A::Field(ident_str!("foo"))],
                         }),
-                        A::DFIndex(C {
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("bar"))],
+                        },
+                    },
+                    Alt {
+                        condition: None,
+                        value: C {
                             root: None,
                             accessors: vec![A::Field(ident_str!("baz"))],
-                        }),
-                    ],
-                }],
+                        },
+                    },
+                ],
                 transform: None,
             })]
         );
     }
 
     #[test]
-    fn test_metering_nested_loads() {
-        let (nodes, loads, strands) = nodes_and_loads("{foo=>[bar->[baz]] | qux->[quy]}");
-        assert_eq!(nodes, 14);
-        assert_eq!(loads, 4);
+    fn test_metering_transform_arguments() {
+        // String arguments to transforms are counted as nodes, but numeric arguments and flags
+        // are not.
+        let (nodes, loads, _) = nodes_and_loads("{foo:dec(9, 2, sep)}");
+        assert_eq!(nodes, 3);
+        assert_eq!(loads, 0);
+
+        let (nodes, loads, strands) = nodes_and_loads("{foo:ts('%Y')} {bar:trunc(3, '...')}");
+        assert_eq!(nodes, 9);
+        assert_eq!(loads, 0);
+        assert_eq!(
+            strands,
+            vec![
+                S::Expr(E {
+                    alternates: vec![Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("foo"))],
+                        },
+                    }],
+                    transform: Some(Transform::Timestamp(Some("%Y".into()))),
+                }),
+                S::Text(" ".into()),
+                S::Expr(E {
+                    alternates: vec![Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![A::Field(ident_str!("bar"))],
+                        },
+                    }],
+                    transform: Some(Transform::Truncate {
+                        len: 3,
+                        suffix: Some("...".into()),
+                    }),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metering_indexed_access() {
+        let (nodes, loads, strands) = nodes_and_loads("{foo[bar]->[baz]}");
+        assert_eq!(nodes, 9);
+        assert_eq!(loads, 1);
         assert_eq!(
             strands,
             vec![S::Expr(E {
-                alternates: vec![
-                    C {
+                alternates: vec![Alt {
+                    condition: None,
+                    value: C {
                         root: None,
                         accessors: vec![
                             A::Field(ident_str!("foo")),
-                            A::DOFIndex(C {
+                            A::Index(C {
                                 root: None,
-                                accessors: vec![
-                                    A::Field(ident_str!("bar")),
-                                    A::DFIndex(C {
-                                        root: None,
-                                        accessors: vec![A::Field(ident_str!("baz"))],
-                                    }),
-                                ],
+                                accessors: vec![A::Field(ident_str!("bar"))],
                             }),
-                        ],
-                    },
-                    C {
-                        root: None,
-                        accessors: vec![
-                            A::Field(ident_str!("qux")),
                             A::DFIndex(C {
                                 root: None,
-                                accessors: vec![A::Field(ident_str!("quy"))],
+                                accessors: vec![A::Field(ident_str!("baz"))],
                             }),
                         ],
+                    }
+                }],
+                transform: None,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_metering_nested_loads() {
+        let (nodes, loads, strands) = nodes_and_loads("{foo=>[bar->[baz]] | qux->[quy]}");
+        assert_eq!(nodes, 14);
+        assert_eq!(loads, 4);
+        assert_eq!(
+            strands,
+            vec![S::Expr(E {
+                alternates: vec![
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![
+                                A::Field(ident_str!("foo")),
+                                A::DOFIndex(C {
+                                    root: None,
+                                    accessors: vec![
+                                        A::Field(ident_str!("bar")),
+                                        A::DFIndex(C {
+                                            root: None,
+                                            accessors: vec![A::Field(ident_str!("baz"))],
+                                        }),
+                                    ],
+                                }),
+                            ],
+                        }
                     },
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: None,
+                            accessors: vec![
+                                A::Field(ident_str!("qux")),
+                                A::DFIndex(C {
+                                    root: None,
+                                    accessors: vec![A::Field(ident_str!("quy"))],
+                                }),
+                            ],
+                        }
+                    }
                 ],
                 transform: None,
             })]
@@ -1573,32 +1898,50 @@ mod tests {
             strands,
             vec![S::Expr(E {
                 alternates: vec![
-                    C {
-                        root: Some(L::Bool(true)),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Bool(true)),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::Address(
-                            AccountAddress::from_hex_literal("0x1234").unwrap()
-                        )),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Address(
+                                AccountAddress::from_hex_literal("0x1234").unwrap()
+                            )),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::U64(5678)),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::U64(5678)),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::ByteArray(vec![0xab, 0xcd, 0xef])),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::ByteArray(vec![0xab, 0xcd, 0xef])),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::ByteArray(b"hello".to_vec())),
-                        accessors: vec![],
-                    },
-                    C {
-                        root: Some(L::String("world".into())),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::ByteArray(b"hello".to_vec())),
+                            accessors: vec![],
+                        }
                     },
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::String("world".into())),
+                            accessors: vec![],
+                        }
+                    }
                 ],
                 transform: None,
             })]
@@ -1622,65 +1965,80 @@ mod tests {
             strands,
             vec![S::Expr(E {
                 alternates: vec![
-                    C {
-                        root: Some(L::Vector(Box::new(Vector {
-                            type_: None,
-                            elements: vec![
-                                C {
-                                    root: Some(L::U8(1)),
-                                    accessors: vec![],
-                                },
-                                C {
-                                    root: Some(L::U8(2)),
-                                    accessors: vec![],
-                                },
-                                C {
-                                    root: Some(L::U8(3)),
-                                    accessors: vec![],
-                                },
-                            ],
-                        }))),
-                        accessors: vec![],
-                    },
-                    C {
-                        root: Some(L::Vector(Box::new(Vector {
-                            type_: Some(TypeTag::U16),
-                            elements: vec![
-                                C {
-                                    root: Some(L::U16(4)),
-                                    accessors: vec![],
-                                },
-                                C {
-                                    root: Some(L::U16(5)),
-                                    accessors: vec![],
-                                },
-                            ],
-                        }))),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Vector(Box::new(Vector {
+                                type_: None,
+                                elements: vec![
+                                    C {
+                                        root: Some(L::U8(1)),
+                                        accessors: vec![],
+                                    },
+                                    C {
+                                        root: Some(L::U8(2)),
+                                        accessors: vec![],
+                                    },
+                                    C {
+                                        root: Some(L::U8(3)),
+                                        accessors: vec![],
+                                    },
+                                ],
+                            }))),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::Vector(Box::new(Vector {
-                            type_: Some(TypeTag::U32),
-                            elements: vec![],
-                        }))),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Vector(Box::new(Vector {
+                                type_: Some(TypeTag::U16),
+                                elements: vec![
+                                    C {
+                                        root: Some(L::U16(4)),
+                                        accessors: vec![],
+                                    },
+                                    C {
+                                        root: Some(L::U16(5)),
+                                        accessors: vec![],
+                                    },
+                                ],
+                            }))),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::Vector(Box::new(Vector {
-                            type_: Some(TypeTag::U64),
-                            elements: vec![],
-                        }))),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Vector(Box::new(Vector {
+                                type_: Some(TypeTag::U32),
+                                elements: vec![],
+                            }))),
+                            accessors: vec![],
+                        }
                     },
-                    C {
-                        root: Some(L::Vector(Box::new(Vector {
-                            type_: Some(
-                                TypeTag::from_str("0x2::coin::Coin<0x2::rtd::RTD>").unwrap()
-                            ),
-                            elements: vec![],
-                        }))),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Vector(Box::new(Vector {
+                                type_: Some(TypeTag::U64),
+                                elements: vec![],
+                            }))),
+                            accessors: vec![],
+                        }
                     },
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Vector(Box::new(Vector {
+                                type_: Some(
+                                    TypeTag::from_str("0x2::coin::Coin<0x2::rtd::RTD>").unwrap()
+                                ),
+                                elements: vec![],
+                            }))),
+                            accessors: vec![],
+                        }
+                    }
                 ],
                 transform: None,
             })]
@@ -1700,57 +2058,64 @@ mod tests {
             strands,
             vec![S::Expr(E {
                 alternates: vec![
-                    C {
-                        root: Some(L::Struct(Box::new(Struct {
-                            type_: StructTag::from_str("0x1::string::String").unwrap(),
-                            fields: Fields::Positional(vec![
-                                C {
-                                    root: Some(L::U64(42)),
-                                    accessors: vec![],
-                                },
-                                C {
-                                    root: Some(L::String("foo".into())),
-                                    accessors: vec![],
-                                },
-                                C {
-                                    root: Some(L::Vector(Box::new(Vector {
-                                        type_: None,
-                                        elements: vec![
-                                            C {
-                                                root: Some(L::U256(1u64.into())),
-                                                accessors: vec![],
-                                            },
-                                            C {
-                                                root: Some(L::U256(2u64.into())),
-                                                accessors: vec![],
-                                            },
-                                            C {
-                                                root: Some(L::U256(3u64.into())),
-                                                accessors: vec![],
-                                            },
-                                        ],
-                                    }))),
-                                    accessors: vec![],
-                                },
-                            ]),
-                        }))),
-                        accessors: vec![],
-                    },
-                    C {
-                        root: Some(L::Enum(Box::new(Enum {
-                            type_: StructTag::from_str("0x2::coin::Coin<0x2::rtd::RTD>").unwrap(),
-                            variant_name: Some("Foo"),
-                            variant_index: 1,
-                            fields: Fields::Named(vec![(
-                                "balance",
-                                C {
-                                    root: Some(L::U64(100)),
-                                    accessors: vec![],
-                                },
-                            )]),
-                        }))),
-                        accessors: vec![],
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Struct(Box::new(Struct {
+                                type_: StructTag::from_str("0x1::string::String").unwrap(),
+                                fields: Fields::Positional(vec![
+                                    C {
+                                        root: Some(L::U64(42)),
+                                        accessors: vec![],
+                                    },
+                                    C {
+                                        root: Some(L::String("foo".into())),
+                                        accessors: vec![],
+                                    },
+                                    C {
+                                        root: Some(L::Vector(Box::new(Vector {
+                                            type_: None,
+                                            elements: vec![
+                                                C {
+                                                    root: Some(L::U256(1u64.into())),
+                                                    accessors: vec![],
+                                                },
+                                                C {
+                                                    root: Some(L::U256(2u64.into())),
+                                                    accessors: vec![],
+                                                },
+                                                C {
+                                                    root: Some(L::U256(3u64.into())),
+                                                    accessors: vec![],
+                                                },
+                                            ],
+                                        }))),
+                                        accessors: vec![],
+                                    },
+                                ]),
+                            }))),
+                            accessors: vec![],
+                        }
                     },
+                    Alt {
+                        condition: None,
+                        value: C {
+                            root: Some(L::Enum(Box::new(Enum {
+                                type_: StructTag::from_str("0x2::coin::Coin<0x2::rtd::RTD>")
+                                    .unwrap(),
+                                variant_name: Some("Foo"),
+                                variant_index: 1,
+                                fields: Fields::Named(vec![(
+                                    "balance",
+                                    C {
+                                        root: Some(L::U64(100)),
+                                        accessors: vec![],
+                                    },
+                                )]),
+                            }))),
+                            accessors: vec![],
+                        }
+                    }
                 ],
                 transform: None,
             })]
@@ -1847,6 +2212,67 @@ mod tests {
         assert_snapshot!(strands(r#"{foo.bar.baz:str}"#));
     }
 
+    #[test]
+    fn test_conditional_alternates() {
+        assert_snapshot!(strands(r#"{listed ? price | 'unlisted'}"#), @r###"
+        { listed ? price
+        | "unlisted"
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_decimal_transforms() {
+        assert_snapshot!(
+            strands(r#"{a:dec(9)}{b:dec(9, 2)}{c:dec(6, sep)}{d:dec(18, 4, sep,)}{e:sep}"#),
+            @r###"
+        { a
+        : dec(9)}
+        { b
+        : dec(9, 2)}
+        { c
+        : dec(6, sep)}
+        { d
+        : dec(18, 4, sep)}
+        { e
+        : dec(0, sep)}
+        "###
+        );
+    }
+
+    #[test]
+    fn test_string_transforms() {
+        assert_snapshot!(
+            strands(r#"{a:ts}{b:ts('%Y-%m-%d')}{c:trunc(8)}{d:trunc(10, '...')}"#),
+            @r###"
+        { a
+        : ts}
+        { b
+        : ts("%Y-%m-%d")}
+        { c
+        : trunc(8)}
+        { d
+        : trunc(10, "...")}
+        "###
+        );
+    }
+
+    #[test]
+    fn test_decimal_separator_before_precision() {
+        assert_snapshot!(
+            strands(r#"{a:dec(9, sep, 2)}"#),
+            @r###"Error: Unexpected whitespace followed by decimal number "2" at offset 15, expected one of 'sep', or a decimal number"###
+        );
+    }
+
+    #[test]
+    fn test_timestamp_format_invalid() {
+        assert_snapshot!(
+            strands(r#"{a:ts('%Q')}"#),
+            @r###"Error: Invalid timestamp format string "%Q" at offset 7"###
+        );
+    }
+
     #[test]
     fn test_address_literal() {
         assert_snapshot!(strands(r#"{@0x1 | @ 0x2 | @42 | @0x12_34_56}"#));
@@ -38,6 +38,7 @@ use rtd_types::object::rpc_visitor::Writer as _;
 
 use crate::v2::error::FormatError;
 use crate::v2::parser::Base64Modifier;
+use crate::v2::parser::DecimalFormat;
 use crate::v2::parser::Transform;
 use crate::v2::writer::JsonWriter;
 use crate::v2::writer::StringWriter;
@@ -137,7 +138,7 @@ impl Value<'_> {
     /// is too large. If it succeds, `w` will be modified to include the newly written data.
     pub(crate) fn format(
         self,
-        transform: Transform,
+        transform: &Transform<'_>,
         w: &mut StringWriter<'_>,
     ) -> Result<(), FormatError> {
         match transform {
@@ -147,10 +148,16 @@ impl Value<'_> {
                 Ok(write!(w, "{}", xmod.engine().encode(bytes))?)
             }
 
+            Transform::Decimal(format) => Atom::try_from(self)?.format_as_decimal(format, w),
             Transform::Hex => Atom::try_from(self)?.format_as_hex(w),
             Transform::Json => Err(FormatError::TransformInvalid("unexpected 'json' in string")),
             Transform::Str => Atom::try_from(self)?.format_as_str(w),
-            Transform::Timestamp => Atom::try_from(self)?.format_as_timestamp(w),
+            Transform::Timestamp(format) => {
+                Atom::try_from(self)?.format_as_timestamp(format.as_deref(), w)
+            }
+            Transform::Truncate { len, suffix } => {
+                Atom::try_from(self)?.format_as_truncated(*len, suffix.as_deref(), w)
+            }
             Transform::Url => Atom::try_from(self)?.format_as_url(w),
         }
     }
@@ -219,6 +226,59 @@ impl Value<'_> {
         }
     }
 
+    /// Attempt to coerce this value into a `bool` if that's possible. This works for boolean
+    /// literals and boolean values sliced out of Move values.
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            Value::Slice(Slice {
+                layout: MoveTypeLayout::Bool,
+                bytes,
+            }) => bcs::from_bytes(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether this value is an empty string, byte array, or vector. Alternates that evaluate to
+    /// empty values are skipped in favour of later alternates.
+    pub(crate) fn is_empty(&self) -> bool {
+        use MoveTypeLayout as L;
+        use Value as V;
+
+        match self {
+            V::Bytes(bs) | V::String(bs) => bs.is_empty(),
+            V::Vector(v) => v.elements.is_empty(),
+
+            // Vectors (and strings, which wrap byte vectors) are serialized with a ULEB128 length
+            // prefix, which is a single zero byte if and only if the vector is empty.
+            V::Slice(Slice { layout, bytes }) => match layout {
+                L::Vector(_) => bytes.first() == Some(&0),
+                L::Struct(layout)
+                    if [
+                        move_ascii_str_layout(),
+                        move_utf8_str_layout(),
+                        url_layout(),
+                    ]
+                    .contains(layout.as_ref()) =>
+                {
+                    bytes.first() == Some(&0)
+                }
+                _ => false,
+            },
+
+            V::Address(_)
+            | V::Bool(_)
+            | V::Enum(_)
+            | V::Struct(_)
+            | V::U8(_)
+            | V::U16(_)
+            | V::U32(_)
+            | V::U64(_)
+            | V::U128(_)
+            | V::U256(_) => false,
+        }
+    }
+
     /// Attempt to coerce this value into a `u64` if that's possible. This works for any numeric
     /// value that can be represented within 64 bits.
     pub(crate) fn as_u64(&self) -> Option<u64> {
@@ -290,28 +350,87 @@ impl Atom<'_> {
 
     /// Format the atom as a string.
     fn format_as_str(&self, w: &mut StringWriter<'_>) -> Result<(), FormatError> {
-        match self {
-            Atom::Address(a) => write!(w, "{}", a.to_canonical_display(true))?,
-            Atom::Bool(b) => write!(w, "{b}")?,
-            Atom::U8(n) => write!(w, "{n}")?,
-            Atom::U16(n) => write!(w, "{n}")?,
-            Atom::U32(n) => write!(w, "{n}")?,
-            Atom::U64(n) => write!(w, "{n}")?,
-            Atom::U128(n) => write!(w, "{n}")?,
-            Atom::U256(n) => write!(w, "{n}")?,
-            Atom::Bytes(bs) => {
-                let s = str::from_utf8(bs)
-                    .map_err(|_| FormatError::TransformInvalid("expected utf8 bytes"))?;
-                write!(w, "{s}")?;
+        w.write_str(&self.as_str()?)?;
+        Ok(())
+    }
+
+    /// Format the atom as a string, keeping at most `len` characters. If any characters were
+    /// removed, the output is followed by `suffix`.
+    fn format_as_truncated(
+        &self,
+        len: u32,
+        suffix: Option<&str>,
+        w: &mut StringWriter<'_>,
+    ) -> Result<(), FormatError> {
+        let s = self.as_str()?;
+        match s.char_indices().nth(len as usize) {
+            None => w.write_str(&s)?,
+            Some((end, _)) => {
+                w.write_str(&s[..end])?;
+                if let Some(suffix) = suffix {
+                    w.write_str(suffix)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Format the atom as a fixed-point decimal number, with `format.decimals` of its digits after
+    /// the decimal point. Only supported for numeric atoms.
+    fn format_as_decimal(
+        &self,
+        format: &DecimalFormat,
+        w: &mut StringWriter<'_>,
+    ) -> Result<(), FormatError> {
+        let digits = match self {
+            Atom::U8(n) => n.to_string(),
+            Atom::U16(n) => n.to_string(),
+            Atom::U32(n) => n.to_string(),
+            Atom::U64(n) => n.to_string(),
+            Atom::U128(n) => n.to_string(),
+            Atom::U256(n) => n.to_string(),
+            Atom::Address(_) | Atom::Bool(_) | Atom::Bytes(_) => {
+                return Err(FormatError::TransformInvalid("expected unsigned integer"));
+            }
+        };
+
+        // Pad with leading zeroes so that there is at least one digit before the decimal point.
+        let decimals = format.decimals as usize;
+        let digits = format!("{digits:0>width$}", width = decimals + 1);
+        let (int, frac) = digits.split_at(digits.len() - decimals);
+
+        let frac = match format.precision.map(|p| p as usize) {
+            None => Cow::Borrowed(frac.trim_end_matches('0')),
+            Some(p) if p <= frac.len() => Cow::Borrowed(&frac[..p]),
+            Some(p) => Cow::Owned(format!("{frac:0<p$}")),
+        };
+
+        if format.separators {
+            for (i, c) in int.char_indices() {
+                if i > 0 && (int.len() - i).is_multiple_of(3) {
+                    w.write_char(',')?;
+                }
+                w.write_char(c)?;
             }
+        } else {
+            w.write_str(int)?;
+        }
+
+        if !frac.is_empty() {
+            write!(w, ".{frac}")?;
         }
 
         Ok(())
     }
 
     /// Coerce the atom into an `i64`, interpreted as an offset in milliseconds since the Unix
-    /// epoch, and format it as an ISO8601 timestamp.
-    fn format_as_timestamp(&self, w: &mut StringWriter<'_>) -> Result<(), FormatError> {
+    /// epoch, and format it as an ISO8601 timestamp, or using a custom `format`, if provided.
+    fn format_as_timestamp(
+        &self,
+        format: Option<&str>,
+        w: &mut StringWriter<'_>,
+    ) -> Result<(), FormatError> {
         let ts = self
             .as_i64()
             .and_then(DateTime::from_timestamp_millis)
@@ -319,7 +438,11 @@ impl Atom<'_> {
                 FormatError::TransformInvalid("expected unix timestamp in milliseconds")
             })?;
 
-        write!(w, "{ts:?}")?;
+        match format {
+            None => write!(w, "{ts:?}")?,
+            Some(format) => write!(w, "{}", ts.format(format))?,
+        }
+
         Ok(())
     }
 
@@ -371,6 +494,24 @@ impl Atom<'_> {
         Ok(())
     }
 
+    /// The string representation of this atom. Byte arrays must be valid UTF-8.
+    fn as_str(&self) -> Result<Cow<'_, str>, FormatError> {
+        Ok(match self {
+            Atom::Address(a) => Cow::Owned(a.to_canonical_display(true).to_string()),
+            Atom::Bool(b) => Cow::Owned(b.to_string()),
+            Atom::U8(n) => Cow::Owned(n.to_string()),
+            Atom::U16(n) => Cow::Owned(n.to_string()),
+            Atom::U32(n) => Cow::Owned(n.to_string()),
+            Atom::U64(n) => Cow::Owned(n.to_string()),
+            Atom::U128(n) => Cow::Owned(n.to_string()),
+            Atom::U256(n) => Cow::Owned(n.to_string()),
+            Atom::Bytes(bs) => Cow::Borrowed(
+                str::from_utf8(bs)
+                    .map_err(|_| FormatError::TransformInvalid("expected utf8 bytes"))?,
+            ),
+        })
+    }
+
     /// Attempt to coerce this atom into an `i64`, if possible.
     fn as_i64(&self) -> Option<i64> {
         match self {
@@ -0,0 +1,516 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::iter;
+use std::sync::Arc;
+
+use anyhow::bail;
+use move_core_types::ident_str;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tokio::time::{Duration, MissedTickBehavior};
+use tracing::{error, info};
+
+use crate::faucet_config::{AdditionalCoin, CoinSource};
+use crate::ledger::RequestLedger;
+use crate::{CoinInfo, FaucetConfig, FaucetError};
+use rtd_keys::keystore::AccountKeystore;
+use rtd_sdk::rpc_types::{
+    ObjectChange, RtdExecutionStatus, RtdObjectDataOptions, RtdTransactionBlockEffectsAPI,
+    RtdTransactionBlockResponse, RtdTransactionBlockResponseOptions,
+};
+use rtd_sdk::types::RTD_FRAMEWORK_PACKAGE_ID;
+use rtd_sdk::types::base_types::{ObjectID, ObjectRef, ObjectType, RtdAddress};
+use rtd_sdk::types::coin::{Coin, TreasuryCap};
+use rtd_sdk::types::digests::TransactionDigest;
+use rtd_sdk::types::gas_coin::GAS;
+use rtd_sdk::types::object::Owner;
+use rtd_sdk::types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use rtd_sdk::types::quorum_driver_types::ExecuteTransactionRequestType;
+use rtd_sdk::types::transaction::{ObjectArg, Transaction, TransactionData};
+use rtd_sdk::wallet_context::WalletContext;
+use shared_crypto::intent::Intent;
+
+/// Gas budget for each request served by a transaction.
+const GAS_BUDGET: u64 = 10_000_000;
+const NUM_RETRIES: u8 = 2;
+
+/// Maximum number of requests waiting to be dispensed.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Maximum number of coins split off a single coin in one command, to stay under the limit on the
+/// number of arguments to a command.
+const MAX_SPLITS: usize = 500;
+
+/// A request that has passed its quota checks, waiting to be dispensed.
+pub(crate) struct Request {
+    pub(crate) recipient: RtdAddress,
+    /// The request's reservation in the ledger.
+    pub(crate) reservation: u64,
+    pub(crate) reply: oneshot::Sender<Result<Vec<CoinInfo>, FaucetError>>,
+}
+
+/// A type of coin that is sent with each request.
+struct Allotment {
+    coin_type: StructTag,
+    source: Source,
+    /// Amount of each coin sent.
+    amount: u64,
+    /// Number of coins sent with each request.
+    count: usize,
+}
+
+enum Source {
+    Gas,
+    TreasuryCap(ObjectID),
+    Coin(ObjectID),
+}
+
+/// Sends coins to the recipients of requests. Requests are queued up and sent together, in a
+/// single transaction, once per tick. The dispenser is the only user of the faucet's gas coin, so
+/// transactions never contend for it.
+pub(crate) struct Dispenser {
+    wallet: WalletContext,
+    active_address: RtdAddress,
+    gas_coin: ObjectID,
+    allotments: Vec<Allotment>,
+    ledger: Arc<Mutex<RequestLedger>>,
+    batch_interval: Duration,
+    max_batch_size: usize,
+}
+
+impl Dispenser {
+    /// Set up a dispenser that pays for transactions with `gas_coin`, owned by `active_address`,
+    /// and sends RTD as well as the configured additional coins with each request.
+    pub(crate) async fn new(
+        wallet: WalletContext,
+        active_address: RtdAddress,
+        gas_coin: ObjectID,
+        config: &FaucetConfig,
+        ledger: Arc<Mutex<RequestLedger>>,
+    ) -> Result<Self, FaucetError> {
+        let mut allotments = vec![Allotment {
+            coin_type: GAS::type_(),
+            source: Source::Gas,
+            amount: config.amount,
+            count: config.num_coins,
+        }];
+
+        for coin in &config.additional_coins {
+            let allotment = resolve_allotment(&wallet, active_address, coin).await?;
+            info!(
+                "Dispensing {} of {} with each request",
+                allotment.amount,
+                allotment.coin_type.to_canonical_display(true),
+            );
+            allotments.push(allotment);
+        }
+
+        let max_coins = allotments.iter().map(|a| a.count).max().unwrap_or(1).max(1);
+        let max_batch_size = config.max_batch_size.min(MAX_SPLITS / max_coins).max(1);
+
+        Ok(Self {
+            wallet,
+            active_address,
+            gas_coin,
+            allotments,
+            ledger,
+            batch_interval: Duration::from_millis(config.batch_interval_ms.max(1)),
+            max_batch_size,
+        })
+    }
+
+    /// Start dispensing requests in the background. Returns the queue to send requests on. The
+    /// dispenser stops once the queue is closed.
+    pub(crate) fn spawn(self) -> mpsc::Sender<Request> {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(self.run(rx));
+        tx
+    }
+
+    async fn run(self, mut rx: mpsc::Receiver<Request>) {
+        let mut interval = tokio::time::interval(self.batch_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut batch = Vec::with_capacity(self.max_batch_size);
+        loop {
+            interval.tick().await;
+            if rx.recv_many(&mut batch, self.max_batch_size).await == 0 {
+                break;
+            }
+
+            self.dispense(std::mem::take(&mut batch)).await;
+        }
+    }
+
+    /// Serve all the requests in `batch` with a single transaction, record the grants in the
+    /// ledger, and reply to each request.
+    async fn dispense(&self, batch: Vec<Request>) {
+        let recipients: Vec<_> = batch.iter().map(|r| r.recipient).collect();
+        let result = self.execute(&recipients).await;
+        let mut ledger = self.ledger.lock().await;
+
+        match result {
+            Ok(coins) => {
+                for (request, coins) in batch.into_iter().zip(coins) {
+                    // The coins have already been sent, so failing to record the grant should not
+                    // fail the request.
+                    if let Err(e) = ledger.fulfil(request.reservation, coins.clone()) {
+                        error!("Failed to record grant to {}: {e}", request.recipient);
+                    }
+
+                    let _ = request.reply.send(Ok(coins));
+                }
+            }
+
+            Err(e) => {
+                error!("Failed to dispense {} request(s): {e}", recipients.len());
+                for request in batch {
+                    ledger.cancel(request.reservation);
+                    let _ = request.reply.send(Err(e.clone()));
+                }
+            }
+        }
+    }
+
+    /// Send every allotment to each of the `recipients` in a single transaction, and return the
+    /// coins that were sent to each of them.
+    async fn execute(&self, recipients: &[RtdAddress]) -> Result<Vec<Vec<CoinInfo>>, FaucetError> {
+        let gas_price = self
+            .wallet
+            .get_reference_gas_price()
+            .await
+            .map_err(|e| FaucetError::internal(format!("Failed to get gas price: {}", e)))?;
+
+        let mut ptb = ProgrammableTransactionBuilder::new();
+        for allotment in &self.allotments {
+            let n = recipients.len() * allotment.count;
+            let amounts = vec![allotment.amount; n];
+            let payees: Vec<_> = recipients
+                .iter()
+                .flat_map(|r| iter::repeat_n(*r, allotment.count))
+                .collect();
+
+            match allotment.source {
+                Source::Gas => ptb
+                    .pay_rtd(payees, amounts)
+                    .map_err(FaucetError::internal)?,
+
+                Source::Coin(id) => {
+                    let coin = self.object_ref(id).await?;
+                    ptb.pay(vec![coin], payees, amounts)
+                        .map_err(FaucetError::internal)?;
+                }
+
+                Source::TreasuryCap(id) => {
+                    let cap = self.object_ref(id).await?;
+                    let cap = ptb
+                        .obj(ObjectArg::ImmOrOwnedObject(cap))
+                        .map_err(FaucetError::internal)?;
+
+                    for (payee, amount) in payees.into_iter().zip(amounts) {
+                        let amount = ptb.pure(amount).map_err(FaucetError::internal)?;
+                        let payee = ptb.pure(payee).map_err(FaucetError::internal)?;
+                        ptb.programmable_move_call(
+                            RTD_FRAMEWORK_PACKAGE_ID,
+                            ident_str!("coin").to_owned(),
+                            ident_str!("mint_and_transfer").to_owned(),
+                            vec![TypeTag::Struct(Box::new(allotment.coin_type.clone()))],
+                            vec![cap, amount, payee],
+                        );
+                    }
+                }
+            }
+        }
+
+        let gas = self.object_ref(self.gas_coin).await?;
+        let tx_data = TransactionData::new_programmable(
+            self.active_address,
+            vec![gas],
+            ptb.finish(),
+            GAS_BUDGET * recipients.len() as u64,
+            gas_price,
+        );
+
+        let response = self
+            .execute_txn_with_retries(tx_data, NUM_RETRIES)
+            .await
+            .map_err(FaucetError::internal)?;
+
+        let Some(effects) = &response.effects else {
+            return Err(FaucetError::internal(
+                "Failed to get effects from response".to_string(),
+            ));
+        };
+
+        if let RtdExecutionStatus::Failure { error } = effects.status() {
+            return Err(FaucetError::Transfer(error.clone()));
+        }
+
+        let created = response
+            .object_changes
+            .iter()
+            .flatten()
+            .filter_map(|change| {
+                let ObjectChange::Created {
+                    owner: Owner::AddressOwner(owner),
+                    object_type,
+                    object_id,
+                    ..
+                } = change
+                else {
+                    return None;
+                };
+
+                Some((*owner, object_type.clone(), *object_id))
+            });
+
+        Ok(assign_coins(
+            recipients,
+            &self.allotments,
+            created,
+            *effects.transaction_digest(),
+        ))
+    }
+
+    async fn object_ref(&self, id: ObjectID) -> Result<ObjectRef, FaucetError> {
+        self.wallet
+            .get_object_ref(id)
+            .await
+            .map_err(|e| FaucetError::internal(format!("Failed to get object ref: {}", e)))
+    }
+
+    async fn execute_txn(
+        &self,
+        tx_data: &TransactionData,
+    ) -> Result<RtdTransactionBlockResponse, anyhow::Error> {
+        let signature = self
+            .wallet
+            .config
+            .keystore
+            .sign_secure(&self.active_address, &tx_data, Intent::rtd_transaction())
+            .await
+            .map_err(FaucetError::internal)?;
+        let tx = Transaction::from_data(tx_data.clone(), vec![signature]);
+
+        let client = self.wallet.get_client().await?;
+
+        Ok(client
+            .quorum_driver_api()
+            .execute_transaction_block(
+                tx.clone(),
+                RtdTransactionBlockResponseOptions::new()
+                    .with_effects()
+                    .with_object_changes(),
+                Some(ExecuteTransactionRequestType::WaitForLocalExecution),
+            )
+            .await
+            .map_err(|e| {
+                FaucetError::internal(format!(
+                    "Failed to execute faucet transaction with gas coin {:?}, with err {:?}",
+                    self.gas_coin, e
+                ))
+            })?)
+    }
+
+    async fn execute_txn_with_retries(
+        &self,
+        tx: TransactionData,
+        num_retries: u8,
+    ) -> Result<RtdTransactionBlockResponse, anyhow::Error> {
+        let mut retry_delay = Duration::from_millis(500);
+        let mut i = 0;
+
+        loop {
+            if i == num_retries {
+                bail!("Failed to execute transaction after {num_retries} retries",);
+            }
+            let res = self.execute_txn(&tx).await;
+
+            if res.is_ok() {
+                return res;
+            }
+            i += 1;
+            tokio::time::sleep(retry_delay).await;
+            retry_delay *= 2;
+        }
+    }
+}
+
+/// Check that the object backing `coin` is owned by `owner` and is of the right kind, and find
+/// out which type of coin it dispenses.
+async fn resolve_allotment(
+    wallet: &WalletContext,
+    owner: RtdAddress,
+    coin: &AdditionalCoin,
+) -> Result<Allotment, FaucetError> {
+    let id = coin.source.object_id();
+    let client = wallet
+        .get_client()
+        .await
+        .map_err(|e| FaucetError::Wallet(e.to_string()))?;
+
+    let response = client
+        .read_api()
+        .get_object_with_options(id, RtdObjectDataOptions::new().with_type().with_owner())
+        .await
+        .map_err(|e| FaucetError::Wallet(e.to_string()))?;
+
+    let Some(data) = response.data else {
+        return Err(FaucetError::Wallet(format!("Object {id} not found")));
+    };
+
+    if data.owner != Some(Owner::AddressOwner(owner)) {
+        return Err(FaucetError::Wallet(format!(
+            "Object {id} is not owned by the faucet's address {owner}"
+        )));
+    }
+
+    let tag = match data.object_type() {
+        Ok(ObjectType::Struct(type_)) => StructTag::from(type_),
+        _ => {
+            return Err(FaucetError::Wallet(format!(
+                "Object {id} is not a Move object"
+            )));
+        }
+    };
+
+    let (coin_type, source) = match coin.source {
+        CoinSource::TreasuryCap(id) => (
+            TreasuryCap::is_treasury_with_coin_type(&tag),
+            Source::TreasuryCap(id),
+        ),
+        CoinSource::Coin(id) => (Coin::is_coin_with_coin_type(&tag), Source::Coin(id)),
+    };
+
+    let Some(coin_type) = coin_type else {
+        return Err(FaucetError::Wallet(format!(
+            "Object {id} has unexpected type {}",
+            tag.to_canonical_display(true)
+        )));
+    };
+
+    Ok(Allotment {
+        coin_type: coin_type.clone(),
+        source,
+        amount: coin.amount,
+        count: 1,
+    })
+}
+
+/// Share out the coins `created` by a transaction (identified by their owner, type and ID) between
+/// the `recipients` of the requests it served, so that each request gets its allotments. Requests
+/// for the same recipient in one batch split the coins sent to that recipient between them.
+fn assign_coins(
+    recipients: &[RtdAddress],
+    allotments: &[Allotment],
+    created: impl IntoIterator<Item = (RtdAddress, StructTag, ObjectID)>,
+    digest: TransactionDigest,
+) -> Vec<Vec<CoinInfo>> {
+    let mut coins: BTreeMap<(RtdAddress, StructTag), Vec<ObjectID>> = BTreeMap::new();
+    for (owner, type_, id) in created {
+        if let Some(coin_type) = Coin::is_coin_with_coin_type(&type_) {
+            coins
+                .entry((owner, coin_type.clone()))
+                .or_default()
+                .push(id);
+        }
+    }
+
+    recipients
+        .iter()
+        .map(|recipient| {
+            let mut sent = vec![];
+            for allotment in allotments {
+                let key = (*recipient, allotment.coin_type.clone());
+                let Some(ids) = coins.get_mut(&key) else {
+                    continue;
+                };
+
+                let start = ids.len().saturating_sub(allotment.count);
+                sent.extend(ids.drain(start..).map(|id| CoinInfo {
+                    amount: allotment.amount,
+                    id,
+                    transfer_tx_digest: digest,
+                    coin_type: Some(allotment.coin_type.to_canonical_string(true)),
+                }));
+            }
+
+            sent
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_assign_coins() {
+        let usdc = StructTag::from_str("0x42::usdc::USDC").unwrap();
+        let allotments = vec![
+            Allotment {
+                coin_type: GAS::type_(),
+                source: Source::Gas,
+                amount: 100,
+                count: 2,
+            },
+            Allotment {
+                coin_type: usdc.clone(),
+                source: Source::TreasuryCap(ObjectID::random()),
+                amount: 5,
+                count: 1,
+            },
+        ];
+
+        let alice = RtdAddress::random_for_testing_only();
+        let bob = RtdAddress::random_for_testing_only();
+        let digest = TransactionDigest::random();
+
+        let rtd = Coin::type_(GAS::type_tag());
+        let usdc_coin = Coin::type_(TypeTag::Struct(Box::new(usdc.clone())));
+        let created = vec![
+            (alice, rtd.clone(), ObjectID::random()),
+            (bob, rtd.clone(), ObjectID::random()),
+            (alice, rtd.clone(), ObjectID::random()),
+            (bob, usdc_coin.clone(), ObjectID::random()),
+            (alice, usdc_coin.clone(), ObjectID::random()),
+            (bob, rtd.clone(), ObjectID::random()),
+            (alice, rtd.clone(), ObjectID::random()),
+            (alice, rtd.clone(), ObjectID::random()),
+            (alice, usdc_coin.clone(), ObjectID::random()),
+            // Objects that aren't coins are ignored.
+            (alice, usdc.clone(), ObjectID::random()),
+        ];
+
+        // Alice made two requests in this batch, so the coins sent to her are split between them.
+        let assigned = assign_coins(&[alice, bob, alice], &allotments, created, digest);
+        let summary: Vec<Vec<_>> = assigned
+            .iter()
+            .map(|coins| {
+                coins
+                    .iter()
+                    .map(|c| (c.amount, c.coin_type.clone().unwrap()))
+                    .collect()
+            })
+            .collect();
+
+        let rtd = GAS::type_().to_canonical_string(true);
+        let usdc = usdc.to_canonical_string(true);
+        let expect = vec![(100, rtd.clone()), (100, rtd.clone()), (5, usdc.clone())];
+
+        assert_eq!(summary, vec![expect.clone(), expect.clone(), expect]);
+        assert!(
+            assigned
+                .iter()
+                .flatten()
+                .all(|c| c.transfer_tx_digest == digest)
+        );
+
+        let ids: BTreeSet<_> = assigned.iter().flatten().map(|c| c.id).collect();
+        assert_eq!(ids.len(), 9);
+    }
+}
@@ -4,7 +4,7 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Error, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Error, Debug, Clone, PartialEq, Eq)]
 pub enum FaucetError {
     #[error("Wallet Error: `{0}`")]
     Wallet(String),
@@ -12,6 +12,22 @@ pub enum FaucetError {
     #[error("Coin Transfer Failed `{0}`")]
     Transfer(String),
 
+    #[error("Request denied: {0}")]
+    Denied(String),
+
+    #[error(
+        "Too many requests {limit}, next request allowed in {retry_after_secs}s \
+         (at {next_allowed_at_ms}ms since the Unix epoch)"
+    )]
+    TooManyRequests {
+        /// Describes the quota that was exceeded.
+        limit: String,
+        /// Milliseconds since the Unix epoch when the quota will allow the next request.
+        next_allowed_at_ms: u64,
+        /// Seconds from the time the request was rejected until the next request is allowed.
+        retry_after_secs: u64,
+    },
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
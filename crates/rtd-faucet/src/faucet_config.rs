@@ -1,11 +1,18 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use anyhow::{Context, bail};
 use clap::Parser;
-use std::net::Ipv4Addr;
+use rtd_sdk::types::base_types::{ObjectID, RtdAddress};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::str::FromStr;
 
 pub const DEFAULT_AMOUNT: u64 = 200_000_000_000;
 pub const DEFAULT_NUM_COINS: usize = 5;
+pub const DEFAULT_QUOTA_WINDOW_SECS: u64 = 24 * 60 * 60;
+pub const DEFAULT_BATCH_INTERVAL_MS: u64 = 100;
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
 
 #[derive(Parser, Clone)]
 #[clap(
@@ -29,6 +36,65 @@ pub struct FaucetConfig {
 
     #[clap(long, default_value_t = 60)]
     pub wallet_client_timeout_secs: u64,
+
+    /// Another coin type to send with each request, alongside RTD, as
+    /// `<treasury-cap|coin>:<OBJECT_ID>:<AMOUNT>`. Coins are either minted using a `TreasuryCap`,
+    /// or split off a pre-funded coin, owned by the faucet's address. Can be repeated.
+    #[clap(long = "additional-coin", value_name = "SOURCE:OBJECT_ID:AMOUNT")]
+    pub additional_coins: Vec<AdditionalCoin>,
+
+    /// How often to send the requests that have accumulated, in a single transaction.
+    #[clap(long, default_value_t = DEFAULT_BATCH_INTERVAL_MS)]
+    pub batch_interval_ms: u64,
+
+    /// Maximum number of requests to serve in a single transaction.
+    #[clap(long, default_value_t = DEFAULT_MAX_BATCH_SIZE)]
+    pub max_batch_size: usize,
+
+    /// Maximum number of requests that will be granted to the same recipient address within the
+    /// quota window. Unlimited if not set.
+    #[clap(long)]
+    pub max_requests_per_address: Option<u32>,
+
+    /// Maximum number of requests that will be granted to the same source IP within the quota
+    /// window. Unlimited if not set.
+    #[clap(long)]
+    pub max_requests_per_ip: Option<u32>,
+
+    /// Length of the rolling window that request quotas are counted over.
+    #[clap(long, default_value_t = DEFAULT_QUOTA_WINDOW_SECS)]
+    pub quota_window_secs: u64,
+
+    /// Recipient addresses that are not subject to quotas.
+    #[clap(long, value_delimiter = ',')]
+    pub allowed_addresses: Vec<RtdAddress>,
+
+    /// Source IPs that are not subject to quotas.
+    #[clap(long, value_delimiter = ',')]
+    pub allowed_ips: Vec<IpAddr>,
+
+    /// Recipient addresses that are never granted any requests.
+    #[clap(long, value_delimiter = ',')]
+    pub denied_addresses: Vec<RtdAddress>,
+
+    /// Source IPs that are never granted any requests.
+    #[clap(long, value_delimiter = ',')]
+    pub denied_ips: Vec<IpAddr>,
+
+    /// File to record granted requests in. Grants recorded here are reloaded on start-up, so that
+    /// quotas survive restarts. If not set, grants are only tracked in memory.
+    #[clap(long)]
+    pub ledger_path: Option<PathBuf>,
+
+    /// Token that requests to the admin endpoints must supply as a bearer token. The admin
+    /// endpoints are disabled if this is not set.
+    #[clap(long)]
+    pub admin_token: Option<String>,
+
+    /// Identify the source IP of requests from the `X-Forwarded-For` header, rather than the
+    /// socket address. The value is the number of proxies between the client and the faucet.
+    #[clap(long)]
+    pub x_forwarded_for_hops: Option<usize>,
 }
 
 impl Default for FaucetConfig {
@@ -39,6 +105,98 @@ impl Default for FaucetConfig {
             amount: DEFAULT_AMOUNT,
             num_coins: DEFAULT_NUM_COINS,
             wallet_client_timeout_secs: 60,
+            additional_coins: vec![],
+            batch_interval_ms: DEFAULT_BATCH_INTERVAL_MS,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            max_requests_per_address: None,
+            max_requests_per_ip: None,
+            quota_window_secs: DEFAULT_QUOTA_WINDOW_SECS,
+            allowed_addresses: vec![],
+            allowed_ips: vec![],
+            denied_addresses: vec![],
+            denied_ips: vec![],
+            ledger_path: None,
+            admin_token: None,
+            x_forwarded_for_hops: None,
+        }
+    }
+}
+
+/// Where the faucet gets coins of an additional type from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoinSource {
+    /// Mint new coins using a `TreasuryCap`.
+    TreasuryCap(ObjectID),
+    /// Split new coins off an existing coin.
+    Coin(ObjectID),
+}
+
+/// A coin type that the faucet sends with each request, in addition to RTD.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdditionalCoin {
+    pub source: CoinSource,
+    /// Amount of the coin sent with each request.
+    pub amount: u64,
+}
+
+impl CoinSource {
+    pub fn object_id(&self) -> ObjectID {
+        match self {
+            CoinSource::TreasuryCap(id) | CoinSource::Coin(id) => *id,
         }
     }
 }
+
+impl FromStr for AdditionalCoin {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(':').collect();
+        let [kind, id, amount] = parts.as_slice() else {
+            bail!("Expected <treasury-cap|coin>:<OBJECT_ID>:<AMOUNT>, got {s:?}");
+        };
+
+        let id = ObjectID::from_str(id).with_context(|| format!("Invalid object ID {id:?}"))?;
+        let source = match *kind {
+            "treasury-cap" => CoinSource::TreasuryCap(id),
+            "coin" => CoinSource::Coin(id),
+            _ => bail!("Unknown coin source {kind:?}, expected 'treasury-cap' or 'coin'"),
+        };
+
+        let amount = amount
+            .parse()
+            .with_context(|| format!("Invalid amount {amount:?}"))?;
+
+        Ok(Self { source, amount })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_additional_coin() {
+        let id = ObjectID::from_hex_literal("0x42").unwrap();
+
+        assert_eq!(
+            "treasury-cap:0x42:1000".parse::<AdditionalCoin>().unwrap(),
+            AdditionalCoin {
+                source: CoinSource::TreasuryCap(id),
+                amount: 1000,
+            }
+        );
+
+        assert_eq!(
+            "coin:0x42:5".parse::<AdditionalCoin>().unwrap(),
+            AdditionalCoin {
+                source: CoinSource::Coin(id),
+                amount: 5,
+            }
+        );
+
+        assert!("vault:0x42:5".parse::<AdditionalCoin>().is_err());
+        assert!("coin:0x42".parse::<AdditionalCoin>().is_err());
+        assert!("coin:0x42:lots".parse::<AdditionalCoin>().is_err());
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rtd_sdk::types::base_types::RtdAddress;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{CoinInfo, FaucetError};
+
+/// Default number of grants returned by a ledger query.
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// Maximum number of grants returned by a ledger query.
+const MAX_QUERY_LIMIT: usize = 1000;
+
+/// A request that the faucet granted.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Grant {
+    /// Milliseconds since the Unix epoch when the request was granted.
+    pub timestamp_ms: u64,
+    pub recipient: RtdAddress,
+    /// The IP the request came from, if it is known.
+    pub source_ip: Option<IpAddr>,
+    pub coins_sent: Vec<CoinInfo>,
+}
+
+/// Criteria for selecting grants from the ledger.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerQuery {
+    pub recipient: Option<RtdAddress>,
+    pub source_ip: Option<IpAddr>,
+    /// Only include grants made at or after this time (milliseconds since the Unix epoch).
+    pub since_ms: Option<u64>,
+    /// Only include grants made before this time (milliseconds since the Unix epoch).
+    pub until_ms: Option<u64>,
+    /// Maximum number of grants to return (most recent first).
+    pub limit: Option<usize>,
+}
+
+/// A record of all the requests that the faucet has granted, in the order they were granted.
+///
+/// If the ledger is backed by a file, each grant is appended to it as a line of JSON as soon as it
+/// is recorded, and the file is replayed when the ledger is opened, so that the ledger survives
+/// restarts.
+///
+/// Requests that have been accepted but whose coins have not been sent yet are tracked as
+/// reservations, which are only kept in memory.
+pub struct RequestLedger {
+    grants: Vec<Grant>,
+    file: Option<File>,
+    reservations: BTreeMap<u64, Grant>,
+    next_reservation: u64,
+}
+
+impl RequestLedger {
+    /// A ledger that is only kept in memory.
+    pub fn in_memory() -> Self {
+        Self {
+            grants: vec![],
+            file: None,
+            reservations: BTreeMap::new(),
+            next_reservation: 0,
+        }
+    }
+
+    /// Open the ledger stored at `path`, creating it if it does not exist yet.
+    pub fn open(path: &Path) -> Result<Self, FaucetError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| {
+                FaucetError::internal(format!("Failed to open ledger {}: {e}", path.display()))
+            })?;
+
+        let mut grants: Vec<Grant> = vec![];
+        for (i, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line.map_err(|e| {
+                FaucetError::internal(format!("Failed to read ledger {}: {e}", path.display()))
+            })?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // A partially written line can be left behind if the faucet stopped while recording a
+            // grant. The grant was not acknowledged, so it is safe to skip.
+            match serde_json::from_str(&line) {
+                Ok(grant) => grants.push(grant),
+                Err(e) => warn!("Skipping malformed entry on line {} of ledger: {e}", i + 1),
+            }
+        }
+
+        // Make sure that the next grant starts on its own line, even if the file ends in a
+        // partially written line.
+        let mut last = [0u8];
+        if file.seek(SeekFrom::End(-1)).is_ok()
+            && file.read_exact(&mut last).is_ok()
+            && last[0] != b'\n'
+        {
+            file.write_all(b"\n").map_err(|e| {
+                FaucetError::internal(format!("Failed to repair ledger {}: {e}", path.display()))
+            })?;
+        }
+
+        grants.sort_by_key(|g| g.timestamp_ms);
+        Ok(Self {
+            grants,
+            file: Some(file),
+            reservations: BTreeMap::new(),
+            next_reservation: 0,
+        })
+    }
+
+    /// Add `grant` to the ledger. Grants are assumed to be recorded in time order, so `grant`'s
+    /// timestamp is bumped if it is earlier than the last recorded grant's (e.g. because the
+    /// clock went backwards).
+    pub fn record(&mut self, mut grant: Grant) -> Result<(), FaucetError> {
+        if let Some(last) = self.grants.last() {
+            grant.timestamp_ms = grant.timestamp_ms.max(last.timestamp_ms);
+        }
+
+        if let Some(file) = &mut self.file {
+            let mut line = serde_json::to_vec(&grant).map_err(FaucetError::internal)?;
+            line.push(b'\n');
+            file.write_all(&line)
+                .and_then(|_| file.sync_data())
+                .map_err(|e| FaucetError::internal(format!("Failed to write to ledger: {e}")))?;
+        }
+
+        self.grants.push(grant);
+        Ok(())
+    }
+
+    /// Reserve a grant for a request to send coins to `recipient` that has been accepted at
+    /// `timestamp_ms`, but not dispensed yet. Returns an identifier for the reservation, which must
+    /// later be either fulfilled or cancelled.
+    pub(crate) fn reserve(
+        &mut self,
+        recipient: RtdAddress,
+        source_ip: Option<IpAddr>,
+        timestamp_ms: u64,
+    ) -> u64 {
+        let reservation = self.next_reservation;
+        self.next_reservation += 1;
+        self.reservations.insert(
+            reservation,
+            Grant {
+                timestamp_ms,
+                recipient,
+                source_ip,
+                coins_sent: vec![],
+            },
+        );
+        reservation
+    }
+
+    /// Record the grant for `reservation`, now that `coins_sent` have been sent.
+    pub(crate) fn fulfil(
+        &mut self,
+        reservation: u64,
+        coins_sent: Vec<CoinInfo>,
+    ) -> Result<(), FaucetError> {
+        let Some(grant) = self.reservations.remove(&reservation) else {
+            return Err(FaucetError::internal(format!(
+                "Unknown reservation {reservation}"
+            )));
+        };
+
+        self.record(Grant {
+            timestamp_ms: now_ms(),
+            coins_sent,
+            ..grant
+        })
+    }
+
+    /// Forget `reservation`, because its request could not be dispensed.
+    pub(crate) fn cancel(&mut self, reservation: u64) {
+        self.reservations.remove(&reservation);
+    }
+
+    /// Grants that have been reserved but not fulfilled or cancelled yet.
+    pub(crate) fn reserved(&self) -> impl Iterator<Item = &Grant> {
+        self.reservations.values()
+    }
+
+    /// Grants recorded at or after `since_ms`, oldest first.
+    pub fn grants_since(&self, since_ms: u64) -> &[Grant] {
+        let start = self.grants.partition_point(|g| g.timestamp_ms < since_ms);
+        &self.grants[start..]
+    }
+
+    /// Grants matching `query`, most recent first.
+    pub fn query(&self, query: &LedgerQuery) -> Vec<Grant> {
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .min(MAX_QUERY_LIMIT);
+
+        self.grants_since(query.since_ms.unwrap_or(0))
+            .iter()
+            .rev()
+            .filter(|g| query.until_ms.is_none_or(|until| g.timestamp_ms < until))
+            .filter(|g| query.recipient.is_none_or(|r| g.recipient == r))
+            .filter(|g| query.source_ip.is_none_or(|ip| g.source_ip == Some(ip)))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// The current time in milliseconds since the Unix epoch.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtd_sdk::types::base_types::ObjectID;
+    use rtd_sdk::types::digests::TransactionDigest;
+
+    fn grant(timestamp_ms: u64, recipient: RtdAddress, source_ip: Option<IpAddr>) -> Grant {
+        Grant {
+            timestamp_ms,
+            recipient,
+            source_ip,
+            coins_sent: vec![CoinInfo {
+                amount: 1000,
+                id: ObjectID::random(),
+                transfer_tx_digest: TransactionDigest::random(),
+                coin_type: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_ledger_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let alice = RtdAddress::random_for_testing_only();
+        let bob = RtdAddress::random_for_testing_only();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let mut ledger = RequestLedger::open(&path).unwrap();
+        ledger.record(grant(100, alice, Some(ip))).unwrap();
+        ledger.record(grant(200, bob, None)).unwrap();
+        drop(ledger);
+
+        // Simulate a grant that was cut off part-way through being written.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"timestampMs\":300,\"recip").unwrap();
+        drop(file);
+
+        let mut ledger = RequestLedger::open(&path).unwrap();
+        assert_eq!(ledger.grants_since(0).len(), 2);
+        ledger.record(grant(400, alice, None)).unwrap();
+        drop(ledger);
+
+        let ledger = RequestLedger::open(&path).unwrap();
+        let timestamps: Vec<_> = ledger
+            .grants_since(0)
+            .iter()
+            .map(|g| (g.timestamp_ms, g.recipient))
+            .collect();
+        assert_eq!(timestamps, vec![(100, alice), (200, bob), (400, alice)]);
+        assert_eq!(ledger.grants_since(0)[0].source_ip, Some(ip));
+    }
+
+    #[test]
+    fn test_ledger_query() {
+        let alice = RtdAddress::random_for_testing_only();
+        let bob = RtdAddress::random_for_testing_only();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let mut ledger = RequestLedger::in_memory();
+        ledger.record(grant(100, alice, Some(ip))).unwrap();
+        ledger.record(grant(200, bob, Some(ip))).unwrap();
+        ledger.record(grant(300, alice, None)).unwrap();
+        // Out of order timestamps are bumped to keep the ledger sorted.
+        ledger.record(grant(250, bob, None)).unwrap();
+
+        let timestamps = |query: LedgerQuery| -> Vec<u64> {
+            ledger
+                .query(&query)
+                .iter()
+                .map(|g| g.timestamp_ms)
+                .collect()
+        };
+
+        assert_eq!(timestamps(LedgerQuery::default()), vec![300, 300, 200, 100]);
+
+        assert_eq!(
+            timestamps(LedgerQuery {
+                recipient: Some(alice),
+                ..Default::default()
+            }),
+            vec![300, 100],
+        );
+
+        assert_eq!(
+            timestamps(LedgerQuery {
+                source_ip: Some(ip),
+                ..Default::default()
+            }),
+            vec![200, 100],
+        );
+
+        assert_eq!(
+            timestamps(LedgerQuery {
+                since_ms: Some(150),
+                until_ms: Some(300),
+                ..Default::default()
+            }),
+            vec![200],
+        );
+
+        assert_eq!(
+            timestamps(LedgerQuery {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            vec![300],
+        );
+    }
+}
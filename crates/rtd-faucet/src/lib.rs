@@ -2,15 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod app_state;
+mod dispenser;
 mod errors;
 mod faucet_config;
+mod ledger;
 mod local_faucet;
+mod quota;
 mod server;
 mod types;
 
 pub use app_state::AppState;
 pub use errors::FaucetError;
-pub use faucet_config::FaucetConfig;
+pub use faucet_config::{AdditionalCoin, CoinSource, FaucetConfig};
+pub use ledger::{Grant, LedgerQuery, RequestLedger};
 pub use local_faucet::LocalFaucet;
 pub use server::{create_wallet_context, start_faucet};
-pub use types::{CoinInfo, FaucetRequest, FaucetResponse, FixedAmountRequest, RequestStatus};
+pub use types::{
+    CoinInfo, FaucetRequest, FaucetResponse, FixedAmountRequest, LedgerResponse, RequestStatus,
+};
@@ -2,41 +2,28 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::fmt;
+use std::net::IpAddr;
 use std::sync::Arc;
 
-use anyhow::bail;
-use tokio::sync::Mutex;
-use tokio::time::Duration;
+use tokio::sync::{Mutex, mpsc, oneshot};
 use tracing::info;
 
+use crate::CoinInfo;
 use crate::FaucetConfig;
 use crate::FaucetError;
-use rtd_sdk::{
-    rpc_types::{RtdTransactionBlockResponse, RtdTransactionBlockResponseOptions},
-    types::quorum_driver_types::ExecuteTransactionRequestType,
-};
-
-use crate::CoinInfo;
-use shared_crypto::intent::Intent;
-use rtd_keys::keystore::AccountKeystore;
-use rtd_sdk::rpc_types::RtdTransactionBlockEffectsAPI;
-use rtd_sdk::types::programmable_transaction_builder::ProgrammableTransactionBuilder;
-use rtd_sdk::types::{
-    base_types::{ObjectID, RtdAddress},
-    gas_coin::GasCoin,
-    transaction::{Transaction, TransactionData},
-};
+use crate::dispenser::{Dispenser, Request};
+use crate::ledger::{Grant, LedgerQuery, RequestLedger, now_ms};
+use crate::quota::Quotas;
+use rtd_sdk::types::{base_types::RtdAddress, gas_coin::GasCoin};
 use rtd_sdk::wallet_context::WalletContext;
 
-const GAS_BUDGET: u64 = 10_000_000;
-const NUM_RETRIES: u8 = 2;
-
 pub struct LocalFaucet {
-    wallet: WalletContext,
     active_address: RtdAddress,
-    coin_id: Arc<Mutex<ObjectID>>,
     coin_amount: u64,
     num_coins: usize,
+    quotas: Quotas,
+    ledger: Arc<Mutex<RequestLedger>>,
+    requests: mpsc::Sender<Request>,
 }
 
 /// We do not just derive(Debug) because WalletContext and the WriteAheadLog do not implement Debug / are also hard
@@ -58,12 +45,27 @@ impl LocalFaucet {
         let (coins, active_address) = find_gas_coins_and_address(&mut wallet, &config).await?;
         info!("Starting faucet with address: {:?}", active_address);
 
-        Ok(Arc::new(LocalFaucet {
+        let ledger = Arc::new(Mutex::new(match &config.ledger_path {
+            Some(path) => RequestLedger::open(path)?,
+            None => RequestLedger::in_memory(),
+        }));
+
+        let dispenser = Dispenser::new(
             wallet,
             active_address,
-            coin_id: Arc::new(Mutex::new(*coins[0].id())),
+            *coins[0].id(),
+            &config,
+            ledger.clone(),
+        )
+        .await?;
+
+        Ok(Arc::new(LocalFaucet {
+            active_address,
             coin_amount: config.amount,
             num_coins: config.num_coins,
+            quotas: Quotas::new(&config),
+            ledger,
+            requests: dispenser.spawn(),
         }))
     }
 
@@ -72,112 +74,47 @@ impl LocalFaucet {
         &self,
         recipient: RtdAddress,
     ) -> Result<Vec<CoinInfo>, FaucetError> {
-        let gas_price = self
-            .wallet
-            .get_reference_gas_price()
-            .await
-            .map_err(|e| FaucetError::internal(format!("Failed to get gas price: {}", e)))?;
-
-        let mut ptb = ProgrammableTransactionBuilder::new();
-        let recipients = vec![recipient; self.num_coins];
-        let amounts = vec![self.coin_amount; self.num_coins];
-        ptb.pay_rtd(recipients, amounts)
-            .map_err(FaucetError::internal)?;
-
-        let ptb = ptb.finish();
-
-        let coin_id = self.coin_id.lock().await;
-        let coin_id_ref = self
-            .wallet
-            .get_object_ref(*coin_id)
-            .await
-            .map_err(|e| FaucetError::internal(format!("Failed to get object ref: {}", e)))?;
-        let tx_data = TransactionData::new_programmable(
-            self.active_address,
-            vec![coin_id_ref],
-            ptb,
-            GAS_BUDGET,
-            gas_price,
-        );
-
-        let tx = self
-            .execute_txn_with_retries(tx_data, *coin_id, NUM_RETRIES)
-            .await
-            .map_err(FaucetError::internal)?;
-
-        let Some(ref effects) = tx.effects else {
-            return Err(FaucetError::internal(
-                "Failed to get coin id from response".to_string(),
-            ));
-        };
-
-        let coins: Vec<CoinInfo> = effects
-            .created()
-            .iter()
-            .map(|o| CoinInfo {
-                amount: self.coin_amount,
-                id: o.object_id(),
-                transfer_tx_digest: *effects.transaction_digest(),
-            })
-            .collect();
-
-        Ok(coins)
+        self.request_execute_tx(recipient, None).await
     }
 
-    async fn execute_txn(
+    /// Check the request against the faucet's quotas, and if it is allowed, queue it up to be sent
+    /// with the next batch of requests, and wait for its coins to be sent. `source_ip` is the IP
+    /// that the request came from, if it is known.
+    pub async fn request_execute_tx(
         &self,
-        tx_data: &TransactionData,
-        coin_id: ObjectID,
-    ) -> Result<RtdTransactionBlockResponse, anyhow::Error> {
-        let signature = self
-            .wallet
-            .config
-            .keystore
-            .sign_secure(&self.active_address, &tx_data, Intent::rtd_transaction())
+        recipient: RtdAddress,
+        source_ip: Option<IpAddr>,
+    ) -> Result<Vec<CoinInfo>, FaucetError> {
+        // Make room in the queue before reserving the grant, so that the request is guaranteed to
+        // be handed over to the dispenser (which fulfils or cancels the reservation) once it has
+        // been reserved, even if this future is dropped.
+        let permit = self
+            .requests
+            .reserve()
             .await
-            .map_err(FaucetError::internal)?;
-        let tx = Transaction::from_data(tx_data.clone(), vec![signature]);
-
-        let client = self.wallet.get_client().await?;
-
-        Ok(client
-            .quorum_driver_api()
-            .execute_transaction_block(
-                tx.clone(),
-                RtdTransactionBlockResponseOptions::new().with_effects(),
-                Some(ExecuteTransactionRequestType::WaitForLocalExecution),
-            )
+            .map_err(|_| FaucetError::internal("Faucet is shutting down"))?;
+
+        let (reply, response) = oneshot::channel();
+        {
+            let mut ledger = self.ledger.lock().await;
+            self.quotas.check(&ledger, recipient, source_ip, now_ms())?;
+
+            let reservation = ledger.reserve(recipient, source_ip, now_ms());
+            permit.send(Request {
+                recipient,
+                reservation,
+                reply,
+            });
+        }
+
+        response
             .await
-            .map_err(|e| {
-                FaucetError::internal(format!(
-                    "Failed to execute PayRtd transaction for coin {:?}, with err {:?}",
-                    coin_id, e
-                ))
-            })?)
+            .map_err(|_| FaucetError::internal("Request was dropped before it was dispensed"))?
     }
 
-    async fn execute_txn_with_retries(
-        &self,
-        tx: TransactionData,
-        coin_id: ObjectID,
-        num_retries: u8,
-    ) -> Result<RtdTransactionBlockResponse, anyhow::Error> {
-        let mut retry_delay = Duration::from_millis(500);
-        let mut i = 0;
-
-        loop {
-            if i == num_retries {
-                bail!("Failed to execute transaction after {num_retries} retries",);
-            }
-            let res = self.execute_txn(&tx, coin_id).await;
-
-            if res.is_ok() {
-                return res;
-            }
-            i += 1;
-            tokio::time::sleep(retry_delay).await;
-            retry_delay *= 2;
-        }
+    /// Grants recorded in the faucet's ledger that match `query`, most recent first.
+    pub async fn ledger_entries(&self, query: &LedgerQuery) -> Vec<Grant> {
+        self.ledger.lock().await.query(query)
     }
 
     pub fn get_coin_amount(&self) -> u64 {
@@ -261,6 +198,49 @@ mod tests {
         assert_eq!(coins.data.len(), 2 * local_faucet.num_coins);
     }
 
+    #[tokio::test]
+    async fn test_local_faucet_batches_requests() {
+        let cluster = TestClusterBuilder::new().build().await;
+        let client = cluster.rtd_client().clone();
+
+        let config = FaucetConfig {
+            batch_interval_ms: 1000,
+            ..Default::default()
+        };
+        let local_faucet = LocalFaucet::new(cluster.wallet, config).await.unwrap();
+
+        let mut requests = tokio::task::JoinSet::new();
+        let recipients: Vec<_> = (0..5)
+            .map(|_| RtdAddress::random_for_testing_only())
+            .collect();
+        for recipient in &recipients {
+            let faucet = local_faucet.clone();
+            let recipient = *recipient;
+            requests.spawn(async move { faucet.local_request_execute_tx(recipient).await });
+        }
+
+        // The first request may be sent on its own, but the rest arrive within the same tick, so
+        // they are sent together.
+        let mut digests = std::collections::BTreeSet::new();
+        while let Some(coins) = requests.join_next().await {
+            let coins = coins.unwrap().unwrap();
+            assert_eq!(coins.len(), local_faucet.num_coins);
+            digests.extend(coins.iter().map(|c| c.transfer_tx_digest));
+        }
+
+        assert!(digests.len() <= 2, "Too many transactions: {digests:?}");
+
+        for recipient in recipients {
+            let coins = client
+                .coin_read_api()
+                .get_coins(recipient, None, None, None)
+                .await
+                .unwrap();
+
+            assert_eq!(coins.data.len(), local_faucet.num_coins);
+        }
+    }
+
     #[tokio::test]
     async fn test_find_gas_coins_and_address() {
         let mut cluster = TestClusterBuilder::new().build().await;
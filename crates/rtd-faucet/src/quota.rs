@@ -0,0 +1,305 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+
+use rtd_sdk::types::base_types::RtdAddress;
+
+use crate::FaucetError;
+use crate::FaucetConfig;
+use crate::ledger::{Grant, RequestLedger};
+
+/// Decides whether a request can be granted, based on allow and deny lists, and on the number of
+/// requests recently granted to the same recipient or source IP.
+pub(crate) struct Quotas {
+    max_per_address: Option<u32>,
+    max_per_ip: Option<u32>,
+    window_ms: u64,
+    allowed_addresses: BTreeSet<RtdAddress>,
+    allowed_ips: BTreeSet<IpAddr>,
+    denied_addresses: BTreeSet<RtdAddress>,
+    denied_ips: BTreeSet<IpAddr>,
+}
+
+impl Quotas {
+    pub(crate) fn new(config: &FaucetConfig) -> Self {
+        Self {
+            max_per_address: config.max_requests_per_address,
+            max_per_ip: config.max_requests_per_ip,
+            window_ms: config.quota_window_secs.saturating_mul(1000),
+            allowed_addresses: config.allowed_addresses.iter().copied().collect(),
+            allowed_ips: config.allowed_ips.iter().copied().collect(),
+            denied_addresses: config.denied_addresses.iter().copied().collect(),
+            denied_ips: config.denied_ips.iter().copied().collect(),
+        }
+    }
+
+    /// Check whether a request to send coins to `recipient`, coming from `source_ip`, can be
+    /// granted at `now_ms`, given the grants already in `ledger`.
+    ///
+    /// Denied addresses and IPs are always rejected, and allowed addresses and IPs are never
+    /// subject to quotas. If a quota is exceeded, the error reports when the next request will be
+    /// allowed.
+    pub(crate) fn check(
+        &self,
+        ledger: &RequestLedger,
+        recipient: RtdAddress,
+        source_ip: Option<IpAddr>,
+        now_ms: u64,
+    ) -> Result<(), FaucetError> {
+        if self.denied_addresses.contains(&recipient) {
+            return Err(FaucetError::Denied(format!(
+                "address {recipient} is not allowed to request coins"
+            )));
+        }
+
+        if let Some(ip) = source_ip
+            && self.denied_ips.contains(&ip)
+        {
+            return Err(FaucetError::Denied(format!(
+                "IP {ip} is not allowed to request coins"
+            )));
+        }
+
+        if self.allowed_addresses.contains(&recipient)
+            || source_ip.is_some_and(|ip| self.allowed_ips.contains(&ip))
+        {
+            return Ok(());
+        }
+
+        // Requests that are still being dispensed count towards quotas as well.
+        let window_start = now_ms.saturating_sub(self.window_ms).saturating_add(1);
+        let recent: Vec<_> = ledger
+            .grants_since(window_start)
+            .iter()
+            .chain(ledger.reserved())
+            .collect();
+
+        let by_address = self.max_per_address.map(|max| {
+            let limit = format!("for address {recipient}");
+            (
+                limit,
+                max,
+                self.next_allowed(&recent, max, |g| g.recipient == recipient),
+            )
+        });
+
+        let by_ip = source_ip.zip(self.max_per_ip).map(|(ip, max)| {
+            let limit = format!("from IP {ip}");
+            (
+                limit,
+                max,
+                self.next_allowed(&recent, max, |g| g.source_ip == Some(ip)),
+            )
+        });
+
+        // If both quotas are exceeded, report the one that will take longest to recover.
+        let Some((limit, max, next_allowed_at_ms)) = by_address
+            .into_iter()
+            .chain(by_ip)
+            .filter_map(|(limit, max, next)| Some((limit, max, next?)))
+            .max_by_key(|(_, _, next)| *next)
+        else {
+            return Ok(());
+        };
+
+        if max == 0 {
+            return Err(FaucetError::Denied(format!(
+                "no requests are allowed {limit}"
+            )));
+        }
+
+        Err(FaucetError::TooManyRequests {
+            limit: format!("{limit} (at most {max} per {}s)", self.window_ms / 1000),
+            next_allowed_at_ms,
+            retry_after_secs: next_allowed_at_ms.saturating_sub(now_ms).div_ceil(1000),
+        })
+    }
+
+    /// If `recent` already contains `max` grants that satisfy `pred`, returns the time at which
+    /// enough of them will have left the window for another request to be granted.
+    fn next_allowed(
+        &self,
+        recent: &[&Grant],
+        max: u32,
+        pred: impl Fn(&Grant) -> bool,
+    ) -> Option<u64> {
+        let mut timestamps: Vec<_> = recent
+            .iter()
+            .copied()
+            .filter(|g| pred(*g))
+            .map(|g| g.timestamp_ms)
+            .collect();
+        timestamps.sort();
+
+        // A quota of zero never allows any requests.
+        if max == 0 {
+            return Some(u64::MAX);
+        }
+
+        // The next request is allowed once all but `max - 1` of the grants have left the window.
+        let expiring = timestamps.len().checked_sub(max as usize)?;
+        Some(timestamps[expiring].saturating_add(self.window_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(timestamp_ms: u64, recipient: RtdAddress, source_ip: Option<IpAddr>) -> Grant {
+        Grant {
+            timestamp_ms,
+            recipient,
+            source_ip,
+            coins_sent: vec![],
+        }
+    }
+
+    #[test]
+    fn test_address_quota() {
+        let quotas = Quotas::new(&FaucetConfig {
+            max_requests_per_address: Some(2),
+            quota_window_secs: 10,
+            ..Default::default()
+        });
+
+        let alice = RtdAddress::random_for_testing_only();
+        let bob = RtdAddress::random_for_testing_only();
+
+        let mut ledger = RequestLedger::in_memory();
+        ledger.record(grant(1_000, alice, None)).unwrap();
+        ledger.record(grant(4_000, alice, None)).unwrap();
+        ledger.record(grant(5_000, bob, None)).unwrap();
+
+        // Alice's quota is used up until the first grant leaves the window.
+        let err = quotas.check(&ledger, alice, None, 6_000).unwrap_err();
+        assert_eq!(
+            err,
+            FaucetError::TooManyRequests {
+                limit: format!("for address {alice} (at most 2 per 10s)"),
+                next_allowed_at_ms: 11_000,
+                retry_after_secs: 5,
+            }
+        );
+
+        assert!(quotas.check(&ledger, alice, None, 11_000).is_ok());
+        assert!(quotas.check(&ledger, bob, None, 6_000).is_ok());
+    }
+
+    #[test]
+    fn test_ip_quota() {
+        let quotas = Quotas::new(&FaucetConfig {
+            max_requests_per_address: Some(2),
+            max_requests_per_ip: Some(1),
+            quota_window_secs: 10,
+            ..Default::default()
+        });
+
+        let alice = RtdAddress::random_for_testing_only();
+        let bob = RtdAddress::random_for_testing_only();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let mut ledger = RequestLedger::in_memory();
+        ledger.record(grant(1_000, alice, Some(ip))).unwrap();
+        ledger.record(grant(2_000, alice, Some(other))).unwrap();
+
+        // Bob has not received anything, but the IP has used up its quota.
+        let err = quotas.check(&ledger, bob, Some(ip), 3_000).unwrap_err();
+        assert!(matches!(
+            err,
+            FaucetError::TooManyRequests {
+                next_allowed_at_ms: 11_000,
+                retry_after_secs: 8,
+                ..
+            }
+        ));
+
+        // Alice has exceeded both quotas, so the later of the two is reported.
+        let err = quotas.check(&ledger, alice, Some(ip), 3_000).unwrap_err();
+        assert!(matches!(
+            err,
+            FaucetError::TooManyRequests {
+                next_allowed_at_ms: 11_000,
+                ..
+            }
+        ));
+
+        // Requests without a known source IP are only subject to the address quota.
+        assert!(quotas.check(&ledger, bob, None, 3_000).is_ok());
+    }
+
+    #[test]
+    fn test_reservations_count_towards_quota() {
+        let quotas = Quotas::new(&FaucetConfig {
+            max_requests_per_address: Some(1),
+            quota_window_secs: 10,
+            ..Default::default()
+        });
+
+        let alice = RtdAddress::random_for_testing_only();
+        let mut ledger = RequestLedger::in_memory();
+
+        let reservation = ledger.reserve(alice, None, 1_000);
+        assert!(matches!(
+            quotas.check(&ledger, alice, None, 2_000),
+            Err(FaucetError::TooManyRequests {
+                next_allowed_at_ms: 11_000,
+                ..
+            })
+        ));
+
+        // Cancelled reservations no longer count.
+        ledger.cancel(reservation);
+        assert!(quotas.check(&ledger, alice, None, 2_000).is_ok());
+
+        let reservation = ledger.reserve(alice, None, 2_000);
+        ledger.fulfil(reservation, vec![]).unwrap();
+        assert_eq!(ledger.reserved().count(), 0);
+        assert_eq!(ledger.grants_since(0).len(), 1);
+        assert!(quotas.check(&ledger, alice, None, 3_000).is_err());
+    }
+
+    #[test]
+    fn test_allow_and_deny_lists() {
+        let alice = RtdAddress::random_for_testing_only();
+        let bob = RtdAddress::random_for_testing_only();
+        let trusted: IpAddr = "10.0.0.1".parse().unwrap();
+        let banned: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let quotas = Quotas::new(&FaucetConfig {
+            max_requests_per_address: Some(0),
+            allowed_addresses: vec![alice],
+            allowed_ips: vec![trusted],
+            denied_ips: vec![banned],
+            ..Default::default()
+        });
+
+        let ledger = RequestLedger::in_memory();
+        assert!(quotas.check(&ledger, alice, None, 0).is_ok());
+        assert!(quotas.check(&ledger, bob, Some(trusted), 0).is_ok());
+        assert!(matches!(
+            quotas.check(&ledger, bob, None, 0),
+            Err(FaucetError::Denied(_))
+        ));
+        assert!(matches!(
+            quotas.check(&ledger, alice, Some(banned), 0),
+            Err(FaucetError::Denied(_))
+        ));
+
+        let quotas = Quotas::new(&FaucetConfig {
+            allowed_ips: vec![trusted],
+            denied_addresses: vec![alice],
+            ..Default::default()
+        });
+
+        // Deny lists take precedence over allow lists.
+        assert!(matches!(
+            quotas.check(&ledger, alice, Some(trusted), 0),
+            Err(FaucetError::Denied(_))
+        ));
+        assert!(quotas.check(&ledger, bob, None, 0).is_ok());
+    }
+}
@@ -2,15 +2,16 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::types::*;
-use crate::{AppState, FaucetConfig, FaucetError, FaucetRequest};
+use crate::{AppState, FaucetConfig, FaucetError, FaucetRequest, LedgerQuery};
 use axum::{
     BoxError, Extension, Json, Router,
     error_handling::HandleErrorLayer,
+    extract::{ConnectInfo, Query},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
 };
-use http::Method;
+use http::{HeaderMap, HeaderValue, Method, header};
 use std::{
     borrow::Cow,
     net::{IpAddr, SocketAddr},
@@ -22,7 +23,7 @@ use rtd_config::RTD_CLIENT_CONFIG;
 use rtd_sdk::wallet_context::WalletContext;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
 
 /// basic handler that responds with a static string
 async fn health() -> &'static str {
@@ -31,46 +32,99 @@ async fn health() -> &'static str {
 
 async fn request_local_gas(
     Extension(state): Extension<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<FaucetRequest>,
-    // ) -> &'static str {
 ) -> impl IntoResponse {
     let FaucetRequest::FixedAmountRequest(request) = payload;
-    info!("Local request for address: {}", request.recipient);
+    let source_ip = client_ip(&state.config, addr, &headers);
+    info!(
+        "Local request for address: {} from {}",
+        request.recipient, source_ip
+    );
+
     let request = state
         .faucet
-        .local_request_execute_tx(request.recipient)
+        .request_execute_tx(request.recipient, Some(source_ip))
         .await;
 
-    if let Err(e) = request {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
+    match request {
+        Ok(coins) => (
+            StatusCode::OK,
+            HeaderMap::new(),
             Json(FaucetResponse {
-                status: RequestStatus::Failure(e),
-                coins_sent: None,
+                status: RequestStatus::Success,
+                coins_sent: Some(coins),
             }),
-        );
+        ),
+
+        Err(e) => {
+            let mut headers = HeaderMap::new();
+            let status = match &e {
+                FaucetError::Denied(_) => StatusCode::FORBIDDEN,
+                FaucetError::TooManyRequests {
+                    retry_after_secs, ..
+                } => {
+                    headers.insert(header::RETRY_AFTER, HeaderValue::from(*retry_after_secs));
+                    StatusCode::TOO_MANY_REQUESTS
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+
+            (status, headers, Json(FaucetResponse::from(e)))
+        }
     }
+}
 
-    let Ok(coins) = request else {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(FaucetResponse {
-                status: RequestStatus::Failure(FaucetError::internal(format!(
-                    "Failed to execute transaction: {}",
-                    request.unwrap_err()
-                ))),
-                coins_sent: None,
-            }),
-        );
+/// Query the ledger of requests that the faucet has granted. Requires the admin token to be
+/// supplied as a bearer token.
+async fn admin_ledger(
+    Extension(state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<LedgerQuery>,
+) -> impl IntoResponse {
+    let authorized = state.config.admin_token.as_ref().is_some_and(|token| {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|supplied| supplied == token)
+    });
+
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(Json(LedgerResponse {
+        grants: state.faucet.ledger_entries(&query).await,
+    }))
+}
+
+/// The IP that a request originated from. If the faucet is configured to sit behind proxies, this
+/// is read from the `X-Forwarded-For` header, skipping over the configured number of proxies, and
+/// otherwise it is the IP of the connection's peer.
+fn client_ip(config: &FaucetConfig, addr: SocketAddr, headers: &HeaderMap) -> IpAddr {
+    let Some(num_hops) = config.x_forwarded_for_hops else {
+        return addr.ip();
     };
 
-    (
-        StatusCode::OK,
-        Json(FaucetResponse {
-            status: RequestStatus::Success,
-            coins_sent: Some(coins),
-        }),
-    )
+    let forwarded: Option<Vec<_>> = headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').map(str::trim).collect());
+
+    let ip = forwarded.as_ref().and_then(|contents| {
+        let i = contents.len().checked_sub(num_hops)?;
+        contents.get(i)?.parse().ok()
+    });
+
+    ip.unwrap_or_else(|| {
+        warn!(
+            "Could not find client IP {num_hops} hop(s) back in x-forwarded-for: {forwarded:?}, \
+             using peer address {addr}",
+        );
+        addr.ip()
+    })
 }
 
 pub fn create_wallet_context(
@@ -106,22 +160,32 @@ pub async fn start_faucet(app_state: Arc<AppState>) -> Result<(), anyhow::Error>
         .allow_methods(vec![Method::GET, Method::POST])
         .allow_headers(Any)
         .allow_origin(Any);
-    let FaucetConfig { port, host_ip, .. } = app_state.config;
+    let FaucetConfig {
+        port,
+        host_ip,
+        ref admin_token,
+        ..
+    } = app_state.config;
 
     info!("Starting faucet in local mode");
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/", get(health))
         .route("/v2/gas", post(request_local_gas))
         .route("/v1/gas", post(request_local_gas))
-        .route("/gas", post(request_local_gas))
-        .layer(
-            ServiceBuilder::new()
-                .layer(HandleErrorLayer::new(handle_error))
-                .load_shed()
-                .layer(Extension(app_state.clone()))
-                .layer(cors)
-                .into_inner(),
-        );
+        .route("/gas", post(request_local_gas));
+
+    if admin_token.is_some() {
+        app = app.route("/admin/ledger", get(admin_ledger));
+    }
+
+    let app = app.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(handle_error))
+            .load_shed()
+            .layer(Extension(app_state.clone()))
+            .layer(cors)
+            .into_inner(),
+    );
 
     let addr = SocketAddr::new(IpAddr::V4(host_ip), port);
     info!("listening on {}", addr);
@@ -203,4 +267,88 @@ mod tests {
         assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
         handle.abort();
     }
+
+    #[tokio::test]
+    async fn test_gas_endpoint_quota() {
+        let cluster = TestClusterBuilder::new().build().await;
+        let port = 9091;
+        let config = FaucetConfig {
+            host_ip: "127.0.0.1".parse().unwrap(),
+            port,
+            max_requests_per_address: Some(1),
+            admin_token: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let local_faucet = LocalFaucet::new(cluster.wallet, config.clone())
+            .await
+            .unwrap();
+
+        let app_state = Arc::new(AppState {
+            faucet: local_faucet,
+            config,
+        });
+
+        let handle = tokio::spawn(async move {
+            start_faucet(app_state)
+                .await
+                .expect("Failed to start faucet");
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let recipient = RtdAddress::random_for_testing_only();
+        let req = FaucetRequest::new_fixed_amount_request(recipient);
+
+        let response = client
+            .post(format!("http://127.0.0.1:{port}/v2/gas"))
+            .json(&req)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The second request for the same address exceeds its quota.
+        let response = client
+            .post(format!("http://127.0.0.1:{port}/v2/gas"))
+            .json(&req)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        let faucet_response = response.json::<FaucetResponse>().await.unwrap();
+        assert!(matches!(
+            faucet_response.status,
+            RequestStatus::Failure(FaucetError::TooManyRequests { .. })
+        ));
+
+        // The admin endpoint requires the token.
+        let response = client
+            .get(format!("http://127.0.0.1:{port}/admin/ledger"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .get(format!(
+                "http://127.0.0.1:{port}/admin/ledger?recipient={recipient}"
+            ))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let ledger = response.json::<LedgerResponse>().await.unwrap();
+        assert_eq!(ledger.grants.len(), 1);
+        assert_eq!(ledger.grants[0].recipient, recipient);
+        assert_eq!(
+            ledger.grants[0].source_ip,
+            Some("127.0.0.1".parse().unwrap())
+        );
+        handle.abort();
+    }
 }
@@ -1,7 +1,7 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::FaucetError;
+use crate::{FaucetError, Grant};
 use serde::{Deserialize, Serialize};
 use rtd_sdk::types::{
     base_types::{ObjectID, RtdAddress},
@@ -44,6 +44,14 @@ pub struct CoinInfo {
     pub amount: u64,
     pub id: ObjectID,
     pub transfer_tx_digest: TransactionDigest,
+    /// The type of coin that was sent, e.g. `0x2::rtd::RTD`.
+    #[serde(default)]
+    pub coin_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LedgerResponse {
+    pub grants: Vec<Grant>,
 }
 
 impl From<FaucetError> for FaucetResponse {
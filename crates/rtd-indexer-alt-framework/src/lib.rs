@@ -1,7 +1,12 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeSet, sync::Arc, time::Duration};
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context, bail, ensure};
 use ingestion::{ClientArgs, IngestionConfig, IngestionService, ingestion_client::IngestionClient};
@@ -23,6 +28,7 @@ use crate::metrics::IngestionMetrics;
 use crate::pipeline::{
     Processor,
     concurrent::{self, ConcurrentConfig},
+    derived::{Taps, Upstream, upstream_watermark},
     sequential::{self, Handler, SequentialConfig},
 };
 use crate::service::Service;
@@ -34,6 +40,8 @@ pub mod metrics;
 pub mod pipeline;
 #[cfg(feature = "postgres")]
 pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
 #[cfg(test)]
 pub mod mocks;
@@ -134,6 +142,15 @@ pub struct Indexer<S: Store> {
     /// with the same name isn't added twice.
     added_pipelines: BTreeSet<&'static str>,
 
+    /// The taps on the processors of the pipelines that will run, by pipeline name, for pipelines
+    /// derived from them to subscribe to. Each is a `Taps<P>` for the pipeline's processor, `P`.
+    taps: HashMap<&'static str, Box<dyn Any + Send + Sync>>,
+
+    /// Upstreams created by [Self::upstream] that have not been claimed by a derived pipeline yet,
+    /// with the names of the pipelines they are the output of. They are claimed by the next
+    /// pipeline to be added.
+    unclaimed_upstreams: Vec<(&'static str, ClaimUpstream)>,
+
     /// The checkpoint for the indexer to start ingesting from. This is derived from the committer
     /// watermarks of pipelines added to the indexer. Pipelines without watermarks default to 0,
     /// unless overridden by [Self::default_next_checkpoint].
@@ -147,6 +164,11 @@ pub struct Indexer<S: Store> {
     pipelines: Vec<Service>,
 }
 
+/// Connects an [Upstream] to the pipeline derived from it, given the derived pipeline's task and
+/// the first checkpoint it will process, returning the task that tracks both pipelines'
+/// watermarks for it.
+type ClaimUpstream = Box<dyn FnOnce(String, u64) -> Service + Send + Sync>;
+
 /// Configuration for a tasked indexer.
 #[derive(Clone)]
 pub(crate) struct Task {
@@ -219,6 +241,8 @@ impl<S: Store> Indexer<S> {
                 Some(pipeline.into_iter().collect())
             },
             added_pipelines: BTreeSet::new(),
+            taps: HashMap::new(),
+            unclaimed_upstreams: vec![],
             first_ingestion_checkpoint: u64::MAX,
             next_sequential_checkpoint: None,
             pipelines: vec![],
@@ -286,18 +310,73 @@ impl<S: Store> Indexer<S> {
             self.store.clone(),
             self.task.clone(),
             self.ingestion_service.subscribe().0,
+            self.add_taps::<H>(),
             self.metrics.clone(),
         ));
 
         Ok(())
     }
 
+    /// The output of pipeline `P`, which must already have been added to this indexer, for the
+    /// processors of pipelines derived from it to consume. Derived pipelines are added like any
+    /// other pipeline, and are passed the returned [Upstream] to read `P`'s values for each
+    /// checkpoint from, once `P` has committed them, instead of decoding the checkpoint again.
+    ///
+    /// The returned [Upstream] is claimed by the next pipeline added to this indexer, which must be
+    /// the pipeline derived from it. `P`'s values are only passed to it once it has been claimed,
+    /// starting from the derived pipeline's first checkpoint, and if the derived pipeline is not
+    /// enabled, they are never passed to it. The indexer fails to run if an [Upstream] is not
+    /// claimed.
+    ///
+    /// Fails if `P` was not added, or is not enabled.
+    pub fn upstream<P: Processor>(&mut self) -> Result<Upstream<P>>
+    where
+        P::Value: Clone,
+    {
+        let Some(taps) = self.taps.get(P::NAME) else {
+            bail!(
+                "Cannot derive from pipeline {:?}: it has not been added, or is not enabled",
+                P::NAME,
+            );
+        };
+
+        let Some(taps) = taps.downcast_ref::<Taps<P>>() else {
+            bail!(
+                "Cannot derive from pipeline {:?}: it was added with a different processor",
+                P::NAME,
+            );
+        };
+
+        let upstream_task =
+            pipeline_task::<S>(P::NAME, self.task.as_ref().map(|t| t.task.as_str()))?;
+
+        let (upstream, tap) = Upstream::new();
+        let claim: ClaimUpstream = Box::new({
+            let upstream = upstream.clone();
+            let taps = taps.clone();
+            let store = self.store.clone();
+            move |derived_task, next_checkpoint| {
+                upstream.skip_below(next_checkpoint);
+                taps.write().unwrap().push(tap);
+                upstream_watermark::<P, S>(upstream, upstream_task, derived_task, store)
+            }
+        });
+
+        self.unclaimed_upstreams.push((P::NAME, claim));
+        Ok(upstream)
+    }
+
     /// Start ingesting checkpoints from `first_ingestion_checkpoint`. Individual pipelines
     /// will start processing and committing once the ingestion service has caught up to their
     /// respective watermarks.
     ///
     /// Ingestion will stop after consuming the configured `last_checkpoint` if one is provided.
     pub async fn run(self) -> Result<Service> {
+        if !self.unclaimed_upstreams.is_empty() {
+            let upstreams: Vec<_> = self.unclaimed_upstreams.iter().map(|(p, _)| *p).collect();
+            bail!("No pipelines were added to derive from the upstreams of: {upstreams:?}");
+        }
+
         if let Some(enabled_pipelines) = self.enabled_pipelines {
             ensure!(
                 enabled_pipelines.is_empty(),
@@ -333,6 +412,9 @@ impl<S: Store> Indexer<S> {
     /// Update the starting ingestion checkpoint as the minimum across all the next checkpoints
     /// calculated above.
     ///
+    /// The pipeline claims the upstreams created since the last pipeline was added, as the
+    /// pipeline derived from them.
+    ///
     /// Returns `Ok(None)` if the pipeline is disabled.
     async fn add_pipeline<P: Processor + 'static>(&mut self) -> Result<Option<u64>> {
         ensure!(
@@ -341,6 +423,8 @@ impl<S: Store> Indexer<S> {
             P::NAME,
         );
 
+        let upstreams = std::mem::take(&mut self.unclaimed_upstreams);
+
         if let Some(enabled_pipelines) = &mut self.enabled_pipelines
             && !enabled_pipelines.remove(P::NAME)
         {
@@ -367,8 +451,23 @@ impl<S: Store> Indexer<S> {
 
         self.first_ingestion_checkpoint = next_checkpoint.min(self.first_ingestion_checkpoint);
 
+        for (_, claim) in upstreams {
+            // The task polling the upstream watermark should not keep the indexer running by
+            // itself.
+            let watermarks = claim(pipeline_task.clone(), next_checkpoint);
+            self.pipelines.push(Service::new().attach(watermarks));
+        }
+
         Ok(Some(next_checkpoint))
     }
+
+    /// Create the taps for the processor of pipeline `P`, and register them so that pipelines can
+    /// be derived from it.
+    fn add_taps<P: Processor>(&mut self) -> Taps<P> {
+        let taps = Taps::<P>::default();
+        self.taps.insert(P::NAME, Box::new(taps.clone()));
+        taps
+    }
 }
 
 impl<T: TransactionalStore> Indexer<T> {
@@ -410,6 +509,7 @@ impl<T: TransactionalStore> Indexer<T> {
 
         let (checkpoint_rx, watermark_tx) = self.ingestion_service.subscribe();
 
+        let taps = self.add_taps::<H>();
         self.pipelines.push(sequential::pipeline::<H>(
             handler,
             next_checkpoint,
@@ -417,6 +517,7 @@ impl<T: TransactionalStore> Indexer<T> {
             self.store.clone(),
             checkpoint_rx,
             watermark_tx,
+            taps,
             self.metrics.clone(),
         ));
 
@@ -2190,4 +2291,161 @@ mod tests {
             );
         }
     }
+
+    /// A pipeline derived from [MockHandler], doubling its values.
+    struct DerivedHandler {
+        upstream: Upstream<MockHandler>,
+    }
+
+    #[async_trait]
+    impl Processor for DerivedHandler {
+        const NAME: &'static str = "derived";
+        type Value = MockValue;
+
+        async fn process(
+            &self,
+            checkpoint: &Arc<rtd_types::full_checkpoint_content::Checkpoint>,
+        ) -> anyhow::Result<Vec<Self::Value>> {
+            self.upstream
+                .derive(checkpoint.summary.sequence_number, |values| {
+                    Ok(values.iter().map(|v| MockValue(v.0 * 2)).collect())
+                })
+                .await
+        }
+    }
+
+    #[async_trait]
+    impl crate::pipeline::concurrent::Handler for DerivedHandler {
+        type Store = MockStore;
+        type Batch = Vec<MockValue>;
+
+        fn batch(
+            &self,
+            batch: &mut Self::Batch,
+            values: &mut std::vec::IntoIter<Self::Value>,
+        ) -> crate::pipeline::concurrent::BatchStatus {
+            batch.extend(values);
+            crate::pipeline::concurrent::BatchStatus::Pending
+        }
+
+        async fn commit<'a>(
+            &self,
+            batch: &Self::Batch,
+            conn: &mut <Self::Store as Store>::Connection<'a>,
+        ) -> anyhow::Result<usize> {
+            for value in batch {
+                conn.0
+                    .commit_data(Self::NAME, value.0 / 2, vec![value.0])
+                    .await?;
+            }
+            Ok(batch.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_derived_pipeline() {
+        let registry = Registry::new();
+        let store = MockStore::default();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        synthetic_ingestion::generate_ingestion(synthetic_ingestion::Config {
+            ingestion_dir: temp_dir.path().to_owned(),
+            starting_checkpoint: 0,
+            num_checkpoints: 10,
+            checkpoint_size: 1,
+        })
+        .await;
+
+        let mut indexer = Indexer::new(
+            store.clone(),
+            IndexerArgs {
+                last_checkpoint: Some(9),
+                ..Default::default()
+            },
+            ClientArgs {
+                ingestion: IngestionClientArgs {
+                    local_ingestion_path: Some(temp_dir.path().to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            IngestionConfig::default(),
+            None,
+            &registry,
+        )
+        .await
+        .unwrap();
+
+        // Pipelines can only be derived from pipelines that have been added.
+        assert!(indexer.upstream::<MockHandler>().is_err());
+
+        indexer
+            .concurrent_pipeline(MockHandler, ConcurrentConfig::default())
+            .await
+            .unwrap();
+        let upstream = indexer.upstream::<MockHandler>().unwrap();
+        indexer
+            .concurrent_pipeline(DerivedHandler { upstream }, ConcurrentConfig::default())
+            .await
+            .unwrap();
+
+        indexer.run().await.unwrap().join().await.unwrap();
+
+        let data = store.data.get(DerivedHandler::NAME).unwrap();
+        for cp in 0..10 {
+            assert_eq!(*data.get(&cp).unwrap(), vec![cp * 2]);
+        }
+
+        // Values are only derived once the upstream pipeline has committed them.
+        let upstream = store.watermark(MockHandler::NAME).unwrap();
+        assert_eq!(upstream.checkpoint_hi_inclusive, 9);
+    }
+
+    #[tokio::test]
+    async fn test_unclaimed_upstreams() {
+        let registry = Registry::new();
+        let store = MockStore::default();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let mut indexer = Indexer::new(
+            store,
+            IndexerArgs {
+                pipeline: vec![MockHandler::NAME.to_owned()],
+                ..Default::default()
+            },
+            ClientArgs {
+                ingestion: IngestionClientArgs {
+                    local_ingestion_path: Some(temp_dir.path().to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            IngestionConfig::default(),
+            None,
+            &registry,
+        )
+        .await
+        .unwrap();
+
+        indexer
+            .concurrent_pipeline(MockHandler, ConcurrentConfig::default())
+            .await
+            .unwrap();
+
+        // The derived pipeline is not enabled, so the upstream pipeline's values are not passed to
+        // it.
+        let upstream = indexer.upstream::<MockHandler>().unwrap();
+        indexer
+            .concurrent_pipeline(DerivedHandler { upstream }, ConcurrentConfig::default())
+            .await
+            .unwrap();
+
+        let taps = indexer.taps.get(MockHandler::NAME).unwrap();
+        let taps = taps.downcast_ref::<Taps<MockHandler>>().unwrap();
+        assert!(taps.read().unwrap().is_empty());
+
+        // An upstream that no pipeline is derived from stops the indexer from running.
+        indexer.upstream::<MockHandler>().unwrap();
+        assert!(indexer.run().await.is_err());
+    }
 }
@@ -13,7 +13,9 @@ use crate::{
     Task, metrics::IndexerMetrics, store::Store, types::full_checkpoint_content::Checkpoint,
 };
 
-use super::{CommitterConfig, PIPELINE_BUFFER, Processor, WatermarkPart, processor::processor};
+use super::{
+    CommitterConfig, PIPELINE_BUFFER, Processor, WatermarkPart, derived::Taps, processor::processor,
+};
 
 use self::{
     collector::collector, commit_watermark::commit_watermark, committer::committer,
@@ -199,8 +201,9 @@ impl Default for PrunerConfig {
 /// The pipeline also maintains a row in the `watermarks` table for the pipeline which tracks the
 /// watermark below which all data has been committed (modulo pruning).
 ///
-/// Checkpoint data is fed into the pipeline through the `checkpoint_rx` channel, and internal
-/// channels are created to communicate between its various components. The pipeline will shutdown
+/// Checkpoint data is fed into the pipeline through the `checkpoint_rx` channel, the processor
+/// shares its output with pipelines derived from this one through `taps`, and internal channels
+/// are created to communicate between its various components. The pipeline will shutdown
 /// if any of its input or output channels close, any of its independent tasks fail, or if it is
 /// signalled to shutdown through the returned service handle.
 pub(crate) fn pipeline<H: Handler + Send + Sync + 'static>(
@@ -210,6 +213,7 @@ pub(crate) fn pipeline<H: Handler + Send + Sync + 'static>(
     store: H::Store,
     task: Option<Task>,
     checkpoint_rx: mpsc::Receiver<Arc<Checkpoint>>,
+    taps: Taps<H>,
     metrics: Arc<IndexerMetrics>,
 ) -> Service {
     info!(
@@ -237,6 +241,7 @@ pub(crate) fn pipeline<H: Handler + Send + Sync + 'static>(
         handler.clone(),
         checkpoint_rx,
         processor_tx,
+        taps,
         metrics.clone(),
     );
 
@@ -408,6 +413,7 @@ mod tests {
                 store.clone(),
                 None,
                 checkpoint_rx,
+                Default::default(),
                 metrics,
             );
 
@@ -0,0 +1,380 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use rtd_futures::service::Service;
+use tokio::sync::Notify;
+use tokio::time::interval;
+use tracing::{debug, warn};
+
+use crate::store::{Connection, Store};
+
+use super::Processor;
+
+/// How often a derived pipeline checks its upstream pipeline's committer watermark.
+const UPSTREAM_WATERMARK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Everything listening to the values produced by a pipeline's processor. Listeners are added when
+/// pipelines are derived from this one, which happens after the pipeline has been added, but before
+/// the indexer is run.
+pub(crate) type Taps<P> = Arc<RwLock<Vec<Arc<dyn Tap<P>>>>>;
+
+/// Receives a copy of the values that a pipeline's processor produces for each checkpoint.
+pub(crate) trait Tap<P: Processor>: Send + Sync {
+    fn tap(&self, checkpoint: u64, values: &[P::Value]);
+}
+
+/// The output of an upstream pipeline `P`, for use by the processor of another pipeline, derived
+/// from it. Derived pipelines receive the same checkpoints as every other pipeline, but instead of
+/// decoding them, they can wait for the values that `P` produced for the same checkpoint, once `P`
+/// has committed them (its committer watermark has reached the checkpoint), and derive their own
+/// values from those:
+///
+/// ```ignore
+/// async fn process(&self, checkpoint: &Arc<Checkpoint>) -> Result<Vec<Self::Value>> {
+///     let cp = checkpoint.summary.sequence_number;
+///     self.swaps.derive(cp, |swaps| Ok(daily_volumes(swaps))).await
+/// }
+/// ```
+///
+/// Values are buffered in memory from when `P` processes them until they are derived from, or the
+/// derived pipeline has committed their checkpoint, so every checkpoint from the derived
+/// pipeline's first checkpoint onwards must eventually be derived from, by exactly one caller.
+/// Values for checkpoints before the derived pipeline's first checkpoint are not buffered at all.
+/// If `P` is a sequential pipeline configured with a checkpoint lag, that lag must be smaller than
+/// the derived pipeline's processor buffer, otherwise the two pipelines will wait on each other.
+///
+/// Created by [crate::Indexer::upstream].
+pub struct Upstream<P: Processor> {
+    inner: Arc<Inner<P>>,
+}
+
+struct Inner<P: Processor> {
+    state: Mutex<State<P::Value>>,
+    /// Notified when values are added, or the upstream watermark advances.
+    changed: Notify,
+}
+
+struct State<V> {
+    /// The upstream pipeline's committer watermark, if it has one.
+    checkpoint_hi_inclusive: Option<u64>,
+    /// Values produced by the upstream processor, by checkpoint, that have not been derived from
+    /// yet.
+    pending: BTreeMap<u64, Vec<V>>,
+    /// The first checkpoint the derived pipeline may still derive from. Values for earlier
+    /// checkpoints are dropped.
+    derived_lo: u64,
+}
+
+impl<P: Processor> Upstream<P> {
+    /// Create the output of upstream pipeline `P`, along with the tap to register on `P`'s
+    /// processor to feed it.
+    pub(crate) fn new() -> (Self, Arc<dyn Tap<P>>)
+    where
+        P::Value: Clone,
+    {
+        let inner = Arc::new(Inner {
+            state: Mutex::new(State {
+                checkpoint_hi_inclusive: None,
+                pending: BTreeMap::new(),
+                derived_lo: 0,
+            }),
+            changed: Notify::new(),
+        });
+
+        (
+            Self {
+                inner: inner.clone(),
+            },
+            inner,
+        )
+    }
+
+    /// Wait until the upstream pipeline has committed its values for `checkpoint`, and pass them
+    /// to `f`. If `f` fails, the values are kept, so that the call can be retried (e.g. by the
+    /// processor's retry loop).
+    pub async fn derive<R, F>(&self, checkpoint: u64, f: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&[P::Value]) -> anyhow::Result<R> + Send,
+    {
+        let values = loop {
+            // Register interest in changes before checking the state, so that a change that
+            // happens between the check and the wait is not missed.
+            let changed = self.inner.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            {
+                let mut state = self.inner.state.lock().unwrap();
+                if state
+                    .checkpoint_hi_inclusive
+                    .is_some_and(|hi| checkpoint <= hi)
+                    && let Some(values) = state.pending.remove(&checkpoint)
+                {
+                    break values;
+                }
+            }
+
+            changed.await;
+        };
+
+        match f(&values) {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                let mut state = self.inner.state.lock().unwrap();
+                state.pending.insert(checkpoint, values);
+                Err(e)
+            }
+        }
+    }
+
+    /// The highest checkpoint the upstream pipeline is known to have committed, if any.
+    pub fn checkpoint_hi_inclusive(&self) -> Option<u64> {
+        self.inner.state.lock().unwrap().checkpoint_hi_inclusive
+    }
+
+    /// Record that the upstream pipeline has committed all checkpoints up to and including
+    /// `checkpoint_hi_inclusive`. The watermark never moves backwards.
+    fn advance(&self, checkpoint_hi_inclusive: u64) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state
+            .checkpoint_hi_inclusive
+            .is_some_and(|hi| checkpoint_hi_inclusive <= hi)
+        {
+            return;
+        }
+
+        state.checkpoint_hi_inclusive = Some(checkpoint_hi_inclusive);
+        drop(state);
+        self.inner.changed.notify_waiters();
+    }
+
+    /// Record that the derived pipeline will not derive from checkpoints before `checkpoint`,
+    /// because it starts after them, or has already committed them. Values for those checkpoints
+    /// are dropped, and no longer buffered when they are produced. This never moves backwards.
+    pub(crate) fn skip_below(&self, checkpoint: u64) {
+        let mut state = self.inner.state.lock().unwrap();
+        if checkpoint <= state.derived_lo {
+            return;
+        }
+
+        state.derived_lo = checkpoint;
+        state.pending = state.pending.split_off(&checkpoint);
+    }
+}
+
+impl<P: Processor> Clone for Upstream<P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<P: Processor> Tap<P> for Inner<P>
+where
+    P::Value: Clone,
+{
+    fn tap(&self, checkpoint: u64, values: &[P::Value]) {
+        let mut state = self.state.lock().unwrap();
+        if checkpoint < state.derived_lo {
+            return;
+        }
+
+        state.pending.insert(checkpoint, values.to_vec());
+        drop(state);
+        self.changed.notify_waiters();
+    }
+}
+
+/// The upstream watermark task keeps `upstream`'s view of the upstream pipeline's committer
+/// watermark up-to-date, by polling the store (recorded under `upstream_task`). It also polls the
+/// committer watermark of the pipeline derived from it (recorded under `derived_task`), to drop
+/// values for checkpoints that the derived pipeline has already committed.
+pub(crate) fn upstream_watermark<P: Processor, S: Store>(
+    upstream: Upstream<P>,
+    upstream_task: String,
+    derived_task: String,
+    store: S,
+) -> Service {
+    Service::new().spawn_aborting(async move {
+        let mut poll = interval(UPSTREAM_WATERMARK_INTERVAL);
+
+        loop {
+            poll.tick().await;
+
+            let Ok(mut conn) = store.connect().await else {
+                warn!(
+                    pipeline = P::NAME,
+                    "Upstream watermark task failed to get connection for DB"
+                );
+                continue;
+            };
+
+            match conn.committer_watermark(&upstream_task).await {
+                Ok(Some(watermark)) => {
+                    debug!(
+                        pipeline = P::NAME,
+                        checkpoint_hi_inclusive = watermark.checkpoint_hi_inclusive,
+                        "Upstream watermark",
+                    );
+                    upstream.advance(watermark.checkpoint_hi_inclusive);
+                }
+
+                Ok(None) => {
+                    debug!(pipeline = P::NAME, "No upstream watermark yet");
+                }
+
+                Err(e) => {
+                    warn!(pipeline = P::NAME, "Failed to get upstream watermark: {e}");
+                }
+            }
+
+            match conn.committer_watermark(&derived_task).await {
+                Ok(Some(watermark)) => {
+                    upstream.skip_below(watermark.checkpoint_hi_inclusive + 1);
+                }
+
+                Ok(None) => {}
+
+                Err(e) => {
+                    warn!(
+                        pipeline = P::NAME,
+                        derived_task, "Failed to get derived pipeline watermark: {e}",
+                    );
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use rtd_types::full_checkpoint_content::Checkpoint;
+    use tokio::time::timeout;
+
+    use super::*;
+
+    struct DataPipeline;
+
+    #[async_trait]
+    impl Processor for DataPipeline {
+        const NAME: &'static str = "data";
+        type Value = u64;
+
+        async fn process(&self, _checkpoint: &Arc<Checkpoint>) -> anyhow::Result<Vec<Self::Value>> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_derive_waits_for_upstream_commit() {
+        let (upstream, tap) = Upstream::<DataPipeline>::new();
+
+        let derived = tokio::spawn({
+            let upstream = upstream.clone();
+            async move {
+                upstream
+                    .derive(1, |values| Ok(values.iter().sum::<u64>()))
+                    .await
+            }
+        });
+
+        // Processed, but not committed.
+        tap.tap(1, &[1, 2, 3]);
+        upstream.advance(0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!derived.is_finished());
+
+        // Committed.
+        upstream.advance(1);
+        let sum = timeout(Duration::from_secs(1), derived)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(sum, 6);
+        assert_eq!(upstream.checkpoint_hi_inclusive(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_derive_committed_before_processed() {
+        let (upstream, tap) = Upstream::<DataPipeline>::new();
+
+        // The upstream watermark can be ahead of its processor, e.g. after a restart.
+        upstream.advance(10);
+        let derived = tokio::spawn({
+            let upstream = upstream.clone();
+            async move { upstream.derive(5, |values| Ok(values.to_vec())).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!derived.is_finished());
+
+        tap.tap(5, &[5]);
+        let values = timeout(Duration::from_secs(1), derived)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(values, vec![5]);
+
+        // The watermark does not go backwards.
+        upstream.advance(7);
+        assert_eq!(upstream.checkpoint_hi_inclusive(), Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_derive_retry_after_failure() {
+        let (upstream, tap) = Upstream::<DataPipeline>::new();
+        tap.tap(1, &[1, 2]);
+        upstream.advance(1);
+
+        upstream
+            .derive(1, |_| -> anyhow::Result<()> {
+                anyhow::bail!("Transient failure")
+            })
+            .await
+            .unwrap_err();
+
+        // The values are still available to the retry.
+        let values = timeout(
+            Duration::from_secs(1),
+            upstream.derive(1, |values| Ok(values.to_vec())),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(values, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_skip_below() {
+        let (upstream, tap) = Upstream::<DataPipeline>::new();
+        tap.tap(1, &[1]);
+        tap.tap(2, &[2]);
+        tap.tap(3, &[3]);
+
+        // Values below the derived pipeline's low watermark are dropped, and no longer buffered.
+        upstream.skip_below(3);
+        tap.tap(2, &[2]);
+        assert_eq!(
+            upstream.inner.state.lock().unwrap().pending,
+            BTreeMap::from([(3, vec![3])]),
+        );
+
+        // The low watermark does not go backwards.
+        upstream.skip_below(1);
+        tap.tap(1, &[1]);
+        assert_eq!(
+            upstream.inner.state.lock().unwrap().pending,
+            BTreeMap::from([(3, vec![3])]),
+        );
+    }
+}
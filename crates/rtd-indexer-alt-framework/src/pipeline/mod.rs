@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use crate::store::CommitterWatermark;
 
 pub mod concurrent;
+pub mod derived;
 mod logging;
 mod processor;
 pub mod sequential;
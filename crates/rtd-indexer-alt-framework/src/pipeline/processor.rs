@@ -17,6 +17,7 @@ use tracing::{debug, error, info};
 use crate::metrics::{CheckpointLagMetricReporter, IndexerMetrics};
 
 use super::IndexedCheckpoint;
+use super::derived::Taps;
 use async_trait::async_trait;
 
 /// If the processor needs to retry processing a checkpoint, it will wait this long initially.
@@ -59,11 +60,12 @@ pub trait Processor: Send + Sync + 'static {
 /// distributes them among `H::FANOUT` workers.
 ///
 /// Each worker processes a checkpoint into rows and sends them on to the committer using the `tx`
-/// channel.
+/// channel, after sharing them with any `taps` (pipelines derived from this one).
 pub(super) fn processor<P: Processor>(
     processor: Arc<P>,
     rx: mpsc::Receiver<Arc<Checkpoint>>,
     tx: mpsc::Sender<IndexedCheckpoint<P>>,
+    taps: Taps<P>,
     metrics: Arc<IndexerMetrics>,
 ) -> Service {
     Service::new().spawn_aborting(async move {
@@ -77,6 +79,7 @@ pub(super) fn processor<P: Processor>(
         match ReceiverStream::new(rx)
             .try_for_each_spawned(P::FANOUT, |checkpoint| {
                 let tx = tx.clone();
+                let taps = taps.clone();
                 let metrics = metrics.clone();
                 let checkpoint_lag_reporter = checkpoint_lag_reporter.clone();
                 let processor = processor.clone();
@@ -135,6 +138,10 @@ pub(super) fn processor<P: Processor>(
                         .with_label_values(&[P::NAME])
                         .inc_by(values.len() as u64);
 
+                    for tap in taps.read().unwrap().iter() {
+                        tap.tap(cp_sequence_number, &values);
+                    }
+
                     tx.send(IndexedCheckpoint::new(
                         epoch,
                         cp_sequence_number,
@@ -233,7 +240,7 @@ mod tests {
         let metrics = IndexerMetrics::new(None, &Default::default());
 
         // Spawn the processor task
-        let _svc = super::processor(processor, data_rx, indexed_tx, metrics);
+        let _svc = super::processor(processor, data_rx, indexed_tx, Default::default(), metrics);
 
         // Send both checkpoints
         data_tx.send(checkpoint1.clone()).await.unwrap();
@@ -287,7 +294,7 @@ mod tests {
         let metrics = IndexerMetrics::new(None, &Default::default());
 
         // Spawn the processor task
-        let svc = super::processor(processor, data_rx, indexed_tx, metrics);
+        let svc = super::processor(processor, data_rx, indexed_tx, Default::default(), metrics);
 
         // Send first checkpoint.
         data_tx.send(checkpoint1.clone()).await.unwrap();
@@ -355,7 +362,7 @@ mod tests {
         let metrics = IndexerMetrics::new(None, &Default::default());
 
         // Spawn the processor task
-        let _svc = super::processor(processor, data_rx, indexed_tx, metrics);
+        let _svc = super::processor(processor, data_rx, indexed_tx, Default::default(), metrics);
 
         // Send and verify first checkpoint (should succeed immediately)
         data_tx.send(checkpoint1.clone()).await.unwrap();
@@ -415,7 +422,7 @@ mod tests {
         let metrics = IndexerMetrics::new(None, &Default::default());
 
         // Spawn processor task
-        let _svc = super::processor(processor, data_rx, indexed_tx, metrics);
+        let _svc = super::processor(processor, data_rx, indexed_tx, Default::default(), metrics);
 
         // Send all checkpoints and measure time
         let start = std::time::Instant::now();
@@ -8,7 +8,7 @@ use rtd_futures::service::Service;
 use tokio::sync::mpsc;
 use tracing::info;
 
-use super::{CommitterConfig, PIPELINE_BUFFER, Processor, processor::processor};
+use super::{CommitterConfig, PIPELINE_BUFFER, Processor, derived::Taps, processor::processor};
 
 use crate::{
     metrics::IndexerMetrics,
@@ -106,8 +106,9 @@ pub struct SequentialConfig {
 /// watermark.
 ///
 /// Checkpoint data is fed into the pipeline through the `checkpoint_rx` channel, watermark updates
-/// are communicated to the ingestion service through the `watermark_tx` channel and internal
-/// channels are created to communicate between its various components. The pipeline will shutdown
+/// are communicated to the ingestion service through the `watermark_tx` channel, the processor
+/// shares its output with pipelines derived from this one through `taps`, and internal channels
+/// are created to communicate between its various components. The pipeline will shutdown
 /// if any of its input or output channels close, any of its independent tasks fail, or if it is
 /// signalled to shutdown through the returned service handle.
 pub(crate) fn pipeline<H: Handler + Send + Sync + 'static>(
@@ -117,6 +118,7 @@ pub(crate) fn pipeline<H: Handler + Send + Sync + 'static>(
     db: H::Store,
     checkpoint_rx: mpsc::Receiver<Arc<Checkpoint>>,
     watermark_tx: mpsc::UnboundedSender<(&'static str, u64)>,
+    taps: Taps<H>,
     metrics: Arc<IndexerMetrics>,
 ) -> Service {
     info!(
@@ -132,6 +134,7 @@ pub(crate) fn pipeline<H: Handler + Send + Sync + 'static>(
         handler.clone(),
         checkpoint_rx,
         processor_tx,
+        taps,
         metrics.clone(),
     );
 
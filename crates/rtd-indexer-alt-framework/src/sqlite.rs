@@ -0,0 +1,201 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Support for indexers that write to an embedded SQLite database, so that they can run as a single
+//! binary, without a database server.
+//!
+//! Unlike Postgres, there is no SQLite-specific handler trait: pipelines implement
+//! [crate::pipeline::concurrent::Handler] or [crate::pipeline::sequential::Handler] directly, with
+//! `type Store = Db`. Sequential pipelines commit each batch and their watermark in a single
+//! transaction.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use diesel_migrations::EmbeddedMigrations;
+use prometheus::Registry;
+use rtd_sqlite_db::temp::TempDb;
+use tempfile::tempdir;
+
+use crate::{
+    Indexer, IndexerArgs,
+    ingestion::{ClientArgs, IngestionConfig, ingestion_client::IngestionClientArgs},
+};
+
+pub use rtd_sqlite_db::*;
+
+/// An opinionated indexer implementation that uses an embedded SQLite database as the store.
+impl Indexer<Db> {
+    /// Create a new instance of the indexer framework, writing to the SQLite database file at
+    /// `path`, which is created if it does not exist. `db_args` configures the connection pool, and
+    /// `indexer_args`, `client_args`, and `ingestion_config` are passed on to [Indexer::new].
+    ///
+    /// Optional `migrations` contains the SQL to run in order to bring the database schema
+    /// up-to-date for the specific instance of the indexer, generated using diesel's
+    /// `embed_migrations!` macro. They are run alongside the migrations for the watermarks table,
+    /// as part of initializing the indexer.
+    ///
+    /// After initialization, at least one pipeline must be added using [Self::concurrent_pipeline]
+    /// or [Self::sequential_pipeline], before the indexer is started using [Self::run].
+    pub async fn new_from_sqlite(
+        path: impl AsRef<Path>,
+        db_args: DbArgs,
+        indexer_args: IndexerArgs,
+        client_args: ClientArgs,
+        ingestion_config: IngestionConfig,
+        migrations: Option<&'static EmbeddedMigrations>,
+        metrics_prefix: Option<&str>,
+        registry: &Registry,
+    ) -> Result<Self> {
+        let store = Db::for_write(path, db_args)
+            .await
+            .context("Failed to open database")?;
+
+        // At indexer initialization, we ensure that the DB schema is up-to-date.
+        store
+            .run_migrations(migrations)
+            .await
+            .context("Failed to run pending migrations")?;
+
+        Indexer::new(
+            store,
+            indexer_args,
+            client_args,
+            ingestion_config,
+            metrics_prefix,
+            registry,
+        )
+        .await
+    }
+
+    /// Create a new temporary database and runs provided migrations in tandem with the migrations
+    /// necessary to support watermark operations on the indexer. The indexer is then instantiated
+    /// and returned along with the temporary database.
+    pub async fn new_sqlite_for_testing(
+        migrations: &'static EmbeddedMigrations,
+    ) -> (Indexer<Db>, TempDb) {
+        let temp_db = TempDb::new().unwrap();
+        let store = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        store.run_migrations(Some(migrations)).await.unwrap();
+
+        let indexer = Indexer::new(
+            store,
+            IndexerArgs::default(),
+            ClientArgs {
+                ingestion: IngestionClientArgs {
+                    local_ingestion_path: Some(tempdir().unwrap().keep()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            IngestionConfig::default(),
+            None,
+            &Registry::new(),
+        )
+        .await
+        .unwrap();
+        (indexer, temp_db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use rtd_indexer_alt_framework_store_traits::{CommitterWatermark, Connection as _, Store as _};
+    use rtd_types::full_checkpoint_content::Checkpoint;
+
+    use super::*;
+
+    use crate::pipeline::{Processor, concurrent, sequential};
+    use crate::{ConcurrentConfig, SequentialConfig};
+
+    struct ConcurrentPipeline;
+    struct SequentialPipeline;
+
+    #[async_trait]
+    impl Processor for ConcurrentPipeline {
+        const NAME: &'static str = "concurrent";
+        type Value = u64;
+        async fn process(&self, _checkpoint: &Arc<Checkpoint>) -> anyhow::Result<Vec<u64>> {
+            todo!()
+        }
+    }
+
+    #[async_trait]
+    impl concurrent::Handler for ConcurrentPipeline {
+        type Store = Db;
+        type Batch = Vec<u64>;
+
+        fn batch(
+            &self,
+            _batch: &mut Self::Batch,
+            _values: &mut std::vec::IntoIter<u64>,
+        ) -> concurrent::BatchStatus {
+            todo!()
+        }
+
+        async fn commit<'a>(
+            &self,
+            _batch: &Self::Batch,
+            _conn: &mut Connection<'a>,
+        ) -> anyhow::Result<usize> {
+            todo!()
+        }
+    }
+
+    #[async_trait]
+    impl Processor for SequentialPipeline {
+        const NAME: &'static str = "sequential";
+        type Value = u64;
+        async fn process(&self, _checkpoint: &Arc<Checkpoint>) -> anyhow::Result<Vec<u64>> {
+            todo!()
+        }
+    }
+
+    #[async_trait]
+    impl sequential::Handler for SequentialPipeline {
+        type Store = Db;
+        type Batch = Vec<u64>;
+
+        fn batch(&self, _batch: &mut Self::Batch, _values: std::vec::IntoIter<u64>) {
+            todo!()
+        }
+
+        async fn commit<'a>(
+            &self,
+            _batch: &Self::Batch,
+            _conn: &mut Connection<'a>,
+        ) -> anyhow::Result<usize> {
+            todo!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_pipelines() {
+        let (mut indexer, _temp_db) = Indexer::new_sqlite_for_testing(&MIGRATIONS).await;
+        {
+            let watermark = CommitterWatermark::new_for_testing(10);
+            let mut conn = indexer.store().connect().await.unwrap();
+            assert!(
+                conn.set_committer_watermark(SequentialPipeline::NAME, watermark)
+                    .await
+                    .unwrap()
+            );
+        }
+
+        indexer
+            .sequential_pipeline(SequentialPipeline, SequentialConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(indexer.first_ingestion_checkpoint, 11);
+
+        indexer
+            .concurrent_pipeline(ConcurrentPipeline, ConcurrentConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(indexer.first_ingestion_checkpoint, 0);
+    }
+}
@@ -3,6 +3,7 @@
 
 use std::{
     future::Future,
+    ops::Range,
     pin::Pin,
     sync::{Arc, RwLock},
     task::{Context, Poll},
@@ -15,7 +16,7 @@ use gcp_auth::{Token, TokenProvider};
 use http::{HeaderValue, Request, Response};
 use prometheus::Registry;
 use rtd_types::{
-    base_types::{EpochId, ObjectID, TransactionDigest},
+    base_types::{EpochId, ObjectID, SequenceNumber, TransactionDigest},
     digests::CheckpointDigest,
     effects::TransactionEvents,
     full_checkpoint_content::CheckpointData,
@@ -41,10 +42,11 @@ use crate::bigtable::proto::bigtable::v2::{
     MutateRowsRequest, MutateRowsResponse, Mutation, ReadRowsRequest, RequestStats, RowRange,
     RowSet, bigtable_client::BigtableClient as BigtableInternalClient, mutate_rows_request::Entry,
     mutation, mutation::SetCell, read_rows_response::cell_chunk::RowStatus,
-    request_stats::StatsView, row_range::EndKey,
+    request_stats::StatsView, row_range::EndKey, row_range::StartKey,
 };
 use crate::{
-    Checkpoint, KeyValueStoreReader, KeyValueStoreWriter, TransactionData, TransactionEventsData,
+    Checkpoint, KeyValueStoreReader, KeyValueStoreWriter, Page, TransactionData,
+    TransactionEventsData,
 };
 
 const OBJECTS_TABLE: &str = "objects";
@@ -426,6 +428,70 @@ impl KeyValueStoreReader for BigTableClient {
 
         Ok(results)
     }
+
+    async fn get_object_versions(
+        &mut self,
+        object_id: &ObjectID,
+        cursor: Option<SequenceNumber>,
+        limit: usize,
+    ) -> Result<Page<Object, SequenceNumber>> {
+        let start = ObjectKey(*object_id, cursor.unwrap_or(SequenceNumber::MIN));
+        let range = RowRange {
+            start_key: Some(StartKey::StartKeyClosed(Self::raw_object_key(&start)?)),
+            end_key: Some(EndKey::EndKeyClosed(Self::raw_object_key(
+                &ObjectKey::max_for_id(object_id),
+            )?)),
+        };
+        let rows = self.page_scan(OBJECTS_TABLE, range, limit).await?;
+        let mut objects = vec![];
+        for (_, row) in rows {
+            if let Some((_, value)) = row.into_iter().next() {
+                objects.push(bcs::from_bytes::<Object>(&value)?);
+            }
+        }
+        Ok(into_page(objects, limit, |object| object.version()))
+    }
+
+    async fn get_epochs(
+        &mut self,
+        epochs: Range<EpochId>,
+        cursor: Option<EpochId>,
+        limit: usize,
+    ) -> Result<Page<EpochInfo, EpochId>> {
+        let start = cursor.unwrap_or(epochs.start).max(epochs.start);
+        if start >= epochs.end {
+            return Ok(Page {
+                data: vec![],
+                next_cursor: None,
+            });
+        }
+        let range = RowRange {
+            start_key: Some(StartKey::StartKeyClosed(start.to_be_bytes().to_vec())),
+            end_key: Some(EndKey::EndKeyOpen(epochs.end.to_be_bytes().to_vec())),
+        };
+        let rows = self.page_scan(EPOCHS_TABLE, range, limit).await?;
+        let mut result = vec![];
+        for (_, mut row) in rows {
+            if let Some((_, value)) = row.pop() {
+                result.push(bcs::from_bytes::<EpochInfo>(&value)?);
+            }
+        }
+        Ok(into_page(result, limit, |epoch| epoch.epoch))
+    }
+}
+
+/// Split the `limit + 1` values read for a page into the page and the cursor of the next one.
+fn into_page<T, C>(mut values: Vec<T>, limit: usize, cursor: impl Fn(&T) -> C) -> Page<T, C> {
+    let next_cursor = if values.len() > limit {
+        values.truncate(limit + 1);
+        values.pop().as_ref().map(cursor)
+    } else {
+        None
+    };
+    Page {
+        data: values,
+        next_cursor,
+    }
 }
 
 impl BigTableClient {
@@ -759,9 +825,43 @@ impl BigTableClient {
         &mut self,
         table_name: &str,
         upper_limit: Bytes,
+    ) -> Result<Vec<(Bytes, Vec<(Bytes, Bytes)>)>> {
+        let range = RowRange {
+            start_key: None,
+            end_key: Some(EndKey::EndKeyClosed(upper_limit)),
+        };
+        self.scan(table_name, range, 1, None, true).await
+    }
+
+    /// Read the rows of a page of at most `limit` rows in `range`, plus the first row of the next
+    /// page if there is one.
+    async fn page_scan(
+        &mut self,
+        table_name: &str,
+        range: RowRange,
+        limit: usize,
+    ) -> Result<Vec<(Bytes, Vec<(Bytes, Bytes)>)>> {
+        anyhow::ensure!(limit > 0, "limit must be positive");
+        let rows_limit = i64::try_from(limit)?.saturating_add(1);
+        let version_filter = RowFilter {
+            filter: Some(Filter::CellsPerColumnLimitFilter(1)),
+        };
+        self.scan(table_name, range, rows_limit, Some(version_filter), false)
+            .await
+    }
+
+    async fn scan(
+        &mut self,
+        table_name: &str,
+        range: RowRange,
+        rows_limit: i64,
+        filter: Option<RowFilter>,
+        reversed: bool,
     ) -> Result<Vec<(Bytes, Vec<(Bytes, Bytes)>)>> {
         let start_time = Instant::now();
-        let result = self.reversed_scan_internal(table_name, upper_limit).await;
+        let result = self
+            .scan_internal(table_name, range, rows_limit, filter, reversed)
+            .await;
         let elapsed_ms = start_time.elapsed().as_millis() as f64;
         let labels = [&self.client_name, table_name];
         match &self.metrics {
@@ -786,23 +886,23 @@ impl BigTableClient {
         }
     }
 
-    async fn reversed_scan_internal(
+    async fn scan_internal(
         &mut self,
         table_name: &str,
-        upper_limit: Bytes,
+        range: RowRange,
+        rows_limit: i64,
+        filter: Option<RowFilter>,
+        reversed: bool,
     ) -> Result<Vec<(Bytes, Vec<(Bytes, Bytes)>)>> {
-        let range = RowRange {
-            start_key: None,
-            end_key: Some(EndKey::EndKeyClosed(upper_limit)),
-        };
         let request = ReadRowsRequest {
             table_name: format!("{}{}", self.table_prefix, table_name),
-            rows_limit: 1,
+            rows_limit,
             rows: Some(RowSet {
                 row_keys: vec![],
                 row_ranges: vec![range],
             }),
-            reversed: true,
+            filter,
+            reversed,
             ..ReadRowsRequest::default()
         };
         self.read_rows(request, table_name).await
@@ -865,3 +965,26 @@ impl Service<Request<Body>> for AuthChannel {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_page() {
+        // Fewer values than the limit, or exactly the limit: this is the last page.
+        let page = into_page(vec![1, 2], 3, |v| *v);
+        assert_eq!((page.data, page.next_cursor), (vec![1, 2], None));
+        let page = into_page(vec![1, 2, 3], 3, |v| *v);
+        assert_eq!((page.data, page.next_cursor), (vec![1, 2, 3], None));
+
+        // The extra value read is the start of the next page.
+        let page = into_page(vec![1, 2, 3, 4], 3, |v| *v);
+        assert_eq!((page.data, page.next_cursor), (vec![1, 2, 3], Some(4)));
+        let page = into_page(vec![4, 5, 6, 7, 8], 3, |v| *v * 10);
+        assert_eq!((page.data, page.next_cursor), (vec![4, 5, 6], Some(70)));
+
+        let page = into_page(Vec::<u64>::new(), 3, |v| *v);
+        assert!(page.data.is_empty() && page.next_cursor.is_none());
+    }
+}
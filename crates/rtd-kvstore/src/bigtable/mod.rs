@@ -5,4 +5,3 @@ pub(crate) mod client;
 mod metrics;
 pub(crate) mod progress_store;
 mod proto;
-pub(crate) mod worker;
@@ -1,13 +1,19 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 mod bigtable;
+mod local;
+mod worker;
 use anyhow::Result;
 use async_trait::async_trait;
 pub use bigtable::client::BigTableClient;
 pub use bigtable::progress_store::BigTableProgressStore;
-pub use bigtable::worker::KvWorker;
+pub use local::client::LocalKvStore;
+pub use local::progress_store::LocalProgressStore;
+pub use worker::KvWorker;
 use serde::{Deserialize, Serialize};
-use rtd_types::base_types::ObjectID;
+use std::collections::HashMap;
+use std::ops::Range;
+use rtd_types::base_types::{ObjectID, SequenceNumber};
 use rtd_types::committee::EpochId;
 use rtd_types::crypto::AuthorityStrongQuorumSignInfo;
 use rtd_types::digests::{CheckpointDigest, TransactionDigest};
@@ -46,6 +52,102 @@ pub trait KeyValueStoreReader {
         &mut self,
         keys: &[TransactionDigest],
     ) -> Result<Vec<(TransactionDigest, TransactionEventsData)>>;
+    /// Versions of an object in ascending order, starting from the version `cursor`.
+    async fn get_object_versions(
+        &mut self,
+        object_id: &ObjectID,
+        cursor: Option<SequenceNumber>,
+        limit: usize,
+    ) -> Result<Page<Object, SequenceNumber>>;
+    /// Epochs in `epochs` in ascending order, starting from the epoch `cursor`.
+    async fn get_epochs(
+        &mut self,
+        epochs: Range<EpochId>,
+        cursor: Option<EpochId>,
+        limit: usize,
+    ) -> Result<Page<EpochInfo, EpochId>>;
+    /// Transactions of the checkpoints in `checkpoints`, in execution order, starting from
+    /// `cursor`. The page ends early at the first checkpoint that is not stored, with a cursor
+    /// pointing at it, so that it can be resumed once that checkpoint is available.
+    async fn get_transactions_in_checkpoints(
+        &mut self,
+        checkpoints: Range<CheckpointSequenceNumber>,
+        cursor: Option<TransactionCursor>,
+        limit: usize,
+    ) -> Result<Page<TransactionData, TransactionCursor>>
+    where
+        Self: Send,
+    {
+        anyhow::ensure!(limit > 0, "limit must be positive");
+        let mut cursor = cursor
+            .filter(|cursor| cursor.checkpoint >= checkpoints.start)
+            .unwrap_or(TransactionCursor {
+                checkpoint: checkpoints.start,
+                index: 0,
+            });
+        let mut digests = vec![];
+        let mut next_cursor = None;
+        'checkpoints: while cursor.checkpoint < checkpoints.end {
+            let batch_end = checkpoints
+                .end
+                .min(cursor.checkpoint.saturating_add(CHECKPOINT_BATCH_SIZE));
+            let sequence_numbers: Vec<_> = (cursor.checkpoint..batch_end).collect();
+            let mut batch = self
+                .get_checkpoints(&sequence_numbers)
+                .await?
+                .into_iter()
+                .peekable();
+            for sequence_number in sequence_numbers {
+                let start = if sequence_number == cursor.checkpoint {
+                    cursor.index
+                } else {
+                    0
+                };
+                // Checkpoints that are not stored are left out of the batch.
+                let Some(checkpoint) = batch
+                    .next_if(|checkpoint| checkpoint.summary.sequence_number == sequence_number)
+                else {
+                    next_cursor = Some(TransactionCursor {
+                        checkpoint: sequence_number,
+                        index: start,
+                    });
+                    break 'checkpoints;
+                };
+                for (index, execution_digests) in checkpoint.contents.iter().enumerate().skip(start)
+                {
+                    if digests.len() == limit {
+                        next_cursor = Some(TransactionCursor {
+                            checkpoint: sequence_number,
+                            index,
+                        });
+                        break 'checkpoints;
+                    }
+                    digests.push(execution_digests.transaction);
+                }
+            }
+            cursor = TransactionCursor {
+                checkpoint: batch_end,
+                index: 0,
+            };
+        }
+
+        // Transactions are not necessarily returned in the order they are requested
+        let mut transactions: HashMap<_, _> = self
+            .get_transactions(&digests)
+            .await?
+            .into_iter()
+            .map(|transaction| (*transaction.transaction.digest(), transaction))
+            .collect();
+        let data = digests
+            .iter()
+            .map(|digest| {
+                transactions
+                    .remove(digest)
+                    .ok_or_else(|| anyhow::anyhow!("transaction {} is missing", digest))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Page { data, next_cursor })
+    }
 }
 
 #[async_trait]
@@ -57,6 +159,24 @@ pub trait KeyValueStoreWriter {
     async fn save_epoch(&mut self, epoch: EpochInfo) -> Result<()>;
 }
 
+/// Number of checkpoints fetched at once when listing the transactions of a checkpoint range.
+const CHECKPOINT_BATCH_SIZE: u64 = 100;
+
+/// A page of results, and the cursor to pass to get the next page, if there is one.
+#[derive(Clone, Debug)]
+pub struct Page<T, C> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<C>,
+}
+
+/// The position of a transaction in the transactions of a checkpoint range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionCursor {
+    pub checkpoint: CheckpointSequenceNumber,
+    /// Index of the transaction in the contents of the checkpoint.
+    pub index: usize,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Checkpoint {
     pub summary: CheckpointSummary,
@@ -0,0 +1,526 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rtd_types::{
+    base_types::{EpochId, ObjectID, SequenceNumber, TransactionDigest},
+    digests::CheckpointDigest,
+    full_checkpoint_content::CheckpointData,
+    messages_checkpoint::{CheckpointSequenceNumber, CheckpointSummary},
+    messages_consensus::TimestampMs,
+    object::Object,
+    storage::{EpochInfo, ObjectKey},
+};
+use typed_store::rocks::{DBMap, MetricConf};
+use typed_store::{DBMapUtils, Map, TypedStoreError};
+
+use crate::{
+    Checkpoint, KeyValueStoreReader, KeyValueStoreWriter, Page, TransactionData,
+    TransactionEventsData,
+};
+
+/// The tables of the local store, mirroring the tables of the BigTable instance.
+#[derive(DBMapUtils)]
+pub struct LocalKvTables {
+    objects: DBMap<ObjectKey, Object>,
+    transactions: DBMap<TransactionDigest, TransactionData>,
+    checkpoints: DBMap<CheckpointSequenceNumber, Checkpoint>,
+    checkpoints_by_digest: DBMap<CheckpointDigest, CheckpointSequenceNumber>,
+    /// The sequence number of the next checkpoint to be ingested.
+    watermark: DBMap<(), CheckpointSequenceNumber>,
+    epochs: DBMap<EpochId, EpochInfo>,
+}
+
+/// A key value store backed by a local RocksDB database, for development and for deployments
+/// that cannot reach BigTable.
+#[derive(Clone)]
+pub struct LocalKvStore {
+    tables: Arc<LocalKvTables>,
+}
+
+impl LocalKvStore {
+    pub fn new(path: PathBuf) -> Self {
+        let tables = LocalKvTables::open_tables_read_write(
+            path,
+            MetricConf::new("local_kv_store"),
+            None,
+            None,
+        );
+        Self {
+            tables: Arc::new(tables),
+        }
+    }
+}
+
+#[async_trait]
+impl KeyValueStoreWriter for LocalKvStore {
+    async fn save_objects(&mut self, objects: &[&Object], _: TimestampMs) -> Result<()> {
+        let mut batch = self.tables.objects.batch();
+        batch.insert_batch(
+            &self.tables.objects,
+            objects
+                .iter()
+                .map(|object| (ObjectKey(object.id(), object.version()), *object)),
+        )?;
+        Ok(batch.write()?)
+    }
+
+    async fn save_transactions(&mut self, transactions: &[TransactionData]) -> Result<()> {
+        let mut batch = self.tables.transactions.batch();
+        batch.insert_batch(
+            &self.tables.transactions,
+            transactions
+                .iter()
+                .map(|transaction| (*transaction.transaction.digest(), transaction)),
+        )?;
+        Ok(batch.write()?)
+    }
+
+    async fn save_checkpoint(&mut self, checkpoint: &CheckpointData) -> Result<()> {
+        let summary = checkpoint.checkpoint_summary.data();
+        let sequence_number = summary.sequence_number;
+        let value = Checkpoint {
+            summary: summary.clone(),
+            contents: checkpoint.checkpoint_contents.clone(),
+            signatures: checkpoint.checkpoint_summary.auth_sig().clone(),
+        };
+        let mut batch = self.tables.checkpoints.batch();
+        batch.insert_batch(&self.tables.checkpoints, [(sequence_number, value)])?;
+        batch.insert_batch(
+            &self.tables.checkpoints_by_digest,
+            [(*checkpoint.checkpoint_summary.digest(), sequence_number)],
+        )?;
+        Ok(batch.write()?)
+    }
+
+    async fn save_watermark(&mut self, watermark: CheckpointSequenceNumber) -> Result<()> {
+        Ok(self.tables.watermark.insert(&(), &watermark)?)
+    }
+
+    async fn save_epoch(&mut self, epoch: EpochInfo) -> Result<()> {
+        Ok(self.tables.epochs.insert(&epoch.epoch, &epoch)?)
+    }
+}
+
+#[async_trait]
+impl KeyValueStoreReader for LocalKvStore {
+    async fn get_objects(&mut self, object_keys: &[ObjectKey]) -> Result<Vec<Object>> {
+        let objects = self.tables.objects.multi_get(object_keys)?;
+        Ok(objects.into_iter().flatten().collect())
+    }
+
+    async fn get_transactions(
+        &mut self,
+        transactions: &[TransactionDigest],
+    ) -> Result<Vec<TransactionData>> {
+        let transactions = self.tables.transactions.multi_get(transactions)?;
+        Ok(transactions.into_iter().flatten().collect())
+    }
+
+    async fn get_checkpoints(
+        &mut self,
+        sequence_numbers: &[CheckpointSequenceNumber],
+    ) -> Result<Vec<Checkpoint>> {
+        let checkpoints = self.tables.checkpoints.multi_get(sequence_numbers)?;
+        Ok(checkpoints.into_iter().flatten().collect())
+    }
+
+    async fn get_checkpoint_by_digest(
+        &mut self,
+        digest: CheckpointDigest,
+    ) -> Result<Option<Checkpoint>> {
+        let Some(sequence_number) = self.tables.checkpoints_by_digest.get(&digest)? else {
+            return Ok(None);
+        };
+        Ok(self.tables.checkpoints.get(&sequence_number)?)
+    }
+
+    async fn get_latest_checkpoint(&mut self) -> Result<CheckpointSequenceNumber> {
+        Ok(self.tables.watermark.get(&())?.unwrap_or(0))
+    }
+
+    async fn get_latest_checkpoint_summary(&mut self) -> Result<Option<CheckpointSummary>> {
+        let sequence_number = self.get_latest_checkpoint().await?;
+        if sequence_number == 0 {
+            return Ok(None);
+        }
+        Ok(self
+            .tables
+            .checkpoints
+            .get(&(sequence_number - 1))?
+            .map(|checkpoint| checkpoint.summary))
+    }
+
+    async fn get_latest_object(&mut self, object_id: &ObjectID) -> Result<Option<Object>> {
+        let latest = self
+            .tables
+            .objects
+            .reversed_safe_iter_with_bounds(None, Some(ObjectKey::max_for_id(object_id)))?
+            .next()
+            .transpose()?;
+        Ok(latest
+            .filter(|(key, _)| key.0 == *object_id)
+            .map(|(_, object)| object))
+    }
+
+    async fn get_epoch(&mut self, epoch_id: EpochId) -> Result<Option<EpochInfo>> {
+        Ok(self.tables.epochs.get(&epoch_id)?)
+    }
+
+    async fn get_latest_epoch(&mut self) -> Result<Option<EpochInfo>> {
+        Ok(self
+            .tables
+            .epochs
+            .reversed_safe_iter_with_bounds(None, None)?
+            .next()
+            .transpose()?
+            .map(|(_, epoch)| epoch))
+    }
+
+    async fn get_events_for_transactions(
+        &mut self,
+        transaction_digests: &[TransactionDigest],
+    ) -> Result<Vec<(TransactionDigest, TransactionEventsData)>> {
+        let transactions = self.tables.transactions.multi_get(transaction_digests)?;
+        Ok(transaction_digests
+            .iter()
+            .zip(transactions)
+            .filter_map(|(digest, transaction)| {
+                let transaction = transaction?;
+                Some((
+                    *digest,
+                    TransactionEventsData {
+                        events: transaction.events.map(|e| e.data).unwrap_or_default(),
+                        timestamp_ms: transaction.timestamp,
+                    },
+                ))
+            })
+            .collect())
+    }
+
+    async fn get_object_versions(
+        &mut self,
+        object_id: &ObjectID,
+        cursor: Option<SequenceNumber>,
+        limit: usize,
+    ) -> Result<Page<Object, SequenceNumber>> {
+        let start = ObjectKey(*object_id, cursor.unwrap_or(SequenceNumber::MIN));
+        let end = ObjectKey::max_for_id(object_id);
+        page(
+            self.tables.objects.safe_range_iter(start..=end),
+            limit,
+            |key| key.1,
+        )
+    }
+
+    async fn get_epochs(
+        &mut self,
+        epochs: Range<EpochId>,
+        cursor: Option<EpochId>,
+        limit: usize,
+    ) -> Result<Page<EpochInfo, EpochId>> {
+        let start = cursor.unwrap_or(epochs.start).max(epochs.start);
+        page(
+            self.tables.epochs.safe_range_iter(start..epochs.end),
+            limit,
+            |epoch| *epoch,
+        )
+    }
+}
+
+/// Collect up to `limit` values from `iter`, and the cursor of the value after them.
+fn page<K, V, C>(
+    iter: impl Iterator<Item = Result<(K, V), TypedStoreError>>,
+    limit: usize,
+    cursor: impl Fn(&K) -> C,
+) -> Result<Page<V, C>> {
+    anyhow::ensure!(limit > 0, "limit must be positive");
+    let mut data = vec![];
+    for entry in iter {
+        let (key, value) = entry?;
+        if data.len() == limit {
+            return Ok(Page {
+                data,
+                next_cursor: Some(cursor(&key)),
+            });
+        }
+        data.push(value);
+    }
+    Ok(Page {
+        data,
+        next_cursor: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rtd_data_ingestion_core::ProgressStore;
+    use rtd_types::object::Owner;
+    use rtd_types::test_checkpoint_data_builder::TestCheckpointBuilder;
+
+    use super::*;
+    use crate::{LocalProgressStore, TransactionCursor};
+
+    fn object(id: ObjectID, version: u64) -> Object {
+        Object::with_id_owner_version_for_testing(
+            id,
+            SequenceNumber::from_u64(version),
+            Owner::Immutable,
+        )
+    }
+
+    fn epoch(epoch: EpochId) -> EpochInfo {
+        EpochInfo {
+            epoch,
+            protocol_version: Some(1),
+            start_timestamp_ms: Some(epoch * 1000),
+            end_timestamp_ms: None,
+            start_checkpoint: Some(epoch * 10),
+            end_checkpoint: None,
+            reference_gas_price: Some(1000),
+            system_state: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn objects_and_epochs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = LocalKvStore::new(dir.path().to_owned());
+        let (id, other) = (ObjectID::random(), ObjectID::random());
+        let objects = [object(id, 1), object(id, 2), object(other, 5)];
+        store
+            .save_objects(&objects.iter().collect::<Vec<_>>(), 0)
+            .await
+            .unwrap();
+        store.save_epoch(epoch(0)).await.unwrap();
+        store.save_epoch(epoch(1)).await.unwrap();
+
+        let keys = [
+            ObjectKey(id, SequenceNumber::from_u64(2)),
+            ObjectKey(id, SequenceNumber::from_u64(3)),
+            ObjectKey(other, SequenceNumber::from_u64(5)),
+        ];
+        let found = store.get_objects(&keys).await.unwrap();
+        assert_eq!(found, vec![objects[1].clone(), objects[2].clone()]);
+        assert_eq!(
+            store.get_latest_object(&id).await.unwrap(),
+            Some(objects[1].clone())
+        );
+        assert_eq!(
+            store.get_latest_object(&ObjectID::ZERO).await.unwrap(),
+            None
+        );
+
+        assert_eq!(store.get_epoch(1).await.unwrap().unwrap().epoch, 1);
+        assert!(store.get_epoch(2).await.unwrap().is_none());
+        let latest = store.get_latest_epoch().await.unwrap().unwrap();
+        assert_eq!(latest.start_checkpoint, Some(10));
+
+        // Nothing has been ingested yet.
+        assert_eq!(store.get_latest_checkpoint().await.unwrap(), 0);
+        assert!(
+            store
+                .get_latest_checkpoint_summary()
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_page() {
+        let entries = |n: u64| (0..n).map(|i| Ok((i, i * 10)));
+
+        let result = page(entries(3), 3, |key| *key).unwrap();
+        assert_eq!((result.data, result.next_cursor), (vec![0, 10, 20], None));
+        let result = page(entries(4), 3, |key| *key).unwrap();
+        assert_eq!(
+            (result.data, result.next_cursor),
+            (vec![0, 10, 20], Some(3))
+        );
+        let result = page(entries(0), 3, |key| *key).unwrap();
+        assert!(result.data.is_empty() && result.next_cursor.is_none());
+        assert!(page(entries(3), 0, |key| *key).is_err());
+
+        // Errors are returned, rather than ending the page.
+        let failing = [
+            Ok((0, 0)),
+            Err(TypedStoreError::RocksDBError("".to_string())),
+        ];
+        assert!(page(failing.into_iter(), 3, |key| *key).is_err());
+    }
+
+    #[tokio::test]
+    async fn resume_from_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = LocalKvStore::new(dir.path().to_owned());
+        let (id, other) = (ObjectID::ZERO, ObjectID::MAX);
+        let objects: Vec<_> = (1..=5).map(|version| object(id, version)).collect();
+        let other_object = object(other, 1);
+        store
+            .save_objects(
+                &objects.iter().chain([&other_object]).collect::<Vec<_>>(),
+                0,
+            )
+            .await
+            .unwrap();
+        for e in 0..5 {
+            store.save_epoch(epoch(e)).await.unwrap();
+        }
+
+        // Versions of one object only, in pages of two.
+        let mut versions = vec![];
+        let mut cursor = None;
+        loop {
+            let page = store.get_object_versions(&id, cursor, 2).await.unwrap();
+            assert!(page.data.len() <= 2);
+            versions.extend(page.data.iter().map(|object| object.version().value()));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(versions, vec![1, 2, 3, 4, 5]);
+
+        let page = store
+            .get_object_versions(&id, Some(SequenceNumber::from_u64(4)), 2)
+            .await
+            .unwrap();
+        assert_eq!(page.data, objects[3..].to_vec());
+        assert_eq!(page.next_cursor, None);
+
+        // Epochs stay within the range, even with a cursor before its start.
+        let page = store.get_epochs(1..4, None, 2).await.unwrap();
+        let epochs: Vec<_> = page.data.iter().map(|e| e.epoch).collect();
+        assert_eq!((epochs, page.next_cursor), (vec![1, 2], Some(3)));
+        let page = store.get_epochs(1..4, page.next_cursor, 2).await.unwrap();
+        let epochs: Vec<_> = page.data.iter().map(|e| e.epoch).collect();
+        assert_eq!((epochs, page.next_cursor), (vec![3], None));
+        let page = store.get_epochs(1..4, Some(0), 1).await.unwrap();
+        assert_eq!(page.data[0].epoch, 1);
+    }
+
+    async fn save_checkpoint(store: &mut LocalKvStore, checkpoint: &CheckpointData) {
+        let transactions: Vec<_> = checkpoint
+            .transactions
+            .iter()
+            .map(|transaction| TransactionData {
+                transaction: transaction.transaction.clone(),
+                effects: transaction.effects.clone(),
+                events: transaction.events.clone(),
+                checkpoint_number: checkpoint.checkpoint_summary.sequence_number,
+                timestamp: checkpoint.checkpoint_summary.timestamp_ms,
+            })
+            .collect();
+        store.save_transactions(&transactions).await.unwrap();
+        store.save_checkpoint(checkpoint).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn transactions_in_checkpoints_with_gap() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = LocalKvStore::new(dir.path().to_owned());
+        let mut builder = TestCheckpointBuilder::new(0);
+        let mut checkpoints = vec![];
+        for _ in 0..5 {
+            for sender in 0..2 {
+                builder = builder.start_transaction(sender).finish_transaction();
+            }
+            checkpoints.push(CheckpointData::from(builder.build_checkpoint()));
+        }
+        // Checkpoint 2 is not stored.
+        for checkpoint in checkpoints
+            .iter()
+            .filter(|c| c.checkpoint_summary.sequence_number != 2)
+        {
+            save_checkpoint(&mut store, checkpoint).await;
+        }
+        let digests = |page: &Page<TransactionData, TransactionCursor>| -> Vec<_> {
+            page.data
+                .iter()
+                .map(|transaction| *transaction.transaction.digest())
+                .collect()
+        };
+        let expected = |range: Range<usize>| -> Vec<_> {
+            checkpoints[range]
+                .iter()
+                .flat_map(|checkpoint| &checkpoint.transactions)
+                .map(|transaction| *transaction.transaction.digest())
+                .collect()
+        };
+        let cursor = |checkpoint, index| Some(TransactionCursor { checkpoint, index });
+
+        // The page ends at the missing checkpoint, rather than at the end of the range.
+        let page = store
+            .get_transactions_in_checkpoints(0..5, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(digests(&page), expected(0..2));
+        assert_eq!(page.next_cursor, cursor(2, 0));
+        let page = store
+            .get_transactions_in_checkpoints(0..5, page.next_cursor, 10)
+            .await
+            .unwrap();
+        assert!(page.data.is_empty());
+        assert_eq!(page.next_cursor, cursor(2, 0));
+
+        // A page that fills up within a checkpoint resumes from there.
+        let page = store
+            .get_transactions_in_checkpoints(0..5, None, 3)
+            .await
+            .unwrap();
+        assert_eq!(page.next_cursor, cursor(1, 1));
+        let page = store
+            .get_transactions_in_checkpoints(0..5, page.next_cursor, 3)
+            .await
+            .unwrap();
+        assert_eq!(digests(&page), expected(1..2)[1..].to_vec());
+        assert_eq!(page.next_cursor, cursor(2, 0));
+
+        // Once the missing checkpoint is stored, the range can be read to its end.
+        save_checkpoint(&mut store, &checkpoints[2]).await;
+        let page = store
+            .get_transactions_in_checkpoints(0..5, page.next_cursor, 10)
+            .await
+            .unwrap();
+        assert_eq!(digests(&page), expected(2..5));
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = ObjectID::random();
+        {
+            let mut store = LocalKvStore::new(dir.path().to_owned());
+            let mut progress = LocalProgressStore::new(store.clone());
+            assert_eq!(progress.load("task".to_string()).await.unwrap(), 0);
+            progress.save("task".to_string(), 42).await.unwrap();
+            store.save_objects(&[&object(id, 7)], 0).await.unwrap();
+            store.save_epoch(epoch(3)).await.unwrap();
+        }
+
+        let mut store = LocalKvStore::new(dir.path().to_owned());
+        assert_eq!(store.get_latest_checkpoint().await.unwrap(), 42);
+        assert_eq!(
+            store
+                .get_latest_object(&id)
+                .await
+                .unwrap()
+                .unwrap()
+                .version(),
+            SequenceNumber::from_u64(7)
+        );
+        assert_eq!(store.get_latest_epoch().await.unwrap().unwrap().epoch, 3);
+
+        let mut progress = LocalProgressStore::new(store);
+        assert_eq!(progress.load("task".to_string()).await.unwrap(), 42);
+        progress.save("task".to_string(), 43).await.unwrap();
+        assert_eq!(progress.load("task".to_string()).await.unwrap(), 43);
+    }
+}
@@ -0,0 +1,5 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+pub(crate) mod client;
+pub(crate) mod progress_store;
@@ -0,0 +1,29 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{KeyValueStoreReader, KeyValueStoreWriter, LocalKvStore};
+use anyhow::Result;
+use async_trait::async_trait;
+use rtd_data_ingestion_core::ProgressStore;
+use rtd_types::messages_checkpoint::CheckpointSequenceNumber;
+
+pub struct LocalProgressStore {
+    store: LocalKvStore,
+}
+
+impl LocalProgressStore {
+    pub fn new(store: LocalKvStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl ProgressStore for LocalProgressStore {
+    async fn load(&mut self, _: String) -> Result<CheckpointSequenceNumber> {
+        self.store.get_latest_checkpoint().await
+    }
+
+    async fn save(&mut self, _: String, checkpoint_number: CheckpointSequenceNumber) -> Result<()> {
+        self.store.save_watermark(checkpoint_number).await
+    }
+}
@@ -1,12 +1,18 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
 use prometheus::Registry;
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::str::FromStr;
-use rtd_data_ingestion_core::{DataIngestionMetrics, IndexerExecutor, ReaderOptions, WorkerPool};
-use rtd_kvstore::{BigTableClient, BigTableProgressStore, KeyValueStoreReader, KvWorker};
+use rtd_data_ingestion_core::{
+    DataIngestionMetrics, IndexerExecutor, ProgressStore, ReaderOptions, WorkerPool,
+};
+use rtd_kvstore::{
+    BigTableClient, BigTableProgressStore, KeyValueStoreReader, KeyValueStoreWriter, KvWorker,
+    LocalKvStore, LocalProgressStore,
+};
 use rtd_types::base_types::ObjectID;
 use rtd_types::digests::TransactionDigest;
 use rtd_types::storage::ObjectKey;
@@ -14,8 +20,13 @@ use telemetry_subscribers::TelemetryConfig;
 use tokio::sync::oneshot;
 
 #[derive(Parser)]
+#[command(group(ArgGroup::new("store").required(true).args(["instance_id", "local_path"])))]
 struct App {
-    instance_id: String,
+    /// The BigTable instance to use.
+    instance_id: Option<String>,
+    /// Use a local RocksDB store at this path instead of BigTable.
+    #[arg(long)]
+    local_path: Option<PathBuf>,
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -50,75 +61,107 @@ async fn main() -> Result<()> {
         Some(Command::Ingestion {
             network,
             concurrency,
-        }) => {
-            let client = BigTableClient::new_remote(
-                app.instance_id,
-                false,
-                None,
-                "ingestion".to_string(),
-                None,
-                None,
-            )
-            .await?;
-            let (_exit_sender, exit_receiver) = oneshot::channel();
-            let mut executor = IndexerExecutor::new(
-                BigTableProgressStore::new(client.clone()),
-                1,
-                DataIngestionMetrics::new(&Registry::new()),
-            );
-            let worker_pool =
-                WorkerPool::new(KvWorker { client }, "bigtable".to_string(), concurrency);
-            executor.register(worker_pool).await?;
-            executor
-                .run(
-                    tempfile::tempdir()?.keep(),
-                    Some(format!("https://checkpoints.{}.rtd.io", network)),
-                    vec![],
-                    ReaderOptions::default(),
-                    exit_receiver,
+        }) => match (app.instance_id, app.local_path) {
+            (_, Some(path)) => {
+                let store = LocalKvStore::new(path);
+                let progress_store = LocalProgressStore::new(store.clone());
+                ingest(store, progress_store, "local", network, concurrency).await?;
+            }
+            (Some(instance_id), None) => {
+                let client = BigTableClient::new_remote(
+                    instance_id,
+                    false,
+                    None,
+                    "ingestion".to_string(),
+                    None,
+                    None,
                 )
                 .await?;
-        }
-        Some(Command::Fetch { entry }) => {
-            let mut client = BigTableClient::new_remote(
-                app.instance_id,
-                true,
-                None,
-                "cli".to_string(),
-                None,
-                None,
-            )
-            .await?;
-            let result = match entry {
-                Entry::Epoch { id } => client.get_epoch(id).await?.map(|e| bcs::to_bytes(&e)),
-                Entry::Object { id, version } => {
-                    let objects = client
-                        .get_objects(&[ObjectKey(ObjectID::from_str(&id)?, version.into())])
-                        .await?;
-                    objects.first().map(bcs::to_bytes)
-                }
-                Entry::Checkpoint { id } => {
-                    let checkpoints = client.get_checkpoints(&[id]).await?;
-                    checkpoints.first().map(bcs::to_bytes)
-                }
-                Entry::Transaction { id } => {
-                    let transactions = client
-                        .get_transactions(&[TransactionDigest::from_str(&id)?])
-                        .await?;
-                    transactions.first().map(bcs::to_bytes)
-                }
-                Entry::Watermark => {
-                    let watermark = client.get_latest_checkpoint().await?;
-                    println!("watermark is {}", watermark);
-                    return Ok(());
-                }
-            };
-            match result {
-                Some(bytes) => io::stdout().write_all(&bytes?)?,
-                None => println!("not found"),
+                let progress_store = BigTableProgressStore::new(client.clone());
+                ingest(client, progress_store, "bigtable", network, concurrency).await?;
             }
-        }
+            (None, None) => unreachable!("a store is required"),
+        },
+        Some(Command::Fetch { entry }) => match (app.instance_id, app.local_path) {
+            (_, Some(path)) => fetch(LocalKvStore::new(path), entry).await?,
+            (Some(instance_id), None) => {
+                let client = BigTableClient::new_remote(
+                    instance_id,
+                    true,
+                    None,
+                    "cli".to_string(),
+                    None,
+                    None,
+                )
+                .await?;
+                fetch(client, entry).await?
+            }
+            (None, None) => unreachable!("a store is required"),
+        },
         None => println!("no command provided"),
     }
     Ok(())
 }
+
+async fn ingest<C, P>(
+    client: C,
+    progress_store: P,
+    name: &str,
+    network: String,
+    concurrency: usize,
+) -> Result<()>
+where
+    C: KeyValueStoreReader + KeyValueStoreWriter + Clone + Send + Sync + 'static,
+    P: ProgressStore,
+{
+    let (_exit_sender, exit_receiver) = oneshot::channel();
+    let mut executor = IndexerExecutor::new(
+        progress_store,
+        1,
+        DataIngestionMetrics::new(&Registry::new()),
+    );
+    let worker_pool = WorkerPool::new(KvWorker { client }, name.to_string(), concurrency);
+    executor.register(worker_pool).await?;
+    executor
+        .run(
+            tempfile::tempdir()?.keep(),
+            Some(format!("https://checkpoints.{}.rtd.io", network)),
+            vec![],
+            ReaderOptions::default(),
+            exit_receiver,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn fetch(mut client: impl KeyValueStoreReader, entry: Entry) -> Result<()> {
+    let result = match entry {
+        Entry::Epoch { id } => client.get_epoch(id).await?.map(|e| bcs::to_bytes(&e)),
+        Entry::Object { id, version } => {
+            let objects = client
+                .get_objects(&[ObjectKey(ObjectID::from_str(&id)?, version.into())])
+                .await?;
+            objects.first().map(bcs::to_bytes)
+        }
+        Entry::Checkpoint { id } => {
+            let checkpoints = client.get_checkpoints(&[id]).await?;
+            checkpoints.first().map(bcs::to_bytes)
+        }
+        Entry::Transaction { id } => {
+            let transactions = client
+                .get_transactions(&[TransactionDigest::from_str(&id)?])
+                .await?;
+            transactions.first().map(bcs::to_bytes)
+        }
+        Entry::Watermark => {
+            let watermark = client.get_latest_checkpoint().await?;
+            println!("watermark is {}", watermark);
+            return Ok(());
+        }
+    };
+    match result {
+        Some(bytes) => io::stdout().write_all(&bytes?)?,
+        None => println!("not found"),
+    }
+    Ok(())
+}
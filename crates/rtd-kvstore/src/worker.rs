@@ -6,12 +6,16 @@ use async_trait::async_trait;
 use rtd_data_ingestion_core::Worker;
 use rtd_types::full_checkpoint_content::CheckpointData;
 
-pub struct KvWorker {
-    pub client: BigTableClient,
+/// Writes the content of every checkpoint to a key value store.
+pub struct KvWorker<C = BigTableClient> {
+    pub client: C,
 }
 
 #[async_trait]
-impl Worker for KvWorker {
+impl<C> Worker for KvWorker<C>
+where
+    C: KeyValueStoreReader + KeyValueStoreWriter + Clone + Send + Sync + 'static,
+{
     type Result = ();
 
     async fn process_checkpoint(&self, checkpoint: &CheckpointData) -> anyhow::Result<()> {
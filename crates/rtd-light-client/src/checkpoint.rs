@@ -1,16 +1,15 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::committee::extract_new_committee_info;
 use crate::config::Config;
 use crate::graphql::query_last_checkpoint_of_epoch;
 use crate::object_store::RtdObjectStore;
+use crate::store::LightClientStore;
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::io::Read;
 use std::{fs, io::Write};
-use rtd_config::genesis::Genesis;
 use rtd_data_ingestion_core::end_of_epoch_data;
 use rtd_sdk::RtdClientBuilder;
 use rtd_types::{
@@ -227,7 +226,10 @@ async fn sync_checkpoint_list_to_latest_using_graphql(
     Ok(checkpoints_list)
 }
 
-pub async fn check_and_sync_checkpoints(config: &Config) -> anyhow::Result<()> {
+pub async fn check_and_sync_checkpoints(
+    config: &Config,
+    store: &LightClientStore,
+) -> anyhow::Result<()> {
     let checkpoints_list = sync_checkpoint_list_to_latest(config)
         .await
         .map_err(|e| anyhow!(format!("Cannot refresh list: {e}")))?;
@@ -235,48 +237,38 @@ pub async fn check_and_sync_checkpoints(config: &Config) -> anyhow::Result<()> {
     // Write the fetched checkpoint list to disk
     write_checkpoint_list(config, &checkpoints_list)?;
 
-    // Load the genesis committee
-    let mut genesis_path = config.checkpoint_summary_dir.clone();
-    genesis_path.push(&config.genesis_filename);
-    let genesis_committee = Genesis::load(&genesis_path)?
-        .committee()
-        .map_err(|e| anyhow!(format!("Cannot load Genesis: {e}")))?;
-
-    // Check the signatures of all checkpoints
-    // And download any missing ones
-
-    let mut prev_committee = genesis_committee;
+    // Check the signatures of all checkpoints not yet in the store
+    // And download any missing ones. The list holds the last checkpoint
+    // of every epoch, so the store already has one summary per previous epoch.
     let object_store = RtdObjectStore::new(config)?;
-    for ckp_id in &checkpoints_list.checkpoints {
-        // check if there is a file with this name ckp_id.yaml in the checkpoint_summary_dir
-        let mut checkpoint_path = config.checkpoint_summary_dir.clone();
-        checkpoint_path.push(format!("{}.yaml", ckp_id));
-
-        // If file exists read the file otherwise download it from the server
+    let latest_epoch = store.latest_epoch()?;
+    for ckp_id in checkpoints_list
+        .checkpoints
+        .iter()
+        .skip(latest_epoch as usize)
+    {
+        // Import summary files written by previous versions of the light client,
+        // otherwise download the checkpoint from the server
+        let checkpoint_path = config.checkpoint_path(*ckp_id, None);
         let summary = if checkpoint_path.exists() {
             read_checkpoint(config, *ckp_id)
                 .map_err(|e| anyhow!(format!("Cannot read checkpoint: {e}")))?
         } else {
-            // Download the checkpoint from the server
-            let summary = object_store
+            object_store
                 .download_checkpoint_summary(*ckp_id)
                 .await
-                .map_err(|e| anyhow!(format!("Cannot download summary: {e}")))?;
-            summary.clone().try_into_verified(&prev_committee)?;
-            // Write the checkpoint summary to a file
-            write_checkpoint(config, &summary)?;
-            summary
+                .map_err(|e| anyhow!(format!("Cannot download summary: {e}")))?
         };
 
+        // Verify the summary and store it along with the new committee
+        store.append_end_of_epoch_summary(&summary)?;
+
         // Print the id of the checkpoint and the epoch number
         info!(
             "Epoch: {} Checkpoint ID: {}",
             summary.epoch(),
             summary.digest()
         );
-
-        // Extract the new committee information
-        prev_committee = extract_new_committee_info(&summary)?;
     }
 
     Ok(())
@@ -58,6 +58,10 @@ impl Config {
     pub fn genesis_path(&self) -> PathBuf {
         self.checkpoint_summary_dir.join(&self.genesis_filename)
     }
+
+    pub fn store_path(&self) -> PathBuf {
+        self.checkpoint_summary_dir.join("store")
+    }
 }
 
 #[cfg(test)]
@@ -110,4 +114,10 @@ mod tests {
         let genesis_path = config.genesis_path();
         assert_eq!(genesis_path.file_name().unwrap(), "genesis.blob");
     }
+
+    #[test]
+    fn test_store_path() {
+        let (config, temp_dir) = create_test_config();
+        assert_eq!(config.store_path(), temp_dir.path().join("store"));
+    }
 }
@@ -14,6 +14,10 @@ pub mod graphql;
 
 pub mod mmr;
 
+pub mod server;
+
+pub mod store;
+
 pub mod verifier;
 
 #[doc(inline)]
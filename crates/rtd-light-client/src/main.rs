@@ -10,11 +10,16 @@ use rtd_types::{
 use rtd_package_resolver::Resolver;
 
 use clap::{Parser, Subcommand};
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{fs, net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use rtd_light_client::bundle::ProofBundle;
 use rtd_light_client::checkpoint::check_and_sync_checkpoints;
 use rtd_light_client::config::Config;
 use rtd_light_client::package_store::RemotePackageStore;
-use rtd_light_client::verifier::{get_verified_effects_and_events, get_verified_object};
+use rtd_light_client::server::start_server;
+use rtd_light_client::store::LightClientStore;
+use rtd_light_client::verifier::{
+    get_verified_effects_and_events, get_verified_object, verify_proof_bundle,
+};
 
 use tracing::info;
 
@@ -48,6 +53,31 @@ enum SCommands {
         #[arg(short, long, value_name = "OID")]
         oid: String,
     },
+
+    /// Runs the light client as a daemon that keeps the committees synced and serves proofs
+    Serve {
+        /// Address to serve the HTTP API on
+        #[arg(short, long, value_name = "ADDR", default_value = "127.0.0.1:9188")]
+        address: SocketAddr,
+
+        /// Seconds between two syncs of the end-of-epoch checkpoints
+        #[arg(short, long, value_name = "SECS", default_value_t = 600)]
+        sync_interval_secs: u64,
+    },
+
+    /// Removes the synced checkpoints and committees of all epochs before the given one
+    Prune {
+        /// The first epoch to keep
+        #[arg(short, long, value_name = "EPOCH")]
+        epoch: u64,
+    },
+
+    /// Verifies a proof bundle served by a light client daemon against the local committees
+    VerifyBundle {
+        /// Path to the JSON encoded proof bundle
+        #[arg(short, long, value_name = "FILE")]
+        file: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -70,13 +100,15 @@ pub async fn main() {
         config.checkpoint_summary_dir.display()
     );
 
-    let remote_package_store = RemotePackageStore::new(config.clone());
+    let store = Arc::new(LightClientStore::open(&config).expect("Cannot open light client store"));
+    let remote_package_store = RemotePackageStore::new(config.clone(), store.clone());
     let resolver = Resolver::new(remote_package_store);
 
     match args.command {
         Some(SCommands::Transaction { tid }) => {
             let (effects, events) = get_verified_effects_and_events(
                 &config,
+                &store,
                 TransactionDigest::from_str(&tid).unwrap(),
             )
             .await
@@ -113,7 +145,7 @@ pub async fn main() {
         }
         Some(SCommands::Object { oid }) => {
             let oid = ObjectID::from_str(&oid).unwrap();
-            let object = get_verified_object(&config, oid).await.unwrap();
+            let object = get_verified_object(&config, &store, oid).await.unwrap();
             info!("Successfully verified object: {}", oid);
 
             if let Data::Move(move_object) = &object.data {
@@ -142,10 +174,36 @@ pub async fn main() {
         }
 
         Some(SCommands::Sync {}) => {
-            check_and_sync_checkpoints(&config)
+            check_and_sync_checkpoints(&config, &store)
                 .await
                 .expect("Failed to sync checkpoints");
         }
+
+        Some(SCommands::Serve {
+            address,
+            sync_interval_secs,
+        }) => {
+            start_server(
+                config,
+                store,
+                address,
+                Duration::from_secs(sync_interval_secs),
+            )
+            .await
+            .expect("Light client daemon failed");
+        }
+
+        Some(SCommands::Prune { epoch }) => {
+            store.prune(epoch).expect("Failed to prune store");
+        }
+
+        Some(SCommands::VerifyBundle { file }) => {
+            let reader = fs::File::open(&file)
+                .unwrap_or_else(|_| panic!("Unable to load bundle from {}", file.display()));
+            let bundle: ProofBundle = serde_json::from_reader(reader).unwrap();
+            verify_proof_bundle(&store, bundle).expect("Invalid proof bundle");
+            println!("Proof bundle is valid");
+        }
         _ => {
             println!("No command...");
         }
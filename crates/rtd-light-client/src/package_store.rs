@@ -11,17 +11,20 @@ use tokio::sync::Mutex;
 use tracing::{error, info};
 
 use crate::config::Config;
+use crate::store::LightClientStore;
 use crate::verifier::get_verified_object;
 
 pub struct RemotePackageStore {
     config: Config,
+    store: Arc<LightClientStore>,
     cache: Mutex<HashMap<AccountAddress, Arc<Package>>>,
 }
 
 impl RemotePackageStore {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, store: Arc<LightClientStore>) -> Self {
         Self {
             config,
+            store,
             cache: Mutex::new(HashMap::new()),
         }
     }
@@ -37,9 +40,19 @@ impl PackageStore for RemotePackageStore {
                 return Ok(package.clone());
             }
 
-            info!("Fetch Package: {}", id);
-
-            let object = get_verified_object(&self.config, id.into()).await?;
+            // Packages in the local store have been verified when they were fetched
+            let object = if let Some(object) = self.store.get_package(id.into())? {
+                info!("Fetch Package: {} store hit", id);
+                object
+            } else {
+                info!("Fetch Package: {}", id);
+                let object = get_verified_object(&self.config, &self.store, id.into()).await?;
+                // System packages are upgraded in place, so only keep them in memory
+                if !object.is_system_package() {
+                    self.store.insert_package(&object)?;
+                }
+                object
+            };
             let package = Arc::new(Package::read_from_object(&object)?);
 
             // Add to the cache
@@ -0,0 +1,64 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use rtd_types::committee::{Committee, EpochId};
+
+use crate::proof::{
+    base::{Proof, ProofTarget, ProofVerifier},
+    error::{ProofError, ProofResult},
+};
+
+/// A self-contained proof that can be verified offline by anyone holding a trusted committee.
+///
+/// The bundle carries the chain of committee proofs that links the trusted committee to the
+/// committee that signed the checkpoint of the main proof. An empty chain means the main proof
+/// is signed by the trusted committee directly.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProofBundle {
+    /// Committee proofs for consecutive end of epoch checkpoints, starting from the epoch of
+    /// the trusted committee.
+    pub committee_proofs: Vec<Proof>,
+
+    /// The proof for the actual targets.
+    pub proof: Proof,
+}
+
+impl ProofBundle {
+    pub fn new(committee_proofs: Vec<Proof>, proof: Proof) -> Self {
+        Self {
+            committee_proofs,
+            proof,
+        }
+    }
+
+    /// The epoch of the committee the verifier needs to trust for this bundle.
+    pub fn trusted_epoch(&self) -> EpochId {
+        self.committee_proofs
+            .first()
+            .unwrap_or(&self.proof)
+            .checkpoint_summary
+            .epoch()
+    }
+
+    /// Verify the committee chain starting from `trusted_committee`, then the main proof
+    /// against the last committee of the chain.
+    pub fn verify(self, trusted_committee: &Committee) -> ProofResult<()> {
+        if trusted_committee.epoch != self.trusted_epoch() {
+            return Err(ProofError::EpochMismatch);
+        }
+
+        let mut committee = trusted_committee.clone();
+        for committee_proof in self.committee_proofs {
+            let ProofTarget::Committee(target) = &committee_proof.targets else {
+                return Err(ProofError::MismatchedTargetAndProofType);
+            };
+            let next_committee = target.committee.clone();
+            committee_proof.verify(&committee)?;
+            committee = next_committee;
+        }
+        // MILESTONE: Committee of the proof checkpoint is certified
+
+        self.proof.verify(&committee)
+    }
+}
@@ -21,27 +21,38 @@ pub struct CommitteeTarget {
     pub committee: Committee,
 }
 
-impl ProofBuilder for CommitteeTarget {
-    fn construct(self, checkpoint: &CheckpointData) -> ProofResult<Proof> {
+impl CommitteeTarget {
+    /// Construct a committee proof from an end of epoch checkpoint summary alone, since the
+    /// committee proof does not need the checkpoint contents.
+    pub fn construct_from_summary(
+        self,
+        summary: &CertifiedCheckpointSummary,
+    ) -> ProofResult<Proof> {
         // Do a minimal check that the given checkpoint data is consistent with the committee
         // Check we have the correct epoch
-        if checkpoint.checkpoint_summary.epoch() + 1 != self.committee.epoch {
+        if summary.epoch() + 1 != self.committee.epoch {
             return Err(ProofError::EpochMismatch);
         }
 
         // Check its an end of epoch checkpoint
-        if checkpoint.checkpoint_summary.end_of_epoch_data.is_none() {
+        if summary.end_of_epoch_data.is_none() {
             return Err(ProofError::ExpectedEndOfEpochCheckpoint);
         }
 
         Ok(Proof {
             targets: ProofTarget::Committee(self),
-            checkpoint_summary: checkpoint.checkpoint_summary.clone(),
+            checkpoint_summary: summary.clone(),
             proof_contents: ProofContents::CommitteeProof(CommitteeProof {}),
         })
     }
 }
 
+impl ProofBuilder for CommitteeTarget {
+    fn construct(self, checkpoint: &CheckpointData) -> ProofResult<Proof> {
+        self.construct_from_summary(&checkpoint.checkpoint_summary)
+    }
+}
+
 /// Note: The summary is enough to verify the committee.
 /// This is a placeholder for the committee proof.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,5 +12,8 @@ pub mod ocs;
 // Proofs
 pub mod transaction_proof;
 
+// Bundles
+pub mod bundle;
+
 // Error types
 pub mod error;
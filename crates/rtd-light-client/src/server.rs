@@ -0,0 +1,310 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::checkpoint::check_and_sync_checkpoints;
+use crate::committee::CommitteeTarget;
+use crate::config::Config;
+use crate::object_store::RtdObjectStore;
+use crate::proof::{
+    base::{Proof, ProofBuilder, ProofTarget},
+    bundle::ProofBundle,
+};
+use crate::store::LightClientStore;
+use anyhow::{Result, anyhow};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use rtd_json_rpc_types::RtdTransactionBlockResponseOptions;
+use rtd_sdk::{RtdClient, RtdClientBuilder};
+use rtd_types::{
+    base_types::ObjectID,
+    committee::{Committee, EpochId},
+    digests::TransactionDigest,
+    event::EventID,
+    full_checkpoint_content::CheckpointData,
+    messages_checkpoint::CheckpointSequenceNumber,
+};
+use tracing::{error, info};
+
+/// State shared by the sync loop and the HTTP handlers of the light client daemon.
+pub struct LightClientState {
+    config: Config,
+    client: RtdClient,
+    object_store: RtdObjectStore,
+    store: Arc<LightClientStore>,
+}
+
+impl LightClientState {
+    pub async fn new(config: Config, store: Arc<LightClientStore>) -> Result<Self> {
+        let client = RtdClientBuilder::default()
+            .build(config.full_node_url.as_str())
+            .await?;
+        let object_store = RtdObjectStore::new(&config)?;
+
+        Ok(Self {
+            config,
+            client,
+            object_store,
+            store,
+        })
+    }
+
+    /// Sync the end of epoch checkpoints, extending the verified committee chain in the store.
+    pub async fn refresh(&self) -> Result<()> {
+        check_and_sync_checkpoints(&self.config, &self.store).await?;
+        info!(
+            "Committee chain synced to epoch {}",
+            self.store.latest_epoch()?
+        );
+        Ok(())
+    }
+
+    /// Committee proofs linking the committee of `from_epoch` to the committee of `to_epoch`.
+    fn committee_proofs(&self, from_epoch: EpochId, to_epoch: EpochId) -> Result<Vec<Proof>> {
+        (from_epoch..to_epoch)
+            .map(|epoch| {
+                let summary = self.store.end_of_epoch_summary(epoch)?;
+                let target = CommitteeTarget {
+                    committee: self.store.committee(epoch + 1)?,
+                };
+                Ok(target.construct_from_summary(&summary)?)
+            })
+            .collect()
+    }
+
+    /// Build the proof for `target` at the given checkpoint, along with the committee proofs
+    /// needed to verify it starting from `trusted_epoch`.
+    fn prove(
+        &self,
+        target: ProofTarget,
+        checkpoint: &CheckpointData,
+        trusted_epoch: Option<EpochId>,
+    ) -> Result<ProofBundle> {
+        let epoch = checkpoint.checkpoint_summary.epoch();
+        let trusted_epoch = trusted_epoch.unwrap_or(epoch);
+        anyhow::ensure!(
+            trusted_epoch <= epoch,
+            "Trusted epoch {} is after the checkpoint epoch {}",
+            trusted_epoch,
+            epoch
+        );
+
+        let committee = self.store.committee(epoch)?;
+
+        // Check the checkpoint before handing out any proof about it
+        checkpoint
+            .checkpoint_summary
+            .verify_with_contents(&committee, Some(&checkpoint.checkpoint_contents))?;
+
+        let proof = target.construct(checkpoint)?;
+        let committee_proofs = self.committee_proofs(trusted_epoch, epoch)?;
+        Ok(ProofBundle::new(committee_proofs, proof))
+    }
+
+    async fn prove_object(
+        &self,
+        id: ObjectID,
+        seq: CheckpointSequenceNumber,
+        trusted_epoch: Option<EpochId>,
+    ) -> Result<ProofBundle> {
+        let checkpoint = self.object_store.get_full_checkpoint(seq).await?;
+
+        // The state of the object at the end of the checkpoint is the last version written
+        let object = checkpoint
+            .transactions
+            .iter()
+            .rev()
+            .find_map(|tx| tx.output_objects.iter().find(|o| o.id() == id))
+            .ok_or(ApiError::not_found(format!(
+                "Object {} is not written in checkpoint {}",
+                id, seq
+            )))?;
+
+        let target =
+            ProofTarget::new_objects(vec![(object.compute_object_reference(), object.clone())]);
+        self.prove(target, &checkpoint, trusted_epoch)
+    }
+
+    async fn prove_event(
+        &self,
+        tx_digest: TransactionDigest,
+        event_seq: u64,
+        trusted_epoch: Option<EpochId>,
+    ) -> Result<ProofBundle> {
+        let seq = self
+            .client
+            .read_api()
+            .get_transaction_with_options(tx_digest, RtdTransactionBlockResponseOptions::new())
+            .await
+            .map_err(|e| anyhow!(format!("Cannot get transaction: {e}")))?
+            .checkpoint
+            .ok_or(ApiError::not_found(format!(
+                "Transaction {} is not checkpointed",
+                tx_digest
+            )))?;
+
+        let checkpoint = self.object_store.get_full_checkpoint(seq).await?;
+        let event = checkpoint
+            .transactions
+            .iter()
+            .find(|tx| tx.transaction.digest() == &tx_digest)
+            .and_then(|tx| tx.events.as_ref())
+            .and_then(|events| events.data.get(event_seq as usize))
+            .ok_or(ApiError::not_found(format!(
+                "Event {} not found in transaction {}",
+                event_seq, tx_digest
+            )))?;
+
+        let target =
+            ProofTarget::new_events(vec![(EventID::from((tx_digest, event_seq)), event.clone())]);
+        self.prove(target, &checkpoint, trusted_epoch)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProofQuery {
+    /// The epoch of the committee the caller already trusts. Defaults to the epoch of the
+    /// checkpoint being proven.
+    trusted_epoch: Option<EpochId>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusResponse {
+    latest_epoch: EpochId,
+    latest_end_of_epoch_checkpoint: Option<CheckpointSequenceNumber>,
+}
+
+/// Error returned by the HTTP handlers, rendered as a status code and a message.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found(message: String) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message,
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<ApiError>() {
+            Ok(e) => e,
+            Err(e) => Self {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, self.message).into_response()
+    }
+}
+
+async fn health() -> &'static str {
+    "OK"
+}
+
+async fn status(
+    State(state): State<Arc<LightClientState>>,
+) -> Result<Json<StatusResponse>, ApiError> {
+    let latest_epoch = state.store.latest_epoch()?;
+    let latest_end_of_epoch_checkpoint = latest_epoch
+        .checked_sub(1)
+        .and_then(|epoch| state.store.end_of_epoch_summary(epoch).ok())
+        .map(|summary| *summary.sequence_number());
+    Ok(Json(StatusResponse {
+        latest_epoch,
+        latest_end_of_epoch_checkpoint,
+    }))
+}
+
+async fn committee(
+    State(state): State<Arc<LightClientState>>,
+    Path(epoch): Path<EpochId>,
+) -> Result<Json<Committee>, ApiError> {
+    let committee = state
+        .store
+        .committee(epoch)
+        .map_err(|e| ApiError::not_found(e.to_string()))?;
+    Ok(Json(committee))
+}
+
+async fn object_proof(
+    State(state): State<Arc<LightClientState>>,
+    Path((id, seq)): Path<(ObjectID, CheckpointSequenceNumber)>,
+    Query(query): Query<ProofQuery>,
+) -> Result<Json<ProofBundle>, ApiError> {
+    Ok(Json(
+        state.prove_object(id, seq, query.trusted_epoch).await?,
+    ))
+}
+
+async fn event_proof(
+    State(state): State<Arc<LightClientState>>,
+    Path((tx_digest, event_seq)): Path<(TransactionDigest, u64)>,
+    Query(query): Query<ProofQuery>,
+) -> Result<Json<ProofBundle>, ApiError> {
+    Ok(Json(
+        state
+            .prove_event(tx_digest, event_seq, query.trusted_epoch)
+            .await?,
+    ))
+}
+
+/// Run the light client as a daemon: keep the committee chain synced every `sync_interval` and
+/// serve proof bundles over HTTP on `address`.
+pub async fn start_server(
+    config: Config,
+    store: Arc<LightClientStore>,
+    address: SocketAddr,
+    sync_interval: Duration,
+) -> Result<()> {
+    let state = Arc::new(LightClientState::new(config, store).await?);
+    state.refresh().await?;
+
+    let sync_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(sync_interval);
+        // The first tick completes immediately, and we just synced
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = sync_state.refresh().await {
+                error!("Failed to sync checkpoints: {:?}", e);
+            }
+        }
+    });
+
+    let app = Router::new()
+        .route("/", get(health))
+        .route("/status", get(status))
+        .route("/committees/{epoch}", get(committee))
+        .route(
+            "/proofs/objects/{object_id}/checkpoints/{checkpoint}",
+            get(object_proof),
+        )
+        .route("/proofs/events/{tx_digest}/{event_seq}", get(event_proof))
+        .with_state(state);
+
+    info!("listening on {}", address);
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,331 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::committee::extract_new_committee_info;
+use crate::config::Config;
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use rtd_config::genesis::Genesis;
+use rtd_types::{
+    base_types::ObjectID,
+    committee::{Committee, EpochId},
+    messages_checkpoint::CertifiedCheckpointSummary,
+    object::Object,
+};
+use tracing::{info, warn};
+use typed_store::rocks::{DBMap, MetricConf};
+use typed_store::{DBMapUtils, Map};
+
+#[derive(DBMapUtils)]
+pub struct LightClientTables {
+    /// Verified end of epoch checkpoint summaries, keyed by the epoch they end.
+    end_of_epoch_summaries: DBMap<EpochId, CertifiedCheckpointSummary>,
+    /// Committee of every retained epoch. The lowest one is the trust anchor of the store: the
+    /// genesis committee, or the committee of the first epoch kept after pruning.
+    committees: DBMap<EpochId, Committee>,
+    /// Verified packages, cached so they do not need to be fetched again.
+    packages: DBMap<ObjectID, Object>,
+}
+
+/// A local store of the verified committee chain and of cached packages.
+///
+/// Every end of epoch summary is verified against the committee of its epoch before being
+/// written, atomically with the committee it certifies for the next epoch. The chain is
+/// verified again when the store is opened.
+pub struct LightClientStore {
+    tables: LightClientTables,
+}
+
+impl LightClientStore {
+    /// Open the store under the checkpoint summary directory, seeding it with the genesis
+    /// committee if it is empty.
+    pub fn open(config: &Config) -> Result<Self> {
+        let genesis_committee = Genesis::load(config.genesis_path())?
+            .committee()
+            .map_err(|e| anyhow!(format!("Cannot load Genesis: {e}")))?;
+        Self::open_with_genesis_committee(&config.store_path(), genesis_committee)
+    }
+
+    pub fn open_with_genesis_committee(path: &Path, genesis_committee: Committee) -> Result<Self> {
+        let tables = LightClientTables::open_tables_read_write(
+            path.to_path_buf(),
+            MetricConf::new("light_client"),
+            None,
+            None,
+        );
+        let store = Self { tables };
+
+        match store.lowest_epoch()? {
+            None => store
+                .tables
+                .committees
+                .insert(&genesis_committee.epoch, &genesis_committee)?,
+            Some(0) => {
+                let stored_genesis_committee = store.committee(0)?;
+                anyhow::ensure!(
+                    stored_genesis_committee == genesis_committee,
+                    "Stored genesis committee does not match the genesis blob"
+                );
+            }
+            Some(_) => {}
+        }
+
+        store.verify_integrity()?;
+        Ok(store)
+    }
+
+    /// The epoch of the trust anchor of the store.
+    fn lowest_epoch(&self) -> Result<Option<EpochId>> {
+        Ok(self
+            .tables
+            .committees
+            .safe_iter()
+            .next()
+            .transpose()?
+            .map(|(epoch, _)| epoch))
+    }
+
+    /// The latest epoch with a verified committee.
+    pub fn latest_epoch(&self) -> Result<EpochId> {
+        self.tables
+            .committees
+            .reversed_safe_iter_with_bounds(None, None)?
+            .next()
+            .transpose()?
+            .map(|(epoch, _)| epoch)
+            .ok_or(anyhow!("Light client store has no committee"))
+    }
+
+    pub fn committee(&self, epoch: EpochId) -> Result<Committee> {
+        self.tables
+            .committees
+            .get(&epoch)?
+            .ok_or(anyhow!("Committee for epoch {} is not synced", epoch))
+    }
+
+    /// The verified summary of the last checkpoint of `epoch`.
+    pub fn end_of_epoch_summary(&self, epoch: EpochId) -> Result<CertifiedCheckpointSummary> {
+        self.tables
+            .end_of_epoch_summaries
+            .get(&epoch)?
+            .ok_or(anyhow!("End of epoch {} is not synced", epoch))
+    }
+
+    pub fn has_end_of_epoch_summary(&self, epoch: EpochId) -> Result<bool> {
+        Ok(self.tables.end_of_epoch_summaries.contains_key(&epoch)?)
+    }
+
+    /// Verify the end of epoch summary of the latest epoch and append it, along with the
+    /// committee of the next epoch, in a single atomic write.
+    pub fn append_end_of_epoch_summary(&self, summary: &CertifiedCheckpointSummary) -> Result<()> {
+        let epoch = self.latest_epoch()?;
+        anyhow::ensure!(
+            summary.epoch() == epoch,
+            "Checkpoint {} ends epoch {}, expected the end of epoch {}",
+            summary.sequence_number(),
+            summary.epoch(),
+            epoch
+        );
+
+        let committee = self.committee(epoch)?;
+        summary.clone().try_into_verified(&committee)?;
+        let next_committee = extract_new_committee_info(summary)?;
+
+        let mut batch = self.tables.end_of_epoch_summaries.batch();
+        batch.insert_batch(&self.tables.end_of_epoch_summaries, [(epoch, summary)])?;
+        batch.insert_batch(
+            &self.tables.committees,
+            [(next_committee.epoch, &next_committee)],
+        )?;
+        batch.write()?;
+        Ok(())
+    }
+
+    pub fn get_package(&self, id: ObjectID) -> Result<Option<Object>> {
+        Ok(self.tables.packages.get(&id)?)
+    }
+
+    /// Cache a package that has been verified by the caller.
+    pub fn insert_package(&self, package: &Object) -> Result<()> {
+        anyhow::ensure!(
+            package.is_package(),
+            "Object {} is not a package",
+            package.id()
+        );
+        Ok(self.tables.packages.insert(&package.id(), package)?)
+    }
+
+    /// Remove the summaries and committees of all epochs before `epoch`. The committee of
+    /// `epoch` becomes the trust anchor of the store.
+    pub fn prune(&self, epoch: EpochId) -> Result<()> {
+        anyhow::ensure!(
+            epoch <= self.latest_epoch()?,
+            "Cannot prune beyond the latest synced epoch"
+        );
+
+        let summaries = self
+            .tables
+            .end_of_epoch_summaries
+            .safe_range_iter(..epoch)
+            .map(|entry| entry.map(|(epoch, _)| epoch))
+            .collect::<Result<Vec<_>, _>>()?;
+        let committees = self
+            .tables
+            .committees
+            .safe_range_iter(..epoch)
+            .map(|entry| entry.map(|(epoch, _)| epoch))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut batch = self.tables.end_of_epoch_summaries.batch();
+        batch.delete_batch(&self.tables.end_of_epoch_summaries, summaries)?;
+        batch.delete_batch(&self.tables.committees, committees)?;
+        batch.write()?;
+
+        info!("Pruned light client store below epoch {}", epoch);
+        Ok(())
+    }
+
+    /// Re-verify the committee chain from the trust anchor, and drop everything from the first
+    /// epoch that does not verify, so that it is synced again.
+    fn verify_integrity(&self) -> Result<()> {
+        let Some(lowest_epoch) = self.lowest_epoch()? else {
+            return Ok(());
+        };
+
+        let mut committee = self.committee(lowest_epoch)?;
+        for entry in self
+            .tables
+            .end_of_epoch_summaries
+            .safe_range_iter(lowest_epoch..)
+        {
+            let (epoch, summary) = entry?;
+            let Ok(next_committee) = self.committee(epoch + 1) else {
+                break;
+            };
+            let verified = epoch == committee.epoch
+                && summary.epoch() == epoch
+                && summary.clone().try_into_verified(&committee).is_ok()
+                && extract_new_committee_info(&summary).ok().as_ref() == Some(&next_committee);
+            if !verified {
+                break;
+            }
+            committee = next_committee;
+        }
+
+        // Everything after the last verified committee is dropped, including committees that are
+        // not certified by a stored summary
+        let summaries = self
+            .tables
+            .end_of_epoch_summaries
+            .safe_range_iter(committee.epoch..)
+            .map(|entry| entry.map(|(epoch, _)| epoch))
+            .collect::<Result<Vec<_>, _>>()?;
+        let committees = self
+            .tables
+            .committees
+            .safe_range_iter(committee.epoch + 1..)
+            .map(|entry| entry.map(|(epoch, _)| epoch))
+            .collect::<Result<Vec<_>, _>>()?;
+        if summaries.is_empty() && committees.is_empty() {
+            return Ok(());
+        }
+
+        warn!(
+            "Light client store does not verify from epoch {}, dropping {} summaries",
+            committee.epoch,
+            summaries.len()
+        );
+        let mut batch = self.tables.end_of_epoch_summaries.batch();
+        batch.delete_batch(&self.tables.end_of_epoch_summaries, summaries)?;
+        batch.delete_batch(&self.tables.committees, committees)?;
+        batch.write()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use rtd_types::full_checkpoint_content::CheckpointData;
+    use tempfile::TempDir;
+
+    fn read_test_checkpoint(seq: u64) -> CheckpointData {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(format!("test_files/{}.chk", seq));
+        let bytes = fs::read(path).unwrap();
+        let (_, checkpoint): (u8, CheckpointData) = bcs::from_bytes(&bytes).unwrap();
+        checkpoint
+    }
+
+    // Returns the committee that signed the end of epoch checkpoint 16005062, and its summary.
+    fn read_test_data() -> (Committee, CertifiedCheckpointSummary) {
+        let committee_checkpoint = read_test_checkpoint(15918264);
+        let committee =
+            extract_new_committee_info(&committee_checkpoint.checkpoint_summary).unwrap();
+        (committee, read_test_checkpoint(16005062).checkpoint_summary)
+    }
+
+    // async: existing runtime is required with typed-store
+    #[tokio::test]
+    async fn test_append_and_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let (committee, summary) = read_test_data();
+        let epoch = committee.epoch;
+
+        {
+            let store =
+                LightClientStore::open_with_genesis_committee(temp_dir.path(), committee.clone())
+                    .unwrap();
+            assert_eq!(store.latest_epoch().unwrap(), epoch);
+            store.append_end_of_epoch_summary(&summary).unwrap();
+            assert_eq!(store.latest_epoch().unwrap(), epoch + 1);
+
+            // The same summary cannot be appended twice
+            assert!(store.append_end_of_epoch_summary(&summary).is_err());
+        }
+
+        let store =
+            LightClientStore::open_with_genesis_committee(temp_dir.path(), committee).unwrap();
+        assert_eq!(store.latest_epoch().unwrap(), epoch + 1);
+        assert!(store.has_end_of_epoch_summary(epoch).unwrap());
+        assert_eq!(
+            store.committee(epoch + 1).unwrap(),
+            extract_new_committee_info(&summary).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_append_wrong_committee() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_committee, summary) = read_test_data();
+
+        // Anchor the store at the committee the summary certifies, so it cannot verify
+        let new_committee = extract_new_committee_info(&summary).unwrap();
+        let mut wrong_committee = new_committee.clone();
+        wrong_committee.epoch = summary.epoch();
+        let store = LightClientStore::open_with_genesis_committee(temp_dir.path(), wrong_committee)
+            .unwrap();
+
+        assert!(store.append_end_of_epoch_summary(&summary).is_err());
+        assert!(!store.has_end_of_epoch_summary(summary.epoch()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_prune() {
+        let temp_dir = TempDir::new().unwrap();
+        let (committee, summary) = read_test_data();
+        let epoch = committee.epoch;
+
+        let store =
+            LightClientStore::open_with_genesis_committee(temp_dir.path(), committee).unwrap();
+        store.append_end_of_epoch_summary(&summary).unwrap();
+        assert!(store.prune(epoch + 2).is_err());
+
+        store.prune(epoch + 1).unwrap();
+        assert!(store.committee(epoch).is_err());
+        assert!(!store.has_end_of_epoch_summary(epoch).unwrap());
+        assert_eq!(store.latest_epoch().unwrap(), epoch + 1);
+    }
+}
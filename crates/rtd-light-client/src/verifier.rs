@@ -1,13 +1,12 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::checkpoint::{CheckpointsList, read_checkpoint, read_checkpoint_list};
-use crate::committee::extract_new_committee_info;
 use crate::config::Config;
 use crate::object_store::RtdObjectStore;
+use crate::proof::bundle::ProofBundle;
+use crate::store::LightClientStore;
 use anyhow::{Result, anyhow};
 use std::sync::Arc;
-use rtd_config::genesis::Genesis;
 use rtd_json_rpc_types::{RtdObjectDataOptions, RtdTransactionBlockResponseOptions};
 use rtd_sdk::RtdClientBuilder;
 use rtd_types::base_types::{ObjectID, TransactionDigest};
@@ -54,7 +53,11 @@ pub fn extract_verified_effects_and_events(
     Ok((matching_tx.effects.clone(), matching_tx.events.clone()))
 }
 
-pub async fn get_verified_object(config: &Config, id: ObjectID) -> Result<Object> {
+pub async fn get_verified_object(
+    config: &Config,
+    store: &LightClientStore,
+    id: ObjectID,
+) -> Result<Object> {
     let rtd_client: Arc<rtd_sdk::RtdClient> = Arc::new(
         RtdClientBuilder::default()
             .build(config.full_node_url.as_str())
@@ -74,7 +77,7 @@ pub async fn get_verified_object(config: &Config, id: ObjectID) -> Result<Object
     let object: Object = object.try_into().expect("Cannot reconstruct object");
 
     // Need to authenticate this object
-    let (effects, _) = get_verified_effects_and_events(config, object.previous_transaction)
+    let (effects, _) = get_verified_effects_and_events(config, store, object.previous_transaction)
         .await
         .expect("Cannot get effects and events");
 
@@ -92,6 +95,7 @@ pub async fn get_verified_object(config: &Config, id: ObjectID) -> Result<Object
 
 pub async fn get_verified_effects_and_events(
     config: &Config,
+    store: &LightClientStore,
     tid: TransactionDigest,
 ) -> Result<(TransactionEffects, Option<TransactionEvents>)> {
     let rtd_mainnet: rtd_sdk::RtdClient = RtdClientBuilder::default()
@@ -119,36 +123,10 @@ pub async fn get_verified_effects_and_events(
         .await
         .map_err(|e| anyhow!(format!("Cannot get full checkpoint: {e}")))?;
 
-    // Load the list of stored checkpoints
-    let checkpoints_list: CheckpointsList = read_checkpoint_list(config)?;
-
-    // find the stored checkpoint before the seq checkpoint
-    let prev_ckp_id = checkpoints_list
-        .checkpoints
-        .iter()
-        .filter(|ckp_id| **ckp_id < seq)
-        .next_back();
-
-    let committee = if let Some(prev_ckp_id) = prev_ckp_id {
-        // Read it from the store
-        let prev_ckp = read_checkpoint(config, *prev_ckp_id)?;
-
-        // Check we have the right checkpoint
-        anyhow::ensure!(
-            prev_ckp.epoch().checked_add(1).unwrap() == full_check_point.checkpoint_summary.epoch(),
-            "Checkpoint sequence number does not match. Need to Sync."
-        );
-
-        // Get the committee from the previous checkpoint
-        extract_new_committee_info(&prev_ckp)?
-    } else {
-        // Since we did not find a small committee checkpoint we use the genesis
-        let mut genesis_path = config.checkpoint_summary_dir.clone();
-        genesis_path.push(&config.genesis_filename);
-        Genesis::load(&genesis_path)?
-            .committee()
-            .map_err(|e| anyhow!(format!("Cannot load Genesis: {e}")))?
-    };
+    // Get the committee of the checkpoint epoch from the verified committee chain
+    let committee = store
+        .committee(full_check_point.checkpoint_summary.epoch())
+        .map_err(|e| anyhow!(format!("{e}. Need to Sync.")))?;
 
     info!("Extracting effects and events for TID: {}", tid);
     extract_verified_effects_and_events(&full_check_point, &committee, tid)
@@ -164,6 +142,7 @@ pub async fn get_verified_effects_and_events(
 pub async fn get_verified_checkpoint(
     id: ObjectID,
     config: &Config,
+    store: &LightClientStore,
 ) -> Result<CheckpointSequenceNumber> {
     let rtd_client: rtd_sdk::RtdClient = RtdClientBuilder::default()
         .build(config.full_node_url.as_str())
@@ -188,7 +167,7 @@ pub async fn get_verified_checkpoint(
         .ok_or(anyhow!("Transaction not found"))?;
 
     // Need to authenticate this object
-    let (effects, _) = get_verified_effects_and_events(config, object.previous_transaction)
+    let (effects, _) = get_verified_effects_and_events(config, store, object.previous_transaction)
         .await
         .expect("Cannot get effects and events");
 
@@ -210,36 +189,10 @@ pub async fn get_verified_checkpoint(
         .await
         .map_err(|e| anyhow!(format!("Cannot get full checkpoint: {e}")))?;
 
-    // Load the list of stored checkpoints
-    let checkpoints_list: CheckpointsList = read_checkpoint_list(config)?;
-
-    // find the stored checkpoint before the seq checkpoint
-    let prev_ckp_id = checkpoints_list
-        .checkpoints
-        .iter()
-        .filter(|ckp_id| **ckp_id < seq)
-        .next_back();
-
-    let committee = if let Some(prev_ckp_id) = prev_ckp_id {
-        // Read it from the store
-        let prev_ckp = read_checkpoint(config, *prev_ckp_id)?;
-
-        // Check we have the right checkpoint
-        anyhow::ensure!(
-            prev_ckp.epoch().checked_add(1).unwrap() == full_check_point.checkpoint_summary.epoch(),
-            "Checkpoint sequence number does not match. Need to Sync."
-        );
-
-        // Get the committee from the previous checkpoint
-        extract_new_committee_info(&prev_ckp)?
-    } else {
-        // Since we did not find a small committee checkpoint we use the genesis
-        let mut genesis_path = config.checkpoint_summary_dir.clone();
-        genesis_path.push(&config.genesis_filename);
-        Genesis::load(&genesis_path)?
-            .committee()
-            .map_err(|e| anyhow!(format!("Cannot load Genesis: {e}")))?
-    };
+    // Get the committee of the checkpoint epoch from the verified committee chain
+    let committee = store
+        .committee(full_check_point.checkpoint_summary.epoch())
+        .map_err(|e| anyhow!(format!("{e}. Need to Sync.")))?;
 
     // Verify that committee signed this checkpoint and checkpoint contents with digest
     full_check_point
@@ -257,6 +210,14 @@ pub async fn get_verified_checkpoint(
     }
 }
 
+/// Verify a proof bundle offline, trusting only the locally synced committees.
+pub fn verify_proof_bundle(store: &LightClientStore, bundle: ProofBundle) -> Result<()> {
+    let committee = store.committee(bundle.trusted_epoch())?;
+    bundle
+        .verify(&committee)
+        .map_err(|e| anyhow!(format!("Cannot verify proof bundle: {e}")))
+}
+
 // Make a test namespace
 #[cfg(test)]
 mod tests {
@@ -265,6 +226,7 @@ mod tests {
     use rtd_types::messages_checkpoint::{CheckpointSummary, FullCheckpointContents};
 
     use super::*;
+    use crate::committee::extract_new_committee_info;
     use std::path::{Path, PathBuf};
     use std::str::FromStr;
     use rtd_types::crypto::AuthorityQuorumSignInfo;
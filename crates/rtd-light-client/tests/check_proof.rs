@@ -5,7 +5,8 @@ use anyhow::anyhow;
 
 use rtd_light_client::proof::{
     base::{Proof, ProofBuilder, ProofContents, ProofTarget, ProofVerifier},
-    committee::{CommitteeProof, extract_new_committee_info},
+    bundle::ProofBundle,
+    committee::{CommitteeProof, CommitteeTarget, extract_new_committee_info},
     objects::ObjectsTarget,
 };
 
@@ -215,3 +216,50 @@ async fn test_event_target_fail_bad_event() {
 
     assert!(event_proof.verify(&committee).is_err());
 }
+
+#[tokio::test]
+async fn test_bundle_without_committee_chain() {
+    let (committee, full_checkpoint) = read_data(15918264, 16005062).await;
+
+    let sample_object: Object = full_checkpoint.transactions[0].output_objects[0].clone();
+    let sample_ref = sample_object.compute_object_reference();
+
+    let target = ProofTarget::new_objects(vec![(sample_ref, sample_object)]);
+    let bundle = ProofBundle::new(vec![], target.construct(&full_checkpoint).unwrap());
+
+    assert_eq!(bundle.trusted_epoch(), committee.epoch);
+    assert!(bundle.verify(&committee).is_ok());
+}
+
+#[tokio::test]
+async fn test_bundle_committee_chain() {
+    let (committee, full_checkpoint) = read_data(15918264, 16005062).await;
+
+    let new_committee = extract_new_committee_info(&full_checkpoint.checkpoint_summary).unwrap();
+    let committee_proof = CommitteeTarget {
+        committee: new_committee.clone(),
+    }
+    .construct_from_summary(&full_checkpoint.checkpoint_summary)
+    .unwrap();
+    let target = ProofTarget::new_committee(new_committee);
+
+    // The main proof is signed by the trusted committee, not the one the chain leads to
+    let bundle = ProofBundle::new(
+        vec![committee_proof],
+        target.construct(&full_checkpoint).unwrap(),
+    );
+
+    assert_eq!(bundle.trusted_epoch(), committee.epoch);
+    assert!(bundle.verify(&committee).is_err());
+}
+
+#[tokio::test]
+async fn test_bundle_wrong_trusted_committee() {
+    let (_committee, full_checkpoint) = read_data(15918264, 16005062).await;
+
+    let new_committee = extract_new_committee_info(&full_checkpoint.checkpoint_summary).unwrap();
+    let target = ProofTarget::new_committee(new_committee.clone());
+    let bundle = ProofBundle::new(vec![], target.construct(&full_checkpoint).unwrap());
+
+    assert!(bundle.verify(&new_committee).is_err());
+}
@@ -11,11 +11,13 @@ use axum::{
 use base64::Engine;
 use humantime::parse_duration;
 use serde::Deserialize;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
 };
+use rtd_config::{Config, transaction_deny_config::TransactionDenyConfig};
 use rtd_types::{
     base_types::AuthorityName,
     crypto::{RandomnessPartialSignature, RandomnessRound, RandomnessSignature},
@@ -77,6 +79,20 @@ use tracing::info;
 // Reconfigure traffic control policy
 //
 //  $ curl 'http://127.0.0.1:1337/traffic-control?error_threshold=100&spam_threshold=100&dry_run=true'
+//
+// View the transaction deny config in use, its version, and the transactions it recently denied
+//
+//  $ curl 'http://127.0.0.1:1337/transaction-deny-config'
+//
+// Report what the transaction deny config in a YAML file would have denied over the recently
+// signed transactions, without installing it. Recently signed transactions are only kept with
+// `transaction-deny-history-capacity` set in the node config
+//
+//  $ curl -X POST 'http://127.0.0.1:1337/transaction-deny-config?path=/opt/rtd/config/deny.yaml&dry_run=true'
+//
+// Install the transaction deny config in a YAML file
+//
+//  $ curl -X POST 'http://127.0.0.1:1337/transaction-deny-config?path=/opt/rtd/config/deny.yaml'
 
 const LOGGING_ROUTE: &str = "/logging";
 const TRACING_ROUTE: &str = "/enable-tracing";
@@ -92,6 +108,7 @@ const RANDOMNESS_INJECT_FULL_SIG_ROUTE: &str = "/randomness-inject-full-sig";
 const GET_TX_COST_ROUTE: &str = "/get-tx-cost";
 const DUMP_CONSENSUS_TX_COST_ESTIMATES_ROUTE: &str = "/dump-consensus-tx-cost-estimates";
 const TRAFFIC_CONTROL: &str = "/traffic-control";
+const TRANSACTION_DENY_CONFIG: &str = "/transaction-deny-config";
 
 struct AppState {
     node: Arc<RtdNode>,
@@ -137,6 +154,11 @@ pub async fn run_admin_server(node: Arc<RtdNode>, port: u16, tracing_handle: Tra
             get(dump_consensus_tx_cost_estimates),
         )
         .route(TRAFFIC_CONTROL, post(traffic_control))
+        .route(TRANSACTION_DENY_CONFIG, get(get_transaction_deny_config))
+        .route(
+            TRANSACTION_DENY_CONFIG,
+            post(update_transaction_deny_config),
+        )
         .with_state(Arc::new(app_state));
 
     let socket_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
@@ -523,3 +545,65 @@ async fn traffic_control(
         Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
     }
 }
+
+async fn get_transaction_deny_config(State(state): State<Arc<AppState>>) -> (StatusCode, String) {
+    let authority_state = state.node.state();
+    let deny_config = &authority_state.transaction_deny_config;
+    let current = deny_config.load();
+    let mut response = format!(
+        "Version: {}\n{:#?}\nRecent denials:\n",
+        current.version, current.config
+    );
+    for denial in deny_config.recent_denials() {
+        response.push_str(&format!(
+            "{:?}: {} (version {})\n",
+            denial.digest, denial.rule, denial.version
+        ));
+    }
+    (StatusCode::OK, response)
+}
+
+#[derive(Deserialize)]
+struct UpdateTransactionDenyConfig {
+    path: PathBuf,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn update_transaction_deny_config(
+    State(state): State<Arc<AppState>>,
+    args: Query<UpdateTransactionDenyConfig>,
+) -> (StatusCode, String) {
+    let Query(UpdateTransactionDenyConfig { path, dry_run }) = args;
+    let config = match TransactionDenyConfig::load(&path) {
+        Ok(config) => config,
+        Err(err) => return (StatusCode::BAD_REQUEST, format!("{err:?}")),
+    };
+
+    let authority_state = state.node.state();
+    let deny_config = &authority_state.transaction_deny_config;
+    if !dry_run {
+        let version = deny_config.update(config);
+        return (
+            StatusCode::OK,
+            format!("Transaction deny config installed as version {version}\n"),
+        );
+    }
+
+    let report = deny_config.dry_run(
+        &config,
+        authority_state.get_backing_package_store().as_ref(),
+    );
+    let mut response = format!(
+        "Checked {} recent transactions against the current version {}.\n\
+         {} would be denied, {} could not be checked:\n",
+        report.transactions_checked,
+        report.current_version,
+        report.denied.len(),
+        report.errors,
+    );
+    for (digest, rule) in report.denied {
+        response.push_str(&format!("{digest:?}: {rule}\n"));
+    }
+    (StatusCode::OK, response)
+}
@@ -795,6 +795,14 @@ impl RtdNode {
                 .unwrap();
         }
 
+        // The config was loaded from the file when the authority state was built.
+        if let Some(path) = &config.transaction_deny_config_path {
+            state
+                .transaction_deny_config
+                .watch_file(path.clone())
+                .expect("failed to watch transaction deny config");
+        }
+
         // Start the loop that receives new randomness and generates transactions for it.
         RandomnessRoundReceiver::spawn(state.clone(), randomness_rx);
 
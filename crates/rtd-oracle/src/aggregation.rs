@@ -0,0 +1,292 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::{AggregationConfig, AggregationMethod, SubmissionPolicyConfig};
+use std::time::{Duration, Instant};
+
+/// A value observed from one data source.
+#[derive(Clone, Debug)]
+pub(crate) struct SourceValue {
+    pub source_name: String,
+    pub value: f64,
+    pub retrieval_instant: Instant,
+    /// Values older than this are not aggregated.
+    pub max_staleness: Duration,
+    /// Values deviating from the median of all fresh values by more than this fraction are
+    /// rejected as outliers.
+    pub max_deviation: Option<f64>,
+}
+
+/// The outcome of aggregating the values of all data sources of a feed.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Aggregate {
+    pub value: f64,
+    /// Names of the sources whose value was stale or an outlier.
+    pub rejected_sources: Vec<String>,
+}
+
+/// Aggregate the latest value of every data source of a feed. Stale values and outliers are
+/// rejected first, and the remaining values are combined with the configured method.
+pub(crate) fn aggregate(
+    values: &[SourceValue],
+    config: &AggregationConfig,
+    now: Instant,
+) -> anyhow::Result<Aggregate> {
+    let mut rejected_sources = vec![];
+    let fresh: Vec<&SourceValue> = values
+        .iter()
+        .filter(|v| {
+            let is_fresh = now.saturating_duration_since(v.retrieval_instant) <= v.max_staleness;
+            if !is_fresh {
+                rejected_sources.push(v.source_name.clone());
+            }
+            is_fresh
+        })
+        .collect();
+
+    let Some(reference) = median(fresh.iter().map(|v| v.value).collect()) else {
+        anyhow::bail!("No fresh value from any data source");
+    };
+    let accepted: Vec<f64> = fresh
+        .into_iter()
+        .filter(|v| {
+            let is_inlier = v
+                .max_deviation
+                .is_none_or(|max_deviation| deviation(v.value, reference) <= max_deviation);
+            if !is_inlier {
+                rejected_sources.push(v.source_name.clone());
+            }
+            is_inlier
+        })
+        .map(|v| v.value)
+        .collect();
+
+    if accepted.len() < config.min_sources {
+        anyhow::bail!(
+            "Only {} data sources are usable, {} are required",
+            accepted.len(),
+            config.min_sources
+        );
+    }
+
+    let value = match &config.method {
+        AggregationMethod::Median => median(accepted),
+        AggregationMethod::TrimmedMean { trim_fraction } => trimmed_mean(accepted, *trim_fraction),
+        AggregationMethod::Quorum { k, tolerance } => {
+            let Some(reference) = median(accepted.clone()) else {
+                anyhow::bail!("No value to aggregate");
+            };
+            let agreeing: Vec<f64> = accepted
+                .into_iter()
+                .filter(|v| deviation(*v, reference) <= *tolerance)
+                .collect();
+            if agreeing.len() < *k {
+                anyhow::bail!(
+                    "Only {} data sources agree within {}, quorum is {}",
+                    agreeing.len(),
+                    tolerance,
+                    k
+                );
+            }
+            median(agreeing)
+        }
+    };
+
+    match value {
+        Some(value) => Ok(Aggregate {
+            value,
+            rejected_sources,
+        }),
+        None => anyhow::bail!("No value to aggregate"),
+    }
+}
+
+/// Tracks the last submitted value of a feed to decide whether a new value is worth submitting.
+#[derive(Debug, Default)]
+pub(crate) struct SubmissionGate {
+    last_submission: Option<(f64, Instant)>,
+}
+
+impl SubmissionGate {
+    /// A value is submitted if it moved enough since the last submission, or if the heartbeat
+    /// expired. Without any policy configured every value is submitted.
+    pub fn should_submit(&self, policy: &SubmissionPolicyConfig, value: f64, now: Instant) -> bool {
+        let Some((last_value, last_instant)) = self.last_submission else {
+            return true;
+        };
+        if policy.min_change_percent.is_none() && policy.heartbeat.is_none() {
+            return true;
+        }
+
+        let moved = policy
+            .min_change_percent
+            .is_some_and(|min_change| deviation(value, last_value) * 100.0 >= min_change);
+        let heartbeat_expired = policy
+            .heartbeat
+            .is_some_and(|heartbeat| now.saturating_duration_since(last_instant) >= heartbeat);
+        moved || heartbeat_expired
+    }
+
+    /// Record that `value`, aggregated at `now`, was submitted. Uploads can finish out of order,
+    /// so a submission older than the last one recorded is ignored.
+    pub fn record_submission(&mut self, value: f64, now: Instant) {
+        if self
+            .last_submission
+            .is_some_and(|(_, last_instant)| now < last_instant)
+        {
+            return;
+        }
+        self.last_submission = Some((value, now));
+    }
+}
+
+/// Relative distance of `value` from `reference`.
+fn deviation(value: f64, reference: f64) -> f64 {
+    if reference == 0.0 {
+        if value == 0.0 { 0.0 } else { f64::INFINITY }
+    } else {
+        ((value - reference) / reference).abs()
+    }
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Mean of the values after dropping `trim_fraction` of them from each end.
+fn trimmed_mean(mut values: Vec<f64>, trim_fraction: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let trimmed = ((values.len() as f64) * trim_fraction.clamp(0.0, 0.5)).floor() as usize;
+    // Always keep at least one value, even if everything would be trimmed
+    let kept = if 2 * trimmed >= values.len() {
+        &values[(values.len() - 1) / 2..values.len() / 2 + 1]
+    } else {
+        &values[trimmed..values.len() - trimmed]
+    };
+    Some(kept.iter().sum::<f64>() / kept.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_values(values: &[f64], now: Instant) -> Vec<SourceValue> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| SourceValue {
+                source_name: format!("source-{i}"),
+                value: *value,
+                retrieval_instant: now,
+                max_staleness: Duration::from_secs(10),
+                max_deviation: None,
+            })
+            .collect()
+    }
+
+    fn config(method: AggregationMethod) -> AggregationConfig {
+        AggregationConfig {
+            method,
+            min_sources: 1,
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        let now = Instant::now();
+        let values = source_values(&[1.0, 100.0, 2.0], now);
+        let result = aggregate(&values, &config(AggregationMethod::Median), now).unwrap();
+        assert_eq!(result.value, 2.0);
+
+        let values = source_values(&[1.0, 2.0, 3.0, 4.0], now);
+        let result = aggregate(&values, &config(AggregationMethod::Median), now).unwrap();
+        assert_eq!(result.value, 2.5);
+    }
+
+    #[test]
+    fn test_trimmed_mean() {
+        let now = Instant::now();
+        let values = source_values(&[1000.0, 2.0, 3.0, 4.0, -1000.0], now);
+        let method = AggregationMethod::TrimmedMean { trim_fraction: 0.2 };
+        let result = aggregate(&values, &config(method), now).unwrap();
+        assert_eq!(result.value, 3.0);
+
+        assert_eq!(trimmed_mean(vec![1.0, 2.0], 0.5), Some(1.5));
+    }
+
+    #[test]
+    fn test_quorum() {
+        let now = Instant::now();
+        let values = source_values(&[100.0, 101.0, 150.0], now);
+        let method = AggregationMethod::Quorum {
+            k: 2,
+            tolerance: 0.05,
+        };
+        let result = aggregate(&values, &config(method), now).unwrap();
+        assert_eq!(result.value, 100.5);
+
+        let method = AggregationMethod::Quorum {
+            k: 3,
+            tolerance: 0.05,
+        };
+        assert!(aggregate(&values, &config(method), now).is_err());
+    }
+
+    #[test]
+    fn test_reject_stale_and_outliers() {
+        let now = Instant::now();
+        let mut values = source_values(&[100.0, 101.0, 102.0, 500.0], now);
+        values[0].retrieval_instant = now - Duration::from_secs(60);
+        values[3].max_deviation = Some(0.1);
+
+        let result = aggregate(&values, &config(AggregationMethod::Median), now).unwrap();
+        assert_eq!(result.value, 101.5);
+        assert_eq!(
+            result.rejected_sources,
+            vec!["source-0".to_string(), "source-3".to_string()]
+        );
+
+        let config = AggregationConfig {
+            method: AggregationMethod::Median,
+            min_sources: 3,
+        };
+        assert!(aggregate(&values, &config, now).is_err());
+    }
+
+    #[test]
+    fn test_submission_gate() {
+        let now = Instant::now();
+        let policy = SubmissionPolicyConfig {
+            min_change_percent: Some(1.0),
+            heartbeat: Some(Duration::from_secs(60)),
+        };
+        let mut gate = SubmissionGate::default();
+        assert!(gate.should_submit(&policy, 100.0, now));
+        gate.record_submission(100.0, now);
+
+        assert!(!gate.should_submit(&policy, 100.5, now + Duration::from_secs(1)));
+        assert!(gate.should_submit(&policy, 101.0, now + Duration::from_secs(1)));
+        assert!(gate.should_submit(&policy, 100.5, now + Duration::from_secs(60)));
+
+        // A submission older than the last one recorded is ignored
+        gate.record_submission(101.0, now + Duration::from_secs(10));
+        gate.record_submission(90.0, now + Duration::from_secs(5));
+        assert!(!gate.should_submit(&policy, 101.5, now + Duration::from_secs(11)));
+
+        // Without a policy every value is submitted
+        let policy = SubmissionPolicyConfig::default();
+        assert!(gate.should_submit(&policy, 100.0, now));
+    }
+}
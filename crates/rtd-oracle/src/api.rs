@@ -0,0 +1,225 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::data_source::{DownloadedFeeds, FeedReading};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use rtd_types::base_types::ObjectID;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tracing::info;
+
+/// The latest value of a download feed, and whether it can be relied on.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeedStatus {
+    pub feed: String,
+    pub object_id: ObjectID,
+    /// The latest value read, or `None` if the feed has not been read yet.
+    pub reading: Option<FeedReading>,
+    /// Age of the value, measured from its on-chain timestamp, or from when it was read if the
+    /// object does not record one.
+    pub staleness_ms: Option<u64>,
+    /// Whether the feed has not been read yet, or its value is older than the configured
+    /// maximum staleness.
+    pub stale: bool,
+}
+
+impl FeedStatus {
+    pub fn new(
+        feed: String,
+        object_id: ObjectID,
+        reading: Option<FeedReading>,
+        max_staleness: Option<Duration>,
+        now_ms: u64,
+    ) -> Self {
+        let staleness_ms = reading
+            .as_ref()
+            .map(|r| now_ms.saturating_sub(r.timestamp_ms.unwrap_or(r.read_at_ms)));
+        let stale = match (staleness_ms, max_staleness) {
+            (None, _) => true,
+            (Some(staleness_ms), Some(max)) => staleness_ms > max.as_millis() as u64,
+            (Some(_), None) => false,
+        };
+        Self {
+            feed,
+            object_id,
+            reading,
+            staleness_ms,
+            stale,
+        }
+    }
+}
+
+/// Serves the latest values of the download feeds over HTTP, so that off-chain consumers can
+/// read the same values that contracts see:
+///
+/// - `GET /feeds` returns the status of every download feed.
+/// - `GET /feeds/{name}` returns the status of one feed.
+pub struct QueryApi {
+    read_feeds: HashMap<String, ObjectID>,
+    max_staleness: Option<Duration>,
+    downloaded_feeds: Arc<DownloadedFeeds>,
+}
+
+impl QueryApi {
+    pub fn new(
+        read_feeds: HashMap<String, ObjectID>,
+        max_staleness: Option<Duration>,
+        downloaded_feeds: Arc<DownloadedFeeds>,
+    ) -> Self {
+        Self {
+            read_feeds,
+            max_staleness,
+            downloaded_feeds,
+        }
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/feeds", get(list_feeds))
+            .route("/feeds/{name}", get(get_feed))
+            .with_state(Arc::new(self))
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> anyhow::Result<()> {
+        info!("Serving query API on {}", listener.local_addr()?);
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+
+    fn status(&self, feed: &str, object_id: ObjectID) -> FeedStatus {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        FeedStatus::new(
+            feed.to_string(),
+            object_id,
+            self.downloaded_feeds.reading(feed),
+            self.max_staleness,
+            now_ms,
+        )
+    }
+}
+
+async fn list_feeds(State(api): State<Arc<QueryApi>>) -> Json<Vec<FeedStatus>> {
+    let mut feeds: Vec<_> = api
+        .read_feeds
+        .iter()
+        .map(|(feed, object_id)| api.status(feed, *object_id))
+        .collect();
+    feeds.sort_by(|a, b| a.feed.cmp(&b.feed));
+    Json(feeds)
+}
+
+async fn get_feed(
+    State(api): State<Arc<QueryApi>>,
+    Path(name): Path<String>,
+) -> Result<Json<FeedStatus>, StatusCode> {
+    let object_id = api.read_feeds.get(&name).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(api.status(&name, *object_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtd_types::base_types::SequenceNumber;
+
+    fn reading(timestamp_ms: Option<u64>, read_at_ms: u64) -> FeedReading {
+        FeedReading {
+            object_id: ObjectID::ZERO,
+            value: 3000.0,
+            version: SequenceNumber::from_u64(5),
+            timestamp_ms,
+            checkpoint: Some(100),
+            read_at_ms,
+        }
+    }
+
+    #[test]
+    fn test_staleness() {
+        let max = Some(Duration::from_secs(60));
+        let status = |reading, max_staleness| {
+            FeedStatus::new(
+                "ETHUSD".to_string(),
+                ObjectID::ZERO,
+                reading,
+                max_staleness,
+                100_000,
+            )
+        };
+
+        // Feeds that have not been read are always stale.
+        assert!(status(None, None).stale);
+
+        // Staleness is measured from the on-chain timestamp, if there is one.
+        let fresh = status(Some(reading(Some(50_000), 99_000)), max);
+        assert_eq!(fresh.staleness_ms, Some(50_000));
+        assert!(!fresh.stale);
+
+        let old = status(Some(reading(Some(30_000), 99_000)), max);
+        assert_eq!(old.staleness_ms, Some(70_000));
+        assert!(old.stale);
+
+        let untimestamped = status(Some(reading(None, 99_000)), max);
+        assert_eq!(untimestamped.staleness_ms, Some(1_000));
+        assert!(!untimestamped.stale);
+
+        // Without a bound, values that have been read are never stale.
+        assert!(!status(Some(reading(Some(0), 99_000)), None).stale);
+    }
+
+    #[tokio::test]
+    async fn test_query_api() {
+        let downloaded_feeds = Arc::new(DownloadedFeeds::default());
+        let api = QueryApi::new(
+            HashMap::from([
+                ("ETHUSD".to_string(), ObjectID::ZERO),
+                ("BTCUSD".to_string(), ObjectID::ZERO),
+            ]),
+            Some(Duration::from_secs(60)),
+            downloaded_feeds.clone(),
+        );
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        downloaded_feeds.record("ETHUSD", reading(Some(now_ms), now_ms));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(api.serve(listener));
+
+        let feeds: Vec<FeedStatus> = reqwest::get(format!("{url}/feeds"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            feeds
+                .iter()
+                .map(|f| (f.feed.as_str(), f.stale))
+                .collect::<Vec<_>>(),
+            vec![("BTCUSD", true), ("ETHUSD", false)]
+        );
+
+        let feed: FeedStatus = reqwest::get(format!("{url}/feeds/ETHUSD"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(feed.reading, Some(reading(Some(now_ms), now_ms)));
+        assert_eq!(downloaded_feeds.get("ETHUSD").unwrap().value, 3000.0);
+
+        let response = reqwest::get(format!("{url}/feeds/SOLUSD")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 use rtd_config::Config;
 use rtd_types::base_types::ObjectID;
@@ -11,18 +12,140 @@ use rtd_types::base_types::ObjectID;
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DataSourceConfig {
-    pub url: String,
-    pub json_path: String,
+    #[serde(flatten)]
+    pub source: DataSourceKind,
+    /// Values older than this are not aggregated. Defaults to the submission interval of the
+    /// feed.
+    #[serde(default)]
+    pub max_staleness: Option<Duration>,
+    /// Values deviating from the median of all sources of the feed by more than this fraction
+    /// (e.g. 0.05 for 5%) are rejected as outliers.
+    #[serde(default)]
+    pub max_deviation: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case",
+    tag = "type"
+)]
+pub enum DataSourceKind {
+    /// Fetch a JSON document over HTTP and extract the value at `json_path`.
+    HttpJson { url: String, json_path: String },
+    /// Listen to a WebSocket stream and keep the value at `json_path` of the latest message.
+    #[serde(rename = "websocket")]
+    WebSocket {
+        url: String,
+        json_path: String,
+        /// Sent after connecting, e.g. to subscribe to a ticker.
+        subscribe_message: Option<String>,
+    },
+    /// Read a local file, as JSON if `json_path` is set or as a plain number otherwise.
+    File {
+        path: PathBuf,
+        json_path: Option<String>,
+    },
+    /// Read the latest value of one of the `download-feeds`.
+    OnChain { read_feed: String },
+    /// Combine the values of two data sources, e.g. a cross rate from two downloaded feeds.
+    Derived {
+        operation: DerivedOperation,
+        left: Box<DataSourceKind>,
+        right: Box<DataSourceKind>,
+    },
+    /// Return the given values in turn, `null` being a failure.
+    #[cfg(test)]
+    Scripted { values: Vec<Option<f64>> },
+    /// An HTTP JSON source given without a `type`.
+    #[serde(untagged)]
+    LegacyHttpJson { url: String, json_path: String },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DerivedOperation {
+    Multiply,
+    Divide,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct UploadFeedConfig {
     pub submission_interval: Duration,
-    pub data_source_config: DataSourceConfig,
+    /// A single data source, named after the source of the feed.
+    #[serde(default)]
+    pub data_source_config: Option<DataSourceConfig>,
+    /// Named data sources whose values are aggregated into the submitted value.
+    #[serde(default)]
+    pub data_source_configs: BTreeMap<String, DataSourceConfig>,
+    #[serde(default)]
+    pub aggregation: AggregationConfig,
+    #[serde(default)]
+    pub submission_policy: SubmissionPolicyConfig,
     pub upload_parameters: UploadParameters,
 }
 
+impl UploadFeedConfig {
+    /// All data sources of the feed, by name. The single `data-source-config`, if set, is named
+    /// `source_name`.
+    pub fn data_sources(&self, source_name: &str) -> Vec<(String, DataSourceConfig)> {
+        self.data_source_config
+            .iter()
+            .map(|config| (source_name.to_string(), config.clone()))
+            .chain(self.data_source_configs.clone())
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AggregationConfig {
+    #[serde(default)]
+    pub method: AggregationMethod,
+    /// Minimum number of fresh, non-outlier values needed to submit anything.
+    #[serde(default = "default_min_sources")]
+    pub min_sources: usize,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            method: AggregationMethod::default(),
+            min_sources: default_min_sources(),
+        }
+    }
+}
+
+fn default_min_sources() -> usize {
+    1
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum AggregationMethod {
+    #[default]
+    Median,
+    /// Mean of the values after dropping `trim_fraction` of them from each end.
+    #[serde(rename_all = "kebab-case")]
+    TrimmedMean { trim_fraction: f64 },
+    /// Median of the values within `tolerance` (a fraction) of the median of all values, if
+    /// there are at least `k` of them.
+    Quorum { k: usize, tolerance: f64 },
+}
+
+/// When to submit a new aggregated value. Without any field set, every value is submitted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SubmissionPolicyConfig {
+    /// Submit when the value moved by at least this percentage since the last submission.
+    #[serde(default)]
+    pub min_change_percent: Option<f64>,
+    /// Submit when the last submission is older than this, even if the value did not move.
+    #[serde(default)]
+    pub heartbeat: Option<Duration>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct UploadParameters {
@@ -37,6 +160,19 @@ pub struct UploadParameters {
 pub struct DownloadFeedConfigs {
     pub read_interval: Option<Duration>,
     pub read_feeds: HashMap<String, ObjectID>,
+    /// JSON path to the `DecimalValue` in the content of the objects of the read feeds. Defaults
+    /// to the value of the dynamic field written by `simple_oracle::submit_data`.
+    #[serde(default = "default_value_path")]
+    pub value_path: String,
+    /// JSON path to the timestamp that the value was written with, in the content of the objects
+    /// of the read feeds. Defaults to the timestamp of the dynamic field written by
+    /// `simple_oracle::submit_data`.
+    #[serde(default = "default_timestamp_path")]
+    pub timestamp_path: String,
+    /// Downloaded values whose on-chain timestamp is older than this are flagged as stale by the
+    /// query API.
+    #[serde(default)]
+    pub max_staleness: Option<Duration>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -48,6 +184,18 @@ pub struct OracleNodeConfig {
 
     #[serde(default = "default_metrics_address")]
     pub metrics_address: SocketAddr,
+    /// Address to serve the query API for the download feeds on. The API is disabled if this is
+    /// not set.
+    #[serde(default)]
+    pub api_address: Option<SocketAddr>,
+}
+
+fn default_value_path() -> String {
+    "$.fields.value.fields.value".to_string()
+}
+
+fn default_timestamp_path() -> String {
+    "$.fields.value.fields.timestamp".to_string()
 }
 
 fn default_metrics_address() -> SocketAddr {
@@ -0,0 +1,588 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::{DataSourceKind, DerivedOperation, DownloadFeedConfigs};
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use rtd_types::base_types::{ObjectID, SequenceNumber};
+use rtd_types::messages_checkpoint::CheckpointSequenceNumber;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+const WEBSOCKET_RECONNECT_SEC: u64 = 5;
+
+/// A value observed by a data source, and when it was observed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observation {
+    pub value: f64,
+    pub instant: Instant,
+}
+
+impl Observation {
+    pub fn now(value: f64) -> Self {
+        Self {
+            value,
+            instant: Instant::now(),
+        }
+    }
+}
+
+/// Where the value of a feed comes from. Every data source of an upload feed is fetched once per
+/// submission interval.
+#[async_trait]
+pub trait DataSource: Send + Sync {
+    async fn fetch(&self) -> anyhow::Result<Observation>;
+}
+
+/// A value read from the on-chain object of a download feed, and where it was read from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeedReading {
+    pub object_id: ObjectID,
+    pub value: f64,
+    /// Version of the object the value was read from.
+    pub version: SequenceNumber,
+    /// When the value was written on chain, in milliseconds since the Unix epoch, if the object
+    /// records it.
+    pub timestamp_ms: Option<u64>,
+    /// The latest checkpoint the full node had executed before the value was read, so the value
+    /// is at least as recent as this checkpoint.
+    pub checkpoint: Option<CheckpointSequenceNumber>,
+    /// When the value was read, in milliseconds since the Unix epoch.
+    pub read_at_ms: u64,
+}
+
+/// Latest values read from the on-chain objects of the download feeds, by feed name.
+#[derive(Debug, Default)]
+pub struct DownloadedFeeds {
+    values: RwLock<HashMap<String, Observation>>,
+    readings: RwLock<HashMap<String, FeedReading>>,
+}
+
+impl DownloadedFeeds {
+    pub fn update(&self, feed_name: &str, observation: Observation) {
+        self.values
+            .write()
+            .unwrap()
+            .insert(feed_name.to_string(), observation);
+    }
+
+    /// Record `reading` as the latest value of `feed_name`.
+    pub fn record(&self, feed_name: &str, reading: FeedReading) {
+        self.update(feed_name, Observation::now(reading.value));
+        self.readings
+            .write()
+            .unwrap()
+            .insert(feed_name.to_string(), reading);
+    }
+
+    pub fn get(&self, feed_name: &str) -> Option<Observation> {
+        self.values.read().unwrap().get(feed_name).copied()
+    }
+
+    /// The latest reading of `feed_name`, if it has been read yet.
+    pub fn reading(&self, feed_name: &str) -> Option<FeedReading> {
+        self.readings.read().unwrap().get(feed_name).cloned()
+    }
+}
+
+/// Build the data source described by `kind`. On-chain sources read the values of `download_feeds`
+/// kept in `downloaded_feeds`.
+pub(crate) fn build_data_source(
+    kind: &DataSourceKind,
+    download_feeds: &DownloadFeedConfigs,
+    downloaded_feeds: &Arc<DownloadedFeeds>,
+) -> anyhow::Result<Arc<dyn DataSource>> {
+    Ok(match kind {
+        DataSourceKind::HttpJson { url, json_path }
+        | DataSourceKind::LegacyHttpJson { url, json_path } => {
+            Arc::new(HttpJsonSource::new(url.clone(), json_path.clone()))
+        }
+        DataSourceKind::WebSocket {
+            url,
+            json_path,
+            subscribe_message,
+        } => Arc::new(WebSocketSource::new(
+            url.clone(),
+            json_path.clone(),
+            subscribe_message.clone(),
+        )),
+        DataSourceKind::File { path, json_path } => Arc::new(FileSource {
+            path: path.clone(),
+            json_path: json_path.clone(),
+        }),
+        DataSourceKind::OnChain { read_feed } => {
+            anyhow::ensure!(
+                download_feeds.read_interval.is_some()
+                    && download_feeds.read_feeds.contains_key(read_feed),
+                "On-chain data source reads feed {read_feed}, which is not downloaded"
+            );
+            Arc::new(OnChainSource {
+                read_feed: read_feed.clone(),
+                downloaded_feeds: downloaded_feeds.clone(),
+            })
+        }
+        DataSourceKind::Derived {
+            operation,
+            left,
+            right,
+        } => Arc::new(DerivedSource {
+            operation: operation.clone(),
+            left: build_data_source(left, download_feeds, downloaded_feeds)?,
+            right: build_data_source(right, download_feeds, downloaded_feeds)?,
+        }),
+        #[cfg(test)]
+        DataSourceKind::Scripted { values } => Arc::new(ScriptedSource::new(values.clone())),
+    })
+}
+
+/// Fetches a JSON document over HTTP and extracts the value at `json_path`.
+pub struct HttpJsonSource {
+    url: String,
+    json_path: String,
+    client: reqwest::Client,
+}
+
+impl HttpJsonSource {
+    pub fn new(url: String, json_path: String) -> Self {
+        Self {
+            url,
+            json_path,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataSource for HttpJsonSource {
+    async fn fetch(&self) -> anyhow::Result<Observation> {
+        let response = self.client.get(&self.url).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to fetch data: {:?}", response);
+        }
+
+        let json_blob: serde_json::Value = response.json().await?;
+        Ok(Observation::now(extract_value(
+            &json_blob,
+            &self.json_path,
+        )?))
+    }
+}
+
+/// Listens to a WebSocket stream and keeps the value at `json_path` of the latest message that
+/// has one. Messages without a value, such as heartbeats, are ignored.
+pub struct WebSocketSource {
+    latest: Arc<Mutex<Option<Observation>>>,
+    task: JoinHandle<()>,
+}
+
+impl WebSocketSource {
+    /// Connect to `url` in the background, sending `subscribe_message` on every (re)connection.
+    pub fn new(url: String, json_path: String, subscribe_message: Option<String>) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let task = tokio::spawn(Self::listen(
+            url,
+            json_path,
+            subscribe_message,
+            latest.clone(),
+        ));
+        Self { latest, task }
+    }
+
+    async fn listen(
+        url: String,
+        json_path: String,
+        subscribe_message: Option<String>,
+        latest: Arc<Mutex<Option<Observation>>>,
+    ) {
+        loop {
+            if let Err(err) =
+                Self::listen_once(&url, &json_path, subscribe_message.as_deref(), &latest).await
+            {
+                warn!(url, "WebSocket data source disconnected: {:?}", err);
+            }
+            tokio::time::sleep(Duration::from_secs(WEBSOCKET_RECONNECT_SEC)).await;
+        }
+    }
+
+    async fn listen_once(
+        url: &str,
+        json_path: &str,
+        subscribe_message: Option<&str>,
+        latest: &Mutex<Option<Observation>>,
+    ) -> anyhow::Result<()> {
+        let (mut stream, _) = tokio_tungstenite::connect_async(url).await?;
+        info!(url, "Connected to WebSocket data source");
+        if let Some(subscribe_message) = subscribe_message {
+            stream
+                .send(Message::text(subscribe_message.to_string()))
+                .await?;
+        }
+
+        while let Some(message) = stream.next().await {
+            let text = match message? {
+                Message::Text(text) => text,
+                Message::Close(frame) => anyhow::bail!("Connection closed: {:?}", frame),
+                _ => continue,
+            };
+            let value = serde_json::from_str::<serde_json::Value>(&text)
+                .map_err(anyhow::Error::from)
+                .and_then(|json_blob| extract_value(&json_blob, json_path));
+            match value {
+                Ok(value) => *latest.lock().unwrap() = Some(Observation::now(value)),
+                Err(err) => debug!(url, "Ignoring WebSocket message: {:?}", err),
+            }
+        }
+        anyhow::bail!("Stream ended")
+    }
+}
+
+impl Drop for WebSocketSource {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[async_trait]
+impl DataSource for WebSocketSource {
+    async fn fetch(&self) -> anyhow::Result<Observation> {
+        (*self.latest.lock().unwrap())
+            .ok_or_else(|| anyhow::anyhow!("No value received from the WebSocket stream yet"))
+    }
+}
+
+/// Reads a value from a local file, either as a JSON document with `json_path` or as a plain
+/// number. The value is as old as the last modification of the file.
+pub struct FileSource {
+    path: PathBuf,
+    json_path: Option<String>,
+}
+
+#[async_trait]
+impl DataSource for FileSource {
+    async fn fetch(&self) -> anyhow::Result<Observation> {
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        let value = match &self.json_path {
+            Some(json_path) => extract_value(&serde_json::from_str(&content)?, json_path)?,
+            None => content.trim().parse::<f64>()?,
+        };
+
+        let age = tokio::fs::metadata(&self.path)
+            .await?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+        let now = Instant::now();
+        Ok(Observation {
+            value,
+            instant: now.checked_sub(age).unwrap_or(now),
+        })
+    }
+}
+
+/// Reads the latest value of a download feed, i.e. of another on-chain data provider object.
+pub struct OnChainSource {
+    read_feed: String,
+    downloaded_feeds: Arc<DownloadedFeeds>,
+}
+
+#[async_trait]
+impl DataSource for OnChainSource {
+    async fn fetch(&self) -> anyhow::Result<Observation> {
+        self.downloaded_feeds
+            .get(&self.read_feed)
+            .ok_or_else(|| anyhow::anyhow!("Feed {} is not downloaded yet", self.read_feed))
+    }
+}
+
+/// Combines the values of two data sources, e.g. a cross rate as the quotient of two prices.
+/// The result is as old as the oldest of the two values.
+pub struct DerivedSource {
+    operation: DerivedOperation,
+    left: Arc<dyn DataSource>,
+    right: Arc<dyn DataSource>,
+}
+
+#[async_trait]
+impl DataSource for DerivedSource {
+    async fn fetch(&self) -> anyhow::Result<Observation> {
+        let (left, right) = futures::try_join!(self.left.fetch(), self.right.fetch())?;
+        let value = match self.operation {
+            DerivedOperation::Multiply => left.value * right.value,
+            DerivedOperation::Divide => {
+                anyhow::ensure!(right.value != 0.0, "Cannot divide by zero");
+                left.value / right.value
+            }
+        };
+        Ok(Observation {
+            value,
+            instant: left.instant.min(right.instant),
+        })
+    }
+}
+
+/// Returns the given values in turn, starting over after the last one. `None` makes the fetch
+/// fail, to simulate an unavailable source.
+#[cfg(test)]
+pub struct ScriptedSource {
+    values: Vec<Option<f64>>,
+    next: Mutex<usize>,
+}
+
+#[cfg(test)]
+impl ScriptedSource {
+    pub fn new(values: Vec<Option<f64>>) -> Self {
+        Self {
+            values,
+            next: Mutex::new(0),
+        }
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl DataSource for ScriptedSource {
+    async fn fetch(&self) -> anyhow::Result<Observation> {
+        let value = {
+            let mut next = self.next.lock().unwrap();
+            let value = self.values.get(*next).copied().flatten();
+            *next = (*next + 1) % self.values.len().max(1);
+            value
+        };
+        value
+            .map(Observation::now)
+            .ok_or_else(|| anyhow::anyhow!("Scripted failure"))
+    }
+}
+
+/// Extract the single value at `json_path`, given either as a number or as a string.
+fn extract_value(json_blob: &serde_json::Value, json_path: &str) -> anyhow::Result<f64> {
+    let data = jsonpath_lib::select(json_blob, json_path)?;
+
+    let Some(value) = data.first() else {
+        anyhow::bail!(
+            "Failed to find data from json blob: {:?} with json path: {:?}",
+            json_blob,
+            json_path
+        );
+    };
+    // Assume there is one single value per request
+    match value {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(value_str) => value_str.parse::<f64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        anyhow::anyhow!(
+            "Failed to parse data {:?} as f64 from json blob: {:?}",
+            value,
+            json_blob
+        )
+    })
+}
+
+/// Read the `DecimalValue` at `value_path` in the content of an on-chain data provider object,
+/// and convert it to a float.
+pub(crate) fn parse_decimal_value(content: &serde_json::Value, value_path: &str) -> Option<f64> {
+    let decimal_value = *jsonpath_lib::select(content, value_path).ok()?.first()?;
+    // Structs are rendered with their type, and their fields under `fields`.
+    let fields = decimal_value.get("fields").unwrap_or(decimal_value);
+    let value = as_u64(fields.get("value")?)?;
+    let decimal = as_u64(fields.get("decimal")?)?;
+    Some(value as f64 / 10f64.powi(decimal as i32))
+}
+
+/// A `u64` rendered as a JSON number or, past 2^53, as a string.
+fn as_u64(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(number) => number.as_u64(),
+        serde_json::Value::String(value_str) => value_str.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Read the timestamp at `timestamp_path` in the content of an on-chain data provider object, in
+/// milliseconds since the Unix epoch.
+pub(crate) fn parse_timestamp_ms(content: &serde_json::Value, timestamp_path: &str) -> Option<u64> {
+    as_u64(
+        jsonpath_lib::select(content, timestamp_path)
+            .ok()?
+            .first()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DataSourceConfig;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_value() {
+        let json_blob = json!({"price": "1.5", "data": [{"rate": 2.5}], "name": "usd"});
+        assert_eq!(extract_value(&json_blob, "$.price").unwrap(), 1.5);
+        assert_eq!(extract_value(&json_blob, "$.data[0].rate").unwrap(), 2.5);
+        assert!(extract_value(&json_blob, "$.name").is_err());
+        assert!(extract_value(&json_blob, "$.missing").is_err());
+    }
+
+    #[test]
+    fn test_parse_decimal_value() {
+        let value_path = "$.fields.value.fields.value";
+        let content = json!({
+            "dataType": "moveObject",
+            "fields": {
+                "id": {"id": "0x1"},
+                "name": "ETHUSD",
+                "value": {
+                    "type": "0x2::simple_oracle::StoredData<0x2::decimal_value::DecimalValue>",
+                    "fields": {
+                        "value": {
+                            "type": "0x2::decimal_value::DecimalValue",
+                            "fields": {"value": "1234500", "decimal": 6},
+                        },
+                        "sequence_number": "7",
+                        "timestamp": "1700000000000",
+                        "identifier": "",
+                    },
+                },
+            },
+        });
+        assert_eq!(parse_decimal_value(&content, value_path), Some(1.2345));
+
+        // Only the configured path is read, not the first decimal value found.
+        let content = json!({
+            "fields": {
+                "previous": {"fields": {"value": "1", "decimal": 0}},
+                "value": {"fields": {"value": {"value": 25, "decimal": 1}}},
+            },
+        });
+        assert_eq!(parse_decimal_value(&content, value_path), Some(2.5));
+        assert_eq!(
+            parse_decimal_value(&content, "$.fields.previous"),
+            Some(1.0)
+        );
+        assert_eq!(parse_decimal_value(&content, "$.fields.missing"), None);
+        assert_eq!(parse_decimal_value(&json!({"value": "1"}), "$"), None);
+    }
+
+    #[test]
+    fn test_parse_timestamp_ms() {
+        let timestamp_path = "$.fields.value.fields.timestamp";
+        let content = json!({
+            "fields": {
+                "id": {"id": "0x1"},
+                "name": "ETHUSD",
+                "value": {
+                    "fields": {
+                        "value": {"fields": {"value": "1234500", "decimal": 6}},
+                        "sequence_number": "7",
+                        "timestamp": "1700000000000",
+                        "identifier": "",
+                    },
+                },
+            },
+        });
+        assert_eq!(
+            parse_timestamp_ms(&content, timestamp_path),
+            Some(1_700_000_000_000)
+        );
+
+        // Only the configured path is read, not the first timestamp found.
+        let content = json!({
+            "fields": {
+                "created": {"fields": {"timestamp": 1}},
+                "value": {"fields": {"timestamp": 1_700_000_000_000u64}},
+            },
+        });
+        assert_eq!(
+            parse_timestamp_ms(&content, timestamp_path),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(
+            parse_timestamp_ms(&json!({"fields": {"timestamp": "1"}}), timestamp_path),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scripted_source() {
+        let source = ScriptedSource::new(vec![Some(1.0), None, Some(3.0)]);
+        assert_eq!(source.fetch().await.unwrap().value, 1.0);
+        assert!(source.fetch().await.is_err());
+        assert_eq!(source.fetch().await.unwrap().value, 3.0);
+        assert_eq!(source.fetch().await.unwrap().value, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_derived_source() {
+        let downloaded_feeds = Arc::new(DownloadedFeeds::default());
+        let download_feeds = DownloadFeedConfigs {
+            read_interval: Some(Duration::from_secs(1)),
+            read_feeds: HashMap::from([("ETHUSD".to_string(), ObjectID::ZERO)]),
+            value_path: "$.fields.value.fields.value".to_string(),
+            timestamp_path: "$.fields.value.fields.timestamp".to_string(),
+            max_staleness: None,
+        };
+        let config: DataSourceConfig = serde_json::from_value(json!({
+            "type": "derived",
+            "operation": "divide",
+            "left": {"type": "on-chain", "read-feed": "ETHUSD"},
+            "right": {"type": "scripted", "values": [2.0]},
+        }))
+        .unwrap();
+        let source = build_data_source(&config.source, &download_feeds, &downloaded_feeds).unwrap();
+        assert!(source.fetch().await.is_err());
+
+        let observation = Observation::now(3000.0);
+        downloaded_feeds.update("ETHUSD", observation);
+        let derived = source.fetch().await.unwrap();
+        assert_eq!(derived.value, 1500.0);
+        assert_eq!(derived.instant, observation.instant);
+
+        // Feeds that are not downloaded cannot be read
+        let config: DataSourceConfig =
+            serde_json::from_value(json!({"type": "on-chain", "read-feed": "BTCUSD"})).unwrap();
+        assert!(build_data_source(&config.source, &download_feeds, &downloaded_feeds).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("price.json");
+        std::fs::write(&path, r#"{"price": "42.5"}"#).unwrap();
+        let source = FileSource {
+            path: path.clone(),
+            json_path: Some("$.price".to_string()),
+        };
+        assert_eq!(source.fetch().await.unwrap().value, 42.5);
+
+        std::fs::write(&path, "7\n").unwrap();
+        let source = FileSource {
+            path,
+            json_path: None,
+        };
+        assert_eq!(source.fetch().await.unwrap().value, 7.0);
+    }
+
+    #[test]
+    fn test_legacy_config() {
+        let config: DataSourceConfig = serde_json::from_value(json!({
+            "url": "https://example.com/price",
+            "json-path": "$.price",
+            "max-deviation": 0.1,
+        }))
+        .unwrap();
+        assert!(matches!(
+            config.source,
+            DataSourceKind::LegacyHttpJson { .. }
+        ));
+        assert_eq!(config.max_deviation, Some(0.1));
+    }
+}
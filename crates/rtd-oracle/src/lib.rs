@@ -2,14 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use chrono::{DateTime, Utc};
+use aggregation::{SourceValue, SubmissionGate};
 use config::{DownloadFeedConfigs, UploadFeedConfig, UploadParameters};
+use api::QueryApi;
+use data_source::{DataSource, DownloadedFeeds, FeedReading};
 use metrics::OracleMetrics;
 use linku_metrics::monitored_scope;
 use once_cell::sync::OnceCell;
 use prometheus::Registry;
+use std::net::SocketAddr;
 use std::ops::Add;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, time::Instant};
 use rtd_json_rpc_types::RtdTransactionBlockResponse;
@@ -19,9 +23,9 @@ use rtd_json_rpc_types::{
 };
 use rtd_sdk::RtdClient;
 use rtd_sdk::apis::ReadApi;
-use rtd_sdk::rpc_types::RtdObjectResponse;
 use rtd_types::Identifier;
 use rtd_types::error::UserInputError;
+use rtd_types::messages_checkpoint::CheckpointSequenceNumber;
 use rtd_types::object::{Object, Owner};
 use rtd_types::parse_rtd_type_tag;
 use rtd_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
@@ -37,7 +41,10 @@ use tap::tap::TapFallible;
 use rtd_sdk::wallet_context::WalletContext;
 use rtd_types::base_types::{ObjectID, ObjectRef, random_object_ref};
 use tracing::{debug, error, info, warn};
+mod aggregation;
+pub mod api;
 pub mod config;
+pub mod data_source;
 mod metrics;
 
 // TODO: allow more flexible decimals
@@ -50,6 +57,7 @@ pub struct OracleNode {
     upload_feeds: HashMap<String, HashMap<String, UploadFeedConfig>>,
     gas_obj_id: ObjectID,
     download_feeds: DownloadFeedConfigs,
+    api_address: Option<SocketAddr>,
     wallet_ctx: WalletContext,
     metrics: Arc<OracleMetrics>,
 }
@@ -59,6 +67,7 @@ impl OracleNode {
         upload_feeds: HashMap<String, HashMap<String, UploadFeedConfig>>,
         gas_obj_id: ObjectID,
         download_feeds: DownloadFeedConfigs,
+        api_address: Option<SocketAddr>,
         wallet_ctx: WalletContext,
         registry: Registry,
     ) -> Self {
@@ -66,6 +75,7 @@ impl OracleNode {
             upload_feeds,
             gas_obj_id,
             download_feeds,
+            api_address,
             wallet_ctx,
             metrics: Arc::new(OracleMetrics::new(&registry)),
         }
@@ -77,17 +87,34 @@ impl OracleNode {
         let client = Arc::new(self.wallet_ctx.get_client().await?);
 
         let wallet_ctx = Arc::new(self.wallet_ctx);
+        let downloaded_feeds = Arc::new(DownloadedFeeds::default());
         DataProviderRunner::new(
             self.upload_feeds,
+            &self.download_feeds,
+            downloaded_feeds.clone(),
             self.gas_obj_id,
             wallet_ctx,
             client.clone(),
             signer_address,
             self.metrics.clone(),
         )
-        .await
+        .await?
         .spawn();
 
+        if let Some(api_address) = self.api_address {
+            let listener = tokio::net::TcpListener::bind(api_address).await?;
+            let api = QueryApi::new(
+                self.download_feeds.read_feeds.clone(),
+                self.download_feeds.max_staleness,
+                downloaded_feeds.clone(),
+            );
+            tokio::spawn(async move {
+                if let Err(err) = api.serve(listener).await {
+                    error!("Query API failed: {:?}", err);
+                }
+            });
+        }
+
         let (sender, mut receiver) = tokio::sync::mpsc::channel(1000);
 
         // Spawn a reader thread if reader_interval is configured.
@@ -97,6 +124,9 @@ impl OracleNode {
                     client: client.clone(),
                     read_interval,
                     read_configs: self.download_feeds.read_feeds,
+                    value_path: self.download_feeds.value_path,
+                    timestamp_path: self.download_feeds.timestamp_path,
+                    downloaded_feeds,
                     metrics: self.metrics.clone(),
                 }
                 .start(sender.clone()),
@@ -124,12 +154,14 @@ struct DataProviderRunner {
 impl DataProviderRunner {
     pub async fn new(
         upload_feeds: HashMap<String, HashMap<String, UploadFeedConfig>>,
+        download_feeds: &DownloadFeedConfigs,
+        downloaded_feeds: Arc<DownloadedFeeds>,
         gas_coin_id: ObjectID,
         wallet_ctx: Arc<WalletContext>,
         client: Arc<RtdClient>,
         signer_address: RtdAddress,
         metrics: Arc<OracleMetrics>,
-    ) -> Self {
+    ) -> anyhow::Result<Self> {
         let mut providers = vec![];
         let mut staleness_tolerance = HashMap::new();
         let mut oracle_object_args = HashMap::new();
@@ -141,11 +173,36 @@ impl DataProviderRunner {
                     data_feed.submission_interval,
                 );
                 let oracle_obj_id = data_feed.upload_parameters.write_data_provider_object_id;
+                let data_sources = data_feed
+                    .data_sources(&source_name)
+                    .into_iter()
+                    .map(|(name, config)| {
+                        Ok(NamedDataSource {
+                            source: data_source::build_data_source(
+                                &config.source,
+                                download_feeds,
+                                &downloaded_feeds,
+                            )?,
+                            name,
+                            max_staleness: config
+                                .max_staleness
+                                .unwrap_or(data_feed.submission_interval),
+                            max_deviation: config.max_deviation,
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                anyhow::ensure!(
+                    !data_sources.is_empty(),
+                    "Feed {feed_name} of source {source_name} has no data source"
+                );
                 let data_provider = DataProvider {
                     feed_name: feed_name.clone(),
                     source_name: source_name.clone(),
+                    data_sources,
                     upload_feed: Arc::new(data_feed),
                     sender: sender.clone(),
+                    latest_values: Mutex::new(HashMap::new()),
+                    submission_gate: Arc::new(Mutex::new(SubmissionGate::default())),
                     metrics: metrics.clone(),
                 };
                 providers.push(Arc::new(data_provider));
@@ -175,10 +232,10 @@ impl DataProviderRunner {
             oracle_object_args,
             metrics: metrics.clone(),
         };
-        Self {
+        Ok(Self {
             providers,
             uploader,
-        }
+        })
     }
 
     pub fn spawn(mut self) {
@@ -222,11 +279,24 @@ async fn get_gas_obj_ref(
     }
 }
 
+struct NamedDataSource {
+    name: String,
+    source: Arc<dyn DataSource>,
+    max_staleness: Duration,
+    max_deviation: Option<f64>,
+}
+
 struct DataProvider {
     pub feed_name: String,
     pub source_name: String,
+    /// Data sources whose values are aggregated into the value of the feed.
+    data_sources: Vec<NamedDataSource>,
     pub upload_feed: Arc<UploadFeedConfig>,
     pub sender: tokio::sync::mpsc::Sender<DataPoint>,
+    /// Latest value retrieved from each data source.
+    latest_values: Mutex<HashMap<String, SourceValue>>,
+    /// Shared with the uploader, which records the values it submitted successfully.
+    submission_gate: Arc<Mutex<SubmissionGate>>,
     metrics: Arc<OracleMetrics>,
 }
 
@@ -235,6 +305,7 @@ impl DataProvider {
         info!(
             feed_name = self.feed_name,
             source_name = self.source_name,
+            data_sources = ?self.data_sources.iter().map(|s| &s.name).collect::<Vec<_>>(),
             "Starting DataProvider"
         );
         let mut interval = tokio::time::interval(self.upload_feed.submission_interval);
@@ -252,70 +323,112 @@ impl DataProvider {
             source_name = self.source_name,
             "Running data provider once."
         );
-        let value = self.retrieve_from_data_source().await;
-        if value.is_err() {
-            error!(
+        let results = futures::future::join_all(
+            self.data_sources
+                .iter()
+                .map(|data_source| data_source.source.fetch()),
+        )
+        .await;
+
+        let now = Instant::now();
+        let values: Vec<SourceValue> = {
+            let mut latest_values = self.latest_values.lock().unwrap();
+            for (data_source, result) in self.data_sources.iter().zip(results) {
+                let data_source_name = &data_source.name;
+                let observation = match result {
+                    Ok(observation) => observation,
+                    Err(err) => {
+                        error!(
+                            feed_name = self.feed_name,
+                            source_name = self.source_name,
+                            data_source_name,
+                            "Failed to retrieve data from data source: {:?}",
+                            err
+                        );
+                        self.metrics
+                            .data_source_errors
+                            .with_label_values(&[&self.feed_name, data_source_name])
+                            .inc();
+                        continue;
+                    }
+                };
+                self.metrics
+                    .data_source_successes
+                    .with_label_values(&[&self.feed_name, data_source_name])
+                    .inc();
+                latest_values.insert(
+                    data_source_name.clone(),
+                    SourceValue {
+                        source_name: data_source_name.clone(),
+                        value: observation.value,
+                        retrieval_instant: observation.instant,
+                        max_staleness: data_source.max_staleness,
+                        max_deviation: data_source.max_deviation,
+                    },
+                );
+            }
+            latest_values.values().cloned().collect()
+        };
+
+        let aggregate = match aggregation::aggregate(&values, &self.upload_feed.aggregation, now) {
+            Ok(aggregate) => aggregate,
+            Err(err) => {
+                error!(
+                    feed_name = self.feed_name,
+                    source_name = self.source_name,
+                    "Failed to aggregate data sources: {:?}",
+                    err
+                );
+                self.metrics
+                    .aggregation_errors
+                    .with_label_values(&[&self.feed_name])
+                    .inc();
+                return;
+            }
+        };
+        for data_source_name in &aggregate.rejected_sources {
+            warn!(
                 feed_name = self.feed_name,
                 source_name = self.source_name,
-                "Failed to retrieve data from data source: {:?}",
-                value
+                data_source_name,
+                "Data source value is stale or an outlier, skipping it."
             );
             self.metrics
-                .data_source_errors
-                .with_label_values(&[&self.feed_name, &self.source_name])
+                .data_source_rejections
+                .with_label_values(&[&self.feed_name, data_source_name])
                 .inc();
-            return;
         }
 
-        self.metrics
-            .data_source_successes
-            .with_label_values(&[&self.feed_name, &self.source_name])
-            .inc();
-
-        // TODO: allow more flexible multiplers and data types
-        let value = (value.unwrap() * METRICS_MULTIPLIER) as u64;
-        self.send_to_uploader(value).await;
-    }
-
-    async fn retrieve_from_data_source(&self) -> anyhow::Result<f64> {
-        // TODO: support websocket
-        let url = &self.upload_feed.data_source_config.url;
-        let json_path = &self.upload_feed.data_source_config.json_path;
-        let response = reqwest::Client::new().get(url).send().await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to fetch data: {:?}", response);
+        {
+            let submission_gate = self.submission_gate.lock().unwrap();
+            if !submission_gate.should_submit(
+                &self.upload_feed.submission_policy,
+                aggregate.value,
+                now,
+            ) {
+                debug!(
+                    feed_name = self.feed_name,
+                    source_name = self.source_name,
+                    value = aggregate.value,
+                    "Value did not move enough since the last submission, skipping it."
+                );
+                self.metrics
+                    .skipped_submissions
+                    .with_label_values(&[&self.feed_name])
+                    .inc();
+                return;
+            }
         }
 
-        let json_blob: serde_json::Value = response.json().await.unwrap();
-        let data = jsonpath_lib::select(&json_blob, json_path)?;
-
-        if data.is_empty() {
-            anyhow::bail!(
-                "Failed to find data from json blob: {:?} with json path: {:?}",
-                json_blob,
-                json_path
-            );
-        }
-        // Assume there is one single value per request
-        match data[0].as_str() {
-            Some(value_str) => match value_str.parse::<f64>() {
-                Ok(value) => Ok(value),
-                Err(_) => anyhow::bail!(
-                    "Failed to parse data {:?} as f64 from json blob: {:?}",
-                    data[0],
-                    json_blob
-                ),
-            },
-            None => anyhow::bail!(
-                "Failed to parse data {:?} as string from json blob: {:?}",
-                data[0],
-                json_blob
-            ),
-        }
+        // TODO: allow more flexible multiplers and data types
+        let value = (aggregate.value * METRICS_MULTIPLIER) as u64;
+        self.send_to_uploader(value, aggregate.value, now).await;
     }
 
-    async fn send_to_uploader(&self, value: u64) {
+    /// Send `value` to the uploader. The submission of `aggregate_value`, aggregated at `now`, is
+    /// only recorded once the uploader has submitted it successfully, so that a failed upload is
+    /// retried on the next run.
+    async fn send_to_uploader(&self, value: u64, aggregate_value: f64, now: Instant) {
         let _ = self
             .sender
             .send(DataPoint {
@@ -324,6 +437,11 @@ impl DataProvider {
                 value,
                 retrieval_timestamp: SystemTime::now(),
                 retrieval_instant: Instant::now(),
+                submission: Submission {
+                    gate: self.submission_gate.clone(),
+                    value: aggregate_value,
+                    instant: now,
+                },
             })
             .await
             .tap_err(|err| error!("Failed to send data point to uploader: {:?}", err));
@@ -547,6 +665,9 @@ impl OnChainDataUploader {
         self.metrics.total_gas_rebate.inc_by(storage_rebate);
 
         if success {
+            for data_point in &data_points {
+                data_point.submission.record();
+            }
             self.metrics
                 .total_data_points_uploaded
                 .inc_by(data_points.len() as u64);
@@ -628,6 +749,25 @@ struct DataPoint {
     value: u64,
     retrieval_timestamp: SystemTime,
     retrieval_instant: Instant,
+    submission: Submission,
+}
+
+/// The aggregated value behind a [DataPoint], to record in its feed's submission gate once it has
+/// been submitted.
+#[derive(Debug)]
+struct Submission {
+    gate: Arc<Mutex<SubmissionGate>>,
+    value: f64,
+    instant: Instant,
+}
+
+impl Submission {
+    fn record(&self) {
+        self.gate
+            .lock()
+            .unwrap()
+            .record_submission(self.value, self.instant);
+    }
 }
 
 struct OnChainDataReader {
@@ -635,6 +775,10 @@ struct OnChainDataReader {
     // For now we share one read interval for all reads
     pub read_interval: Duration,
     pub read_configs: HashMap<String, ObjectID>,
+    pub value_path: String,
+    pub timestamp_path: String,
+    /// Latest values read, for the on-chain data sources of the upload feeds.
+    pub downloaded_feeds: Arc<DownloadedFeeds>,
     metrics: Arc<OracleMetrics>,
 }
 
@@ -648,18 +792,19 @@ impl OnChainDataReader {
         read_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         loop {
             read_interval.tick().await;
+            // Objects read after this are at least as recent as this checkpoint.
+            let checkpoint = self
+                .client
+                .read_api()
+                .get_latest_checkpoint_sequence_number()
+                .await
+                .tap_err(|err| warn!("Failed to read latest checkpoint: {:?}", err))
+                .ok();
             for (feed_name, object_id) in &self.read_configs {
-                match self
-                    .client
-                    .read_api()
-                    .get_object_with_options(*object_id, RtdObjectDataOptions::default())
-                    .await
-                {
-                    Ok(RtdObjectResponse {
-                        data: Some(_data), ..
-                    }) => {
-                        // TODO parse value based on returned BCS
-                        let value = 5_f64;
+                match self.read_feed(*object_id, checkpoint).await {
+                    Ok(Some(reading)) => {
+                        let value = reading.value;
+                        self.downloaded_feeds.record(feed_name, reading);
                         let _ = sender.send((feed_name.clone(), *object_id, value)).await;
                         self.metrics
                             .downloaded_values
@@ -686,6 +831,43 @@ impl OnChainDataReader {
             }
         }
     }
+
+    /// Read the value stored in `object_id` at or after `checkpoint`, or `None` if the object
+    /// does not hold a value.
+    async fn read_feed(
+        &self,
+        object_id: ObjectID,
+        checkpoint: Option<CheckpointSequenceNumber>,
+    ) -> anyhow::Result<Option<FeedReading>> {
+        let response = self
+            .client
+            .read_api()
+            .get_object_with_options(object_id, RtdObjectDataOptions::default().with_content())
+            .await?;
+
+        // TODO allow more generic data types
+        let Some(data) = response.data else {
+            return Ok(None);
+        };
+        let Some(content) = data.content.and_then(|c| serde_json::to_value(c).ok()) else {
+            return Ok(None);
+        };
+        let Some(value) = data_source::parse_decimal_value(&content, &self.value_path) else {
+            return Ok(None);
+        };
+
+        Ok(Some(FeedReading {
+            object_id,
+            value,
+            version: data.version,
+            timestamp_ms: data_source::parse_timestamp_ms(&content, &self.timestamp_path),
+            checkpoint,
+            read_at_ms: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        }))
+    }
 }
 
 async fn get_object_arg(
@@ -721,3 +903,65 @@ async fn get_object_arg(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use config::{AggregationConfig, SubmissionPolicyConfig};
+    use data_source::ScriptedSource;
+
+    use super::*;
+
+    fn data_provider(sender: tokio::sync::mpsc::Sender<DataPoint>) -> DataProvider {
+        DataProvider {
+            feed_name: "BTCUSD".to_string(),
+            source_name: "test".to_string(),
+            data_sources: vec![NamedDataSource {
+                name: "test".to_string(),
+                source: Arc::new(ScriptedSource::new(vec![Some(100.0)])),
+                max_staleness: Duration::from_secs(60),
+                max_deviation: None,
+            }],
+            upload_feed: Arc::new(UploadFeedConfig {
+                submission_interval: Duration::from_secs(1),
+                data_source_config: None,
+                data_source_configs: BTreeMap::new(),
+                aggregation: AggregationConfig::default(),
+                submission_policy: SubmissionPolicyConfig {
+                    min_change_percent: Some(1.0),
+                    heartbeat: None,
+                },
+                upload_parameters: UploadParameters {
+                    write_package_id: ObjectID::ZERO,
+                    write_module_name: "simple_oracle".to_string(),
+                    write_function_name: "submit_data".to_string(),
+                    write_data_provider_object_id: ObjectID::ZERO,
+                },
+            }),
+            sender,
+            latest_values: Mutex::new(HashMap::new()),
+            submission_gate: Arc::new(Mutex::new(SubmissionGate::default())),
+            metrics: Arc::new(OracleMetrics::new(&Registry::new())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submission_recorded_after_upload() {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        let provider = data_provider(sender);
+
+        // Until the uploader records the submission, the value is sent again on every run, so a
+        // failed upload is retried.
+        provider.run_once().await;
+        provider.run_once().await;
+        let first = receiver.try_recv().unwrap();
+        let second = receiver.try_recv().unwrap();
+        assert_eq!(first.value, second.value);
+
+        // Once it has been uploaded, the unchanged value is not sent again.
+        second.submission.record();
+        provider.run_once().await;
+        assert!(receiver.try_recv().is_err());
+    }
+}
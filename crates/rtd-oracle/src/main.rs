@@ -42,6 +42,7 @@ async fn main() -> anyhow::Result<()> {
         config.upload_feeds,
         config.gas_object_id,
         config.download_feeds,
+        config.api_address,
         wallet_ctx,
         prometheus_registry,
     )
@@ -11,6 +11,9 @@ use prometheus::{
 pub struct OracleMetrics {
     pub(crate) data_source_successes: IntCounterVec,
     pub(crate) data_source_errors: IntCounterVec,
+    pub(crate) data_source_rejections: IntCounterVec,
+    pub(crate) aggregation_errors: IntCounterVec,
+    pub(crate) skipped_submissions: IntCounterVec,
     pub(crate) data_staleness: IntCounterVec,
     pub(crate) upload_successes: IntCounterVec,
     pub(crate) upload_data_errors: IntCounterVec,
@@ -42,6 +45,27 @@ impl OracleMetrics {
                 registry,
             )
             .unwrap(),
+            data_source_rejections: register_int_counter_vec_with_registry!(
+                "oracle_data_source_rejections",
+                "Total number of data source values rejected as stale or outliers",
+                &["feed", "source"],
+                registry,
+            )
+            .unwrap(),
+            aggregation_errors: register_int_counter_vec_with_registry!(
+                "oracle_aggregation_errors",
+                "Total number of failed aggregations, e.g. too few usable data sources",
+                &["feed"],
+                registry,
+            )
+            .unwrap(),
+            skipped_submissions: register_int_counter_vec_with_registry!(
+                "oracle_skipped_submissions",
+                "Total number of aggregated values not submitted per the submission policy",
+                &["feed"],
+                registry,
+            )
+            .unwrap(),
             data_staleness: register_int_counter_vec_with_registry!(
                 "oracle_data_staleness",
                 "Total number of stale data that are skipped",
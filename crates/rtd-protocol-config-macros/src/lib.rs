@@ -38,6 +38,9 @@ use syn::{Data, DeriveInput, Fields, Type, parse_macro_input};
 ///
 ///     /// Returns a map of all features to values
 ///     pub fn feature_map(&self) -> std::collections::BTreeMap<String, bool>;
+///
+///     /// Sets a feature by the string name, or returns None if it doesn't exist
+///     pub fn set_feature(&mut self, value: &str, val: bool) -> Option<()>;
 /// ```
 #[proc_macro_derive(ProtocolConfigAccessors)]
 pub fn accessors_macro(input: TokenStream) -> TokenStream {
@@ -184,6 +187,17 @@ pub fn accessors_macro(input: TokenStream) -> TokenStream {
             pub fn feature_map(&self) -> std::collections::BTreeMap<String, bool> {
                 self.feature_flags.attr_map()
             }
+
+            /// Set a feature flag by its string representation
+            pub fn set_feature_for_testing(&mut self, value: String, val: bool) {
+                self.feature_flags.set_attr_for_testing(value, val)
+            }
+
+            /// Set a feature flag by its string representation, or return `None` if there is no
+            /// such feature flag
+            pub fn set_feature(&mut self, value: &str, val: bool) -> Option<()> {
+                self.feature_flags.set_attr(value, val)
+            }
         }
 
         // For each attr, derive a setter from the raw value and from string repr
@@ -315,9 +329,14 @@ pub fn feature_flag_getters_macro(input: TokenStream) -> TokenStream {
                                 quote! {
                                     stringify!(#field_name) => Some(self.#field_name),
                                 },
-                                quote! {
-                                    stringify!(#field_name)
-                                },
+                                (
+                                    quote! {
+                                        stringify!(#field_name) => self.#field_name = val,
+                                    },
+                                    quote! {
+                                        stringify!(#field_name)
+                                    },
+                                ),
                             ),
                         ))
                     }
@@ -329,8 +348,11 @@ pub fn feature_flag_getters_macro(input: TokenStream) -> TokenStream {
         _ => panic!("Only structs supported."),
     };
 
-    let (by_fn_getters, (string_name_getters, field_names)): (Vec<_>, (Vec<_>, Vec<_>)) =
-        getters.unzip();
+    #[allow(clippy::type_complexity)]
+    let (by_fn_getters, (string_name_getters, (string_name_setters, field_names))): (
+        Vec<_>,
+        (Vec<_>, (Vec<_>, Vec<_>)),
+    ) = getters.unzip();
 
     let output = quote! {
         // For each getter, expand it out into a function in the impl block
@@ -352,6 +374,23 @@ pub fn feature_flag_getters_macro(input: TokenStream) -> TokenStream {
                     #(((#field_names).to_owned(), self.lookup_attr((#field_names).to_owned()).unwrap()),)*
                     ].into_iter().collect()
             }
+
+            /// Set a feature flag by its string representation
+            pub fn set_attr_for_testing(&mut self, attr: String, val: bool) {
+                if self.set_attr(&attr, val).is_none() {
+                    panic!("Attempting to set unknown feature flag: {}", attr);
+                }
+            }
+
+            /// Set a feature flag by its string representation, or return `None` if there is no
+            /// such feature flag
+            pub fn set_attr(&mut self, attr: &str, val: bool) -> Option<()> {
+                match attr {
+                    #(#string_name_setters)*
+                    _ => return None,
+                }
+                Some(())
+            }
         }
     };
 
@@ -4884,6 +4884,37 @@ mod test {
         assert_eq!(prot.max_arguments(), 456);
     }
 
+    #[test]
+    fn test_feature_flag_setters() {
+        let mut prot: ProtocolConfig =
+            ProtocolConfig::get_for_version(ProtocolVersion::new(1), Chain::Unknown);
+        assert!(!prot.feature_map()["package_upgrades"]);
+
+        prot.set_feature_for_testing("package_upgrades".to_string(), true);
+        assert!(prot.feature_map()["package_upgrades"]);
+        assert!(
+            prot.feature_flags
+                .lookup_attr("package_upgrades".to_owned())
+                == Some(true)
+        );
+
+        prot.feature_flags
+            .set_attr_for_testing("package_upgrades".to_string(), false);
+        assert!(!prot.feature_map()["package_upgrades"]);
+
+        // Other flags are untouched.
+        let original = ProtocolConfig::get_for_version(ProtocolVersion::new(1), Chain::Unknown);
+        assert_eq!(prot.feature_map(), original.feature_map());
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempting to set unknown feature flag: some random string")]
+    fn set_unknown_feature_flag_test() {
+        let mut prot: ProtocolConfig =
+            ProtocolConfig::get_for_version(ProtocolVersion::new(1), Chain::Unknown);
+        prot.set_feature_for_testing("some random string".to_string(), true);
+    }
+
     #[test]
     #[should_panic(expected = "unsupported version")]
     fn max_version_test() {
@@ -0,0 +1,153 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Access to a local checkpoint archive, that is, a directory of `<sequence_number>.chk` files as
+//! written by the checkpoint ingestion pipeline.
+//!
+//! `ArchiveStore` serves the transactions of a window of archived checkpoints, and the objects
+//! they read and wrote, so that a checkpoint range can be replayed without a round trip per
+//! transaction. Everything the archive does not carry (packages, epoch data, dynamic fields that
+//! were only read) comes from a fallback store.
+
+use anyhow::{Context, Result, bail};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use rtd_data_store::{
+    EpochData, EpochStore, ObjectKey, ObjectStore, ReadDataStore, TransactionInfo,
+    TransactionStore, VersionQuery,
+};
+use rtd_types::{
+    base_types::ObjectID, effects::TransactionEffectsAPI, full_checkpoint_content::CheckpointData,
+    object::Object, supported_protocol_versions::ProtocolConfig,
+};
+
+// Checkpoint files are `Blob`s: a one byte encoding tag followed by the payload.
+// BCS is the only encoding in use.
+const BLOB_ENCODING_BCS: u8 = 1;
+
+/// A directory of checkpoint files.
+pub struct CheckpointArchive {
+    path: PathBuf,
+}
+
+impl CheckpointArchive {
+    pub fn new(path: &Path) -> Result<Self> {
+        if !path.is_dir() {
+            bail!("Checkpoint archive {} is not a directory", path.display());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Read checkpoint `sequence_number` from the archive.
+    pub fn checkpoint(&self, sequence_number: u64) -> Result<CheckpointData> {
+        let file = self.path.join(format!("{sequence_number}.chk"));
+        let bytes = fs::read(&file)
+            .with_context(|| format!("Failed to read checkpoint file {}", file.display()))?;
+        match bytes.split_first() {
+            Some((&BLOB_ENCODING_BCS, data)) => bcs::from_bytes(data).with_context(|| {
+                format!("Failed to deserialize checkpoint file {}", file.display())
+            }),
+            Some((encoding, _)) => bail!(
+                "Unsupported encoding {encoding} in checkpoint file {}",
+                file.display()
+            ),
+            None => bail!("Checkpoint file {} is empty", file.display()),
+        }
+    }
+}
+
+/// A data store over a window of archived checkpoints.
+///
+/// Transactions are looked up in the window only. Objects requested at an exact version are
+/// served from the inputs and outputs of the window's transactions when present; all other
+/// queries go to the fallback store.
+pub struct ArchiveStore<'a, S> {
+    transactions: BTreeMap<String, TransactionInfo>,
+    objects: BTreeMap<(ObjectID, u64), Object>,
+    fallback: &'a S,
+}
+
+impl<'a, S: ReadDataStore> ArchiveStore<'a, S> {
+    pub fn new(checkpoints: &[CheckpointData], fallback: &'a S) -> Self {
+        let mut transactions = BTreeMap::new();
+        let mut objects = BTreeMap::new();
+        for checkpoint in checkpoints {
+            let sequence_number = checkpoint.checkpoint_summary.sequence_number;
+            for tx in &checkpoint.transactions {
+                transactions.insert(
+                    tx.effects.transaction_digest().to_string(),
+                    TransactionInfo {
+                        data: tx.transaction.transaction_data().clone(),
+                        effects: tx.effects.clone(),
+                        checkpoint: sequence_number,
+                    },
+                );
+                for object in tx.input_objects.iter().chain(&tx.output_objects) {
+                    objects.insert((object.id(), object.version().value()), object.clone());
+                }
+            }
+        }
+        Self {
+            transactions,
+            objects,
+            fallback,
+        }
+    }
+}
+
+impl<S: ReadDataStore> TransactionStore for ArchiveStore<'_, S> {
+    fn transaction_data_and_effects(&self, tx_digest: &str) -> Result<Option<TransactionInfo>> {
+        Ok(self.transactions.get(tx_digest).cloned())
+    }
+}
+
+impl<S: ReadDataStore> EpochStore for ArchiveStore<'_, S> {
+    fn epoch_info(&self, epoch: u64) -> Result<Option<EpochData>> {
+        self.fallback.epoch_info(epoch)
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<Option<ProtocolConfig>> {
+        self.fallback.protocol_config(epoch)
+    }
+}
+
+impl<S: ReadDataStore> ObjectStore for ArchiveStore<'_, S> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<(Object, u64)>>> {
+        let mut objects = Vec::with_capacity(keys.len());
+        let mut missing = vec![];
+        for (i, key) in keys.iter().enumerate() {
+            let archived = match key.version_query {
+                VersionQuery::Version(version) => self
+                    .objects
+                    .get(&(key.object_id, version))
+                    .map(|object| (object.clone(), version)),
+                VersionQuery::RootVersion(_) | VersionQuery::AtCheckpoint(_) => None,
+            };
+            if archived.is_none() {
+                missing.push(i);
+            }
+            objects.push(archived);
+        }
+
+        if !missing.is_empty() {
+            let missing_keys = missing.iter().map(|i| keys[*i].clone()).collect::<Vec<_>>();
+            let fetched = self.fallback.get_objects(&missing_keys)?;
+            if fetched.len() != missing_keys.len() {
+                bail!(
+                    "Fallback store returned {} objects for {} keys",
+                    fetched.len(),
+                    missing_keys.len()
+                );
+            }
+            for (i, object) in missing.into_iter().zip(fetched) {
+                objects[i] = object;
+            }
+        }
+        Ok(objects)
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Export and import of replay bundles (see `rtd_data_store::stores::ReplayBundle`).
+//!
+//! A bundle is captured by replaying the transaction through an `InMemoryStore` placed in front
+//! of the file system cache and GraphQL: everything replay reads, including dynamic field
+//! children loaded during execution, ends up in the in-memory store and is exported from there.
+
+use crate::{
+    execution::execute_transaction_to_effects,
+    replay_txn::{ExecutorProvider, ReplayTransaction},
+};
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use rtd_data_store::{
+    Node, SetupStore,
+    stores::{DataStore, FileSystemStore, InMemoryStore, ReadThroughStore, ReplayBundle},
+};
+use tracing::{info, warn};
+
+/// Replay `tx_digest` from `node`, and write everything the replay read to a bundle file at
+/// `output_path`.
+pub fn export_bundle(
+    node: Node,
+    tx_digest: &str,
+    output_path: &Path,
+    version: &str,
+) -> Result<ReplayBundle> {
+    let fs_store = FileSystemStore::new(node.clone())
+        .map_err(|e| anyhow!("Failed to create file system store: {:?}", e))?;
+    let gql_store = DataStore::new(node.clone(), version)
+        .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+    let store = ReadThroughStore::new(
+        InMemoryStore::new(node),
+        ReadThroughStore::new(fs_store, gql_store),
+    );
+    let chain_id = store.setup(None)?;
+
+    let mut executor_provider = ExecutorProvider::new(false);
+    let replay_txn =
+        ReplayTransaction::load(tx_digest, &store, &store, &store, &mut executor_provider)?;
+    let (_, context_and_effects) =
+        execute_transaction_to_effects(replay_txn, &store, &store, &mut None)
+            .with_context(|| format!("Failed to replay transaction {tx_digest}"))?;
+    if context_and_effects.execution_effects != context_and_effects.expected_effects {
+        // Still worth capturing: this is likely the reason for the bundle.
+        warn!(
+            tx_digest,
+            "Replayed effects differ from the on-chain effects"
+        );
+    }
+
+    let bundle = store.primary().export_bundle(tx_digest, chain_id);
+    bundle.write(output_path)?;
+    info!(
+        tx_digest,
+        objects = bundle.objects.len(),
+        path = %output_path.display(),
+        "Exported replay bundle"
+    );
+    Ok(bundle)
+}
+
+/// Import the bundle at `bundle_path` into the file system cache of the node it was captured
+/// from.
+pub fn import_bundle(bundle_path: &Path) -> Result<ReplayBundle> {
+    let bundle = ReplayBundle::read(bundle_path)?;
+    let fs_store = FileSystemStore::new(bundle.node())
+        .map_err(|e| anyhow!("Failed to create file system store: {:?}", e))?;
+    bundle.import_into(&fs_store)?;
+    info!(
+        tx_digest = %bundle.tx_digest,
+        path = %bundle_path.display(),
+        "Imported replay bundle"
+    );
+    Ok(bundle)
+}
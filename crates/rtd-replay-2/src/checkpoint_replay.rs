@@ -0,0 +1,724 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay of a whole checkpoint range from a local checkpoint archive.
+//!
+//! Checkpoints are read from the archive a window at a time. The transactions of a window are
+//! replayed in dependency order: a transaction is only executed once all the transactions it
+//! depends on (as recorded in its effects) have been, and transactions whose dependencies are all
+//! done run in parallel.
+//!
+//! Every replayed transaction produces a `TransactionDivergence` entry, written as a line of JSON
+//! to `report.jsonl`. A diverging transaction is flagged with the digests of its dependencies
+//! that diverged or failed to replay too, so that root causes can be told apart from divergences
+//! they induced.
+//! The report is aggregated per package and per Move function into a `DivergenceSummary`,
+//! written to `summary.json`. Diverging transactions also get their usual replay artifacts, so
+//! that they can be inspected like any other replayed transaction.
+
+use crate::{
+    ReplayConfigExperimental, ReplayConfigStableInternal, StoreMode,
+    archive::{ArchiveStore, CheckpointArchive},
+    artifacts::{Artifact, ArtifactManager},
+    diff_effects,
+    execution::{TxnContextAndEffects, execute_transaction_to_effects},
+    replay_txn::{ExecutorProvider, ReplayTransaction, verify_txn_and_save_effects},
+    summary_metrics::DivergenceSummary,
+    what_if::{WhatIf, WhatIfStore},
+};
+use anyhow::{Result, anyhow, bail};
+use clap::Args;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{Display, Formatter},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use rtd_data_store::{
+    ReadDataStore, SetupStore,
+    stores::{DataStore, FileSystemStore, InMemoryStore, ReadThroughStore},
+};
+use rtd_types::{
+    base_types::{ObjectID, SequenceNumber},
+    digests::{ObjectDigest, TransactionDigest},
+    effects::{IDOperation, ObjectChange, TransactionEffects, TransactionEffectsAPI},
+    execution_status::ExecutionStatus,
+    full_checkpoint_content::CheckpointTransaction,
+    gas::GasCostSummary,
+    transaction::{Command, TransactionData, TransactionDataAPI, TransactionKind},
+};
+use tracing::{debug, error, info, warn};
+
+const REPORT_FILE_NAME: &str = "report.jsonl";
+const SUMMARY_FILE_NAME: &str = "summary.json";
+
+/// Arguments of the `replay-checkpoints` command.
+#[derive(Args, Clone, Debug)]
+pub struct CheckpointReplayArgs {
+    /// Directory holding the checkpoint archive, one `<sequence_number>.chk` file per checkpoint.
+    #[arg(long = "archive")]
+    pub archive: PathBuf,
+
+    /// First checkpoint to replay.
+    #[arg(long = "start")]
+    pub start: u64,
+
+    /// Last checkpoint to replay (inclusive).
+    #[arg(long = "end")]
+    pub end: u64,
+
+    /// Number of transactions replayed in parallel. Defaults to the number of CPUs.
+    #[arg(long = "jobs", short = 'j')]
+    pub jobs: Option<usize>,
+
+    /// Number of checkpoints read from the archive and scheduled together.
+    #[arg(long = "window", default_value_t = 16)]
+    pub window: u64,
+}
+
+/// How a transaction's replay compares to its on-chain execution.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayOutcome {
+    /// The replayed effects are identical to the expected ones.
+    Match,
+    /// The replayed effects differ from the expected ones.
+    Diverged,
+    /// The transaction could not be loaded or executed.
+    Error,
+}
+
+/// A Move function called by a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct MoveFunction {
+    pub package: ObjectID,
+    pub module: String,
+    pub function: String,
+}
+
+impl MoveFunction {
+    /// The Move functions called by a transaction, in command order.
+    pub fn from_transaction(txn_data: &TransactionData) -> Vec<Self> {
+        let TransactionKind::ProgrammableTransaction(ptb) = txn_data.kind() else {
+            return vec![];
+        };
+        ptb.commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::MoveCall(call) => Some(Self {
+                    package: call.package,
+                    module: call.module.clone(),
+                    function: call.function.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Display for MoveFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}::{}", self.package, self.module, self.function)
+    }
+}
+
+/// Gas charged by the replay compared to the expected gas.
+#[derive(Clone, Debug, Serialize)]
+pub struct GasDelta {
+    pub expected: GasCostSummary,
+    pub actual: GasCostSummary,
+    /// Replayed net gas usage minus the expected one.
+    pub net_gas_delta: i64,
+}
+
+impl GasDelta {
+    fn new(expected: &TransactionEffects, actual: &TransactionEffects) -> Self {
+        let expected = expected.gas_cost_summary().clone();
+        let actual = actual.gas_cost_summary().clone();
+        Self {
+            net_gas_delta: actual.net_gas_usage() - expected.net_gas_usage(),
+            expected,
+            actual,
+        }
+    }
+}
+
+/// What a transaction did to an object, as recorded in its effects.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ObjectOutcome {
+    pub input_version: Option<SequenceNumber>,
+    pub output_version: Option<SequenceNumber>,
+    pub output_digest: Option<ObjectDigest>,
+    pub id_operation: IDOperation,
+}
+
+impl From<&ObjectChange> for ObjectOutcome {
+    fn from(change: &ObjectChange) -> Self {
+        Self {
+            input_version: change.input_version,
+            output_version: change.output_version,
+            output_digest: change.output_digest,
+            id_operation: change.id_operation,
+        }
+    }
+}
+
+/// An object that the replay changed differently from the on-chain execution. A side is `None`
+/// if that execution did not touch the object at all.
+#[derive(Clone, Debug, Serialize)]
+pub struct ObjectDivergence {
+    pub object_id: ObjectID,
+    pub expected: Option<ObjectOutcome>,
+    pub actual: Option<ObjectOutcome>,
+}
+
+impl ObjectDivergence {
+    /// The divergent object with the lowest ID, if any.
+    fn first(expected: &TransactionEffects, actual: &TransactionEffects) -> Option<Self> {
+        let outcomes = |effects: &TransactionEffects| {
+            effects
+                .object_changes()
+                .iter()
+                .map(|change| (change.id, ObjectOutcome::from(change)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let mut expected = outcomes(expected);
+        let mut actual = outcomes(actual);
+        let ids: BTreeSet<_> = expected.keys().chain(actual.keys()).copied().collect();
+        ids.into_iter().find_map(|object_id| {
+            let expected = expected.remove(&object_id);
+            let actual = actual.remove(&object_id);
+            (expected != actual).then_some(Self {
+                object_id,
+                expected,
+                actual,
+            })
+        })
+    }
+}
+
+/// The result of replaying one transaction of the range.
+#[derive(Clone, Debug, Serialize)]
+pub struct TransactionDivergence {
+    pub digest: TransactionDigest,
+    pub checkpoint: u64,
+    pub outcome: ReplayOutcome,
+    pub move_calls: Vec<MoveFunction>,
+    /// Dependencies of the transaction that diverged or failed to replay themselves.
+    pub diverged_dependencies: Vec<TransactionDigest>,
+    pub expected_status: Option<ExecutionStatus>,
+    pub actual_status: Option<ExecutionStatus>,
+    pub gas: Option<GasDelta>,
+    pub first_divergent_object: Option<ObjectDivergence>,
+    /// Diff of the expected and replayed effects, only for diverging transactions.
+    pub effects_diff: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TransactionDivergence {
+    fn compare(
+        checkpoint: u64,
+        move_calls: Vec<MoveFunction>,
+        expected: &TransactionEffects,
+        actual: &TransactionEffects,
+    ) -> Self {
+        let diverged = expected != actual;
+        Self {
+            digest: *expected.transaction_digest(),
+            checkpoint,
+            outcome: if diverged {
+                ReplayOutcome::Diverged
+            } else {
+                ReplayOutcome::Match
+            },
+            move_calls,
+            diverged_dependencies: vec![],
+            expected_status: Some(expected.status().clone()),
+            actual_status: Some(actual.status().clone()),
+            gas: Some(GasDelta::new(expected, actual)),
+            first_divergent_object: diverged
+                .then(|| ObjectDivergence::first(expected, actual))
+                .flatten(),
+            effects_diff: diverged.then(|| diff_effects(expected, actual)),
+            error: None,
+        }
+    }
+
+    fn error(
+        digest: TransactionDigest,
+        checkpoint: u64,
+        move_calls: Vec<MoveFunction>,
+        error: &anyhow::Error,
+    ) -> Self {
+        Self {
+            digest,
+            checkpoint,
+            outcome: ReplayOutcome::Error,
+            move_calls,
+            diverged_dependencies: vec![],
+            expected_status: None,
+            actual_status: None,
+            gas: None,
+            first_divergent_object: None,
+            effects_diff: None,
+            error: Some(format!("{error:?}")),
+        }
+    }
+}
+
+/// Replay the checkpoint range selected by `args` with the data store selected by the
+/// experimental config, under the `what_if` overrides, and return the aggregated divergence
+/// report.
+pub async fn handle_checkpoint_replay(
+    args: &CheckpointReplayArgs,
+    stable_config: &ReplayConfigStableInternal,
+    experimental_config: &ReplayConfigExperimental,
+    what_if: &WhatIf,
+    version: &str,
+) -> Result<DivergenceSummary> {
+    if args.start > args.end {
+        bail!(
+            "--start ({}) must not be greater than --end ({})",
+            args.start,
+            args.end
+        );
+    }
+    if args.window == 0 {
+        bail!("--window must be at least 1");
+    }
+
+    let output_root_dir = if let Some(dir) = &stable_config.output_dir {
+        dir.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|e| anyhow!("Failed to get current directory: {e}"))?
+            .join(crate::DEFAULT_OUTPUT_DIR)
+    };
+    let jobs = args.jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    let replay = CheckpointReplay {
+        archive: CheckpointArchive::new(&args.archive)?,
+        start: args.start,
+        end: args.end,
+        window: args.window,
+        jobs: jobs.max(1),
+        output_root_dir,
+        overwrite_existing: stable_config.overwrite,
+        what_if,
+    };
+
+    let node = &experimental_config.node;
+    match experimental_config.store_mode {
+        StoreMode::GqlOnly => {
+            let gql_store = DataStore::new(node.clone(), version)
+                .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+            replay.run(&gql_store)
+        }
+        StoreMode::FsThenGql => {
+            let fs_store = FileSystemStore::new(node.clone())
+                .map_err(|e| anyhow!("Failed to create file system store: {:?}", e))?;
+            let gql_store = DataStore::new(node.clone(), version)
+                .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+            replay.run(&ReadThroughStore::new(fs_store, gql_store))
+        }
+        StoreMode::FsOnly => {
+            let fs_store = FileSystemStore::new(node.clone())
+                .map_err(|e| anyhow!("Failed to create file system store: {:?}", e))?;
+            replay.run(&fs_store)
+        }
+        StoreMode::InmemFs => {
+            let fs_store = FileSystemStore::new(node.clone())
+                .map_err(|e| anyhow!("Failed to create file system store: {:?}", e))?;
+            let in_memory_store = InMemoryStore::new(node.clone());
+            replay.run(&ReadThroughStore::new(in_memory_store, fs_store))
+        }
+        StoreMode::InmemFsGql => {
+            let fs_store = FileSystemStore::new(node.clone())
+                .map_err(|e| anyhow!("Failed to create file system store: {:?}", e))?;
+            let gql_store = DataStore::new(node.clone(), version)
+                .map_err(|e| anyhow!("Failed to create data store: {:?}", e))?;
+            let secondary_store = ReadThroughStore::new(fs_store, gql_store);
+            let in_memory_store = InMemoryStore::new(node.clone());
+            replay.run(&ReadThroughStore::new(in_memory_store, secondary_store))
+        }
+    }
+}
+
+struct CheckpointReplay<'a> {
+    archive: CheckpointArchive,
+    start: u64,
+    end: u64,
+    window: u64,
+    jobs: usize,
+    output_root_dir: PathBuf,
+    overwrite_existing: bool,
+    what_if: &'a WhatIf,
+}
+
+impl CheckpointReplay<'_> {
+    /// Replay the range, using `data_store` for everything the archive does not provide.
+    fn run<S>(&self, data_store: &S) -> Result<DivergenceSummary>
+    where
+        S: ReadDataStore + SetupStore + Sync,
+    {
+        data_store.setup(None)?;
+
+        let report_dir = self
+            .output_root_dir
+            .join(format!("checkpoints_{}_{}", self.start, self.end));
+        fs::create_dir_all(&report_dir).map_err(|e| {
+            anyhow!(
+                "Failed to create report directory {}: {e}",
+                report_dir.display()
+            )
+        })?;
+        let report_path = report_dir.join(REPORT_FILE_NAME);
+        let mut report = BufWriter::new(
+            File::create(&report_path)
+                .map_err(|e| anyhow!("Failed to create report {}: {e}", report_path.display()))?,
+        );
+
+        let mut summary = DivergenceSummary::new();
+        // Digests of all the transactions that diverged or failed to replay so far, so that their
+        // dependents can be flagged.
+        let mut diverged = BTreeSet::new();
+        // Executors are cached per worker, so each protocol version is set up once per worker.
+        let mut executor_providers = (0..self.jobs)
+            .map(|_| ExecutorProvider::new(true))
+            .collect::<Vec<_>>();
+
+        let progress_bar = ProgressBar::new(self.end - self.start + 1);
+        progress_bar.set_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} checkpoints {msg}").unwrap(),
+        );
+
+        let mut window_start = self.start;
+        while window_start <= self.end {
+            let window_end = self.end.min(window_start.saturating_add(self.window - 1));
+            let checkpoints = (window_start..=window_end)
+                .map(|sequence_number| self.archive.checkpoint(sequence_number))
+                .collect::<Result<Vec<_>>>()?;
+            let archive_store = ArchiveStore::new(&checkpoints, data_store);
+            let store = WhatIfStore::new(&archive_store, self.what_if);
+            let transactions = checkpoints
+                .iter()
+                .flat_map(|checkpoint| {
+                    let sequence_number = checkpoint.checkpoint_summary.sequence_number;
+                    checkpoint
+                        .transactions
+                        .iter()
+                        .map(move |tx| (sequence_number, tx))
+                })
+                .collect::<Vec<_>>();
+
+            let results = Mutex::new(vec![None; transactions.len()]);
+            for level in dependency_levels(&transactions) {
+                debug!(
+                    window_start,
+                    window_end,
+                    transactions = level.len(),
+                    "Replaying dependency level"
+                );
+                self.replay_level(
+                    &level,
+                    &transactions,
+                    &store,
+                    &mut executor_providers,
+                    &results,
+                );
+            }
+
+            // Archive order is a causal order, so by the time a transaction is reported all of its
+            // dependencies have been.
+            for ((_, tx), result) in transactions.iter().zip(results.into_inner().unwrap()) {
+                let mut result = result.expect("every transaction is replayed");
+                flag_diverged_dependencies(&mut result, tx.effects.dependencies(), &mut diverged);
+                summary.add_transaction(&result);
+                serde_json::to_writer(&mut report, &result)?;
+                writeln!(report)?;
+            }
+
+            progress_bar.inc(window_end - window_start + 1);
+            progress_bar.set_message(format!(
+                "diverged={} errors={}",
+                summary.total.diverged_count, summary.total.error_count
+            ));
+            window_start = window_end + 1;
+        }
+        progress_bar.finish_and_clear();
+        report.flush()?;
+
+        let summary_path = report_dir.join(SUMMARY_FILE_NAME);
+        fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)
+            .map_err(|e| anyhow!("Failed to write summary {}: {e}", summary_path.display()))?;
+        info!(
+            report = %report_path.display(),
+            summary = %summary_path.display(),
+            "Checkpoint replay done"
+        );
+        Ok(summary)
+    }
+
+    /// Replay the transactions at `level` indices of `transactions` in parallel, storing each
+    /// result at its index in `results`.
+    fn replay_level<S: ReadDataStore + Sync>(
+        &self,
+        level: &[usize],
+        transactions: &[(u64, &CheckpointTransaction)],
+        store: &S,
+        executor_providers: &mut [ExecutorProvider],
+        results: &Mutex<Vec<Option<TransactionDivergence>>>,
+    ) {
+        let next = AtomicUsize::new(0);
+        let next = &next;
+        std::thread::scope(|scope| {
+            for executor_provider in executor_providers.iter_mut().take(level.len()) {
+                scope.spawn(move || {
+                    loop {
+                        let Some(&idx) = level.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                            break;
+                        };
+                        let (checkpoint, tx) = transactions[idx];
+                        let result =
+                            self.replay_transaction(checkpoint, tx, store, executor_provider);
+                        results.lock().unwrap()[idx] = Some(result);
+                    }
+                });
+            }
+        });
+    }
+
+    fn replay_transaction<S: ReadDataStore>(
+        &self,
+        checkpoint: u64,
+        tx: &CheckpointTransaction,
+        store: &S,
+        executor_provider: &mut ExecutorProvider,
+    ) -> TransactionDivergence {
+        let digest = *tx.effects.transaction_digest();
+        let move_calls = MoveFunction::from_transaction(tx.transaction.transaction_data());
+
+        let replayed =
+            ReplayTransaction::load(&digest.to_string(), store, store, store, executor_provider)
+                .and_then(|replay_txn| {
+                    execute_transaction_to_effects(replay_txn, store, store, &mut None)
+                });
+        let context_and_effects = match replayed {
+            Ok((_, context_and_effects)) => context_and_effects,
+            Err(e) => {
+                error!(tx_digest = %digest, error = ?e, "Replay failed");
+                return TransactionDivergence::error(digest, checkpoint, move_calls, &e);
+            }
+        };
+
+        let result = TransactionDivergence::compare(
+            checkpoint,
+            move_calls,
+            &context_and_effects.expected_effects,
+            &context_and_effects.execution_effects,
+        );
+        if result.outcome == ReplayOutcome::Diverged
+            && let Err(e) = self.save_artifacts(&digest, &context_and_effects)
+        {
+            warn!(tx_digest = %digest, error = ?e, "Failed to save replay artifacts");
+        }
+        result
+    }
+
+    /// Save the artifacts of a diverging transaction, as a single transaction replay would.
+    fn save_artifacts(
+        &self,
+        digest: &TransactionDigest,
+        context_and_effects: &TxnContextAndEffects,
+    ) -> Result<()> {
+        let tx_dir = self.output_root_dir.join(digest.to_string());
+        let artifact_manager = ArtifactManager::new(&tx_dir, self.overwrite_existing)?;
+        artifact_manager
+            .member(Artifact::TransactionData)
+            .serialize_artifact(&context_and_effects.txn_data)
+            .transpose()?;
+        artifact_manager
+            .member(Artifact::TransactionGasReport)
+            .serialize_artifact(&context_and_effects.gas_status.gas_usage_report())
+            .transpose()?;
+        verify_txn_and_save_effects(
+            &artifact_manager,
+            &context_and_effects.expected_effects,
+            &context_and_effects.execution_effects,
+        )
+    }
+}
+
+/// Group `transactions` (in archive order) into levels: a transaction's level is one more than
+/// the highest level of the transactions it depends on, so all transactions in a level can run
+/// in parallel once the previous levels are done. Dependencies outside `transactions` are
+/// already done.
+fn dependency_levels(transactions: &[(u64, &CheckpointTransaction)]) -> Vec<Vec<usize>> {
+    let mut index = HashMap::new();
+    let mut levels: Vec<Vec<usize>> = vec![];
+    for (idx, (_, tx)) in transactions.iter().enumerate() {
+        let level = tx
+            .effects
+            .dependencies()
+            .iter()
+            .filter_map(|dependency| index.get(dependency))
+            .map(|level| level + 1)
+            .max()
+            .unwrap_or(0);
+        index.insert(*tx.effects.transaction_digest(), level);
+        if level == levels.len() {
+            levels.push(vec![]);
+        }
+        levels[level].push(idx);
+    }
+    levels
+}
+
+/// Record in `result` which of its `dependencies` are in `diverged`, and add it to `diverged` if
+/// it did not replay to the expected effects, whether it diverged or failed to replay.
+fn flag_diverged_dependencies(
+    result: &mut TransactionDivergence,
+    dependencies: &[TransactionDigest],
+    diverged: &mut BTreeSet<TransactionDigest>,
+) {
+    if result.outcome == ReplayOutcome::Match {
+        return;
+    }
+    result.diverged_dependencies = dependencies
+        .iter()
+        .filter(|digest| diverged.contains(*digest))
+        .copied()
+        .collect();
+    diverged.insert(result.digest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtd_types::full_checkpoint_content::CheckpointData;
+    use rtd_types::test_checkpoint_data_builder::TestCheckpointBuilder;
+
+    /// One checkpoint's worth of transactions, where the `i`-th transaction depends on the
+    /// transactions at the indices in `dependencies[i]`.
+    fn transactions(dependencies: &[&[usize]]) -> Vec<CheckpointTransaction> {
+        let mut builder = TestCheckpointBuilder::new(0);
+        for sender in 0..dependencies.len() {
+            builder = builder.start_transaction(sender as u8).finish_transaction();
+        }
+        let mut transactions = CheckpointData::from(builder.build_checkpoint()).transactions;
+        let digests: Vec<_> = transactions
+            .iter()
+            .map(|tx| *tx.effects.transaction_digest())
+            .collect();
+        for (tx, dependencies) in transactions.iter_mut().zip(dependencies) {
+            *tx.effects.dependencies_mut_for_testing() =
+                dependencies.iter().map(|i| digests[*i]).collect();
+        }
+        transactions
+    }
+
+    fn levels(transactions: &[CheckpointTransaction]) -> Vec<Vec<usize>> {
+        let transactions: Vec<_> = transactions.iter().map(|tx| (0, tx)).collect();
+        dependency_levels(&transactions)
+    }
+
+    #[test]
+    fn test_dependency_levels() {
+        // A chain runs one transaction at a time.
+        let chain = transactions(&[&[], &[0], &[1]]);
+        assert_eq!(levels(&chain), vec![vec![0], vec![1], vec![2]]);
+
+        // Independent transactions all run at once.
+        let independent = transactions(&[&[], &[], &[]]);
+        assert_eq!(levels(&independent), vec![vec![0, 1, 2]]);
+
+        // A diamond runs its two sides in parallel, and joins after the deeper one.
+        let diamond = transactions(&[&[], &[0], &[0], &[1, 2], &[]]);
+        assert_eq!(levels(&diamond), vec![vec![0, 4], vec![1, 2], vec![3]]);
+        let diamond = transactions(&[&[], &[0], &[1], &[0], &[2, 3]]);
+        assert_eq!(
+            levels(&diamond),
+            vec![vec![0], vec![1, 3], vec![2], vec![4]]
+        );
+
+        // Dependencies on transactions of earlier windows are already done.
+        let all = transactions(&[&[], &[0], &[1], &[0, 2]]);
+        assert_eq!(levels(&all[2..]), vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn test_flag_diverged_dependencies() {
+        let txs = transactions(&[&[], &[0], &[1], &[0]]);
+        let digest = |i: usize| *txs[i].effects.transaction_digest();
+        let error = |i: usize| {
+            TransactionDivergence::error(digest(i), 0, vec![], &anyhow!("replay failed"))
+        };
+        let compare = |i: usize, actual: &TransactionEffects| {
+            TransactionDivergence::compare(0, vec![], &txs[i].effects, actual)
+        };
+        let mut diverged = BTreeSet::new();
+        let mut flag = |i: usize, mut result: TransactionDivergence| {
+            flag_diverged_dependencies(&mut result, txs[i].effects.dependencies(), &mut diverged);
+            result.diverged_dependencies
+        };
+
+        // A transaction that fails to replay is a root cause for its dependents.
+        assert!(flag(0, error(0)).is_empty());
+        let mut diverging = txs[1].effects.clone();
+        diverging.unsafe_add_object_tombstone_for_testing((
+            ObjectID::from_hex_literal("0x5").unwrap(),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::random(),
+        ));
+        assert_eq!(flag(1, compare(1, &diverging)), vec![digest(0)]);
+
+        // Errors are flagged too, and matches are not.
+        assert_eq!(flag(2, error(2)), vec![digest(1)]);
+        assert!(flag(3, compare(3, &txs[3].effects)).is_empty());
+    }
+
+    #[test]
+    fn test_first_divergent_object() {
+        let object = |id: &str| {
+            (
+                ObjectID::from_hex_literal(id).unwrap(),
+                SequenceNumber::from_u64(1),
+                ObjectDigest::random(),
+            )
+        };
+
+        let mut expected = transactions(&[&[]]).remove(0).effects;
+        let mut actual = expected.clone();
+        assert!(ObjectDivergence::first(&expected, &actual).is_none());
+
+        // Deleted on chain, but written by the replay.
+        let written = object("0x9");
+        expected.unsafe_add_object_tombstone_for_testing(written);
+        actual.unsafe_add_deleted_live_object_for_testing(written);
+        let divergence = ObjectDivergence::first(&expected, &actual).unwrap();
+        assert_eq!(divergence.object_id, written.0);
+        assert_eq!(
+            divergence.expected.unwrap().id_operation,
+            IDOperation::Deleted
+        );
+        assert_eq!(divergence.actual.unwrap().output_digest, Some(written.2));
+
+        // Only touched by the replay, and at a lower ID, so it is the one reported.
+        let deleted = object("0x5");
+        actual.unsafe_add_object_tombstone_for_testing(deleted);
+        let divergence = ObjectDivergence::first(&expected, &actual).unwrap();
+        assert_eq!(divergence.object_id, deleted.0);
+        assert!(divergence.expected.is_none());
+        assert_eq!(divergence.actual.unwrap().input_version, Some(deleted.1));
+    }
+}
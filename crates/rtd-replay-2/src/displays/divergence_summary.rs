@@ -0,0 +1,79 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    displays::Pretty,
+    summary_metrics::{DivergenceMetrics, DivergenceSummary},
+};
+use std::fmt::{Display, Formatter};
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{Style as TableStyle, style::HorizontalLine},
+};
+
+impl Display for Pretty<'_, DivergenceSummary> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(summary) = self;
+        metrics_table(f, "Total", [("all".to_string(), &summary.total)])?;
+        // Only packages and functions with divergences or errors are worth listing.
+        metrics_table(
+            f,
+            "Package",
+            summary
+                .per_package
+                .iter()
+                .filter(|(_, metrics)| !metrics.is_clean())
+                .map(|(package, metrics)| (package.to_string(), metrics)),
+        )?;
+        metrics_table(
+            f,
+            "Function",
+            summary
+                .per_function
+                .iter()
+                .filter(|(_, metrics)| !metrics.is_clean())
+                .map(|(function, metrics)| (function.clone(), metrics)),
+        )?;
+        writeln!(f, "{}", if summary.is_clean() { "GO" } else { "NO-GO" })
+    }
+}
+
+fn metrics_table<'a>(
+    f: &mut Formatter,
+    key: &str,
+    rows: impl IntoIterator<Item = (String, &'a DivergenceMetrics)>,
+) -> std::fmt::Result {
+    let mut builder = TableBuilder::default();
+    builder.push_record(vec![
+        key,
+        "Transactions",
+        "Matched",
+        "Diverged",
+        "Root Divergences",
+        "Errors",
+        "Net Gas Delta",
+    ]);
+    let mut empty = true;
+    for (name, metrics) in rows {
+        empty = false;
+        builder.push_record(vec![
+            name,
+            metrics.tx_count.to_string(),
+            metrics.matched_count.to_string(),
+            metrics.diverged_count.to_string(),
+            metrics.root_divergence_count.to_string(),
+            metrics.error_count.to_string(),
+            metrics.net_gas_delta.to_string(),
+        ]);
+    }
+    if empty {
+        return Ok(());
+    }
+    let mut table = builder.build();
+
+    table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+        1,
+        TableStyle::modern().get_horizontal(),
+    )]));
+    write!(f, "\n{}\n", table)
+}
@@ -1,6 +1,8 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod divergence_summary;
 mod gas_report;
+mod what_if;
 
 pub struct Pretty<'a, T>(pub &'a T);
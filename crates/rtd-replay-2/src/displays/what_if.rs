@@ -0,0 +1,87 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    displays::Pretty,
+    what_if::{WhatIfComparison, status_label},
+};
+use std::fmt::{Display, Formatter};
+use rtd_types::effects::TransactionEffectsAPI;
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{Style as TableStyle, style::HorizontalLine},
+};
+
+impl Display for Pretty<'_, WhatIfComparison> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(comparison) = self;
+        let expected_gas = comparison.expected.gas_cost_summary();
+        let actual_gas = comparison.actual.gas_cost_summary();
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec!["", "On-chain", "What-if", "Delta"]);
+        builder.push_record(vec![
+            "Status".to_string(),
+            status_label(comparison.expected.status()),
+            status_label(comparison.actual.status()),
+            "".to_string(),
+        ]);
+        macro_rules! record {
+            ($msg:expr, $expected:expr, $actual:expr) => {
+                builder.push_record(vec![
+                    $msg.to_string(),
+                    $expected.to_string(),
+                    $actual.to_string(),
+                    format!("{:+}", $actual as i64 - $expected as i64),
+                ]);
+            };
+        }
+        record!(
+            "Computation Cost",
+            expected_gas.computation_cost,
+            actual_gas.computation_cost
+        );
+        record!(
+            "Storage Cost",
+            expected_gas.storage_cost,
+            actual_gas.storage_cost
+        );
+        record!(
+            "Storage Rebate",
+            expected_gas.storage_rebate,
+            actual_gas.storage_rebate
+        );
+        record!(
+            "Non-Refundable Storage Fee",
+            expected_gas.non_refundable_storage_fee,
+            actual_gas.non_refundable_storage_fee
+        );
+        record!(
+            "Net Gas Usage",
+            expected_gas.net_gas_usage(),
+            actual_gas.net_gas_usage()
+        );
+        record!(
+            "Objects Changed",
+            comparison.expected.object_changes().len(),
+            comparison.actual.object_changes().len()
+        );
+        builder.push_record(vec![
+            "Effects".to_string(),
+            "".to_string(),
+            if comparison.expected == comparison.actual {
+                "unchanged".to_string()
+            } else {
+                "changed".to_string()
+            },
+            "".to_string(),
+        ]);
+
+        let mut table = builder.build();
+        table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+            1,
+            TableStyle::modern().get_horizontal(),
+        )]));
+        write!(f, "\n{}\n", table)
+    }
+}
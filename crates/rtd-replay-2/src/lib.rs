@@ -3,9 +3,11 @@
 
 use crate::{
     artifacts::{Artifact, ArtifactManager},
+    checkpoint_replay::CheckpointReplayArgs,
     displays::Pretty,
     replay_txn::replay_transaction,
     summary_metrics::TotalMetrics,
+    what_if::{ReplayConfigWhatIf, WhatIf, WhatIfComparison, WhatIfStore},
 };
 use anyhow::{Result, anyhow, bail};
 use clap::{Parser, ValueEnum};
@@ -22,20 +24,24 @@ use std::{
 use rtd_config::rtd_config_dir;
 use rtd_data_store::{
     Node, ReadDataStore, SetupStore, StoreSummary,
-    stores::{DataStore, FileSystemStore, InMemoryStore, ReadThroughStore},
+    stores::{BundleStore, DataStore, FileSystemStore, InMemoryStore, ReadThroughStore},
 };
 use rtd_json_rpc_types::RtdTransactionBlockEffects;
 use rtd_types::effects::TransactionEffects;
 // Disambiguate external tracing crate from local `crate::tracing` module using absolute path.
 use ::tracing::{Instrument, debug, error, info_span, warn};
 
+pub mod archive;
 pub mod artifacts;
+pub mod bundle;
+pub mod checkpoint_replay;
 pub mod displays;
 pub mod execution;
 pub mod package_tools;
 pub mod replay_txn;
 pub mod summary_metrics;
 pub mod tracing;
+pub mod what_if;
 
 const DEFAULT_OUTPUT_DIR: &str = ".replay";
 const CONFIG_FILE_NAME: &str = "replay.toml";
@@ -60,6 +66,8 @@ pub struct Config {
     pub replay_stable: ReplayConfigStable,
     #[command(flatten)]
     pub replay_experimental: ReplayConfigExperimental,
+    #[command(flatten)]
+    pub replay_what_if: ReplayConfigWhatIf,
 }
 
 /// Subcommands for the replay tool
@@ -117,6 +125,31 @@ pub enum Command {
         #[arg(short = 'n', long = "node", default_value = "mainnet")]
         node: Node,
     },
+
+    /// Replay a range of checkpoints from a local checkpoint archive and report divergences
+    ReplayCheckpoints(CheckpointReplayArgs),
+
+    /// Capture everything needed to replay a transaction offline into a bundle file
+    ExportBundle {
+        /// Transaction digest to capture
+        #[arg(long = "digest", short)]
+        digest: String,
+
+        /// Output path for the bundle file
+        #[arg(short = 'o', long = "output")]
+        output_path: PathBuf,
+
+        /// RPC of the fullnode used to fetch the transaction data
+        #[arg(short = 'n', long = "node", default_value = "mainnet")]
+        node: Node,
+    },
+
+    /// Import a bundle file into the file system cache of the node it was captured from
+    ImportBundle {
+        /// Path to the bundle file
+        #[arg(long = "bundle")]
+        bundle_path: PathBuf,
+    },
 }
 
 /// Arguments for replay (used for both CLI and config file)
@@ -217,6 +250,11 @@ pub struct ReplayConfigExperimental {
     /// Cache executors across transactions within the same epoch.
     #[arg(long = "cache-executor", default_value = "false")]
     pub cache_executor: bool,
+
+    /// Replay from a bundle file written by `export-bundle`, without network access.
+    /// The store mode and node are ignored, and the digest defaults to the bundle's transaction.
+    #[arg(long = "bundle")]
+    pub bundle: Option<PathBuf>,
 }
 
 impl Default for ReplayConfigExperimental {
@@ -227,6 +265,7 @@ impl Default for ReplayConfigExperimental {
             store_mode: StoreMode::GqlOnly,
             track_time: false,
             cache_executor: false,
+            bundle: None,
         }
     }
 }
@@ -324,6 +363,7 @@ pub fn merge_configs(
 pub async fn handle_replay_config(
     stable_config: &ReplayConfigStableInternal,
     experimental_config: &ReplayConfigExperimental,
+    what_if: &WhatIf,
     version: &str,
 ) -> Result<PathBuf> {
     let ReplayConfigStableInternal {
@@ -343,6 +383,7 @@ pub async fn handle_replay_config(
         store_mode,
         track_time,
         cache_executor,
+        bundle,
     } = experimental_config;
 
     let output_root_dir = if let Some(dir) = output_dir {
@@ -388,6 +429,25 @@ pub async fn handle_replay_config(
 
     debug!("Binary version: {version}");
 
+    if let Some(bundle_path) = bundle {
+        let bundle_store = BundleStore::load(bundle_path)?;
+        run_replay(
+            &bundle_store,
+            &output_root_dir,
+            &digests,
+            &bundle_store.node(),
+            *overwrite_existing,
+            *trace,
+            *verbose,
+            terminate_early,
+            *track_time,
+            *cache_executor,
+            what_if,
+        )
+        .await?;
+        return Ok(output_root_dir);
+    }
+
     // Build the selected data store and run replay
     match store_mode {
         StoreMode::GqlOnly => {
@@ -404,6 +464,7 @@ pub async fn handle_replay_config(
                 terminate_early,
                 *track_time,
                 *cache_executor,
+                what_if,
             )
             .await?;
         }
@@ -424,6 +485,7 @@ pub async fn handle_replay_config(
                 terminate_early,
                 *track_time,
                 *cache_executor,
+                what_if,
             )
             .await?;
         }
@@ -441,6 +503,7 @@ pub async fn handle_replay_config(
                 terminate_early,
                 *track_time,
                 *cache_executor,
+                what_if,
             )
             .await?;
         }
@@ -460,6 +523,7 @@ pub async fn handle_replay_config(
                 terminate_early,
                 *track_time,
                 *cache_executor,
+                what_if,
             )
             .await?;
         }
@@ -482,6 +546,7 @@ pub async fn handle_replay_config(
                 terminate_early,
                 *track_time,
                 *cache_executor,
+                what_if,
             )
             .await?;
         }
@@ -501,6 +566,7 @@ async fn run_replay<S>(
     terminate_early: bool,
     track_time: bool,
     cache_executor: bool,
+    what_if: &WhatIf,
 ) -> Result<()>
 where
     S: ReadDataStore + StoreSummary + SetupStore,
@@ -508,6 +574,7 @@ where
     use crate::replay_txn::ExecutorProvider;
     use std::time::Instant;
 
+    let data_store = &WhatIfStore::new(data_store, what_if);
     data_store.setup(None)?;
     let mut total_metrics = TotalMetrics::new();
     let mut executor_provider = ExecutorProvider::new(cache_executor);
@@ -557,6 +624,12 @@ where
         };
 
         tx_spinner.println(format!("Executed transaction {}{}", tx_digest, time_info));
+        if success
+            && what_if.is_active()
+            && let Ok(comparison) = WhatIfComparison::from_artifacts(&artifact_manager)
+        {
+            tx_spinner.println(format!("  {}", comparison.summary_line()));
+        }
 
         match result {
             Err(e) if terminate_early => {
@@ -651,6 +724,19 @@ pub fn print_effects_or_fork<W: Write>(
     Ok(())
 }
 
+/// Print the on-chain and "what-if" status and gas of a replayed transaction side by side.
+pub fn print_what_if_comparison<W: Write>(
+    digest: &str,
+    output_root: &Path,
+    w: &mut W,
+) -> Result<()> {
+    let output_dir = output_root.join(digest);
+    let manager = ArtifactManager::new(&output_dir, false)?;
+    let comparison = WhatIfComparison::from_artifacts(&manager)?;
+    writeln!(w, "What-if replay of {digest}\n{}", Pretty(&comparison))?;
+    Ok(())
+}
+
 /// Utility to diff `TransactionEffect` in a human readable format
 pub fn diff_effects(
     expected_effect: &TransactionEffects,
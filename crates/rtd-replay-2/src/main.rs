@@ -1,15 +1,21 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use clap::*;
 use core::panic;
 use std::str::FromStr;
 use rtd_replay_2::{
-    Command, Config, handle_replay_config, load_config_file, merge_configs,
+    Command, Config,
+    bundle::{export_bundle, import_bundle},
+    checkpoint_replay::handle_checkpoint_replay,
+    displays::Pretty,
+    handle_replay_config, load_config_file, merge_configs,
     package_tools::{extract_package, overwrite_package, rebuild_package},
-    print_effects_or_fork,
+    print_effects_or_fork, print_what_if_comparison,
+    what_if::WhatIf,
 };
+use rtd_data_store::stores::ReplayBundle;
 use rtd_types::base_types::ObjectID;
 
 // Define the `GIT_REVISION` and `VERSION` consts
@@ -68,6 +74,55 @@ async fn main() -> Result<()> {
 
                 overwrite_package(node.clone(), object_id, package_path.clone())?;
 
+                return Ok(());
+            }
+            Command::ReplayCheckpoints(args) => {
+                let file_config = load_config_file()?;
+                let stable_config = merge_configs(config.replay_stable.clone(), file_config);
+                let what_if =
+                    WhatIf::new(&config.replay_what_if, &config.replay_experimental.node)?;
+
+                let summary = handle_checkpoint_replay(
+                    args,
+                    &stable_config,
+                    &config.replay_experimental,
+                    &what_if,
+                    VERSION,
+                )
+                .await?;
+                println!("{}", Pretty(&summary));
+                if !summary.is_clean() {
+                    bail!(
+                        "{} transactions diverged and {} failed to replay",
+                        summary.total.diverged_count,
+                        summary.total.error_count
+                    );
+                }
+
+                return Ok(());
+            }
+            Command::ExportBundle {
+                digest,
+                output_path,
+                node,
+            } => {
+                let bundle = export_bundle(node.clone(), digest, output_path, VERSION)?;
+                println!(
+                    "Exported transaction {digest} to {} ({} objects, {} epochs)",
+                    output_path.display(),
+                    bundle.objects.len(),
+                    bundle.epochs.len()
+                );
+
+                return Ok(());
+            }
+            Command::ImportBundle { bundle_path } => {
+                let bundle = import_bundle(bundle_path)?;
+                println!(
+                    "Imported transaction {} into the {} cache",
+                    bundle.tx_digest, bundle.network
+                );
+
                 return Ok(());
             }
         }
@@ -75,10 +130,33 @@ async fn main() -> Result<()> {
 
     // Handle regular replay mode
     let file_config = load_config_file()?;
-    let stable_config = merge_configs(config.replay_stable, file_config);
+    let mut stable_config = merge_configs(config.replay_stable, file_config);
+    let bundle = config
+        .replay_experimental
+        .bundle
+        .as_deref()
+        .map(ReplayBundle::read)
+        .transpose()?;
+    // A bundle replays the transaction it was captured for unless told otherwise, on the node it
+    // was captured from.
+    let node = match &bundle {
+        Some(bundle) => {
+            if stable_config.digest.is_none() && stable_config.digests_path.is_none() {
+                stable_config.digest = Some(bundle.tx_digest.clone());
+            }
+            bundle.node()
+        }
+        None => config.replay_experimental.node.clone(),
+    };
+    let what_if = WhatIf::new(&config.replay_what_if, &node)?;
 
-    let output_root =
-        handle_replay_config(&stable_config, &config.replay_experimental, VERSION).await?;
+    let output_root = handle_replay_config(
+        &stable_config,
+        &config.replay_experimental,
+        &what_if,
+        VERSION,
+    )
+    .await?;
 
     if let Some(digest) = &stable_config.digest {
         print_effects_or_fork(
@@ -87,6 +165,9 @@ async fn main() -> Result<()> {
             stable_config.show_effects,
             &mut std::io::stdout(),
         )?;
+        if what_if.is_active() {
+            print_what_if_comparison(digest, &output_root, &mut std::io::stdout())?;
+        }
     }
     Ok(())
 }
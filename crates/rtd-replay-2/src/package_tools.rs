@@ -224,37 +224,15 @@ impl PackageRebuilder {
 
     /// Main entry point to rebuild a package
     pub fn rebuild(&self) -> Result<()> {
-        // Step 1: Extract original package from file system store
-        let (original_object, extracted_version) = self.package_info.extract_original_package()?;
+        let (original_object, rebuilt_object, version) = self.rebuild_object()?;
 
-        // Step 2: Get metadata from original package
-        let (original_package, tx_digest, version) =
-            self.extract_package_metadata(&original_object)?;
-
-        if extracted_version != version.value() {
-            bail!(
-                "Extracted version {} does not actual package match version {}",
-                extracted_version,
-                version
-            );
-        }
-
-        // Step 3: Compile the new source code with the original package ID
-        let compiled_modules = self.compile_package_with_id()?;
-
-        // Step 4: Reconstruct the package with new modules but original metadata
-        let rebuilt_package = self.rebuild_package(original_package, compiled_modules, version)?;
-
-        // Step 5: Create the object wrapper
-        let rebuilt_object = Object::new_from_package(rebuilt_package, tx_digest);
-
-        // Step 6: Serialize and save (either to file or cache)
+        // Serialize and save (either to file or cache)
         match &self.output_path {
             Some(output_path) => {
                 // Save to specified file
                 PackageInfo::save_package_to_file(&rebuilt_object, output_path)?;
 
-                // Step 7: Verify if source unchanged (for testing)
+                // Verify if source unchanged (for testing)
                 self.verify_rebuild(&original_object, &rebuilt_object)?;
 
                 println!(
@@ -268,7 +246,7 @@ impl PackageRebuilder {
                     .package_info
                     .save_package_to_cache(&rebuilt_object, version)?;
 
-                // Step 7: Verify if source unchanged (for testing)
+                // Verify if source unchanged (for testing)
                 self.verify_rebuild(&original_object, &rebuilt_object)?;
 
                 println!("Package rebuilt and updated in cache at: {:?}", cache_path);
@@ -278,6 +256,36 @@ impl PackageRebuilder {
         Ok(())
     }
 
+    /// Rebuild the package without saving it.
+    /// Returns the original package object, the rebuilt one, and their version.
+    pub fn rebuild_object(&self) -> Result<(Object, Object, SequenceNumber)> {
+        // Step 1: Extract original package from file system store
+        let (original_object, extracted_version) = self.package_info.extract_original_package()?;
+
+        // Step 2: Get metadata from original package
+        let (original_package, tx_digest, version) =
+            self.extract_package_metadata(&original_object)?;
+
+        if extracted_version != version.value() {
+            bail!(
+                "Extracted version {} does not actual package match version {}",
+                extracted_version,
+                version
+            );
+        }
+
+        // Step 3: Compile the new source code with the original package ID
+        let compiled_modules = self.compile_package_with_id()?;
+
+        // Step 4: Reconstruct the package with new modules but original metadata
+        let rebuilt_package = self.rebuild_package(original_package, compiled_modules, version)?;
+
+        // Step 5: Create the object wrapper
+        let rebuilt_object = Object::new_from_package(rebuilt_package, tx_digest);
+
+        Ok((original_object, rebuilt_object, version))
+    }
+
     /// Extract metadata from the original package object
     fn extract_package_metadata(
         &self,
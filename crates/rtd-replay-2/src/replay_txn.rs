@@ -223,7 +223,7 @@ pub(crate) async fn replay_transaction<S: ReadDataStore>(
     Ok(exec_ms)
 }
 
-fn verify_txn_and_save_effects(
+pub(crate) fn verify_txn_and_save_effects(
     artifact_manager: &ArtifactManager<'_>,
     expected_effects: &TransactionEffects,
     effects: &TransactionEffects,
@@ -1,6 +1,11 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::checkpoint_replay::{ReplayOutcome, TransactionDivergence};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use rtd_types::base_types::ObjectID;
+
 /// Accumulator for total metrics across all transactions in a replay run.
 #[derive(Debug, Default, Clone)]
 pub struct TotalMetrics {
@@ -28,3 +33,189 @@ impl TotalMetrics {
         self.exec_ms += exec_ms;
     }
 }
+
+/// Divergence counts for a set of transactions replayed by `replay-checkpoints`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DivergenceMetrics {
+    pub tx_count: u64,
+    pub matched_count: u64,
+    pub diverged_count: u64,
+    /// Diverged transactions none of whose dependencies diverged or failed to replay. Divergences
+    /// downstream of another divergence or error are usually a consequence of it.
+    pub root_divergence_count: u64,
+    pub error_count: u64,
+    /// Sum over all transactions of the replayed net gas usage minus the expected one.
+    pub net_gas_delta: i64,
+}
+
+impl DivergenceMetrics {
+    fn add_transaction(&mut self, tx: &TransactionDivergence) {
+        self.tx_count += 1;
+        match tx.outcome {
+            ReplayOutcome::Match => self.matched_count += 1,
+            ReplayOutcome::Diverged => {
+                self.diverged_count += 1;
+                if tx.diverged_dependencies.is_empty() {
+                    self.root_divergence_count += 1;
+                }
+            }
+            ReplayOutcome::Error => self.error_count += 1,
+        }
+        if let Some(gas) = &tx.gas {
+            self.net_gas_delta += gas.net_gas_delta;
+        }
+    }
+
+    /// Whether every transaction replayed to the expected effects.
+    pub fn is_clean(&self) -> bool {
+        self.diverged_count == 0 && self.error_count == 0
+    }
+}
+
+/// Aggregation of a divergence report, overall, per package and per Move function called.
+/// A transaction counts once towards each package and function it calls.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DivergenceSummary {
+    pub total: DivergenceMetrics,
+    pub per_package: BTreeMap<ObjectID, DivergenceMetrics>,
+    /// Keyed by `package::module::function`.
+    pub per_function: BTreeMap<String, DivergenceMetrics>,
+}
+
+impl DivergenceSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accumulate the outcome of a single transaction replay.
+    pub fn add_transaction(&mut self, tx: &TransactionDivergence) {
+        self.total.add_transaction(tx);
+
+        let packages: BTreeSet<_> = tx.move_calls.iter().map(|call| call.package).collect();
+        for package in packages {
+            self.per_package
+                .entry(package)
+                .or_default()
+                .add_transaction(tx);
+        }
+
+        let functions: BTreeSet<_> = tx.move_calls.iter().map(|call| call.to_string()).collect();
+        for function in functions {
+            self.per_function
+                .entry(function)
+                .or_default()
+                .add_transaction(tx);
+        }
+    }
+
+    /// The go/no-go signal: whether the whole range replayed to the expected effects.
+    pub fn is_clean(&self) -> bool {
+        self.total.is_clean()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint_replay::{GasDelta, MoveFunction};
+    use rtd_types::digests::TransactionDigest;
+    use rtd_types::gas::GasCostSummary;
+
+    fn call(package: &str, module: &str, function: &str) -> MoveFunction {
+        MoveFunction {
+            package: ObjectID::from_hex_literal(package).unwrap(),
+            module: module.to_string(),
+            function: function.to_string(),
+        }
+    }
+
+    fn tx(
+        outcome: ReplayOutcome,
+        move_calls: Vec<MoveFunction>,
+        diverged_dependencies: usize,
+        net_gas_delta: Option<i64>,
+    ) -> TransactionDivergence {
+        TransactionDivergence {
+            digest: TransactionDigest::random(),
+            checkpoint: 0,
+            outcome,
+            move_calls,
+            diverged_dependencies: (0..diverged_dependencies)
+                .map(|_| TransactionDigest::random())
+                .collect(),
+            expected_status: None,
+            actual_status: None,
+            gas: net_gas_delta.map(|net_gas_delta| GasDelta {
+                expected: GasCostSummary::default(),
+                actual: GasCostSummary::default(),
+                net_gas_delta,
+            }),
+            first_divergent_object: None,
+            effects_diff: None,
+            error: None,
+        }
+    }
+
+    /// Transactions, matched, diverged, root divergences, errors, and net gas delta.
+    fn counts(metrics: &DivergenceMetrics) -> (u64, u64, u64, u64, u64, i64) {
+        (
+            metrics.tx_count,
+            metrics.matched_count,
+            metrics.diverged_count,
+            metrics.root_divergence_count,
+            metrics.error_count,
+            metrics.net_gas_delta,
+        )
+    }
+
+    #[test]
+    fn test_divergence_summary() {
+        let f = call("0xa", "m", "f");
+        let g = call("0xa", "m", "g");
+        let h = call("0xb", "n", "h");
+
+        let mut summary = DivergenceSummary::new();
+        assert!(summary.is_clean());
+
+        // Calling the same function twice counts once.
+        summary.add_transaction(&tx(
+            ReplayOutcome::Match,
+            vec![f.clone(), f.clone()],
+            0,
+            Some(0),
+        ));
+        summary.add_transaction(&tx(
+            ReplayOutcome::Diverged,
+            vec![g.clone(), h.clone()],
+            0,
+            Some(10),
+        ));
+        summary.add_transaction(&tx(ReplayOutcome::Diverged, vec![h.clone()], 1, Some(-3)));
+        summary.add_transaction(&tx(ReplayOutcome::Error, vec![f.clone()], 0, None));
+        summary.add_transaction(&tx(ReplayOutcome::Match, vec![], 0, Some(0)));
+
+        assert!(!summary.is_clean());
+        assert_eq!(counts(&summary.total), (5, 2, 2, 1, 1, 7));
+
+        assert_eq!(summary.per_package.len(), 2);
+        assert_eq!(
+            counts(&summary.per_package[&f.package]),
+            (3, 1, 1, 1, 1, 10)
+        );
+        assert_eq!(counts(&summary.per_package[&h.package]), (2, 0, 2, 1, 0, 7));
+
+        assert_eq!(summary.per_function.len(), 3);
+        assert_eq!(
+            counts(&summary.per_function[&f.to_string()]),
+            (2, 1, 0, 0, 1, 0)
+        );
+        assert_eq!(
+            counts(&summary.per_function[&g.to_string()]),
+            (1, 0, 1, 1, 0, 10)
+        );
+        assert_eq!(
+            counts(&summary.per_function[&h.to_string()]),
+            (2, 0, 2, 1, 0, 7)
+        );
+    }
+}
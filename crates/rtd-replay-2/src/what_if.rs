@@ -0,0 +1,472 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! "What-if" replay: replay historical transactions under a different protocol version, with
+//! protocol config attributes or feature flags overridden, and with packages substituted (e.g.
+//! a locally patched framework).
+//!
+//! Overrides are applied by `WhatIfStore`, which wraps the data store used for replay: it
+//! rewrites the `ProtocolConfig` returned for an epoch and returns the substituted packages
+//! in place of the on-chain ones. The rest of replay is unchanged, so that a transaction whose
+//! effects change under the overrides is reported as forked, and the on-chain and replayed
+//! effects can be compared.
+
+use crate::{
+    artifacts::{Artifact, ArtifactManager},
+    package_tools::PackageRebuilder,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Args;
+use move_package_alt::schema::EnvironmentName;
+use std::{collections::BTreeMap, fs, io::Write, path::PathBuf, str::FromStr};
+use rtd_data_store::{
+    EpochData, EpochStore, Node, ObjectKey, ObjectStore, SetupStore, StoreSummary, TransactionInfo,
+    TransactionStore,
+};
+use rtd_protocol_config::ProtocolConfigOptional;
+use rtd_types::{
+    base_types::ObjectID,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    execution_status::ExecutionStatus,
+    object::Object,
+    supported_protocol_versions::{Chain, ProtocolConfig, ProtocolVersion},
+};
+use tracing::info;
+
+/// Overrides for "what-if" replay.
+#[derive(Args, Clone, Debug, Default)]
+pub struct ReplayConfigWhatIf {
+    /// Replay under this protocol version instead of the one in effect when the transaction was
+    /// executed.
+    #[arg(long = "protocol-version")]
+    pub protocol_version: Option<u64>,
+
+    /// Override a protocol config attribute or feature flag, as `<name>=<value>`.
+    /// May be repeated.
+    #[arg(long = "protocol-config-override", value_name = "NAME=VALUE")]
+    pub protocol_config_overrides: Vec<String>,
+
+    /// Substitute a package, as `<package-id>=<path>`. The path is either a package file (as
+    /// written by `rebuild-package -o` or `extract-package`) or a package source directory, which
+    /// is rebuilt against the cached on-chain package. May be repeated.
+    #[arg(long = "package-override", value_name = "PKG_ID=PATH")]
+    pub package_overrides: Vec<String>,
+
+    /// Environment used to rebuild packages substituted from source.
+    #[arg(long = "package-build-env", default_value = "mainnet")]
+    pub package_build_env: EnvironmentName,
+}
+
+/// Resolved "what-if" overrides.
+#[derive(Clone, Debug, Default)]
+pub struct WhatIf {
+    chain: Chain,
+    protocol_version: Option<ProtocolVersion>,
+    attributes: serde_json::Map<String, serde_json::Value>,
+    feature_flags: BTreeMap<String, bool>,
+    packages: BTreeMap<ObjectID, Object>,
+}
+
+impl WhatIf {
+    /// Parse and validate the overrides in `config`, rebuilding packages substituted from source.
+    pub fn new(config: &ReplayConfigWhatIf, node: &Node) -> Result<Self> {
+        let chain = node.chain();
+
+        let protocol_version = config
+            .protocol_version
+            .map(|version| {
+                let version = ProtocolVersion::new(version);
+                if version < ProtocolVersion::MIN || version > ProtocolVersion::MAX_ALLOWED {
+                    bail!(
+                        "Protocol version {} is not supported by this binary (supported: {}..={})",
+                        version.as_u64(),
+                        ProtocolVersion::MIN.as_u64(),
+                        ProtocolVersion::MAX_ALLOWED.as_u64(),
+                    );
+                }
+                Ok(version)
+            })
+            .transpose()?;
+
+        // Names are the same at every protocol version, so any config can be used to check them.
+        let reference = ProtocolConfig::get_for_version(ProtocolVersion::MAX, chain);
+        let known_attributes = reference.attr_map();
+        let known_feature_flags = reference.feature_map();
+        let mut attributes = serde_json::Map::new();
+        let mut feature_flags = BTreeMap::new();
+        for entry in &config.protocol_config_overrides {
+            let (name, value) = split_override(entry, "--protocol-config-override")?;
+            if known_feature_flags.contains_key(name) {
+                let value = bool::from_str(value).map_err(|_| {
+                    anyhow!("Feature flag {name} must be true or false, got {value}")
+                })?;
+                feature_flags.insert(name.to_string(), value);
+            } else if known_attributes.contains_key(name) {
+                // Values that are not valid JSON (e.g. unquoted strings) are taken as strings.
+                let value = serde_json::from_str(value)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+                attributes.insert(name.to_string(), value);
+            } else {
+                bail!("Unknown protocol config attribute or feature flag: {name}");
+            }
+        }
+        // Check the values have the right types up front, rather than on first use.
+        serde_json::from_value::<ProtocolConfigOptional>(attributes.clone().into())
+            .context("Invalid protocol config override")?;
+
+        let mut packages = BTreeMap::new();
+        for entry in &config.package_overrides {
+            let (package_id, path) = split_override(entry, "--package-override")?;
+            let package_id = ObjectID::from_str(package_id)
+                .map_err(|e| anyhow!("Invalid package ID {package_id}: {e}"))?;
+            let package = load_package(package_id, PathBuf::from(path), node, config)?;
+            packages.insert(package_id, package);
+        }
+
+        Ok(Self {
+            chain,
+            protocol_version,
+            attributes,
+            feature_flags,
+            packages,
+        })
+    }
+
+    /// Whether any override is set.
+    pub fn is_active(&self) -> bool {
+        self.protocol_version.is_some()
+            || !self.attributes.is_empty()
+            || !self.feature_flags.is_empty()
+            || !self.packages.is_empty()
+    }
+
+    /// The protocol config to replay with, given the historical one.
+    pub fn protocol_config(&self, historical: ProtocolConfig) -> Result<ProtocolConfig> {
+        let mut config = match self.protocol_version {
+            Some(version) => ProtocolConfig::get_for_version(version, self.chain),
+            None => historical,
+        };
+        if !self.attributes.is_empty() {
+            serde_json::from_value::<ProtocolConfigOptional>(self.attributes.clone().into())
+                .context("Invalid protocol config override")?
+                .apply_to(&mut config);
+        }
+        for (name, value) in &self.feature_flags {
+            config
+                .set_feature(name, *value)
+                .ok_or_else(|| anyhow!("Unknown feature flag: {name}"))?;
+        }
+        Ok(config)
+    }
+}
+
+fn split_override<'a>(entry: &'a str, flag: &str) -> Result<(&'a str, &'a str)> {
+    entry
+        .split_once('=')
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .ok_or_else(|| anyhow!("{flag} must be of the form <name>=<value>, got {entry}"))
+}
+
+fn load_package(
+    package_id: ObjectID,
+    path: PathBuf,
+    node: &Node,
+    config: &ReplayConfigWhatIf,
+) -> Result<Object> {
+    let package = if path.is_dir() {
+        let rebuilder = PackageRebuilder::new(
+            node.clone(),
+            package_id,
+            path.clone(),
+            None,
+            config.package_build_env.clone(),
+        );
+        let (_, package, _) = rebuilder
+            .rebuild_object()
+            .with_context(|| format!("Failed to rebuild package {package_id}"))?;
+        package
+    } else {
+        let bytes = fs::read(&path)
+            .with_context(|| format!("Failed to read package file {}", path.display()))?;
+        bcs::from_bytes::<Object>(&bytes)
+            .with_context(|| format!("Invalid package file {}", path.display()))?
+    };
+
+    if !package.is_package() || package.id() != package_id {
+        bail!(
+            "{} does not contain package {package_id} (found object {})",
+            path.display(),
+            package.id()
+        );
+    }
+    info!(
+        "Substituting package {package_id} (version {}) from {}",
+        package.version(),
+        path.display()
+    );
+    Ok(package)
+}
+
+/// A data store applying `WhatIf` overrides to the data store it wraps.
+pub struct WhatIfStore<'a, S> {
+    store: &'a S,
+    what_if: &'a WhatIf,
+}
+
+impl<'a, S> WhatIfStore<'a, S> {
+    pub fn new(store: &'a S, what_if: &'a WhatIf) -> Self {
+        Self { store, what_if }
+    }
+}
+
+impl<S: TransactionStore> TransactionStore for WhatIfStore<'_, S> {
+    fn transaction_data_and_effects(&self, tx_digest: &str) -> Result<Option<TransactionInfo>> {
+        self.store.transaction_data_and_effects(tx_digest)
+    }
+}
+
+impl<S: EpochStore> EpochStore for WhatIfStore<'_, S> {
+    fn epoch_info(&self, epoch: u64) -> Result<Option<EpochData>> {
+        self.store.epoch_info(epoch)
+    }
+
+    fn protocol_config(&self, epoch: u64) -> Result<Option<ProtocolConfig>> {
+        self.store
+            .protocol_config(epoch)?
+            .map(|config| self.what_if.protocol_config(config))
+            .transpose()
+    }
+}
+
+impl<S: ObjectStore> ObjectStore for WhatIfStore<'_, S> {
+    fn get_objects(&self, keys: &[ObjectKey]) -> Result<Vec<Option<(Object, u64)>>> {
+        if self.what_if.packages.is_empty() {
+            return self.store.get_objects(keys);
+        }
+
+        // Substituted packages are returned whatever the version queried.
+        let remaining = keys
+            .iter()
+            .filter(|key| !self.what_if.packages.contains_key(&key.object_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut fetched = self.store.get_objects(&remaining)?.into_iter();
+        Ok(keys
+            .iter()
+            .map(|key| match self.what_if.packages.get(&key.object_id) {
+                Some(package) => Some((package.clone(), package.version().value())),
+                None => fetched.next().flatten(),
+            })
+            .collect())
+    }
+}
+
+impl<S: SetupStore> SetupStore for WhatIfStore<'_, S> {
+    fn setup(&self, chain_id: Option<String>) -> Result<Option<String>> {
+        self.store.setup(chain_id)
+    }
+}
+
+impl<S: StoreSummary> StoreSummary for WhatIfStore<'_, S> {
+    fn summary<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.store.summary(writer)
+    }
+}
+
+/// The on-chain and "what-if" effects of a transaction, for side-by-side display.
+pub struct WhatIfComparison {
+    pub expected: TransactionEffects,
+    pub actual: TransactionEffects,
+}
+
+impl WhatIfComparison {
+    /// Read the effects saved by the replay of a transaction. The replayed effects are only saved
+    /// separately if they differ from the on-chain ones.
+    pub fn from_artifacts(manager: &ArtifactManager<'_>) -> Result<Self> {
+        let expected = manager
+            .member(Artifact::TransactionEffects)
+            .try_get_transaction_effects()
+            .ok_or_else(|| {
+                anyhow!("No transaction effects in {}", manager.base_path.display())
+            })??;
+        let actual = manager
+            .member(Artifact::ForkedTransactionEffects)
+            .try_get_transaction_effects()
+            .transpose()?
+            .unwrap_or_else(|| expected.clone());
+        Ok(Self { expected, actual })
+    }
+
+    /// A one line summary of how status and gas changed.
+    pub fn summary_line(&self) -> String {
+        let expected_gas = self.expected.gas_cost_summary().net_gas_usage();
+        let actual_gas = self.actual.gas_cost_summary().net_gas_usage();
+        format!(
+            "status: {} -> {}, net gas: {} -> {} ({:+}), effects {}",
+            status_label(self.expected.status()),
+            status_label(self.actual.status()),
+            expected_gas,
+            actual_gas,
+            actual_gas - expected_gas,
+            if self.expected == self.actual {
+                "unchanged"
+            } else {
+                "changed"
+            },
+        )
+    }
+}
+
+/// A short description of an execution status.
+pub(crate) fn status_label(status: &ExecutionStatus) -> String {
+    match status {
+        ExecutionStatus::Success => "success".to_string(),
+        ExecutionStatus::Failure { error, command } => match command {
+            Some(command) => format!("failure ({error:?} in command {command})"),
+            None => format!("failure ({error:?})"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtd_data_store::{VersionQuery, stores::InMemoryStore};
+    use rtd_types::{base_types::SequenceNumber, object::Owner};
+
+    fn what_if(protocol_version: Option<u64>, overrides: &[&str]) -> Result<WhatIf> {
+        let config = ReplayConfigWhatIf {
+            protocol_version,
+            protocol_config_overrides: overrides.iter().map(|o| o.to_string()).collect(),
+            ..Default::default()
+        };
+        WhatIf::new(&config, &Node::Mainnet)
+    }
+
+    fn object(id: &str, version: u64) -> Object {
+        Object::with_id_owner_version_for_testing(
+            ObjectID::from_hex_literal(id).unwrap(),
+            SequenceNumber::from_u64(version),
+            Owner::Immutable,
+        )
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_overrides() {
+        let err = what_if(None, &["no_such_attribute=1"]).unwrap_err();
+        assert!(err.to_string().contains("Unknown protocol config"), "{err}");
+
+        let err = what_if(None, &["package_upgrades=yes"]).unwrap_err();
+        assert!(err.to_string().contains("must be true or false"), "{err}");
+
+        let err = what_if(None, &["max_tx_gas=lots"]).unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid protocol config override"),
+            "{err}"
+        );
+
+        let err = what_if(None, &["max_tx_gas"]).unwrap_err();
+        assert!(err.to_string().contains("<name>=<value>"), "{err}");
+
+        let unsupported = ProtocolVersion::MAX_ALLOWED.as_u64() + 1;
+        let err = what_if(Some(unsupported), &[]).unwrap_err();
+        assert!(err.to_string().contains("is not supported"), "{err}");
+
+        assert!(!what_if(None, &[]).unwrap().is_active());
+    }
+
+    #[test]
+    fn test_protocol_config_overrides() {
+        let historical = ProtocolConfig::get_for_version(ProtocolVersion::MAX, Chain::Mainnet);
+        let upgrades = historical.feature_map()["package_upgrades"];
+
+        let overrides = what_if(
+            None,
+            &[
+                "max_tx_gas = 12345",
+                &format!("package_upgrades={}", !upgrades),
+            ],
+        )
+        .unwrap();
+        assert!(overrides.is_active());
+
+        let config = overrides.protocol_config(historical.clone()).unwrap();
+        assert_eq!(config.version, historical.version);
+        assert_eq!(config.max_tx_gas(), 12345);
+        assert_eq!(config.feature_map()["package_upgrades"], !upgrades);
+
+        // Everything that is not overridden is left as it was.
+        let mut attributes = config.attr_map();
+        let mut expected = historical.attr_map();
+        attributes.remove("max_tx_gas");
+        expected.remove("max_tx_gas");
+        assert_eq!(attributes, expected);
+        let mut features = config.feature_map();
+        let mut expected = historical.feature_map();
+        features.remove("package_upgrades");
+        expected.remove("package_upgrades");
+        assert_eq!(features, expected);
+
+        // Overrides are applied on top of the requested protocol version.
+        let overrides =
+            what_if(Some(ProtocolVersion::MIN.as_u64()), &["max_tx_gas=12345"]).unwrap();
+        let config = overrides.protocol_config(historical).unwrap();
+        assert_eq!(config.version, ProtocolVersion::MIN);
+        assert_eq!(config.max_tx_gas(), 12345);
+    }
+
+    #[test]
+    fn test_get_objects_with_substituted_packages() {
+        let store = InMemoryStore::new(Node::Mainnet);
+        store.add_object_data(
+            ObjectID::from_hex_literal("0xa").unwrap(),
+            1,
+            object("0xa", 1),
+        );
+        store.add_object_data(
+            ObjectID::from_hex_literal("0xb").unwrap(),
+            2,
+            object("0xb", 2),
+        );
+
+        // Substitutes need not be packages for the store to return them.
+        let substitute = object("0x5", 7);
+        let what_if = WhatIf {
+            packages: BTreeMap::from([(substitute.id(), substitute.clone())]),
+            ..Default::default()
+        };
+        let store = WhatIfStore::new(&store, &what_if);
+
+        let key = |id: &str, version| ObjectKey {
+            object_id: ObjectID::from_hex_literal(id).unwrap(),
+            version_query: VersionQuery::Version(version),
+        };
+        let objects = store
+            .get_objects(&[
+                key("0x5", 1),
+                key("0xa", 1),
+                key("0x5", 3),
+                key("0xc", 1),
+                key("0xb", 2),
+            ])
+            .unwrap();
+        let found: Vec<_> = objects
+            .iter()
+            .map(|object| {
+                object
+                    .as_ref()
+                    .map(|(object, version)| (object.id(), *version))
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                Some((substitute.id(), 7)),
+                Some((ObjectID::from_hex_literal("0xa").unwrap(), 1)),
+                Some((substitute.id(), 7)),
+                None,
+                Some((ObjectID::from_hex_literal("0xb").unwrap(), 2)),
+            ]
+        );
+    }
+}
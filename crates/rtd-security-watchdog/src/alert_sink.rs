@@ -0,0 +1,233 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::pagerduty::{Body, CreateIncident, Incident, Pagerduty, Service};
+use crate::{AlertSinkKind, SecurityWatchdogConfig};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// An alert raised by a monitoring job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    /// Identifies the condition being alerted on, so that sinks can deduplicate repeated alerts
+    /// for it.
+    pub key: String,
+    /// Name of the job that raised the alert.
+    pub job: String,
+    pub title: String,
+    pub details: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+pub trait AlertSink: Send + Sync + 'static {
+    /// Delivers `alert`, returning an error if it could not be delivered.
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()>;
+}
+
+/// Creates the alert sinks selected by `config`. Credentials that are not part of the config are
+/// read from the environment.
+pub fn make_alert_sinks(
+    config: &SecurityWatchdogConfig,
+) -> anyhow::Result<Vec<Arc<dyn AlertSink>>> {
+    let mut sinks: Vec<Arc<dyn AlertSink>> = vec![];
+    for kind in &config.alert_sink {
+        sinks.push(match kind {
+            AlertSinkKind::Pagerduty => {
+                let api_key = env::var("PD_API_KEY").context("PD_API_KEY env var must be set")?;
+                let service_id = config.pd_wallet_monitoring_service_id.clone().ok_or_else(
+                    || {
+                        anyhow!(
+                            "--pd-wallet-monitoring-service-id is required for the pagerduty sink"
+                        )
+                    },
+                )?;
+                let from = config
+                    .pd_from
+                    .clone()
+                    .ok_or_else(|| anyhow!("--pd-from is required for the pagerduty sink"))?;
+                Arc::new(PagerDutySink::new(
+                    Pagerduty::new(api_key),
+                    service_id,
+                    from,
+                ))
+            }
+            AlertSinkKind::Webhook => {
+                let url = config
+                    .webhook_url
+                    .clone()
+                    .ok_or_else(|| anyhow!("--webhook-url is required for the webhook sink"))?;
+                Arc::new(WebhookSink::new(url, env::var("WEBHOOK_TOKEN").ok()))
+            }
+            AlertSinkKind::File => {
+                let path = config
+                    .alert_file
+                    .clone()
+                    .ok_or_else(|| anyhow!("--alert-file is required for the file sink"))?;
+                Arc::new(FileSink::new(path))
+            }
+            AlertSinkKind::Log => Arc::new(LogSink),
+        });
+    }
+    Ok(sinks)
+}
+
+/// Creates a PagerDuty incident for each alert, using the alert's key as the incident key so that
+/// an alert that is still open is not raised twice.
+pub struct PagerDutySink {
+    pagerduty: Pagerduty,
+    service_id: String,
+    from: String,
+}
+
+impl PagerDutySink {
+    pub fn new(pagerduty: Pagerduty, service_id: String, from: String) -> Self {
+        Self {
+            pagerduty,
+            service_id,
+            from,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertSink for PagerDutySink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let incident = Incident {
+            title: alert.title.clone(),
+            service: Service {
+                id: self.service_id.clone(),
+                ..Default::default()
+            },
+            incident_key: alert.key.clone(),
+            body: Body {
+                details: alert.details.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        self.pagerduty
+            .create_incident(&self.from, CreateIncident { incident })
+            .await
+    }
+}
+
+/// POSTs each alert as JSON to a URL, optionally authenticating with a bearer token.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl WebhookSink {
+    pub fn new(url: String, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            token,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertSink for WebhookSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let mut request = self.client.post(&self.url).json(alert);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        if response.status().is_success() {
+            info!("Sent alert with key: {} to webhook", alert.key);
+            Ok(())
+        } else {
+            let status = response.status();
+            let text = response.text().await?;
+            Err(anyhow!("Webhook returned {}: {}", status, text))
+        }
+    }
+}
+
+/// Appends each alert to a file, as a line of JSON.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertSink for FileSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(alert)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open alert file {}", self.path.display()))?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Logs each alert as a warning.
+pub struct LogSink;
+
+#[async_trait::async_trait]
+impl AlertSink for LogSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        warn!(
+            key = %alert.key,
+            job = %alert.job,
+            "{}: {}",
+            alert.title,
+            alert.details
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.jsonl");
+        let sink = FileSink::new(path.clone());
+
+        let alert = |key: &str| Alert {
+            key: key.to_string(),
+            job: "wallet_balance".to_string(),
+            title: format!("Wallet: {key} is out of compliance"),
+            details: "Current balance: 1, Lower bound: 2".to_string(),
+            timestamp: Utc::now(),
+        };
+
+        let alerts = vec![alert("0x1"), alert("0x2")];
+        for alert in &alerts {
+            sink.send(alert).await.unwrap();
+        }
+
+        let written: Vec<Alert> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(written, alerts);
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::SecurityWatchdogConfig;
+use crate::query_runner::{QueryRunner, Row, as_f64};
+use anyhow::{Context, anyhow};
+use duckdb::Connection;
+use duckdb::types::ValueRef;
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Runs queries against an embedded DuckDB database. Besides the tables in the database itself,
+/// queries can read local Parquet files directly, e.g. those written by `rtd-analytics-indexer`:
+///
+/// ```sql
+/// SELECT sum(amount) FROM read_parquet('/data/transactions/**/*.parquet')
+/// ```
+pub struct DuckDbQueryRunner {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl DuckDbQueryRunner {
+    /// Opens the database at `path`, or an in-memory database if no path is given.
+    pub fn new(path: Option<&Path>) -> anyhow::Result<Self> {
+        let connection = match path {
+            Some(path) => Connection::open(path)
+                .with_context(|| format!("Failed to open DuckDB database {}", path.display()))?,
+            None => Connection::open_in_memory()?,
+        };
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    pub fn from_config(config: &SecurityWatchdogConfig) -> anyhow::Result<Self> {
+        Self::new(config.duckdb_path.as_deref())
+    }
+
+    /// Runs `query` on the blocking thread pool, returning each row's columns in order.
+    async fn query(&self, query: &str) -> anyhow::Result<Vec<Vec<(String, Box<dyn Any + Send>)>>> {
+        let connection = self.connection.clone();
+        let query = query.to_string();
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().unwrap();
+            let mut stmt = connection.prepare(&query)?;
+            let mut rows = stmt.query([])?;
+            let names = rows
+                .as_ref()
+                .ok_or_else(|| anyhow!("Query has no result set"))?
+                .column_names();
+
+            let mut result = vec![];
+            while let Some(row) = rows.next()? {
+                let mut columns = vec![];
+                for (i, name) in names.iter().enumerate() {
+                    if let Some(value) = convert(name, row.get_ref(i)?) {
+                        columns.push((name.clone(), value));
+                    }
+                }
+                result.push(columns);
+            }
+            Ok(result)
+        })
+        .await?
+    }
+}
+
+#[async_trait::async_trait]
+impl QueryRunner for DuckDbQueryRunner {
+    async fn run_single_entry(&self, query: &str) -> anyhow::Result<f64> {
+        let rows = self.query(query).await?;
+        let (_, value) = rows
+            .first()
+            .ok_or_else(|| anyhow!("No rows returned by query"))?
+            .first()
+            .ok_or_else(|| anyhow!("Result is NULL"))?;
+        as_f64(value.as_ref()).ok_or_else(|| anyhow!("Result is not numeric"))
+    }
+
+    async fn run(&self, query: &str) -> anyhow::Result<Vec<Row>> {
+        info!("Running query: {}", query);
+        let rows: Vec<Row> = self
+            .query(query)
+            .await?
+            .into_iter()
+            .map(|columns| columns.into_iter().collect::<HashMap<_, _>>())
+            .collect();
+        info!("Found {} rows", rows.len());
+        Ok(rows)
+    }
+}
+
+/// Converts a DuckDB value to the same Rust types as the other query runners produce, or `None`
+/// if it is NULL or of a type that is not supported.
+fn convert(name: &str, value: ValueRef<'_>) -> Option<Box<dyn Any + Send>> {
+    Some(match value {
+        ValueRef::Null => return None,
+        ValueRef::Boolean(v) => Box::new(v),
+        ValueRef::TinyInt(v) => Box::new(v),
+        ValueRef::SmallInt(v) => Box::new(v),
+        ValueRef::Int(v) => Box::new(v),
+        ValueRef::BigInt(v) => Box::new(v),
+        ValueRef::HugeInt(v) => Box::new(v),
+        ValueRef::UTinyInt(v) => Box::new(v),
+        ValueRef::USmallInt(v) => Box::new(v),
+        ValueRef::UInt(v) => Box::new(v),
+        ValueRef::UBigInt(v) => Box::new(v),
+        ValueRef::Float(v) => Box::new(v),
+        ValueRef::Double(v) => Box::new(v),
+        ValueRef::Decimal(v) if v.scale() == 0 => Box::new(v.mantissa()),
+        ValueRef::Decimal(v) => Box::new(v.to_string().parse::<f64>().ok()?),
+        ValueRef::Text(v) => Box::new(String::from_utf8_lossy(v).into_owned()),
+        ValueRef::Blob(v) => Box::new(format!("0x{}", hex::encode(v))),
+        other => {
+            info!(
+                "Skipping column: {}, data_type: {:?}",
+                name,
+                other.data_type()
+            );
+            return None;
+        }
+    })
+}
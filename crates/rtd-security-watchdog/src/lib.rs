@@ -1,13 +1,39 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::path::PathBuf;
 
+pub mod alert_sink;
+#[cfg(feature = "duckdb")]
+mod duckdb_query_runner;
 mod metrics;
-mod pagerduty;
-mod query_runner;
+pub mod pagerduty;
+mod postgres_query_runner;
+pub mod query_runner;
 pub mod scheduler;
 
+/// Where monitoring queries are run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum QueryRunnerKind {
+    Snowflake,
+    /// A Postgres database, e.g. one written to by `rtd-indexer-alt`.
+    Postgres,
+    /// An embedded DuckDB database, which can also query local Parquet files.
+    Duckdb,
+}
+
+/// Where alerts raised by monitoring jobs are sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AlertSinkKind {
+    Pagerduty,
+    /// POST each alert as JSON to `--webhook-url`.
+    Webhook,
+    /// Append each alert as a line of JSON to `--alert-file`.
+    File,
+    /// Log each alert.
+    Log,
+}
+
 #[derive(Parser, Clone, Debug)]
 #[clap(
     name = "Rtd Security Watchdog",
@@ -16,9 +42,38 @@ pub mod scheduler;
 )]
 pub struct SecurityWatchdogConfig {
     #[clap(long)]
-    pub pd_wallet_monitoring_service_id: String,
+    pub pd_wallet_monitoring_service_id: Option<String>,
+    /// Email address of the PagerDuty user that incidents are created on behalf of. Required for
+    /// the pagerduty alert sink.
+    #[clap(long)]
+    pub pd_from: Option<String>,
     #[clap(long)]
     pub config: PathBuf,
+    /// The database to run monitoring queries against.
+    #[clap(long, value_enum, default_value = "snowflake", global = true)]
+    pub query_runner: QueryRunnerKind,
+    /// Where to send alerts. Can be repeated, or given as a comma-separated list.
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "pagerduty",
+        global = true
+    )]
+    pub alert_sink: Vec<AlertSinkKind>,
+    /// URL of the Postgres database to query, when using the postgres query runner.
+    #[clap(long, default_value = None, global = true)]
+    pub pg_url: Option<String>,
+    /// DuckDB database file to query, when using the duckdb query runner. An in-memory database
+    /// is used if this is not set, which can still read Parquet files through `read_parquet`.
+    #[clap(long, default_value = None, global = true)]
+    pub duckdb_path: Option<PathBuf>,
+    /// URL to send alerts to, when using the webhook alert sink.
+    #[clap(long, default_value = None, global = true)]
+    pub webhook_url: Option<String>,
+    /// File to append alerts to, when using the file alert sink.
+    #[clap(long, default_value = None, global = true)]
+    pub alert_file: Option<PathBuf>,
     #[clap(long, default_value = None, global = true)]
     pub sf_account_identifier: Option<String>,
     #[clap(long, default_value = None, global = true)]
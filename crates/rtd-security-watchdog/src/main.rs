@@ -5,8 +5,9 @@ use prometheus::Registry;
 
 use anyhow::Result;
 use clap::*;
-use std::env;
 use rtd_security_watchdog::SecurityWatchdogConfig;
+use rtd_security_watchdog::alert_sink::make_alert_sinks;
+use rtd_security_watchdog::query_runner::make_query_runner;
 use rtd_security_watchdog::scheduler::SchedulerService;
 
 #[tokio::main]
@@ -16,8 +17,8 @@ async fn main() -> Result<()> {
         .init();
     env_logger::init();
     let config = SecurityWatchdogConfig::parse();
-    let pd_api_key = env::var("PD_API_KEY").expect("PD_API_KEY env var must be set");
-    let sf_password = env::var("SF_PASSWORD").expect("SF_PASSWORD env var must be set");
+    let query_runner = make_query_runner(&config)?;
+    let alert_sinks = make_alert_sinks(&config)?;
 
     let registry_service = linku_metrics::start_prometheus_server(
         format!(
@@ -36,7 +37,7 @@ async fn main() -> Result<()> {
             "N/A",
         ))
         .unwrap();
-    let service = SchedulerService::new(&config, &registry, pd_api_key, sf_password).await?;
+    let service = SchedulerService::new(&config, &registry, query_runner, alert_sinks).await?;
     service.schedule().await?;
     service.start().await?;
     tokio::signal::ctrl_c().await?;
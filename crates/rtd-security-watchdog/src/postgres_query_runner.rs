@@ -0,0 +1,230 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::SecurityWatchdogConfig;
+use crate::query_runner::{QueryRunner, Row, as_f64};
+use anyhow::{Context, anyhow};
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use tokio_postgres::types::{FromSql, Type};
+use tokio_postgres::{Client, NoTls};
+use tracing::{error, info};
+
+/// Runs queries against a Postgres database, such as the one written to by `rtd-indexer-alt`.
+///
+/// Columns are converted to the same Rust types as the Snowflake runner produces: integers and
+/// floats keep their width, `NUMERIC` becomes an `i128` (or an `f64` if it has a fractional
+/// part), text becomes a `String`, and `BYTEA` becomes a `0x`-prefixed hex `String`, so that
+/// addresses and object IDs can be compared with the other runners' output.
+pub struct PostgresQueryRunner {
+    url: String,
+}
+
+impl PostgresQueryRunner {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+
+    pub fn from_config(config: &SecurityWatchdogConfig) -> anyhow::Result<Self> {
+        let url = config
+            .pg_url
+            .as_ref()
+            .ok_or_else(|| anyhow!("--pg-url is required for the postgres query runner"))?;
+        Ok(Self::new(url))
+    }
+
+    /// Connects to the database. Monitoring queries run infrequently, so a new connection is made
+    /// for each one rather than keeping one open.
+    async fn connect(&self) -> anyhow::Result<Client> {
+        let (client, connection) = tokio_postgres::connect(&self.url, NoTls)
+            .await
+            .context("Failed to connect to Postgres")?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!("Postgres connection error: {}", e);
+            }
+        });
+        Ok(client)
+    }
+}
+
+#[async_trait::async_trait]
+impl QueryRunner for PostgresQueryRunner {
+    async fn run_single_entry(&self, query: &str) -> anyhow::Result<f64> {
+        let rows = self.connect().await?.query(query, &[]).await?;
+        let row = rows
+            .first()
+            .ok_or_else(|| anyhow!("No rows returned by query"))?;
+        let value = column_value(row, 0)?.ok_or_else(|| anyhow!("Result is NULL"))?;
+        as_f64(value.as_ref()).ok_or_else(|| anyhow!("Result is not numeric"))
+    }
+
+    async fn run(&self, query: &str) -> anyhow::Result<Vec<Row>> {
+        info!("Running query: {}", query);
+        let rows = self.connect().await?.query(query, &[]).await?;
+        let mut result = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let mut entry: Row = HashMap::new();
+            for (i, column) in row.columns().iter().enumerate() {
+                if let Some(value) = column_value(row, i)? {
+                    entry.insert(column.name().to_string(), value);
+                }
+            }
+            result.push(entry);
+        }
+        info!("Found {} rows", result.len());
+        Ok(result)
+    }
+}
+
+/// The value of column `idx` of `row`, or `None` if it is NULL or of a type that is not
+/// supported.
+fn column_value(
+    row: &tokio_postgres::Row,
+    idx: usize,
+) -> anyhow::Result<Option<Box<dyn Any + Send>>> {
+    fn boxed<T: Any + Send>(value: Option<T>) -> Option<Box<dyn Any + Send>> {
+        value.map(|v| Box::new(v) as Box<dyn Any + Send>)
+    }
+
+    let column = &row.columns()[idx];
+    let value = match *column.type_() {
+        Type::BOOL => boxed(row.try_get::<_, Option<bool>>(idx)?),
+        Type::CHAR => boxed(row.try_get::<_, Option<i8>>(idx)?),
+        Type::INT2 => boxed(row.try_get::<_, Option<i16>>(idx)?),
+        Type::INT4 => boxed(row.try_get::<_, Option<i32>>(idx)?),
+        Type::INT8 => boxed(row.try_get::<_, Option<i64>>(idx)?),
+        Type::OID => boxed(row.try_get::<_, Option<u32>>(idx)?),
+        Type::FLOAT4 => boxed(row.try_get::<_, Option<f32>>(idx)?),
+        Type::FLOAT8 => boxed(row.try_get::<_, Option<f64>>(idx)?),
+        Type::NUMERIC => match row.try_get::<_, Option<Numeric>>(idx)? {
+            Some(Numeric::Int(v)) => boxed(Some(v)),
+            Some(Numeric::Float(v)) => boxed(Some(v)),
+            None => None,
+        },
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            boxed(row.try_get::<_, Option<String>>(idx)?)
+        }
+        Type::BYTEA => boxed(
+            row.try_get::<_, Option<Vec<u8>>>(idx)?
+                .map(|bytes| format!("0x{}", hex::encode(bytes))),
+        ),
+        ref other => {
+            info!("Skipping column: {}, data_type: {}", column.name(), other);
+            None
+        }
+    };
+    Ok(value)
+}
+
+/// A Postgres `NUMERIC` value, decoded from its binary representation.
+#[derive(Debug, PartialEq)]
+enum Numeric {
+    Int(i128),
+    Float(f64),
+}
+
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+const NUMERIC_PINF: u16 = 0xD000;
+const NUMERIC_NINF: u16 = 0xF000;
+
+impl<'a> FromSql<'a> for Numeric {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let read_u16 = |offset: usize| -> Result<u16, Box<dyn Error + Sync + Send>> {
+            let bytes = raw
+                .get(offset..offset + 2)
+                .ok_or("Truncated NUMERIC value")?;
+            Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+        };
+
+        // The value is `sign * sum(digits[i] * 10000^(weight - i))`.
+        let ndigits = read_u16(0)? as usize;
+        let weight = read_u16(2)? as i16 as i32;
+        let sign = read_u16(4)?;
+        let digits = (0..ndigits)
+            .map(|i| read_u16(8 + 2 * i))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match sign {
+            NUMERIC_NAN => return Ok(Numeric::Float(f64::NAN)),
+            NUMERIC_PINF => return Ok(Numeric::Float(f64::INFINITY)),
+            NUMERIC_NINF => return Ok(Numeric::Float(f64::NEG_INFINITY)),
+            _ => {}
+        }
+        let negative = sign == NUMERIC_NEG;
+
+        // Postgres strips trailing zero digits, so the value is an integer exactly when its last
+        // digit is not past the decimal point, whatever its display scale (e.g. `100.00` in a
+        // `NUMERIC(20, 2)` column).
+        let exponent = weight - (ndigits as i32 - 1);
+        if exponent >= 0 {
+            let int = digits
+                .iter()
+                .try_fold(0i128, |acc, d| {
+                    acc.checked_mul(10000)?.checked_add(*d as i128)
+                })
+                .and_then(|acc| acc.checked_mul(10000i128.checked_pow(exponent as u32)?));
+            if let Some(int) = int {
+                return Ok(Numeric::Int(if negative { -int } else { int }));
+            }
+        }
+
+        let float: f64 = digits
+            .iter()
+            .enumerate()
+            .map(|(i, d)| *d as f64 * 10000f64.powi(weight - i as i32))
+            .sum();
+        Ok(Numeric::Float(if negative { -float } else { float }))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeric(weight: i16, sign: u16, dscale: u16, digits: &[u16]) -> Numeric {
+        let mut raw = vec![];
+        raw.extend((digits.len() as u16).to_be_bytes());
+        raw.extend(weight.to_be_bytes());
+        raw.extend(sign.to_be_bytes());
+        raw.extend(dscale.to_be_bytes());
+        for d in digits {
+            raw.extend(d.to_be_bytes());
+        }
+        Numeric::from_sql(&Type::NUMERIC, &raw).unwrap()
+    }
+
+    #[test]
+    fn test_decode_numeric() {
+        // 0
+        assert_eq!(numeric(0, 0, 0, &[]), Numeric::Int(0));
+        // 12345678
+        assert_eq!(numeric(1, 0, 0, &[1234, 5678]), Numeric::Int(12_345_678));
+        // -10000000000000000000, with trailing zero digits stripped.
+        assert_eq!(
+            numeric(4, NUMERIC_NEG, 0, &[1000]),
+            Numeric::Int(-10_000_000_000_000_000_000),
+        );
+        // 1.5
+        assert_eq!(numeric(0, 0, 1, &[1, 5000]), Numeric::Float(1.5));
+        // 100.00, with a non-zero display scale but no fractional digits.
+        assert_eq!(numeric(0, 0, 2, &[100]), Numeric::Int(100));
+        // -20000.000
+        assert_eq!(numeric(1, NUMERIC_NEG, 3, &[2]), Numeric::Int(-20_000));
+        // -0.25
+        assert_eq!(numeric(-1, NUMERIC_NEG, 2, &[2500]), Numeric::Float(-0.25));
+        // Too large for an i128.
+        assert!(
+            matches!(numeric(10, 0, 0, &[1]), Numeric::Float(f) if (f / 1e40 - 1.0).abs() < 1e-9)
+        );
+        assert!(matches!(numeric(0, NUMERIC_NAN, 0, &[]), Numeric::Float(f) if f.is_nan()));
+    }
+}
@@ -1,8 +1,9 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::SecurityWatchdogConfig;
-use anyhow::anyhow;
+use crate::postgres_query_runner::PostgresQueryRunner;
+use crate::{QueryRunnerKind, SecurityWatchdogConfig};
+use anyhow::{Context, anyhow};
 use arrow_array::cast::AsArray;
 use arrow_array::types::{
     Decimal128Type, Float16Type, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type,
@@ -13,6 +14,8 @@ use lexical_util::num::AsPrimitive;
 use snowflake_api::{QueryResult, SnowflakeApi};
 use std::any::Any;
 use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
 use tracing::info;
 
 pub type Row = HashMap<String, Box<dyn Any + Send>>;
@@ -28,6 +31,42 @@ pub trait QueryRunner: Send + Sync + 'static {
     async fn run(&self, query: &str) -> anyhow::Result<Vec<Row>>;
 }
 
+/// Creates the query runner selected by `config`. Credentials that are not part of the config are
+/// read from the environment.
+pub fn make_query_runner(config: &SecurityWatchdogConfig) -> anyhow::Result<Arc<dyn QueryRunner>> {
+    Ok(match config.query_runner {
+        QueryRunnerKind::Snowflake => {
+            let sf_password = env::var("SF_PASSWORD").context("SF_PASSWORD env var must be set")?;
+            Arc::new(SnowflakeQueryRunner::from_config(config, sf_password)?)
+        }
+        QueryRunnerKind::Postgres => Arc::new(PostgresQueryRunner::from_config(config)?),
+        #[cfg(feature = "duckdb")]
+        QueryRunnerKind::Duckdb => Arc::new(
+            crate::duckdb_query_runner::DuckDbQueryRunner::from_config(config)?,
+        ),
+        #[cfg(not(feature = "duckdb"))]
+        QueryRunnerKind::Duckdb => {
+            return Err(anyhow!(
+                "The duckdb query runner requires building with the `duckdb` feature"
+            ));
+        }
+    })
+}
+
+/// Converts a numeric value from a [`Row`] to a floating-point number, if it is numeric.
+pub(crate) fn as_f64(value: &(dyn Any + Send)) -> Option<f64> {
+    macro_rules! try_as {
+        ($($type:ty),*) => {
+            $(if let Some(value) = value.downcast_ref::<$type>() {
+                return Some(*value as f64);
+            })*
+        };
+    }
+
+    try_as!(f64, f32, i128, i64, i32, i16, i8, u64, u32, u16, u8);
+    None
+}
+
 macro_rules! insert_primitive_values {
     ($rows:expr, $column:expr, $name:expr, $type:ty) => {
         if let Some(value) = $column.as_primitive_opt::<$type>() {
@@ -2,9 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::SecurityWatchdogConfig;
+use crate::alert_sink::{Alert, AlertSink};
 use crate::metrics::WatchdogMetrics;
-use crate::pagerduty::{Body, CreateIncident, Incident, Pagerduty, Service};
-use crate::query_runner::{QueryRunner, SnowflakeQueryRunner};
+use crate::query_runner::{QueryRunner, Row};
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use prometheus::{IntGauge, Registry};
@@ -43,7 +43,7 @@ pub struct MetricPublishingEntry {
 }
 
 // WalletMonitoringEntry is a struct that represents the configuration of a job which monitors wallet balances.
-// It creates pagerduty incidents based on the given SQL query and cron schedule.
+// It raises an alert for every wallet returned by the given SQL query, on the given cron schedule.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WalletMonitoringEntry {
     name: String,
@@ -56,25 +56,23 @@ pub struct SchedulerService {
     query_runner: Arc<dyn QueryRunner>,
     metrics: Arc<WatchdogMetrics>,
     entries: Vec<MonitoringEntry>,
-    pagerduty: Pagerduty,
-    pd_wallet_monitoring_service_id: String,
+    alert_sinks: Arc<Vec<Arc<dyn AlertSink>>>,
 }
 
 impl SchedulerService {
     pub async fn new(
         config: &SecurityWatchdogConfig,
         registry: &Registry,
-        pd_api_key: String,
-        sf_password: String,
+        query_runner: Arc<dyn QueryRunner>,
+        alert_sinks: Vec<Arc<dyn AlertSink>>,
     ) -> anyhow::Result<Self> {
         let scheduler = JobScheduler::new().await?;
         Ok(Self {
             scheduler,
-            query_runner: Arc::new(SnowflakeQueryRunner::from_config(config, sf_password)?),
+            query_runner,
             metrics: Arc::new(WatchdogMetrics::new(registry)),
             entries: Self::from_config(config)?,
-            pagerduty: Pagerduty::new(pd_api_key.clone()),
-            pd_wallet_monitoring_service_id: config.pd_wallet_monitoring_service_id.clone(),
+            alert_sinks: Arc::new(alert_sinks),
         })
     }
 
@@ -95,9 +93,8 @@ impl SchedulerService {
                         entry.clone(),
                         self.scheduler.clone(),
                         self.query_runner.clone(),
-                        self.pd_wallet_monitoring_service_id.clone(),
                         self.metrics.clone(),
-                        self.pagerduty.clone(),
+                        self.alert_sinks.clone(),
                     )
                     .await?;
                 }
@@ -124,23 +121,20 @@ impl SchedulerService {
         entry: WalletMonitoringEntry,
         scheduler: JobScheduler,
         query_runner: Arc<dyn QueryRunner>,
-        pd_service_id: String,
         metrics: Arc<WatchdogMetrics>,
-        pagerduty: Pagerduty,
+        alert_sinks: Arc<Vec<Arc<dyn AlertSink>>>,
     ) -> anyhow::Result<Uuid> {
         let name = entry.name.clone();
         let cron_schedule = entry.cron_schedule.clone();
         let job = Job::new_async(cron_schedule.as_str(), move |_uuid, _lock| {
             let entry = entry.clone();
             let query_runner = query_runner.clone();
-            let pd_service_id = pd_service_id.to_string();
-            let pd = pagerduty.clone();
+            let alert_sinks = alert_sinks.clone();
             let metrics = metrics.clone();
             Box::pin(async move {
                 info!("Running wallet monitoring job: {}", entry.name);
                 if let Err(err) =
-                    Self::run_wallet_monitoring_job(&pd, &pd_service_id, &query_runner, &entry)
-                        .await
+                    Self::run_wallet_monitoring_job(&alert_sinks, &query_runner, &entry).await
                 {
                     error!(
                         "Failed to run wallet monitoring job: {} with err: {}",
@@ -160,8 +154,7 @@ impl SchedulerService {
     }
 
     async fn run_wallet_monitoring_job(
-        pagerduty: &Pagerduty,
-        service_id: &str,
+        alert_sinks: &[Arc<dyn AlertSink>],
         query_runner: &Arc<dyn QueryRunner>,
         entry: &WalletMonitoringEntry,
     ) -> anyhow::Result<()> {
@@ -170,73 +163,84 @@ impl SchedulerService {
         } = entry;
         let rows = query_runner.run(sql_query).await?;
         for row in rows {
-            let wallet_id = row
-                .get("WALLET_ID")
+            let wallet_id = Self::get_column(&row, "WALLET_ID")
                 .ok_or_else(|| anyhow!("Missing wallet_id"))?
                 .downcast_ref::<String>()
                 .ok_or(anyhow!("Failed to downcast wallet_id"))?
                 .clone();
             let current_balance = Self::extract_i128(
-                row.get("CURRENT_BALANCE")
+                Self::get_column(&row, "CURRENT_BALANCE")
                     .ok_or_else(|| anyhow!("Missing current_balance"))?,
             )
             .ok_or(anyhow!("Failed to downcast current_balance"))?;
             let lower_bound = Self::extract_i128(
-                row.get("LOWER_BOUND")
+                Self::get_column(&row, "LOWER_BOUND")
                     .ok_or_else(|| anyhow!("Missing lower_bound"))?,
             )
             .ok_or(anyhow!("Failed to downcast lower_bound"))?;
-            Self::create_wallet_monitoring_incident(
-                pagerduty,
-                &wallet_id,
-                current_balance,
-                lower_bound,
-                service_id,
-                name,
-            )
-            .await?;
+            let alert =
+                Self::wallet_monitoring_alert(&wallet_id, current_balance, lower_bound, name);
+            Self::send_alert(alert_sinks, &alert).await?;
         }
         Ok(())
     }
 
-    async fn create_wallet_monitoring_incident(
-        pagerduty: &Pagerduty,
+    fn wallet_monitoring_alert(
         wallet_id: &str,
         current_balance: i128,
         lower_bound: i128,
-        service_id: &str,
         name: &str,
-    ) -> anyhow::Result<()> {
-        let service = Service {
-            id: service_id.to_string(),
-            ..Default::default()
-        };
-        let incident_body = Body {
+    ) -> Alert {
+        Alert {
+            key: wallet_id.to_string(),
+            job: name.to_string(),
+            title: format!(
+                "Wallet: {} is out of compliance, for job: {}",
+                wallet_id, name
+            ),
             details: format!(
                 "Current balance: {}, Lower bound: {}, for job: {}",
                 current_balance / MIST_PER_RTD,
                 lower_bound / MIST_PER_RTD,
                 name
             ),
-            ..Default::default()
-        };
-        let incident = Incident {
-            title: format!(
-                "Wallet: {} is out of compliance, for job: {}",
-                wallet_id, name
-            ),
-            service,
-            incident_key: wallet_id.to_string(),
-            body: incident_body,
-            ..Default::default()
-        };
-        let create_incident = CreateIncident { incident };
-        pagerduty
-            .create_incident("sadhan@linkulabs.com", create_incident)
-            .await?;
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Sends `alert` to every sink, even if some of them fail.
+    async fn send_alert(alert_sinks: &[Arc<dyn AlertSink>], alert: &Alert) -> anyhow::Result<()> {
+        let mut failures = 0;
+        for sink in alert_sinks {
+            if let Err(err) = sink.send(alert).await {
+                error!(
+                    "Failed to send alert with key: {} with err: {}",
+                    alert.key, err
+                );
+                failures += 1;
+            }
+        }
+        if failures > 0 {
+            return Err(anyhow!(
+                "Failed to send alert with key: {} to {} of {} sinks",
+                alert.key,
+                failures,
+                alert_sinks.len()
+            ));
+        }
         Ok(())
     }
 
+    /// Looks up `name` in `row`. Snowflake upper-cases unquoted column names while Postgres and
+    /// DuckDB preserve or lower-case them, so fall back to a case-insensitive match.
+    fn get_column<'a>(row: &'a Row, name: &str) -> Option<&'a Box<dyn Any + Send>> {
+        row.get(name).or_else(|| {
+            row.iter()
+                .find(|(column, _)| column.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })
+    }
+
     async fn schedule_metric_publish_job(
         entry: MetricPublishingEntry,
         scheduler: JobScheduler,
@@ -300,9 +304,15 @@ impl SchedulerService {
         limits.range(..Utc::now()).next_back().map(|(_, val)| *val)
     }
 
+    /// Converts an integer value from a [`Row`] to an `i128`. Floating-point values are accepted
+    /// if they are whole numbers, because runners return decimals with a non-zero scale as floats.
     fn extract_i128(value: &Box<dyn Any + Send>) -> Option<i128> {
         if let Some(value) = value.downcast_ref::<i128>() {
             Some(*value)
+        } else if let Some(value) = value.downcast_ref::<f64>() {
+            (value.is_finite() && value.fract() == 0.0).then_some(*value as i128)
+        } else if let Some(value) = value.downcast_ref::<u64>() {
+            Some(*value as i128)
         } else if let Some(value) = value.downcast_ref::<u32>() {
             Some(*value as i128)
         } else if let Some(value) = value.downcast_ref::<u16>() {
@@ -320,3 +330,24 @@ impl SchedulerService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_i128() {
+        let extract = |value: Box<dyn Any + Send>| SchedulerService::extract_i128(&value);
+        assert_eq!(extract(Box::new(42i128)), Some(42));
+        assert_eq!(extract(Box::new(-7i32)), Some(-7));
+        assert_eq!(extract(Box::new(u64::MAX)), Some(u64::MAX as i128));
+
+        // Scaled decimals that hold whole numbers.
+        assert_eq!(extract(Box::new(100.0f64)), Some(100));
+        assert_eq!(extract(Box::new(-2.5e10f64)), Some(-25_000_000_000));
+
+        assert_eq!(extract(Box::new(1.5f64)), None);
+        assert_eq!(extract(Box::new(f64::NAN)), None);
+        assert_eq!(extract(Box::new("1".to_string())), None);
+    }
+}
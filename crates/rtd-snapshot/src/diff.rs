@@ -0,0 +1,292 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Object level comparison of two formal snapshots, e.g. of consecutive epochs, or of a locally
+//! produced snapshot against the published one.
+
+use crate::reader::{DigestByBucketAndPartition, ObjectRefIter, StateSnapshotReaderV1};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::iter::Peekable;
+use rtd_core::authority::authority_store_tables::LiveObject;
+use rtd_types::base_types::{ObjectID, ObjectRef};
+use tracing::info;
+
+/// The type reported for packages.
+pub const PACKAGE_TYPE: &str = "package";
+/// The type reported for wrapped object tombstones.
+pub const WRAPPED_TYPE: &str = "wrapped";
+
+#[derive(Clone, Debug, Serialize)]
+pub enum ObjectChange {
+    /// The object is only in the right snapshot.
+    Added { new: ObjectRef },
+    /// The object is only in the left snapshot.
+    Removed { old: ObjectRef },
+    /// The object is in both snapshots, at different versions or with different contents.
+    Changed { old: ObjectRef, new: ObjectRef },
+}
+
+impl ObjectChange {
+    pub fn object_id(&self) -> ObjectID {
+        match self {
+            ObjectChange::Added { new } => new.0,
+            ObjectChange::Removed { old } => old.0,
+            ObjectChange::Changed { old, .. } => old.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ObjectDiff {
+    pub bucket_num: u32,
+    pub change: ObjectChange,
+    /// The type of the object in the left snapshot, if it is in the left snapshot.
+    pub old_type: Option<String>,
+    /// The type of the object in the right snapshot, if it is in the right snapshot.
+    pub new_type: Option<String>,
+}
+
+impl ObjectDiff {
+    /// The type the object is counted under in the per-type summary.
+    pub fn type_(&self) -> &str {
+        self.new_type
+            .as_deref()
+            .or(self.old_type.as_deref())
+            .unwrap_or("unknown")
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TypeSummary {
+    pub added: u64,
+    pub removed: u64,
+    pub changed: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnapshotDiff {
+    pub left_epoch: u64,
+    pub right_epoch: u64,
+    /// Bucket partitions whose reference file checksums differ, or that are only in one snapshot.
+    pub divergent_partitions: Vec<(u32, u32)>,
+    /// The first divergent object, by bucket and then object id.
+    pub first_divergence: Option<ObjectDiff>,
+    /// Total number of added, removed and changed objects.
+    pub num_diffs: u64,
+    /// Divergent objects in bucket and object id order, up to the limit passed to
+    /// `diff_snapshots`.
+    pub diffs: Vec<ObjectDiff>,
+    /// Number of added, removed and changed objects, by type.
+    pub summary: BTreeMap<String, TypeSummary>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.num_diffs == 0 && self.divergent_partitions.is_empty()
+    }
+}
+
+/// Compares two snapshots bucket by bucket. Buckets whose reference files have the same checksums
+/// in both snapshots are skipped, and object files are only downloaded for buckets that differ,
+/// one part at a time, to find the types of the divergent objects. At most `max_diffs` divergent
+/// objects are returned, but all of them are counted in the summary.
+pub async fn diff_snapshots(
+    left: &mut StateSnapshotReaderV1,
+    right: &mut StateSnapshotReaderV1,
+    max_diffs: usize,
+) -> Result<SnapshotDiff> {
+    let (left_digests, _) = left.compute_checksum().await?;
+    let (right_digests, _) = right.compute_checksum().await?;
+    let left_digests = left_digests.lock().await.clone();
+    let right_digests = right_digests.lock().await.clone();
+
+    let mut snapshot_diff = SnapshotDiff {
+        left_epoch: left.epoch(),
+        right_epoch: right.epoch(),
+        divergent_partitions: divergent_partitions(&left_digests, &right_digests),
+        first_divergence: None,
+        num_diffs: 0,
+        diffs: vec![],
+        summary: BTreeMap::new(),
+    };
+
+    let buckets: BTreeSet<u32> = left
+        .buckets()?
+        .into_iter()
+        .chain(right.buckets()?)
+        .collect();
+    for bucket_num in buckets {
+        if left_digests.get(&bucket_num) == right_digests.get(&bucket_num) {
+            continue;
+        }
+        info!("Diffing bucket {bucket_num}");
+        let changes = diff_bucket_refs(left, right, bucket_num)?;
+        if changes.is_empty() {
+            continue;
+        }
+
+        let mut diffs: Vec<_> = changes
+            .into_iter()
+            .map(|change| ObjectDiff {
+                bucket_num,
+                change,
+                old_type: None,
+                new_type: None,
+            })
+            .collect();
+        let positions: HashMap<ObjectID, usize> = diffs
+            .iter()
+            .enumerate()
+            .map(|(i, diff)| (diff.change.object_id(), i))
+            .collect();
+        left.for_each_object_in_bucket(bucket_num, |object| {
+            if let Some(i) = positions.get(&object.object_id()) {
+                diffs[*i].old_type = Some(object_type(&object));
+            }
+        })
+        .await?;
+        right
+            .for_each_object_in_bucket(bucket_num, |object| {
+                if let Some(i) = positions.get(&object.object_id()) {
+                    diffs[*i].new_type = Some(object_type(&object));
+                }
+            })
+            .await?;
+
+        for diff in diffs {
+            let summary = snapshot_diff
+                .summary
+                .entry(diff.type_().to_string())
+                .or_default();
+            match diff.change {
+                ObjectChange::Added { .. } => summary.added += 1,
+                ObjectChange::Removed { .. } => summary.removed += 1,
+                ObjectChange::Changed { .. } => summary.changed += 1,
+            }
+            snapshot_diff.num_diffs += 1;
+            if snapshot_diff.first_divergence.is_none() {
+                snapshot_diff.first_divergence = Some(diff.clone());
+            }
+            if snapshot_diff.diffs.len() < max_diffs {
+                snapshot_diff.diffs.push(diff);
+            }
+        }
+    }
+    Ok(snapshot_diff)
+}
+
+fn divergent_partitions(
+    left: &DigestByBucketAndPartition,
+    right: &DigestByBucketAndPartition,
+) -> Vec<(u32, u32)> {
+    let partitions: BTreeSet<(u32, u32)> = [left, right]
+        .into_iter()
+        .flat_map(|digests| {
+            digests
+                .iter()
+                .flat_map(|(bucket, parts)| parts.keys().map(move |part| (*bucket, *part)))
+        })
+        .collect();
+    partitions
+        .into_iter()
+        .filter(|(bucket, part)| {
+            let left = left.get(bucket).and_then(|parts| parts.get(part));
+            let right = right.get(bucket).and_then(|parts| parts.get(part));
+            left != right
+        })
+        .collect()
+}
+
+/// Merges the object references of a bucket in both snapshots. Snapshots are written in object id
+/// order, so the references of a bucket are sorted across its parts.
+fn diff_bucket_refs(
+    left: &StateSnapshotReaderV1,
+    right: &StateSnapshotReaderV1,
+    bucket_num: u32,
+) -> Result<Vec<ObjectChange>> {
+    let mut left_refs = SortedRefs::new(left, bucket_num)?;
+    let mut right_refs = SortedRefs::new(right, bucket_num)?;
+    let mut changes = vec![];
+    loop {
+        let change = match (left_refs.peek()?, right_refs.peek()?) {
+            (None, None) => break,
+            (Some(old), None) => {
+                left_refs.next();
+                ObjectChange::Removed { old }
+            }
+            (None, Some(new)) => {
+                right_refs.next();
+                ObjectChange::Added { new }
+            }
+            (Some(old), Some(new)) if old.0 < new.0 => {
+                left_refs.next();
+                ObjectChange::Removed { old }
+            }
+            (Some(old), Some(new)) if old.0 > new.0 => {
+                right_refs.next();
+                ObjectChange::Added { new }
+            }
+            (Some(old), Some(new)) => {
+                left_refs.next();
+                right_refs.next();
+                if old == new {
+                    continue;
+                }
+                ObjectChange::Changed { old, new }
+            }
+        };
+        changes.push(change);
+    }
+    Ok(changes)
+}
+
+/// The object references of all the parts of a bucket, checked to be in ascending object id order.
+struct SortedRefs {
+    refs: Peekable<Box<dyn Iterator<Item = ObjectRef>>>,
+    last: Option<ObjectID>,
+}
+
+impl SortedRefs {
+    fn new(reader: &StateSnapshotReaderV1, bucket_num: u32) -> Result<Self> {
+        let iters = reader
+            .parts(bucket_num)
+            .into_iter()
+            .map(|part_num| reader.ref_iter(bucket_num, part_num))
+            .collect::<Result<Vec<ObjectRefIter>>>()?;
+        let refs: Box<dyn Iterator<Item = ObjectRef>> = Box::new(iters.into_iter().flatten());
+        Ok(Self {
+            refs: refs.peekable(),
+            last: None,
+        })
+    }
+
+    fn peek(&mut self) -> Result<Option<ObjectRef>> {
+        let Some(object_ref) = self.refs.peek().copied() else {
+            return Ok(None);
+        };
+        if self.last.is_some_and(|last| last >= object_ref.0) {
+            return Err(anyhow!(
+                "Object references are not sorted by object id at {}",
+                object_ref.0
+            ));
+        }
+        Ok(Some(object_ref))
+    }
+
+    fn next(&mut self) {
+        self.last = self.refs.next().map(|object_ref| object_ref.0);
+    }
+}
+
+fn object_type(object: &LiveObject) -> String {
+    match object {
+        LiveObject::Normal(object) if object.is_package() => PACKAGE_TYPE.to_string(),
+        LiveObject::Normal(object) => object
+            .struct_tag()
+            .map(|tag| tag.to_canonical_string(/* with_prefix */ true))
+            .unwrap_or_else(|| "unknown".to_string()),
+        LiveObject::Wrapped(_) => WRAPPED_TYPE.to_string(),
+    }
+}
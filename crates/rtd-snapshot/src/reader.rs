@@ -32,6 +32,7 @@ use rtd_storage::blob::{Blob, BlobEncoding};
 use rtd_storage::object_store::http::HttpDownloaderBuilder;
 use rtd_storage::object_store::util::{copy_files, path_to_filesystem};
 use rtd_storage::object_store::{ObjectStoreGetExt, ObjectStoreListExt, ObjectStorePutExt};
+use rtd_storage::compute_sha3_checksum_for_bytes;
 use rtd_types::base_types::{ObjectDigest, ObjectID, ObjectRef, SequenceNumber};
 use rtd_types::global_state_hash::GlobalStateHash;
 use tokio::sync::Mutex;
@@ -612,10 +613,49 @@ impl StateSnapshotReaderV1 {
         )
     }
 
-    fn buckets(&self) -> Result<Vec<u32>> {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn buckets(&self) -> Result<Vec<u32>> {
         Ok(self.ref_files.keys().copied().collect())
     }
 
+    pub fn parts(&self, bucket_num: u32) -> Vec<u32> {
+        self.ref_files
+            .get(&bucket_num)
+            .map(|parts| parts.keys().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Downloads the object files of a bucket one part at a time, in part order, and calls `f` on
+    /// every object in them. Each file is checked against its digest in the manifest before it is
+    /// read.
+    pub async fn for_each_object_in_bucket(
+        &self,
+        bucket_num: u32,
+        mut f: impl FnMut(LiveObject),
+    ) -> Result<()> {
+        let Some(parts) = self.object_files.get(&bucket_num) else {
+            return Ok(());
+        };
+        for file_metadata in parts.values() {
+            let file_path = file_metadata.file_path(&self.remote_epoch_prefix);
+            let bytes = self.remote_object_store.get_bytes(&file_path).await?;
+            let sha3_digest = compute_sha3_checksum_for_bytes(bytes.clone())?;
+            if sha3_digest != file_metadata.sha3_digest {
+                return Err(anyhow!(
+                    "Checksum: {:?} of {} doesn't match manifest: {:?}",
+                    sha3_digest,
+                    file_path,
+                    file_metadata.sha3_digest
+                ));
+            }
+            LiveObjectIter::new(file_metadata, bytes)?.for_each(&mut f);
+        }
+        Ok(())
+    }
+
     fn epoch_dir(&self) -> Path {
         Path::from(format!("epoch_{}", self.epoch))
     }
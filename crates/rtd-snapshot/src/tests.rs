@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::FileCompression;
+use crate::diff::{ObjectChange, diff_snapshots};
 use crate::reader::StateSnapshotReaderV1;
 use crate::uploader::StateSnapshotUploader;
 use crate::writer::StateSnapshotWriterV1;
@@ -189,6 +190,110 @@ async fn test_snapshot_empty_db() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+async fn write_snapshot(
+    epoch: u64,
+    num_objects: u64,
+    remote_store_config: &ObjectStoreConfig,
+) -> Result<(), anyhow::Error> {
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(temp_dir().join("local_dir")),
+        ..Default::default()
+    };
+    let snapshot_writer = StateSnapshotWriterV1::new(
+        &local_store_config,
+        remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?;
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&temp_dir(), None, None));
+    insert_keys(&perpetual_db, num_objects)?;
+    let root_accumulator =
+        ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db, true).digest());
+    snapshot_writer
+        .write_internal(epoch, true, perpetual_db, root_accumulator)
+        .await
+}
+
+async fn open_snapshot(
+    epoch: u64,
+    remote_store_config: &ObjectStoreConfig,
+) -> Result<StateSnapshotReaderV1, anyhow::Error> {
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(temp_dir().join("local_dir_restore")),
+        ..Default::default()
+    };
+    StateSnapshotReaderV1::new(
+        epoch,
+        remote_store_config,
+        &local_store_config,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+        false, // skip_reset_local_store
+        3,     // max_retries
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_snapshot_diff() -> Result<(), anyhow::Error> {
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(temp_dir().join("remote_dir")),
+        ..Default::default()
+    };
+    write_snapshot(0, 1000, &remote_store_config).await?;
+    write_snapshot(1, 1100, &remote_store_config).await?;
+
+    let mut left = open_snapshot(0, &remote_store_config).await?;
+    let mut right = open_snapshot(0, &remote_store_config).await?;
+    let snapshot_diff = diff_snapshots(&mut left, &mut right, 10).await?;
+    assert!(snapshot_diff.is_empty());
+
+    let mut right = open_snapshot(1, &remote_store_config).await?;
+    let snapshot_diff = diff_snapshots(&mut left, &mut right, 10).await?;
+    assert!(!snapshot_diff.divergent_partitions.is_empty());
+    assert_eq!(snapshot_diff.num_diffs, 100);
+    assert_eq!(snapshot_diff.diffs.len(), 10);
+    assert!(
+        snapshot_diff
+            .diffs
+            .iter()
+            .all(|diff| matches!(diff.change, ObjectChange::Added { .. }))
+    );
+    let first_added = ObjectID::in_range(ObjectID::ZERO, 1100)?[1000];
+    assert_eq!(
+        snapshot_diff
+            .first_divergence
+            .map(|diff| diff.change.object_id()),
+        Some(first_added)
+    );
+    assert_eq!(
+        snapshot_diff
+            .summary
+            .values()
+            .map(|summary| summary.added)
+            .sum::<u64>(),
+        100
+    );
+
+    // Swapping the snapshots turns additions into removals
+    let mut left = open_snapshot(1, &remote_store_config).await?;
+    let mut right = open_snapshot(0, &remote_store_config).await?;
+    let snapshot_diff = diff_snapshots(&mut left, &mut right, 10).await?;
+    assert_eq!(
+        snapshot_diff
+            .summary
+            .values()
+            .map(|summary| summary.removed)
+            .sum::<u64>(),
+        100
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_archive_epoch_if_needed() -> Result<(), anyhow::Error> {
     let db_checkpoint_path = temp_dir().join("db_checkpoints");
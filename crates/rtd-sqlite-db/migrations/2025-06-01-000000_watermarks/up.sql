@@ -0,0 +1,31 @@
+-- The same watermarks as the Postgres store (see rtd-pg-db), with SQLite
+-- types.
+CREATE TABLE IF NOT EXISTS watermarks
+(
+    -- The pipeline governed by this watermark, i.e `epochs`, `checkpoints`,
+    -- `transactions`.
+    pipeline                    TEXT          PRIMARY KEY NOT NULL,
+    -- Inclusive upper epoch bound for this entity's data. Committer updates
+    -- this field.
+    epoch_hi_inclusive          BIGINT        NOT NULL,
+    -- Inclusive upper checkpoint bound for this entity's data. Committer
+    -- updates this field. All data of this entity in the checkpoint must be
+    -- persisted before advancing this watermark.
+    checkpoint_hi_inclusive     BIGINT        NOT NULL,
+    -- Exclusive upper transaction sequence number bound for this entity's
+    -- data. Committer updates this field.
+    tx_hi                       BIGINT        NOT NULL,
+    -- Inclusive upper timestamp bound (in milliseconds). Committer updates
+    -- this field.
+    timestamp_ms_hi_inclusive   BIGINT        NOT NULL,
+    -- Inclusive low watermark that the pruner advances. Data before this
+    -- watermark is considered pruned by a reader.
+    reader_lo                   BIGINT        NOT NULL,
+    -- Updated with the current time (UTC, millisecond precision) when the
+    -- pruner sees that some data needs to be dropped. The pruner waits until
+    -- in-flight reads have had a chance to complete before acting on it.
+    pruner_timestamp            TIMESTAMP     NOT NULL,
+    -- Column used by the pruner to track its true progress. Data below this
+    -- watermark can be immediately pruned.
+    pruner_hi                   BIGINT        NOT NULL
+);
@@ -0,0 +1,499 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An embedded SQLite implementation of the indexer framework's `Store`, for indexers that should
+//! run as a single self-contained binary, without a database server.
+//!
+//! SQLite allows only one writer at a time, so concurrent writes from the connections in the pool
+//! wait on each other (up to [DbArgs::busy_timeout]) rather than proceeding in parallel. Databases
+//! are opened in WAL mode, so readers are not blocked by the writer.
+
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+use diesel::migration::{Migration, MigrationSource, MigrationVersion};
+use diesel::sqlite::Sqlite;
+use diesel::{Connection as _, ConnectionError, SqliteConnection};
+use diesel_async::pooled_connection::ManagerConfig;
+use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
+use diesel_async::{
+    AsyncConnection, SimpleAsyncConnection,
+    pooled_connection::{
+        AsyncDieselConnectionManager,
+        bb8::{Pool, PooledConnection},
+    },
+};
+use futures::FutureExt;
+use tracing::info;
+
+mod model;
+
+pub mod schema;
+pub mod store;
+pub mod temp;
+
+use diesel_migrations::{EmbeddedMigrations, embed_migrations};
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// The connection type pooled by [Db]. Diesel's SQLite connection is synchronous, so it is wrapped
+/// to run its queries on blocking threads.
+pub type SqliteAsyncConnection = SyncConnectionWrapper<SqliteConnection>;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct DbArgs {
+    /// Number of connections to keep in the pool.
+    #[arg(long, default_value_t = Self::default().db_connection_pool_size)]
+    pub db_connection_pool_size: u32,
+
+    /// Time spent waiting for a connection from the pool to become available, in milliseconds.
+    #[arg(long, default_value_t = Self::default().db_connection_timeout_ms)]
+    pub db_connection_timeout_ms: u64,
+
+    /// Time a statement waits for another connection's write lock on the database to be released,
+    /// in milliseconds, before failing.
+    #[arg(long, default_value_t = Self::default().db_busy_timeout_ms)]
+    pub db_busy_timeout_ms: u64,
+}
+
+#[derive(Clone)]
+pub struct Db {
+    pool: Pool<SqliteAsyncConnection>,
+    path: PathBuf,
+}
+
+/// Wrapper struct over the remote `PooledConnection` type for dealing with the `Store` trait.
+pub struct Connection<'a>(PooledConnection<'a, SqliteAsyncConnection>);
+
+impl DbArgs {
+    pub fn connection_timeout(&self) -> Duration {
+        Duration::from_millis(self.db_connection_timeout_ms)
+    }
+
+    pub fn busy_timeout(&self) -> Duration {
+        Duration::from_millis(self.db_busy_timeout_ms)
+    }
+}
+
+impl Db {
+    /// Construct a new DB connection pool for the database file at `path`, that supports writes
+    /// and reads. The file is created if it does not exist. Instances of [Db] can be cloned to
+    /// share access to the same pool.
+    ///
+    /// Each connection in the pool opens the file separately, so `path` must name a file, and not
+    /// an in-memory database.
+    pub async fn for_write(path: impl AsRef<Path>, config: DbArgs) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Ok(Self {
+            pool: pool(&path, config, false).await?,
+            path,
+        })
+    }
+
+    /// Construct a new DB connection pool for the database file at `path`, whose connections
+    /// refuse to write. Instances of [Db] can be cloned to share access to the same pool.
+    pub async fn for_read(path: impl AsRef<Path>, config: DbArgs) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Ok(Self {
+            pool: pool(&path, config, true).await?,
+            path,
+        })
+    }
+
+    /// The path of the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Retrieves a connection from the pool. Can fail with a timeout if a connection cannot be
+    /// established before the [DbArgs::connection_timeout] has elapsed.
+    pub async fn connect(&self) -> anyhow::Result<Connection<'_>> {
+        Ok(Connection(self.pool.get().await?))
+    }
+
+    /// Statistics about the connection pool
+    pub fn state(&self) -> bb8::State {
+        self.pool.state()
+    }
+
+    /// Run migrations on the database. Use Diesel's `embed_migrations!` macro to generate the
+    /// `migrations` parameter for your indexer. Migrations are run on a dedicated connection,
+    /// outside the pool.
+    pub async fn run_migrations(
+        &self,
+        migrations: Option<&'static EmbeddedMigrations>,
+    ) -> anyhow::Result<Vec<MigrationVersion<'static>>> {
+        use diesel_migrations::MigrationHarness;
+
+        let merged_migrations = merge_migrations(migrations);
+        let database_url = database_url(&self.path)?;
+
+        info!("Running migrations ...");
+        let finished_migrations = tokio::task::spawn_blocking(move || {
+            let mut conn = SqliteConnection::establish(&database_url)
+                .map_err(|e| anyhow!("Failed to connect to run migrations: {e}"))?;
+            conn.run_pending_migrations(merged_migrations)
+                .map(|versions| versions.iter().map(MigrationVersion::as_owned).collect())
+                .map_err(|e| anyhow!("Failed to run migrations: {:?}", e))
+        })
+        .await??;
+
+        info!("Migrations complete.");
+        Ok(finished_migrations)
+    }
+}
+
+impl Default for DbArgs {
+    fn default() -> Self {
+        Self {
+            db_connection_pool_size: 4,
+            db_connection_timeout_ms: 60_000,
+            db_busy_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl<'a> Deref for Connection<'a> {
+    type Target = PooledConnection<'a, SqliteAsyncConnection>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Connection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+fn database_url(path: &Path) -> anyhow::Result<String> {
+    path.to_str()
+        .map(str::to_owned)
+        .with_context(|| format!("Database path is not valid UTF-8: {}", path.display()))
+}
+
+async fn pool(
+    path: &Path,
+    args: DbArgs,
+    read_only: bool,
+) -> anyhow::Result<Pool<SqliteAsyncConnection>> {
+    let busy_timeout = args.busy_timeout();
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(move |url| {
+        let url = url.to_owned();
+        async move {
+            let mut conn = SqliteAsyncConnection::establish(&url).await?;
+
+            // WAL mode is a property of the database file, but setting it is idempotent. The
+            // remaining pragmas apply to the connection only. The busy timeout is set first, as
+            // switching to WAL mode needs a lock on the database.
+            let mut pragmas = format!(
+                "PRAGMA busy_timeout = {}; \
+                 PRAGMA journal_mode = WAL; \
+                 PRAGMA synchronous = NORMAL; \
+                 PRAGMA foreign_keys = ON;",
+                busy_timeout.as_millis()
+            );
+
+            if read_only {
+                pragmas.push_str(" PRAGMA query_only = ON;");
+            }
+
+            conn.batch_execute(&pragmas)
+                .await
+                .map_err(ConnectionError::CouldntSetupConfiguration)?;
+
+            Ok(conn)
+        }
+        .boxed()
+    });
+
+    let manager = AsyncDieselConnectionManager::new_with_config(database_url(path)?, config);
+
+    Ok(Pool::builder()
+        .max_size(args.db_connection_pool_size)
+        .connection_timeout(args.connection_timeout())
+        .build(manager)
+        .await?)
+}
+
+/// Returns new migrations derived from the combination of provided migrations and migrations
+/// defined in this crate.
+pub fn merge_migrations(
+    migrations: Option<&'static EmbeddedMigrations>,
+) -> impl MigrationSource<Sqlite> + Send + Sync + 'static {
+    struct Migrations(Option<&'static EmbeddedMigrations>);
+    impl MigrationSource<Sqlite> for Migrations {
+        fn migrations(&self) -> diesel::migration::Result<Vec<Box<dyn Migration<Sqlite>>>> {
+            let mut migrations = MIGRATIONS.migrations()?;
+            if let Some(more_migrations) = self.0 {
+                migrations.extend(more_migrations.migrations()?);
+            }
+            Ok(migrations)
+        }
+    }
+
+    Migrations(migrations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::prelude::QueryableByName;
+    use diesel_async::RunQueryDsl;
+    use rtd_indexer_alt_framework_store_traits::{
+        CommitterWatermark, Connection as _, TransactionalStore as _,
+    };
+    use scoped_futures::ScopedFutureExt;
+
+    #[derive(Debug, QueryableByName)]
+    struct CountResult {
+        #[diesel(sql_type = diesel::sql_types::BigInt)]
+        cnt: i64,
+    }
+
+    async fn count(conn: &mut Connection<'_>) -> i64 {
+        diesel::sql_query("SELECT COUNT(*) AS cnt FROM test_table")
+            .get_result::<CountResult>(conn)
+            .await
+            .unwrap()
+            .cnt
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let temp_db = temp::TempDb::new().unwrap();
+        let writer = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        let reader = Db::for_read(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+
+        let mut conn = writer.connect().await.unwrap();
+        diesel::sql_query("CREATE TABLE test_table (id INTEGER PRIMARY KEY)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        // Writes through the read-only pool fail, but reads see the writer's changes.
+        let mut read_conn = reader.connect().await.unwrap();
+        diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+            .execute(&mut read_conn)
+            .await
+            .expect_err("Write through a read-only connection should fail");
+
+        diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(count(&mut read_conn).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rollback() {
+        let temp_db = temp::TempDb::new().unwrap();
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        db.run_migrations(None).await.unwrap();
+
+        let mut conn = db.connect().await.unwrap();
+        diesel::sql_query("CREATE TABLE test_table (id INTEGER PRIMARY KEY)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        // A failed transaction leaves neither its rows nor its watermark behind.
+        db.transaction(|conn| {
+            async move {
+                diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+                    .execute(conn)
+                    .await?;
+                conn.set_committer_watermark("pipeline", CommitterWatermark::new_for_testing(1))
+                    .await?;
+                Err::<(), _>(anyhow!("Rollback"))
+            }
+            .scope_boxed()
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(count(&mut conn).await, 0);
+        assert!(
+            conn.committer_watermark("pipeline")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        db.transaction(|conn| {
+            async move {
+                diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+                    .execute(conn)
+                    .await?;
+                conn.set_committer_watermark("pipeline", CommitterWatermark::new_for_testing(1))
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .unwrap();
+
+        let mut conn = db.connect().await.unwrap();
+        assert_eq!(count(&mut conn).await, 1);
+        let watermark = conn.committer_watermark("pipeline").await.unwrap().unwrap();
+        assert_eq!(watermark.checkpoint_hi_inclusive, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_transactions() {
+        let temp_db = temp::TempDb::new().unwrap();
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+
+        let mut conn = db.connect().await.unwrap();
+        diesel::sql_query("CREATE TABLE test_table (id INTEGER PRIMARY KEY)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        // Both transactions read before they write, which only succeeds for both if the second
+        // waits for the first to finish before reading.
+        let insert_next = || {
+            db.transaction(|conn| {
+                async move {
+                    let next = count(conn).await + 1;
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    diesel::sql_query(format!("INSERT INTO test_table (id) VALUES ({next})"))
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+        };
+        let (a, b) = tokio::join!(insert_next(), insert_next());
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(count(&mut conn).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_transaction() {
+        let temp_db = temp::TempDb::new().unwrap();
+        let args = DbArgs {
+            db_connection_pool_size: 1,
+            db_busy_timeout_ms: 1_000,
+            ..DbArgs::default()
+        };
+        let db = Db::for_write(temp_db.path(), args).await.unwrap();
+
+        let mut conn = db.connect().await.unwrap();
+        diesel::sql_query("CREATE TABLE test_table (id INTEGER PRIMARY KEY)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        // The transaction is cancelled while it holds the write lock.
+        let stalled = db.transaction(|conn| {
+            async move {
+                diesel::sql_query("INSERT INTO test_table (id) VALUES (1)")
+                    .execute(conn)
+                    .await?;
+                std::future::pending::<anyhow::Result<()>>().await
+            }
+            .scope_boxed()
+        });
+        tokio::time::timeout(Duration::from_millis(100), stalled)
+            .await
+            .unwrap_err();
+
+        // Its connection is not returned to the pool, so later writers neither see its rows nor
+        // wait on its lock.
+        db.transaction(|conn| {
+            async move {
+                diesel::sql_query("INSERT INTO test_table (id) VALUES (2)")
+                    .execute(conn)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .unwrap();
+
+        let mut conn = db.connect().await.unwrap();
+        assert_eq!(count(&mut conn).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_watermarks() {
+        let temp_db = temp::TempDb::new().unwrap();
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        db.run_migrations(None).await.unwrap();
+        let mut conn = db.connect().await.unwrap();
+
+        // Starting from checkpoint 0 does not create a watermark.
+        assert_eq!(conn.init_watermark("a", 0).await.unwrap(), None);
+        assert_eq!(conn.init_watermark("a", 10).await.unwrap(), Some(9));
+        // An existing watermark is not overwritten.
+        assert_eq!(conn.init_watermark("a", 20).await.unwrap(), Some(9));
+
+        // The committer watermark only moves forward.
+        assert!(
+            conn.set_committer_watermark("a", CommitterWatermark::new_for_testing(15))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !conn
+                .set_committer_watermark("a", CommitterWatermark::new_for_testing(12))
+                .await
+                .unwrap()
+        );
+        let reader = conn.reader_watermark("a").await.unwrap().unwrap();
+        assert_eq!(reader.checkpoint_hi_inclusive, 15);
+        assert_eq!(reader.reader_lo, 10);
+
+        // As does the reader watermark, and advancing it restarts the pruner's delay.
+        assert!(conn.set_reader_watermark("a", 12).await.unwrap());
+        assert!(!conn.set_reader_watermark("a", 11).await.unwrap());
+        let pruner = conn
+            .pruner_watermark("a", Duration::from_secs(60))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pruner.reader_lo, 12);
+        assert_eq!(pruner.pruner_hi, 10);
+        assert!(pruner.wait_for_ms > 50_000 && pruner.wait_for_ms <= 60_000);
+
+        assert!(conn.set_pruner_watermark("a", 12).await.unwrap());
+        let pruner = conn
+            .pruner_watermark("a", Duration::ZERO)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pruner.pruner_hi, 12);
+        assert!(pruner.wait_for_ms <= 0);
+
+        // Watermarks survive reopening the database.
+        drop(conn);
+        drop(db);
+        let db = Db::for_write(temp_db.path(), DbArgs::default())
+            .await
+            .unwrap();
+        let mut conn = db.connect().await.unwrap();
+        let watermark = conn.committer_watermark("a").await.unwrap().unwrap();
+        assert_eq!(watermark.checkpoint_hi_inclusive, 15);
+    }
+}
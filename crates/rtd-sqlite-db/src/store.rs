@@ -0,0 +1,264 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
+use diesel::connection::AnsiTransactionManager;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Timestamp};
+use diesel_async::sync_connection_wrapper::SyncTransactionManagerWrapper;
+use diesel_async::{RunQueryDsl, TransactionManager};
+use scoped_futures::ScopedBoxFuture;
+use rtd_indexer_alt_framework_store_traits as store;
+
+use crate::model::StoredWatermark;
+use crate::schema::watermarks;
+use crate::{Connection, Db};
+
+pub use rtd_indexer_alt_framework_store_traits::Store;
+
+/// The database's current time, in UTC, with millisecond precision (`CURRENT_TIMESTAMP` only has
+/// second precision).
+const NOW: &str = "strftime('%Y-%m-%d %H:%M:%f', 'now')";
+
+#[async_trait]
+impl store::Connection for Connection<'_> {
+    async fn init_watermark(
+        &mut self,
+        pipeline_task: &str,
+        default_next_checkpoint: u64,
+    ) -> anyhow::Result<Option<u64>> {
+        let Some(checkpoint_hi_inclusive) = default_next_checkpoint.checked_sub(1) else {
+            // Do not create a watermark record with checkpoint_hi_inclusive = -1.
+            return Ok(self
+                .committer_watermark(pipeline_task)
+                .await?
+                .map(|w| w.checkpoint_hi_inclusive));
+        };
+
+        let stored_watermark = StoredWatermark {
+            pipeline: pipeline_task.to_string(),
+            epoch_hi_inclusive: 0,
+            checkpoint_hi_inclusive: checkpoint_hi_inclusive as i64,
+            tx_hi: 0,
+            timestamp_ms_hi_inclusive: 0,
+            reader_lo: default_next_checkpoint as i64,
+            pruner_timestamp: Utc::now().naive_utc(),
+            pruner_hi: default_next_checkpoint as i64,
+        };
+
+        use diesel::upsert::excluded;
+        let checkpoint_hi_inclusive: i64 = diesel::insert_into(watermarks::table)
+            .values(&stored_watermark)
+            .on_conflict(watermarks::pipeline)
+            // Use `do_update` instead of `do_nothing` to return the existing row with `returning`,
+            // setting the pipeline to itself, so that nothing changes.
+            .do_update()
+            .set(watermarks::pipeline.eq(excluded(watermarks::pipeline)))
+            .returning(watermarks::checkpoint_hi_inclusive)
+            .get_result(self)
+            .await?;
+
+        Ok(Some(checkpoint_hi_inclusive as u64))
+    }
+
+    async fn committer_watermark(
+        &mut self,
+        pipeline_task: &str,
+    ) -> anyhow::Result<Option<store::CommitterWatermark>> {
+        let watermark: Option<(i64, i64, i64, i64)> = watermarks::table
+            .select((
+                watermarks::epoch_hi_inclusive,
+                watermarks::checkpoint_hi_inclusive,
+                watermarks::tx_hi,
+                watermarks::timestamp_ms_hi_inclusive,
+            ))
+            .filter(watermarks::pipeline.eq(pipeline_task))
+            .first(self)
+            .await
+            .optional()?;
+
+        Ok(watermark.map(|watermark| store::CommitterWatermark {
+            epoch_hi_inclusive: watermark.0 as u64,
+            checkpoint_hi_inclusive: watermark.1 as u64,
+            tx_hi: watermark.2 as u64,
+            timestamp_ms_hi_inclusive: watermark.3 as u64,
+        }))
+    }
+
+    async fn reader_watermark(
+        &mut self,
+        pipeline: &'static str,
+    ) -> anyhow::Result<Option<store::ReaderWatermark>> {
+        let watermark: Option<(i64, i64)> = watermarks::table
+            .select((watermarks::checkpoint_hi_inclusive, watermarks::reader_lo))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .first(self)
+            .await
+            .optional()?;
+
+        Ok(watermark.map(|watermark| store::ReaderWatermark {
+            checkpoint_hi_inclusive: watermark.0 as u64,
+            reader_lo: watermark.1 as u64,
+        }))
+    }
+
+    async fn pruner_watermark(
+        &mut self,
+        pipeline: &'static str,
+        delay: Duration,
+    ) -> anyhow::Result<Option<store::PrunerWatermark>> {
+        //     |---------- + delay ---------------------|
+        //                             |--- wait_for ---|
+        //     |-----------------------|----------------|
+        //     ^                       ^
+        //     pruner_timestamp        now
+        //
+        // Julian days are converted to milliseconds.
+        let wait_for = sql::<BigInt>("CAST(")
+            .bind::<BigInt, _>(delay.as_millis() as i64)
+            .sql(" + (julianday(pruner_timestamp) - julianday('now')) * 86400000 AS INTEGER)");
+
+        let watermark: Option<(i64, i64, i64)> = watermarks::table
+            .select((wait_for, watermarks::pruner_hi, watermarks::reader_lo))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .first(self)
+            .await
+            .optional()?;
+
+        Ok(watermark.map(|watermark| store::PrunerWatermark {
+            wait_for_ms: watermark.0,
+            pruner_hi: watermark.1 as u64,
+            reader_lo: watermark.2 as u64,
+        }))
+    }
+
+    async fn set_committer_watermark(
+        &mut self,
+        pipeline_task: &str,
+        watermark: store::CommitterWatermark,
+    ) -> anyhow::Result<bool> {
+        let stored_watermark = StoredWatermark {
+            pipeline: pipeline_task.to_string(),
+            epoch_hi_inclusive: watermark.epoch_hi_inclusive as i64,
+            checkpoint_hi_inclusive: watermark.checkpoint_hi_inclusive as i64,
+            tx_hi: watermark.tx_hi as i64,
+            timestamp_ms_hi_inclusive: watermark.timestamp_ms_hi_inclusive as i64,
+            reader_lo: 0,
+            pruner_timestamp: NaiveDateTime::UNIX_EPOCH,
+            pruner_hi: 0,
+        };
+
+        let inserted = diesel::insert_into(watermarks::table)
+            .values(&stored_watermark)
+            .on_conflict(watermarks::pipeline)
+            .do_nothing()
+            .execute(self)
+            .await?;
+        if inserted > 0 {
+            return Ok(true);
+        }
+
+        // There is an existing entry, so only write the new `hi` values. Diesel does not support
+        // a `WHERE` clause on upserts for SQLite, hence the separate update.
+        Ok(diesel::update(watermarks::table)
+            .set((
+                watermarks::epoch_hi_inclusive.eq(stored_watermark.epoch_hi_inclusive),
+                watermarks::checkpoint_hi_inclusive.eq(stored_watermark.checkpoint_hi_inclusive),
+                watermarks::tx_hi.eq(stored_watermark.tx_hi),
+                watermarks::timestamp_ms_hi_inclusive
+                    .eq(stored_watermark.timestamp_ms_hi_inclusive),
+            ))
+            .filter(watermarks::pipeline.eq(pipeline_task))
+            .filter(
+                watermarks::checkpoint_hi_inclusive.lt(stored_watermark.checkpoint_hi_inclusive),
+            )
+            .execute(self)
+            .await?
+            > 0)
+    }
+
+    async fn set_reader_watermark(
+        &mut self,
+        pipeline: &'static str,
+        reader_lo: u64,
+    ) -> anyhow::Result<bool> {
+        Ok(diesel::update(watermarks::table)
+            .set((
+                watermarks::reader_lo.eq(reader_lo as i64),
+                watermarks::pruner_timestamp.eq(sql::<Timestamp>(NOW)),
+            ))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .filter(watermarks::reader_lo.lt(reader_lo as i64))
+            .execute(self)
+            .await?
+            > 0)
+    }
+
+    async fn set_pruner_watermark(
+        &mut self,
+        pipeline: &'static str,
+        pruner_hi: u64,
+    ) -> anyhow::Result<bool> {
+        Ok(diesel::update(watermarks::table)
+            .set(watermarks::pruner_hi.eq(pruner_hi as i64))
+            .filter(watermarks::pipeline.eq(pipeline))
+            .execute(self)
+            .await?
+            > 0)
+    }
+}
+
+#[async_trait]
+impl store::Store for Db {
+    type Connection<'c> = Connection<'c>;
+
+    async fn connect<'c>(&'c self) -> anyhow::Result<Self::Connection<'c>> {
+        Ok(Connection(self.pool.get().await?))
+    }
+}
+
+#[async_trait]
+impl store::TransactionalStore for Db {
+    /// Runs `f` in an immediate transaction, which takes the database's write lock up-front. A
+    /// deferred transaction that reads before it writes fails outright (rather than waiting out
+    /// the busy timeout) if another connection writes in the meantime.
+    ///
+    /// `AsyncConnection::transaction` only begins deferred transactions, so this begins the
+    /// transaction the way `SyncConnectionWrapper::immediate_transaction` does, but on the pooled
+    /// connection that `f` expects. The transaction is still tracked by the connection's
+    /// transaction manager, so a connection whose transaction is left open (because `f` was
+    /// cancelled or panicked, or the commit failed) is treated as broken, and closed rather than
+    /// returned to the pool holding the write lock.
+    async fn transaction<'a, R, F>(&self, f: F) -> anyhow::Result<R>
+    where
+        R: Send + 'a,
+        F: Send + 'a,
+        F: for<'r> FnOnce(
+            &'r mut Self::Connection<'_>,
+        ) -> ScopedBoxFuture<'a, 'r, anyhow::Result<R>>,
+    {
+        type Manager = SyncTransactionManagerWrapper<AnsiTransactionManager>;
+
+        let mut conn = self.connect().await?;
+        conn.spawn_blocking(|conn| {
+            AnsiTransactionManager::begin_transaction_sql(conn, "BEGIN IMMEDIATE")
+        })
+        .await?;
+        match f(&mut conn).await {
+            Ok(result) => {
+                Manager::commit_transaction(&mut **conn).await?;
+                Ok(result)
+            }
+            Err(e) => {
+                Manager::rollback_transaction(&mut **conn).await?;
+                Err(e)
+            }
+        }
+    }
+}
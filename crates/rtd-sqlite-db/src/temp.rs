@@ -0,0 +1,31 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::{Path, PathBuf};
+
+/// A temporary, local SQLite database file.
+pub struct TempDb {
+    path: PathBuf,
+
+    // Directory holding the database file, and its WAL and shared memory files.
+    //
+    // On drop the directory will be cleaned and its contents deleted.
+    _dir: tempfile::TempDir,
+}
+
+impl TempDb {
+    /// Create a new temporary database. The database file itself is created by the first
+    /// connection to it.
+    pub fn new() -> anyhow::Result<Self> {
+        let dir = tempfile::TempDir::new()?;
+        Ok(Self {
+            path: dir.path().join("db.sqlite"),
+            _dir: dir,
+        })
+    }
+
+    /// The path of the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
@@ -238,6 +238,8 @@ impl ValidatorConfigBuilder {
             name_service_registry_id: None,
             name_service_reverse_registry_id: None,
             transaction_deny_config: Default::default(),
+            transaction_deny_config_path: None,
+            transaction_deny_history_capacity: None,
             certificate_deny_config: Default::default(),
             state_debug_dump_config: Default::default(),
             state_archive_read_config: vec![],
@@ -576,6 +578,8 @@ impl FullnodeConfigBuilder {
             name_service_registry_id: None,
             name_service_reverse_registry_id: None,
             transaction_deny_config: Default::default(),
+            transaction_deny_config_path: None,
+            transaction_deny_history_capacity: None,
             certificate_deny_config: Default::default(),
             state_debug_dump_config: Default::default(),
             state_archive_read_config: vec![],
@@ -0,0 +1,647 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The chain served by the test validator: a [`Simulacrum`] persisted in the data directory,
+//! shared between the gRPC service and the control API.
+//!
+//! The data directory holds the chain itself, in `chain-<generation>`, and the snapshots taken
+//! through the control API, in `snapshots/<id>`. Reverting to a snapshot copies it to the next
+//! generation, so that the snapshot can be reverted to again.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, ensure};
+use move_core_types::annotated_value::MoveTypeLayout;
+use move_core_types::language_storage::StructTag;
+use parking_lot::RwLock;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rtd_protocol_config::ProtocolVersion;
+use rtd_types::base_types::{EpochId, ObjectID, RtdAddress, VersionNumber};
+use rtd_types::committee::Committee;
+use rtd_types::digests::{
+    ChainIdentifier, CheckpointContentsDigest, CheckpointDigest, TransactionDigest,
+};
+use rtd_types::effects::{TransactionEffects, TransactionEffectsAPI, TransactionEvents};
+use rtd_types::error::{RtdError, RtdErrorKind};
+use rtd_types::event::Event;
+use rtd_types::messages_checkpoint::{
+    CheckpointContents, CheckpointSequenceNumber, VerifiedCheckpoint,
+    VersionedFullCheckpointContents,
+};
+use rtd_types::object::{Object, Owner};
+use rtd_types::quorum_driver_types::{
+    EffectsFinalityInfo, ExecuteTransactionRequestV3, ExecuteTransactionResponseV3,
+    FinalizedEffects, QuorumDriverError,
+};
+use rtd_types::storage::error::Result as StorageResult;
+use rtd_types::storage::{
+    BalanceInfo, BalanceIterator, CoinInfo, DynamicFieldKey, EpochInfo, ObjectKey, ObjectStore,
+    OwnedObjectInfo, ReadStore, RpcIndexes, RpcStateReader, TransactionInfo,
+};
+use rtd_types::transaction::{TransactionData, VerifiedTransaction};
+use rtd_types::transaction_executor::{
+    SimulateTransactionResult, TransactionChecks, TransactionExecutor,
+};
+use simulacrum::{AdvanceEpochConfig, PersistedStore, Simulacrum};
+use tracing::info;
+use typed_store::TypedStoreError;
+
+const CHAIN_DIR_PREFIX: &str = "chain-";
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+pub struct LocalChain {
+    data_dir: PathBuf,
+    /// Whether a checkpoint is created after every transaction, so that clients never wait for
+    /// one.
+    auto_checkpoint: bool,
+    state: RwLock<State>,
+}
+
+struct State {
+    sim: Simulacrum<StdRng, PersistedStore>,
+    /// The chain is persisted in `chain-<generation>`. Reverting to a snapshot moves the chain
+    /// to the next generation.
+    generation: u64,
+    next_snapshot_id: u64,
+}
+
+impl LocalChain {
+    /// Open the chain persisted in `data_dir`, or create a new one there with `protocol_version`
+    /// if there is none. The chain's randomness is seeded from `seed`, so that a new chain is
+    /// the same on every run.
+    ///
+    /// Snapshots taken by a previous run are discarded.
+    pub fn open_or_create(
+        data_dir: PathBuf,
+        seed: u64,
+        protocol_version: ProtocolVersion,
+        auto_checkpoint: bool,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(&data_dir)
+            .with_context(|| format!("Failed to create directory {}", data_dir.display()))?;
+        let snapshots_dir = data_dir.join(SNAPSHOTS_DIR);
+        if snapshots_dir.exists() {
+            std::fs::remove_dir_all(&snapshots_dir).with_context(|| {
+                format!("Failed to remove snapshots in {}", snapshots_dir.display())
+            })?;
+        }
+
+        let rng = StdRng::seed_from_u64(seed);
+        let generations = chain_generations(&data_dir)?;
+        let (sim, generation) = match generations.last() {
+            Some(&generation) => {
+                let path = chain_dir(&data_dir, generation);
+                info!(path = %path.display(), "Opening chain");
+                (Simulacrum::open(rng, &path)?, generation)
+            }
+            None => {
+                let path = chain_dir(&data_dir, 0);
+                info!(path = %path.display(), "Creating chain");
+                (Simulacrum::new_persisted(rng, protocol_version, &path)?, 0)
+            }
+        };
+
+        // Older generations are left behind by a revert that did not complete.
+        for stale in generations.iter().filter(|g| **g < generation) {
+            std::fs::remove_dir_all(chain_dir(&data_dir, *stale))?;
+        }
+
+        Ok(Self {
+            data_dir,
+            auto_checkpoint,
+            state: RwLock::new(State {
+                sim,
+                generation,
+                next_snapshot_id: 0,
+            }),
+        })
+    }
+
+    /// Advance the clock by `duration`, and return the new timestamp.
+    pub fn advance_clock(&self, duration: Duration) -> u64 {
+        let mut state = self.state.write();
+        state.sim.advance_clock(duration);
+        if self.auto_checkpoint {
+            state.sim.create_checkpoint();
+        }
+        state.sim.store().get_clock().timestamp_ms()
+    }
+
+    /// Advance to the next epoch, and return the new epoch along with the last checkpoint of the
+    /// previous one.
+    pub fn advance_epoch(&self) -> (EpochId, VerifiedCheckpoint) {
+        let mut state = self.state.write();
+        state.sim.advance_epoch(AdvanceEpochConfig::default());
+        let checkpoint = state.sim.store().get_highest_checkpint().unwrap();
+        (state.sim.epoch_start_state().epoch(), checkpoint)
+    }
+
+    /// Create a checkpoint with the transactions executed since the last one.
+    pub fn create_checkpoint(&self) -> VerifiedCheckpoint {
+        self.state.write().sim.create_checkpoint()
+    }
+
+    /// Send `amount` MIST to `address` in a new coin, and return the funding transaction along
+    /// with the coin.
+    pub fn fund_address(
+        &self,
+        address: RtdAddress,
+        amount: u64,
+    ) -> Result<(TransactionDigest, ObjectID)> {
+        let mut state = self.state.write();
+        let effects = state.sim.request_gas(address, amount)?;
+        if self.auto_checkpoint {
+            state.sim.create_checkpoint();
+        }
+
+        ensure!(
+            effects.status().is_ok(),
+            "Funding {address} failed: {:?}",
+            effects.status()
+        );
+        let ((coin, _, _), _) = effects
+            .created()
+            .into_iter()
+            .find(|(_, owner)| *owner == Owner::AddressOwner(address))
+            .ok_or_else(|| anyhow!("Funding {address} did not create a coin"))?;
+        Ok((*effects.transaction_digest(), coin))
+    }
+
+    /// Save the current state of the chain, and return the ID to revert to it with.
+    pub fn snapshot(&self) -> Result<u64> {
+        let mut state = self.state.write();
+        let id = state.next_snapshot_id;
+        // The copy is only opened to be written, its randomness is never used.
+        state
+            .sim
+            .clone_to(StdRng::seed_from_u64(0), &self.snapshot_dir(id))?;
+        state.next_snapshot_id += 1;
+        info!(id, "Took snapshot");
+        Ok(id)
+    }
+
+    pub fn has_snapshot(&self, id: u64) -> bool {
+        self.snapshot_dir(id).exists()
+    }
+
+    /// Revert the chain to the state saved in snapshot `id`. The snapshot is kept, and can be
+    /// reverted to again.
+    pub fn revert(&self, id: u64) -> Result<()> {
+        let snapshot_dir = self.snapshot_dir(id);
+        ensure!(snapshot_dir.exists(), "Snapshot {id} does not exist");
+
+        let mut state = self.state.write();
+        let snapshot =
+            Simulacrum::<StdRng, PersistedStore>::open(StdRng::seed_from_u64(0), &snapshot_dir)?;
+        let rng = StdRng::from_rng(state.sim.rng())?;
+        let generation = state.generation + 1;
+        let sim = snapshot.clone_to(rng, &chain_dir(&self.data_dir, generation))?;
+
+        let previous = std::mem::replace(&mut state.sim, sim);
+        let previous_generation = std::mem::replace(&mut state.generation, generation);
+        drop(previous);
+        std::fs::remove_dir_all(chain_dir(&self.data_dir, previous_generation))?;
+
+        info!(id, "Reverted to snapshot");
+        Ok(())
+    }
+
+    /// Flush the chain to disk.
+    pub fn flush(&self) -> Result<()> {
+        self.state.read().sim.flush()
+    }
+
+    fn snapshot_dir(&self, id: u64) -> PathBuf {
+        self.data_dir.join(SNAPSHOTS_DIR).join(id.to_string())
+    }
+}
+
+fn chain_dir(data_dir: &Path, generation: u64) -> PathBuf {
+    data_dir.join(format!("{CHAIN_DIR_PREFIX}{generation}"))
+}
+
+/// The generations of the chain in `data_dir`, in ascending order.
+fn chain_generations(data_dir: &Path) -> Result<Vec<u64>> {
+    let mut generations = vec![];
+    for entry in std::fs::read_dir(data_dir)? {
+        let name = entry?.file_name();
+        if let Some(generation) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(CHAIN_DIR_PREFIX))
+            .and_then(|generation| generation.parse().ok())
+        {
+            generations.push(generation);
+        }
+    }
+    generations.sort();
+    Ok(generations)
+}
+
+/// Map an error from executing a transaction to the error a fullnode would return for it.
+fn quorum_driver_error(error: anyhow::Error) -> QuorumDriverError {
+    match error.downcast::<RtdError>() {
+        Ok(error) => match error.as_inner() {
+            RtdErrorKind::InvalidSignature { .. } => QuorumDriverError::InvalidUserSignature(error),
+            RtdErrorKind::UserInputError { .. } => {
+                QuorumDriverError::NonRecoverableTransactionError {
+                    errors: vec![(error, 0, vec![])],
+                }
+            }
+            _ => QuorumDriverError::QuorumDriverInternalError(error),
+        },
+        Err(error) => QuorumDriverError::QuorumDriverInternalError(
+            RtdErrorKind::Unknown(error.to_string()).into(),
+        ),
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionExecutor for LocalChain {
+    async fn execute_transaction(
+        &self,
+        request: ExecuteTransactionRequestV3,
+        _client_addr: Option<SocketAddr>,
+    ) -> Result<ExecuteTransactionResponseV3, QuorumDriverError> {
+        let mut state = self.state.write();
+        let (effects, _) = state
+            .sim
+            .execute_transaction(request.transaction)
+            .map_err(quorum_driver_error)?;
+        let finality_info = if self.auto_checkpoint {
+            let checkpoint = state.sim.create_checkpoint();
+            EffectsFinalityInfo::Checkpointed(checkpoint.epoch, checkpoint.sequence_number)
+        } else {
+            EffectsFinalityInfo::QuorumExecuted(effects.executed_epoch())
+        };
+
+        let store = state.sim.store();
+        let events = request
+            .include_events
+            .then(|| store.get_transaction_events(effects.transaction_digest()))
+            .flatten();
+        let input_objects = request.include_input_objects.then(|| {
+            effects
+                .modified_at_versions()
+                .into_iter()
+                .filter_map(|(id, version)| store.get_object_at_version(&id, version))
+                .collect()
+        });
+        let output_objects = request.include_output_objects.then(|| {
+            effects
+                .written()
+                .into_iter()
+                .filter_map(|(id, version, _)| store.get_object_at_version(&id, version))
+                .collect()
+        });
+
+        Ok(ExecuteTransactionResponseV3 {
+            effects: FinalizedEffects {
+                effects,
+                finality_info,
+            },
+            events,
+            input_objects,
+            output_objects,
+            auxiliary_data: None,
+        })
+    }
+
+    fn simulate_transaction(
+        &self,
+        transaction: TransactionData,
+        checks: TransactionChecks,
+    ) -> Result<SimulateTransactionResult, RtdError> {
+        self.state
+            .read()
+            .sim
+            .simulate_transaction(transaction, checks)
+    }
+}
+
+impl ObjectStore for LocalChain {
+    fn get_object(&self, object_id: &ObjectID) -> Option<Object> {
+        self.state.read().sim.get_object(object_id)
+    }
+
+    fn get_object_by_key(&self, object_id: &ObjectID, version: VersionNumber) -> Option<Object> {
+        self.state.read().sim.get_object_by_key(object_id, version)
+    }
+}
+
+impl ReadStore for LocalChain {
+    fn get_committee(&self, epoch: EpochId) -> Option<Arc<Committee>> {
+        self.state.read().sim.get_committee(epoch)
+    }
+
+    fn get_latest_checkpoint(&self) -> StorageResult<VerifiedCheckpoint> {
+        self.state.read().sim.get_latest_checkpoint()
+    }
+
+    fn get_latest_epoch_id(&self) -> StorageResult<EpochId> {
+        self.state.read().sim.get_latest_epoch_id()
+    }
+
+    fn get_highest_verified_checkpoint(&self) -> StorageResult<VerifiedCheckpoint> {
+        self.state.read().sim.get_highest_verified_checkpoint()
+    }
+
+    fn get_highest_synced_checkpoint(&self) -> StorageResult<VerifiedCheckpoint> {
+        self.state.read().sim.get_highest_synced_checkpoint()
+    }
+
+    fn get_lowest_available_checkpoint(&self) -> StorageResult<CheckpointSequenceNumber> {
+        self.state.read().sim.get_lowest_available_checkpoint()
+    }
+
+    fn get_checkpoint_by_digest(&self, digest: &CheckpointDigest) -> Option<VerifiedCheckpoint> {
+        self.state.read().sim.get_checkpoint_by_digest(digest)
+    }
+
+    fn get_checkpoint_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> Option<VerifiedCheckpoint> {
+        self.state
+            .read()
+            .sim
+            .get_checkpoint_by_sequence_number(sequence_number)
+    }
+
+    fn get_checkpoint_contents_by_digest(
+        &self,
+        digest: &CheckpointContentsDigest,
+    ) -> Option<CheckpointContents> {
+        self.state
+            .read()
+            .sim
+            .get_checkpoint_contents_by_digest(digest)
+    }
+
+    fn get_checkpoint_contents_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> Option<CheckpointContents> {
+        self.state
+            .read()
+            .sim
+            .get_checkpoint_contents_by_sequence_number(sequence_number)
+    }
+
+    fn get_transaction(&self, tx_digest: &TransactionDigest) -> Option<Arc<VerifiedTransaction>> {
+        self.state.read().sim.get_transaction(tx_digest)
+    }
+
+    fn get_transaction_effects(&self, tx_digest: &TransactionDigest) -> Option<TransactionEffects> {
+        self.state.read().sim.get_transaction_effects(tx_digest)
+    }
+
+    fn get_events(&self, tx_digest: &TransactionDigest) -> Option<TransactionEvents> {
+        self.state.read().sim.get_events(tx_digest)
+    }
+
+    fn get_full_checkpoint_contents(
+        &self,
+        sequence_number: Option<CheckpointSequenceNumber>,
+        digest: &CheckpointContentsDigest,
+    ) -> Option<VersionedFullCheckpointContents> {
+        self.state
+            .read()
+            .sim
+            .get_full_checkpoint_contents(sequence_number, digest)
+    }
+
+    fn get_unchanged_loaded_runtime_objects(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<Vec<ObjectKey>> {
+        self.state
+            .read()
+            .sim
+            .get_unchanged_loaded_runtime_objects(digest)
+    }
+
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.state.read().sim.get_transaction_checkpoint(digest)
+    }
+}
+
+impl RpcStateReader for LocalChain {
+    fn get_lowest_available_checkpoint_objects(&self) -> StorageResult<CheckpointSequenceNumber> {
+        self.state
+            .read()
+            .sim
+            .get_lowest_available_checkpoint_objects()
+    }
+
+    fn get_chain_identifier(&self) -> StorageResult<ChainIdentifier> {
+        self.state.read().sim.get_chain_identifier()
+    }
+
+    fn indexes(&self) -> Option<&dyn RpcIndexes> {
+        Some(self)
+    }
+
+    fn get_struct_layout(&self, struct_tag: &StructTag) -> StorageResult<Option<MoveTypeLayout>> {
+        self.state.read().sim.get_struct_layout(struct_tag)
+    }
+}
+
+// The iterators of the chain's indexes borrow the chain, so they are collected before the lock is
+// released.
+impl RpcIndexes for LocalChain {
+    fn get_epoch_info(&self, epoch: EpochId) -> StorageResult<Option<EpochInfo>> {
+        self.state.read().sim.get_epoch_info(epoch)
+    }
+
+    fn get_transaction_info(
+        &self,
+        digest: &TransactionDigest,
+    ) -> StorageResult<Option<TransactionInfo>> {
+        self.state.read().sim.get_transaction_info(digest)
+    }
+
+    fn owned_objects_iter(
+        &self,
+        owner: RtdAddress,
+        object_type: Option<StructTag>,
+        cursor: Option<OwnedObjectInfo>,
+    ) -> StorageResult<Box<dyn Iterator<Item = Result<OwnedObjectInfo, TypedStoreError>> + '_>>
+    {
+        let state = self.state.read();
+        let objects: Vec<_> = state
+            .sim
+            .owned_objects_iter(owner, object_type, cursor)?
+            .collect();
+        Ok(Box::new(objects.into_iter()))
+    }
+
+    fn dynamic_field_iter(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> StorageResult<Box<dyn Iterator<Item = Result<DynamicFieldKey, TypedStoreError>> + '_>>
+    {
+        let state = self.state.read();
+        let fields: Vec<_> = state.sim.dynamic_field_iter(parent, cursor)?.collect();
+        Ok(Box::new(fields.into_iter()))
+    }
+
+    fn get_coin_info(&self, coin_type: &StructTag) -> StorageResult<Option<CoinInfo>> {
+        self.state.read().sim.get_coin_info(coin_type)
+    }
+
+    fn get_balance(
+        &self,
+        owner: &RtdAddress,
+        coin_type: &StructTag,
+    ) -> StorageResult<Option<BalanceInfo>> {
+        self.state.read().sim.get_balance(owner, coin_type)
+    }
+
+    fn balance_iter(
+        &self,
+        owner: &RtdAddress,
+        cursor: Option<(RtdAddress, StructTag)>,
+    ) -> StorageResult<BalanceIterator<'_>> {
+        let state = self.state.read();
+        let balances: Vec<_> = state.sim.balance_iter(owner, cursor)?.collect();
+        Ok(Box::new(balances.into_iter()))
+    }
+
+    fn package_versions_iter(
+        &self,
+        original_id: ObjectID,
+        cursor: Option<u64>,
+    ) -> StorageResult<Box<dyn Iterator<Item = Result<(u64, ObjectID), TypedStoreError>> + '_>>
+    {
+        let state = self.state.read();
+        let versions: Vec<_> = state
+            .sim
+            .package_versions_iter(original_id, cursor)?
+            .collect();
+        Ok(Box::new(versions.into_iter()))
+    }
+
+    fn get_highest_indexed_checkpoint_seq_number(
+        &self,
+    ) -> StorageResult<Option<CheckpointSequenceNumber>> {
+        self.state
+            .read()
+            .sim
+            .get_highest_indexed_checkpoint_seq_number()
+    }
+
+    fn authenticated_event_iter(
+        &self,
+        stream_id: RtdAddress,
+        start_checkpoint: u64,
+        start_accumulator_version: Option<u64>,
+        start_transaction_idx: Option<u32>,
+        start_event_idx: Option<u32>,
+        end_checkpoint: u64,
+        limit: u32,
+    ) -> StorageResult<
+        Box<dyn Iterator<Item = Result<(u64, u64, u32, u32, Event), TypedStoreError>> + '_>,
+    > {
+        let state = self.state.read();
+        let events: Vec<_> = state
+            .sim
+            .authenticated_event_iter(
+                stream_id,
+                start_checkpoint,
+                start_accumulator_version,
+                start_transaction_idx,
+                start_event_idx,
+                end_checkpoint,
+                limit,
+            )?
+            .collect();
+        Ok(Box::new(events.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rtd_rpc_api::RpcService;
+    use rtd_rpc_api::proto::rtd::rpc::v2::state_service_server::StateService;
+    use rtd_rpc_api::proto::rtd::rpc::v2::{
+        GetBalanceRequest, ListBalancesRequest, ListOwnedObjectsRequest,
+    };
+    use rtd_types::gas_coin::{GAS, MIST_PER_RTD};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn balances_and_owned_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let chain = Arc::new(
+            LocalChain::open_or_create(dir.path().to_owned(), 0, ProtocolVersion::MAX, true)
+                .unwrap(),
+        );
+        let service = RpcService::new(chain.clone());
+        let address = RtdAddress::random_for_testing_only();
+        let coin_type = GAS::type_().to_canonical_string(true);
+
+        let (digest, small) = chain.fund_address(address, MIST_PER_RTD).unwrap();
+        let checkpoint = chain.get_latest_checkpoint().unwrap();
+        let (_, large) = chain.fund_address(address, 2 * MIST_PER_RTD).unwrap();
+        assert_eq!(
+            chain.get_transaction_checkpoint(&digest),
+            Some(checkpoint.sequence_number)
+        );
+
+        let mut request = GetBalanceRequest::default();
+        request.owner = Some(address.to_string());
+        request.coin_type = Some(coin_type.clone());
+        let response = service
+            .get_balance(tonic::Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.balance.unwrap().balance, Some(3 * MIST_PER_RTD));
+
+        let mut request = ListBalancesRequest::default();
+        request.owner = Some(address.to_string());
+        let response = service
+            .list_balances(tonic::Request::new(request))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.balances.len(), 1);
+        assert_eq!(response.balances[0].balance, Some(3 * MIST_PER_RTD));
+
+        // Coins are listed from the greatest balance to the least, one page at a time.
+        let mut owned = vec![];
+        let mut page_token = None;
+        loop {
+            let mut request = ListOwnedObjectsRequest::default();
+            request.owner = Some(address.to_string());
+            request.page_size = Some(1);
+            request.page_token = page_token;
+            let response = service
+                .list_owned_objects(tonic::Request::new(request))
+                .await
+                .unwrap()
+                .into_inner();
+            owned.extend(response.objects.into_iter().map(|object| object.object_id));
+            page_token = response.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+        assert_eq!(
+            owned,
+            vec![
+                Some(large.to_canonical_string(true)),
+                Some(small.to_canonical_string(true)),
+            ]
+        );
+    }
+}
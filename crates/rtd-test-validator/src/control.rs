@@ -0,0 +1,205 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! JSON-RPC endpoints to control the local chain from tests, which a real network does not
+//! offer: moving time forward, creating checkpoints on demand, minting funds, and saving and
+//! restoring the state of the chain.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE};
+use rtd_types::base_types::{EpochId, ObjectID, RtdAddress};
+use rtd_types::digests::{CheckpointDigest, TransactionDigest};
+use rtd_types::messages_checkpoint::{CheckpointSequenceNumber, VerifiedCheckpoint};
+use rtd_types::rtd_serde::BigInt;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::LocalChain;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockResponse {
+    pub timestamp_ms: BigInt<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointResponse {
+    pub sequence_number: BigInt<CheckpointSequenceNumber>,
+    pub digest: CheckpointDigest,
+    pub epoch: BigInt<EpochId>,
+    pub timestamp_ms: BigInt<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochResponse {
+    pub epoch: BigInt<EpochId>,
+    /// The last checkpoint of the previous epoch.
+    pub checkpoint: CheckpointResponse,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundResponse {
+    pub digest: TransactionDigest,
+    pub coin_id: ObjectID,
+}
+
+impl From<VerifiedCheckpoint> for CheckpointResponse {
+    fn from(checkpoint: VerifiedCheckpoint) -> Self {
+        Self {
+            sequence_number: checkpoint.sequence_number.into(),
+            digest: *checkpoint.digest(),
+            epoch: checkpoint.epoch.into(),
+            timestamp_ms: checkpoint.timestamp_ms.into(),
+        }
+    }
+}
+
+#[rpc(server, namespace = "simulacrum")]
+pub trait ControlApi {
+    /// Advance the clock by `duration_ms` milliseconds, and return the new time.
+    #[method(name = "advanceClock", blocking)]
+    fn advance_clock(&self, duration_ms: BigInt<u64>) -> RpcResult<ClockResponse>;
+
+    /// Advance to the next epoch.
+    #[method(name = "advanceEpoch", blocking)]
+    fn advance_epoch(&self) -> RpcResult<EpochResponse>;
+
+    /// Create a checkpoint with the transactions executed since the last one.
+    #[method(name = "createCheckpoint", blocking)]
+    fn create_checkpoint(&self) -> RpcResult<CheckpointResponse>;
+
+    /// Send `amount` MIST to `address`, in a new coin.
+    #[method(name = "fundAddress", blocking)]
+    fn fund_address(&self, address: RtdAddress, amount: BigInt<u64>) -> RpcResult<FundResponse>;
+
+    /// Save the state of the chain, and return the ID of the snapshot to revert to it with.
+    #[method(name = "snapshot", blocking)]
+    fn snapshot(&self) -> RpcResult<BigInt<u64>>;
+
+    /// Revert the chain to the state saved in a snapshot. The snapshot is kept.
+    #[method(name = "revert", blocking)]
+    fn revert(&self, snapshot_id: BigInt<u64>) -> RpcResult<()>;
+}
+
+pub struct Control(pub Arc<LocalChain>);
+
+impl ControlApiServer for Control {
+    fn advance_clock(&self, duration_ms: BigInt<u64>) -> RpcResult<ClockResponse> {
+        let Self(chain) = self;
+        let timestamp_ms = chain.advance_clock(Duration::from_millis(*duration_ms));
+        Ok(ClockResponse {
+            timestamp_ms: timestamp_ms.into(),
+        })
+    }
+
+    fn advance_epoch(&self) -> RpcResult<EpochResponse> {
+        let Self(chain) = self;
+        let (epoch, checkpoint) = chain.advance_epoch();
+        Ok(EpochResponse {
+            epoch: epoch.into(),
+            checkpoint: checkpoint.into(),
+        })
+    }
+
+    fn create_checkpoint(&self) -> RpcResult<CheckpointResponse> {
+        let Self(chain) = self;
+        Ok(chain.create_checkpoint().into())
+    }
+
+    fn fund_address(&self, address: RtdAddress, amount: BigInt<u64>) -> RpcResult<FundResponse> {
+        let Self(chain) = self;
+        let (digest, coin_id) = chain
+            .fund_address(address, *amount)
+            .map_err(internal_error)?;
+        Ok(FundResponse { digest, coin_id })
+    }
+
+    fn snapshot(&self) -> RpcResult<BigInt<u64>> {
+        let Self(chain) = self;
+        Ok(chain.snapshot().map_err(internal_error)?.into())
+    }
+
+    fn revert(&self, snapshot_id: BigInt<u64>) -> RpcResult<()> {
+        let Self(chain) = self;
+        if !chain.has_snapshot(*snapshot_id) {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                format!("Snapshot {snapshot_id} does not exist"),
+                None::<()>,
+            ));
+        }
+        chain.revert(*snapshot_id).map_err(internal_error)
+    }
+}
+
+fn internal_error(error: anyhow::Error) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, format!("{error:#}"), None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+    use rtd_protocol_config::ProtocolVersion;
+    use rtd_types::gas_coin::MIST_PER_RTD;
+    use rtd_types::storage::{ObjectStore, ReadStore};
+
+    use super::*;
+
+    fn open_chain(data_dir: &std::path::Path) -> Control {
+        let chain =
+            LocalChain::open_or_create(data_dir.to_owned(), 0, ProtocolVersion::MAX, true).unwrap();
+        Control(Arc::new(chain))
+    }
+
+    #[test]
+    fn snapshot_and_revert() {
+        let dir = tempfile::tempdir().unwrap();
+        let control = open_chain(dir.path());
+        let Control(chain) = &control;
+        let address = RtdAddress::random_for_testing_only();
+
+        let snapshot = control.snapshot().unwrap();
+        let funded = control.fund_address(address, MIST_PER_RTD.into()).unwrap();
+        assert!(chain.get_object(&funded.coin_id).is_some());
+
+        control.revert(snapshot).unwrap();
+        assert!(chain.get_object(&funded.coin_id).is_none());
+
+        // Snapshots can be reverted to more than once.
+        let funded = control.fund_address(address, MIST_PER_RTD.into()).unwrap();
+        control.revert(snapshot).unwrap();
+        assert!(chain.get_object(&funded.coin_id).is_none());
+        assert!(control.revert(1.into()).is_err());
+    }
+
+    #[test]
+    fn clock_and_epochs() {
+        let dir = tempfile::tempdir().unwrap();
+        let control = open_chain(dir.path());
+
+        let start = control.advance_clock(1_000.into()).unwrap();
+        let clock = control.advance_clock(500.into()).unwrap();
+        assert_eq!(*clock.timestamp_ms, *start.timestamp_ms + 500);
+
+        let epoch = control.advance_epoch().unwrap();
+        assert_eq!(*epoch.epoch, *epoch.checkpoint.epoch + 1);
+
+        // The chain is persisted, and picks up where it left off.
+        drop(control);
+        let control = open_chain(dir.path());
+        control.advance_clock(1.into()).unwrap();
+        let Control(chain) = &control;
+        let checkpoint = chain.get_latest_checkpoint().unwrap();
+        assert_eq!(checkpoint.epoch, *epoch.epoch);
+        assert_eq!(
+            checkpoint.sequence_number,
+            *epoch.checkpoint.sequence_number + 1
+        );
+    }
+}
@@ -1,25 +1,114 @@
 // Copyright (c) LinkU Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-fn main() {
-    println!("rtd-test-validator binary has been deprecated in favor of rtd start, which is a more powerful command that allows you to start the local network with more options.
-
-How to install/build the rtd binary IF:
-    A: you only need the basic functionality, so just faucet and no persistence (no indexer, no GraphQL service), build from source as usual (cargo build --bin rtd) or download latest archive from release archives (starting from testnet v1.28.1 or devnet v1.29) and use rtd binary.
-    B: you need to also start an indexer (--with-indexer ), or a GraphQL service (--with-graphql), you either:
-    - download latest archive from release archives (starting from testnet v1.28.1 or devnet v1.29) and use rtd-pg binary (note that with v1.34.0 rtd-pg no longer exists in the release. Use `rtd` binary instead).
-  OR
-    - build from source. This requires to have libpq/postgresql dependencies installed (just as when using rtd-test-validator):
-        - cargo build --bin rtd
-        - cargo run --bin rtd -- start --with-faucet --force-regenesis --with-indexer --with-graphql
-
-Running the local network:
- - (Preferred) In the simplest form, you can replace rtd-test-validator with rtd start --with-faucet --force-regenesis. This will create a network from a new genesis and start a faucet (127.0.0.1:9123). This will not persist state.
- - Use the drop-in replacement script: rtd/scripts/rtd-test-validator.sh and pass in all the flags/options as you used to.
-
-Use rtd start --help to see all the flags and options, such as:
-  * --with-indexer --> to start the indexer on the default host and port. Note that this requires \
-a Postgres database to be running locally, or you need to set the different options to connect to a \
-remote indexer database.
-  * --with-graphql --> to start the GraphQL server on the default host and port");
+//! A local, deterministic, single-process chain for tests.
+//!
+//! The chain is a Simulacrum: transactions are executed as soon as they are submitted, and time
+//! only moves when told to. It is served over the standard gRPC API, alongside JSON-RPC control
+//! endpoints for tests to advance the clock and epochs, create checkpoints, fund addresses, and
+//! snapshot and revert the state of the chain.
+
+mod chain;
+mod control;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, bail};
+use clap::Parser;
+use jsonrpsee::server::ServerBuilder;
+use rtd_protocol_config::ProtocolVersion;
+use rtd_rpc_api::{RpcService, ServerVersion};
+use telemetry_subscribers::TelemetryConfig;
+use tracing::info;
+
+use crate::chain::LocalChain;
+use crate::control::{Control, ControlApiServer};
+
+bin_version::bin_version!();
+
+#[derive(Parser)]
+#[clap(name = env!("CARGO_BIN_NAME"), version = VERSION)]
+struct Args {
+    /// Address to serve the gRPC API on.
+    #[clap(long, default_value = "127.0.0.1:9000")]
+    rpc_address: SocketAddr,
+
+    /// Address to serve the JSON-RPC control endpoints on.
+    #[clap(long, default_value = "127.0.0.1:9001")]
+    control_address: SocketAddr,
+
+    /// Directory to persist the chain in. The chain in the directory is resumed if there is one.
+    /// Defaults to a temporary directory, removed on exit.
+    #[clap(long)]
+    data_dir: Option<PathBuf>,
+
+    /// Seed for the randomness of a new chain (its keys, and the IDs of the objects it creates).
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Protocol version of a new chain. Defaults to the latest.
+    #[clap(long)]
+    protocol_version: Option<u64>,
+
+    /// Only create checkpoints when requested through the control endpoints, instead of after
+    /// every transaction.
+    #[clap(long)]
+    manual_checkpoints: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let _guard = TelemetryConfig::new().with_env().init();
+    let args = Args::parse();
+
+    let temp_dir;
+    let data_dir = match args.data_dir {
+        Some(data_dir) => data_dir,
+        None => {
+            temp_dir = tempfile::tempdir()?;
+            temp_dir.path().to_owned()
+        }
+    };
+    let protocol_version = args
+        .protocol_version
+        .map(ProtocolVersion::new)
+        .unwrap_or(ProtocolVersion::MAX);
+    if protocol_version < ProtocolVersion::MIN || protocol_version > ProtocolVersion::MAX {
+        bail!(
+            "Protocol version {} is not supported by this binary (supported: {}..={})",
+            protocol_version.as_u64(),
+            ProtocolVersion::MIN.as_u64(),
+            ProtocolVersion::MAX.as_u64(),
+        );
+    }
+
+    let chain = Arc::new(LocalChain::open_or_create(
+        data_dir,
+        args.seed,
+        protocol_version,
+        !args.manual_checkpoints,
+    )?);
+
+    let control = ServerBuilder::new()
+        .http_only()
+        .build(args.control_address)
+        .await
+        .context("Failed to bind control endpoints")?
+        .start(Control(chain.clone()).into_rpc());
+    info!("Serving control endpoints on {}", args.control_address);
+
+    let mut rpc = RpcService::new(chain.clone());
+    rpc.with_server_version(ServerVersion::new(env!("CARGO_BIN_NAME"), VERSION));
+    rpc.with_executor(chain.clone());
+    info!("Serving gRPC API on {}", args.rpc_address);
+
+    tokio::select! {
+        _ = rpc.start_service(args.rpc_address) => {}
+        _ = tokio::signal::ctrl_c() => info!("Shutting down"),
+    }
+
+    control.stop()?;
+    chain.flush()
 }
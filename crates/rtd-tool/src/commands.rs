@@ -4,10 +4,10 @@
 #[cfg(not(tidehunter))]
 use crate::db_tool::{DbToolCommand, execute_db_tool_command, print_db_all_tables};
 use crate::{
-    ConciseObjectOutput, GroupedObjectOutput, SnapshotVerifyMode, VerboseObjectOutput,
-    check_completed_snapshot, download_db_snapshot, download_formal_snapshot,
-    get_latest_available_epoch, get_object, get_transaction_block, make_clients,
-    restore_from_db_checkpoint,
+    ConciseObjectOutput, GroupedObjectOutput, SnapshotDiffOutput, SnapshotVerifyMode,
+    VerboseObjectOutput, check_completed_snapshot, diff_formal_snapshots, download_db_snapshot,
+    download_formal_snapshot, get_latest_available_epoch, get_object, get_transaction_block,
+    make_clients, restore_from_db_checkpoint,
 };
 use anyhow::Result;
 use consensus_core::storage::{Store, rocksdb_store::RocksDBStore};
@@ -344,6 +344,87 @@ pub enum ToolCommand {
         max_retries: usize,
     },
 
+    /// Compare two formal snapshots object by object, e.g. of consecutive epochs, or a locally
+    /// produced snapshot against the published one. Reports added, removed and changed objects
+    /// with their types, a summary by type, and the first divergent object.
+    #[command(name = "diff-formal-snapshots")]
+    DiffFormalSnapshots {
+        /// Epoch of the first snapshot.
+        #[clap(long = "epoch")]
+        epoch: u64,
+        /// Epoch of the second snapshot. Defaults to `--epoch`.
+        #[clap(long = "other-epoch")]
+        other_epoch: Option<u64>,
+        /// Directory to stage the reference files of both snapshots in.
+        #[clap(long = "path")]
+        path: PathBuf,
+        /// Number of parallel downloads to perform. Defaults to 50, max 200.
+        #[clap(long = "num-parallel-downloads")]
+        num_parallel_downloads: Option<usize>,
+        /// Network the snapshots are for. Defaults to "mainnet".
+        /// Used to construct default bucket names if no bucket is specified.
+        #[clap(long = "network", default_value = "mainnet")]
+        network: Chain,
+        /// Bucket of the first snapshot. If not specified, defaults are
+        /// based on value of `--network` flag.
+        #[clap(long = "snapshot-bucket", conflicts_with = "no_sign_request")]
+        snapshot_bucket: Option<String>,
+        /// Bucket type of the first snapshot.
+        #[clap(
+            long = "snapshot-bucket-type",
+            conflicts_with = "no_sign_request",
+            help = "Required if --no-sign-request is not set"
+        )]
+        snapshot_bucket_type: Option<ObjectStoreType>,
+        /// Path to the snapshot directory of the first snapshot on local filesystem.
+        /// Only applicable if `--snapshot-bucket-type` is "file".
+        #[clap(long = "snapshot-path")]
+        snapshot_path: Option<PathBuf>,
+        /// If true, no authentication is needed to read the first snapshot.
+        #[clap(
+            long = "no-sign-request",
+            conflicts_with_all = &["snapshot_bucket", "snapshot_bucket_type"],
+        )]
+        no_sign_request: bool,
+        /// Bucket of the second snapshot. If none of the `--other-*` store
+        /// flags are set, the second snapshot is read from the same store as the first.
+        #[clap(
+            long = "other-snapshot-bucket",
+            conflicts_with = "other_no_sign_request"
+        )]
+        other_snapshot_bucket: Option<String>,
+        /// Bucket type of the second snapshot.
+        #[clap(
+            long = "other-snapshot-bucket-type",
+            conflicts_with = "other_no_sign_request"
+        )]
+        other_snapshot_bucket_type: Option<ObjectStoreType>,
+        /// Path to the snapshot directory of the second snapshot on local filesystem.
+        /// Only applicable if `--other-snapshot-bucket-type` is "file".
+        #[clap(long = "other-snapshot-path")]
+        other_snapshot_path: Option<PathBuf>,
+        /// If true, no authentication is needed to read the second snapshot.
+        #[clap(
+            long = "other-no-sign-request",
+            conflicts_with_all = &["other_snapshot_bucket", "other_snapshot_bucket_type"],
+        )]
+        other_no_sign_request: bool,
+        /// Maximum number of divergent objects to list. All of them are
+        /// counted in the summary.
+        #[clap(long = "max-objects", default_value = "100")]
+        max_objects: usize,
+        /// Print the diff as JSON.
+        #[clap(long = "json")]
+        json: bool,
+        /// If false (default), log level will be overridden to "off",
+        /// and output will be reduced to necessary status information.
+        #[clap(long = "verbose")]
+        verbose: bool,
+        /// Number of retries for failed HTTP requests when downloading snapshot files.
+        #[clap(long = "max-retries", default_value = "3")]
+        max_retries: usize,
+    },
+
     #[clap(name = "replay")]
     Replay {
         #[arg(long = "rpc")]
@@ -446,6 +527,96 @@ async fn check_locked_object(
     Ok(())
 }
 
+/// Builds the config of the object store holding formal snapshots, falling back to the default
+/// buckets of `network` when no bucket is given.
+fn formal_snapshot_store_config(
+    network: Chain,
+    snapshot_bucket: Option<String>,
+    snapshot_bucket_type: Option<ObjectStoreType>,
+    snapshot_path: Option<PathBuf>,
+    no_sign_request: bool,
+) -> ObjectStoreConfig {
+    let snapshot_bucket = snapshot_bucket.or_else(|| match (network, no_sign_request) {
+        (Chain::Mainnet, false) => Some(
+            env::var("MAINNET_FORMAL_SIGNED_BUCKET").unwrap_or("linku-mainnet-formal".to_string()),
+        ),
+        (Chain::Mainnet, true) => env::var("MAINNET_FORMAL_UNSIGNED_BUCKET").ok(),
+        (Chain::Testnet, true) => env::var("TESTNET_FORMAL_UNSIGNED_BUCKET").ok(),
+        (Chain::Testnet, _) => Some(
+            env::var("TESTNET_FORMAL_SIGNED_BUCKET").unwrap_or("linku-testnet-formal".to_string()),
+        ),
+        (Chain::Unknown, _) => {
+            panic!("Cannot generate default snapshot bucket for unknown network");
+        }
+    });
+
+    let aws_endpoint = env::var("AWS_SNAPSHOT_ENDPOINT").ok().or_else(|| {
+        if no_sign_request {
+            if network == Chain::Mainnet {
+                Some("https://formal-snapshot.mainnet.rtd.io".to_string())
+            } else if network == Chain::Testnet {
+                Some("https://formal-snapshot.testnet.rtd.io".to_string())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    });
+
+    let snapshot_bucket_type = if no_sign_request {
+        ObjectStoreType::S3
+    } else {
+        snapshot_bucket_type
+            .expect("You must set either --snapshot-bucket-type or --no-sign-request")
+    };
+    match snapshot_bucket_type {
+        ObjectStoreType::S3 => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::S3),
+            bucket: snapshot_bucket.filter(|s| !s.is_empty()),
+            aws_access_key_id: env::var("AWS_SNAPSHOT_ACCESS_KEY_ID").ok(),
+            aws_secret_access_key: env::var("AWS_SNAPSHOT_SECRET_ACCESS_KEY").ok(),
+            aws_region: env::var("AWS_SNAPSHOT_REGION").ok(),
+            aws_endpoint: aws_endpoint.filter(|s| !s.is_empty()),
+            aws_virtual_hosted_style_request: env::var("AWS_SNAPSHOT_VIRTUAL_HOSTED_REQUESTS")
+                .ok()
+                .and_then(|b| b.parse().ok())
+                .unwrap_or(no_sign_request),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::GCS => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::GCS),
+            bucket: snapshot_bucket,
+            google_service_account: env::var("GCS_SNAPSHOT_SERVICE_ACCOUNT_FILE_PATH").ok(),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::Azure => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::Azure),
+            bucket: snapshot_bucket,
+            azure_storage_account: env::var("AZURE_SNAPSHOT_STORAGE_ACCOUNT").ok(),
+            azure_storage_access_key: env::var("AZURE_SNAPSHOT_STORAGE_ACCESS_KEY").ok(),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::File => {
+            if snapshot_path.is_some() {
+                ObjectStoreConfig {
+                    object_store: Some(ObjectStoreType::File),
+                    directory: snapshot_path,
+                    ..Default::default()
+                }
+            } else {
+                panic!("--snapshot-path must be specified for --snapshot-bucket-type=file");
+            }
+        }
+    }
+}
+
 impl ToolCommand {
     #[allow(clippy::format_in_format_args)]
     pub async fn execute(self, tracing_handle: TracingHandle) -> Result<(), anyhow::Error> {
@@ -690,94 +861,13 @@ impl ToolCommand {
                         .expect("Failed to update log level");
                 }
                 let num_parallel_downloads = num_parallel_downloads.unwrap_or(50).min(200);
-                let snapshot_bucket =
-                    snapshot_bucket.or_else(|| match (network, no_sign_request) {
-                        (Chain::Mainnet, false) => Some(
-                            env::var("MAINNET_FORMAL_SIGNED_BUCKET")
-                                .unwrap_or("linku-mainnet-formal".to_string()),
-                        ),
-                        (Chain::Mainnet, true) => env::var("MAINNET_FORMAL_UNSIGNED_BUCKET").ok(),
-                        (Chain::Testnet, true) => env::var("TESTNET_FORMAL_UNSIGNED_BUCKET").ok(),
-                        (Chain::Testnet, _) => Some(
-                            env::var("TESTNET_FORMAL_SIGNED_BUCKET")
-                                .unwrap_or("linku-testnet-formal".to_string()),
-                        ),
-                        (Chain::Unknown, _) => {
-                            panic!("Cannot generate default snapshot bucket for unknown network");
-                        }
-                    });
-
-                let aws_endpoint = env::var("AWS_SNAPSHOT_ENDPOINT").ok().or_else(|| {
-                    if no_sign_request {
-                        if network == Chain::Mainnet {
-                            Some("https://formal-snapshot.mainnet.rtd.io".to_string())
-                        } else if network == Chain::Testnet {
-                            Some("https://formal-snapshot.testnet.rtd.io".to_string())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                });
-
-                let snapshot_bucket_type = if no_sign_request {
-                    ObjectStoreType::S3
-                } else {
-                    snapshot_bucket_type
-                        .expect("You must set either --snapshot-bucket-type or --no-sign-request")
-                };
-                let snapshot_store_config = match snapshot_bucket_type {
-                    ObjectStoreType::S3 => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::S3),
-                        bucket: snapshot_bucket.filter(|s| !s.is_empty()),
-                        aws_access_key_id: env::var("AWS_SNAPSHOT_ACCESS_KEY_ID").ok(),
-                        aws_secret_access_key: env::var("AWS_SNAPSHOT_SECRET_ACCESS_KEY").ok(),
-                        aws_region: env::var("AWS_SNAPSHOT_REGION").ok(),
-                        aws_endpoint: aws_endpoint.filter(|s| !s.is_empty()),
-                        aws_virtual_hosted_style_request: env::var(
-                            "AWS_SNAPSHOT_VIRTUAL_HOSTED_REQUESTS",
-                        )
-                        .ok()
-                        .and_then(|b| b.parse().ok())
-                        .unwrap_or(no_sign_request),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::GCS => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::GCS),
-                        bucket: snapshot_bucket,
-                        google_service_account: env::var("GCS_SNAPSHOT_SERVICE_ACCOUNT_FILE_PATH")
-                            .ok(),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::Azure => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::Azure),
-                        bucket: snapshot_bucket,
-                        azure_storage_account: env::var("AZURE_SNAPSHOT_STORAGE_ACCOUNT").ok(),
-                        azure_storage_access_key: env::var("AZURE_SNAPSHOT_STORAGE_ACCESS_KEY")
-                            .ok(),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::File => {
-                        if snapshot_path.is_some() {
-                            ObjectStoreConfig {
-                                object_store: Some(ObjectStoreType::File),
-                                directory: snapshot_path,
-                                ..Default::default()
-                            }
-                        } else {
-                            panic!(
-                                "--snapshot-path must be specified for --snapshot-bucket-type=file"
-                            );
-                        }
-                    }
-                };
+                let snapshot_store_config = formal_snapshot_store_config(
+                    network,
+                    snapshot_bucket,
+                    snapshot_bucket_type,
+                    snapshot_path,
+                    no_sign_request,
+                );
 
                 let ingestion_url = match network {
                     Chain::Mainnet => "https://checkpoints.mainnet.rtd.io",
@@ -814,6 +904,70 @@ impl ToolCommand {
                 )
                 .await?;
             }
+            ToolCommand::DiffFormalSnapshots {
+                epoch,
+                other_epoch,
+                path,
+                num_parallel_downloads,
+                network,
+                snapshot_bucket,
+                snapshot_bucket_type,
+                snapshot_path,
+                no_sign_request,
+                other_snapshot_bucket,
+                other_snapshot_bucket_type,
+                other_snapshot_path,
+                other_no_sign_request,
+                max_objects,
+                json,
+                verbose,
+                max_retries,
+            } => {
+                if !verbose {
+                    tracing_handle
+                        .update_log("off")
+                        .expect("Failed to update log level");
+                }
+                let num_parallel_downloads = num_parallel_downloads.unwrap_or(50).min(200);
+                let snapshot_store_config = formal_snapshot_store_config(
+                    network,
+                    snapshot_bucket,
+                    snapshot_bucket_type,
+                    snapshot_path,
+                    no_sign_request,
+                );
+                let other_snapshot_store_config = if other_snapshot_bucket.is_some()
+                    || other_snapshot_bucket_type.is_some()
+                    || other_snapshot_path.is_some()
+                    || other_no_sign_request
+                {
+                    formal_snapshot_store_config(
+                        network,
+                        other_snapshot_bucket,
+                        other_snapshot_bucket_type,
+                        other_snapshot_path,
+                        other_no_sign_request,
+                    )
+                } else {
+                    snapshot_store_config.clone()
+                };
+                let snapshot_diff = diff_formal_snapshots(
+                    &path,
+                    epoch,
+                    &snapshot_store_config,
+                    other_epoch.unwrap_or(epoch),
+                    &other_snapshot_store_config,
+                    num_parallel_downloads,
+                    max_retries,
+                    max_objects,
+                )
+                .await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&snapshot_diff)?);
+                } else {
+                    print!("{}", SnapshotDiffOutput(&snapshot_diff));
+                }
+            }
             ToolCommand::DownloadDBSnapshot {
                 epoch,
                 path,
@@ -51,6 +51,7 @@ use rtd_core::authority::authority_store_tables::AuthorityPerpetualTables;
 use rtd_core::checkpoints::CheckpointStore;
 use rtd_core::epoch::committee_store::CommitteeStore;
 use rtd_core::storage::RocksDbStore;
+use rtd_snapshot::diff::{ObjectChange, ObjectDiff, SnapshotDiff, diff_snapshots};
 use rtd_snapshot::reader::StateSnapshotReaderV1;
 use rtd_snapshot::setup_db_state;
 use rtd_storage::object_store::ObjectStoreGetExt;
@@ -340,6 +341,91 @@ impl std::fmt::Display for VerboseObjectOutput {
     }
 }
 
+struct SnapshotDiffOutput<'a>(&'a SnapshotDiff);
+
+impl std::fmt::Display for SnapshotDiffOutput<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let diff = self.0;
+        writeln!(
+            f,
+            "Snapshot of epoch {} vs snapshot of epoch {}",
+            diff.left_epoch, diff.right_epoch
+        )?;
+        if diff.is_empty() {
+            return writeln!(f, "Snapshots are identical");
+        }
+
+        writeln!(f, "Divergent bucket partitions:")?;
+        for (bucket, part) in &diff.divergent_partitions {
+            writeln!(f, "  {bucket}_{part}")?;
+        }
+        if let Some(first) = &diff.first_divergence {
+            writeln!(f, "First divergent object: {}", first.change.object_id())?;
+            write_object_diff(f, first)?;
+        }
+
+        writeln!(f, "{:<100}", "-".repeat(100))?;
+        writeln!(f, "{:<8} {:<8} {:<8} type", "added", "removed", "changed")?;
+        for (type_, summary) in &diff.summary {
+            writeln!(
+                f,
+                "{:<8} {:<8} {:<8} {}",
+                summary.added, summary.removed, summary.changed, type_
+            )?;
+        }
+
+        writeln!(f, "{:<100}", "-".repeat(100))?;
+        writeln!(
+            f,
+            "Showing {} of {} divergent objects",
+            diff.diffs.len(),
+            diff.num_diffs
+        )?;
+        for object_diff in &diff.diffs {
+            write_object_diff(f, object_diff)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_object_diff(f: &mut std::fmt::Formatter<'_>, diff: &ObjectDiff) -> std::fmt::Result {
+    match &diff.change {
+        ObjectChange::Added { new } => writeln!(
+            f,
+            "  + {} version: {} digest: {} type: {}",
+            new.0,
+            new.1.value(),
+            new.2,
+            diff.type_()
+        ),
+        ObjectChange::Removed { old } => writeln!(
+            f,
+            "  - {} version: {} digest: {} type: {}",
+            old.0,
+            old.1.value(),
+            old.2,
+            diff.type_()
+        ),
+        ObjectChange::Changed { old, new } => {
+            writeln!(
+                f,
+                "  ~ {} version: {} -> {} digest: {} -> {}",
+                old.0,
+                old.1.value(),
+                new.1.value(),
+                old.2,
+                new.2
+            )?;
+            writeln!(
+                f,
+                "      type: {} -> {}",
+                diff.old_type.as_deref().unwrap_or("unknown"),
+                diff.new_type.as_deref().unwrap_or("unknown")
+            )
+        }
+    }
+}
+
 pub async fn get_object(
     obj_id: ObjectID,
     version: Option<u64>,
@@ -1007,6 +1093,72 @@ pub async fn download_formal_snapshot(
     Ok(())
 }
 
+/// Compares the formal snapshots of `epoch` in `snapshot_store_config` and of `other_epoch` in
+/// `other_snapshot_store_config`. Reference files of both snapshots are staged under `path`, while
+/// object files are only downloaded for buckets that differ.
+pub async fn diff_formal_snapshots(
+    path: &Path,
+    epoch: EpochId,
+    snapshot_store_config: &ObjectStoreConfig,
+    other_epoch: EpochId,
+    other_snapshot_store_config: &ObjectStoreConfig,
+    num_parallel_downloads: usize,
+    max_retries: usize,
+    max_objects: usize,
+) -> Result<SnapshotDiff, anyhow::Error> {
+    let m = MultiProgress::new();
+    m.println(format!(
+        "Comparing formal snapshots of epoch {} and epoch {}",
+        epoch, other_epoch
+    ))?;
+
+    let mut left = open_formal_snapshot(
+        &path.join("left"),
+        epoch,
+        snapshot_store_config,
+        num_parallel_downloads,
+        max_retries,
+        m.clone(),
+    )
+    .await?;
+    let mut right = open_formal_snapshot(
+        &path.join("right"),
+        other_epoch,
+        other_snapshot_store_config,
+        num_parallel_downloads,
+        max_retries,
+        m,
+    )
+    .await?;
+    diff_snapshots(&mut left, &mut right, max_objects).await
+}
+
+async fn open_formal_snapshot(
+    path: &Path,
+    epoch: EpochId,
+    snapshot_store_config: &ObjectStoreConfig,
+    num_parallel_downloads: usize,
+    max_retries: usize,
+    m: MultiProgress,
+) -> Result<StateSnapshotReaderV1, anyhow::Error> {
+    check_completed_snapshot(snapshot_store_config, epoch).await?;
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(path.to_path_buf()),
+        ..Default::default()
+    };
+    StateSnapshotReaderV1::new(
+        epoch,
+        snapshot_store_config,
+        &local_store_config,
+        NonZeroUsize::new(num_parallel_downloads).unwrap(),
+        m,
+        false, // skip_reset_local_store
+        max_retries,
+    )
+    .await
+}
+
 async fn backfill_epoch_transaction_digests(
     perpetual_db: Arc<AuthorityPerpetualTables>,
     epoch: EpochId,
@@ -7,25 +7,84 @@ use rtd_config::{
     transaction_deny_config::TransactionDenyConfig,
 };
 use rtd_types::{
-    base_types::ObjectRef,
+    base_types::{ObjectID, ObjectRef, RtdAddress},
     error::{RtdError, RtdErrorKind, RtdResult, UserInputError},
     signature::GenericSignature,
     storage::BackingPackageStore,
     transaction::{Command, InputObjectKind, TransactionData, TransactionDataAPI},
 };
+use std::fmt;
 use tracing::{error, warn};
+
+pub mod reload;
+
 macro_rules! deny_if_true {
-    ($cond:expr, $msg:expr) => {
+    ($cond:expr, $rule:expr, $msg:expr) => {
         if ($cond) {
-            return Err(RtdError(Box::new(RtdErrorKind::UserInputError {
-                error: UserInputError::TransactionDenied {
-                    error: $msg.to_string(),
-                },
-            })));
+            return Err(Denial::Denied {
+                rule: $rule,
+                message: $msg.to_string(),
+            });
         }
     };
 }
 
+/// The rule of the deny config that denied a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DenyRule {
+    UserTransactionDisabled,
+    ZkLoginSigDisabled,
+    ZkLoginProviderDisabled(String),
+    PackagePublishDisabled,
+    PackageUpgradeDisabled,
+    DeniedAddress(RtdAddress),
+    DeniedObject(ObjectID),
+    SharedObjectDisabled,
+    DeniedPackage(ObjectID),
+    ReceivingObjectsDisabled,
+    DeniedReceivingObject(ObjectID),
+    DynamicTransactionCheck,
+}
+
+impl fmt::Display for DenyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UserTransactionDisabled => write!(f, "user-transaction-disabled"),
+            Self::ZkLoginSigDisabled => write!(f, "zklogin-sig-disabled"),
+            Self::ZkLoginProviderDisabled(provider) => {
+                write!(f, "zklogin-disabled-providers: {provider}")
+            }
+            Self::PackagePublishDisabled => write!(f, "package-publish-disabled"),
+            Self::PackageUpgradeDisabled => write!(f, "package-upgrade-disabled"),
+            Self::DeniedAddress(address) => write!(f, "address-deny-list: {address}"),
+            Self::DeniedObject(id) => write!(f, "object-deny-list: {id}"),
+            Self::SharedObjectDisabled => write!(f, "shared-object-disabled"),
+            Self::DeniedPackage(id) => write!(f, "package-deny-list: {id}"),
+            Self::ReceivingObjectsDisabled => write!(f, "receiving-objects-disabled"),
+            Self::DeniedReceivingObject(id) => write!(f, "object-deny-list (receiving): {id}"),
+            Self::DynamicTransactionCheck => write!(f, "dynamic-transaction-checks"),
+        }
+    }
+}
+
+/// Outcome of a failed check: either a rule denies the transaction, or the check itself failed.
+enum Denial {
+    Denied { rule: DenyRule, message: String },
+    Error(RtdError),
+}
+
+impl From<RtdError> for Denial {
+    fn from(e: RtdError) -> Self {
+        Self::Error(e)
+    }
+}
+
+impl From<RtdErrorKind> for Denial {
+    fn from(e: RtdErrorKind) -> Self {
+        Self::Error(e.into())
+    }
+}
+
 /// Check that the provided transaction is allowed to be signed according to the
 /// deny config.
 pub fn check_transaction_for_signing(
@@ -36,6 +95,56 @@ pub fn check_transaction_for_signing(
     filter_config: &TransactionDenyConfig,
     package_store: &dyn BackingPackageStore,
 ) -> RtdResult {
+    find_deny_rule(
+        tx_data,
+        tx_signatures,
+        input_object_kinds,
+        receiving_objects,
+        filter_config,
+        package_store,
+    )
+    .map(|_| ())
+}
+
+/// Like `check_transaction_for_signing`, but also returns the rule that denied the transaction.
+/// The outer error is returned when the checks cannot be performed, e.g. when a package is
+/// missing.
+pub fn find_deny_rule(
+    tx_data: &TransactionData,
+    tx_signatures: &[GenericSignature],
+    input_object_kinds: &[InputObjectKind],
+    receiving_objects: &[ObjectRef],
+    filter_config: &TransactionDenyConfig,
+    package_store: &dyn BackingPackageStore,
+) -> RtdResult<Result<(), (DenyRule, RtdError)>> {
+    match run_checks(
+        tx_data,
+        tx_signatures,
+        input_object_kinds,
+        receiving_objects,
+        filter_config,
+        package_store,
+    ) {
+        Ok(()) => Ok(Ok(())),
+        Err(Denial::Denied { rule, message }) => Ok(Err((
+            rule,
+            RtdErrorKind::UserInputError {
+                error: UserInputError::TransactionDenied { error: message },
+            }
+            .into(),
+        ))),
+        Err(Denial::Error(e)) => Err(e),
+    }
+}
+
+fn run_checks(
+    tx_data: &TransactionData,
+    tx_signatures: &[GenericSignature],
+    input_object_kinds: &[InputObjectKind],
+    receiving_objects: &[ObjectRef],
+    filter_config: &TransactionDenyConfig,
+    package_store: &dyn BackingPackageStore,
+) -> Result<(), Denial> {
     check_disabled_features(filter_config, tx_data, tx_signatures)?;
 
     check_signers(filter_config, tx_data)?;
@@ -64,7 +173,7 @@ fn dynamic_transaction_checks(
     tx_signatures: &[GenericSignature],
     input_object_kinds: &[InputObjectKind],
     receiving_objects: &[ObjectRef],
-) -> RtdResult {
+) -> Result<(), Denial> {
     let Some(dynamic_check) = filter_config.dynamic_transaction_checks() else {
         return Ok(());
     };
@@ -82,12 +191,10 @@ fn dynamic_transaction_checks(
                 "Dynamic transaction predicate rejected transaction: {:?}",
                 tx_data.digest()
             );
-            Err(RtdErrorKind::UserInputError {
-                error: UserInputError::TransactionDenied {
-                    error: "Dynamic transaction predicate failed".to_string(),
-                },
-            }
-            .into())
+            Err(Denial::Denied {
+                rule: DenyRule::DynamicTransactionCheck,
+                message: "Dynamic transaction predicate failed".to_string(),
+            })
         }
         // Non-predicate failure, so be conservative and deny the transaction.
         Err(e) => {
@@ -97,12 +204,10 @@ fn dynamic_transaction_checks(
                 e,
                 tx_data.digest()
             );
-            Err(RtdErrorKind::UserInputError {
-                error: UserInputError::TransactionDenied {
-                    error: e.to_string(),
-                },
-            }
-            .into())
+            Err(Denial::Denied {
+                rule: DenyRule::DynamicTransactionCheck,
+                message: e.to_string(),
+            })
         }
     }
 }
@@ -110,14 +215,16 @@ fn dynamic_transaction_checks(
 fn check_receiving_objects(
     filter_config: &TransactionDenyConfig,
     receiving_objects: &[ObjectRef],
-) -> RtdResult {
+) -> Result<(), Denial> {
     deny_if_true!(
         filter_config.receiving_objects_disabled() && !receiving_objects.is_empty(),
+        DenyRule::ReceivingObjectsDisabled,
         "Receiving objects is temporarily disabled".to_string()
     );
     for (id, _, _) in receiving_objects {
         deny_if_true!(
             filter_config.get_object_deny_set().contains(id),
+            DenyRule::DeniedReceivingObject(*id),
             format!("Access to object {:?} is temporarily disabled", id)
         );
     }
@@ -128,9 +235,10 @@ fn check_disabled_features(
     filter_config: &TransactionDenyConfig,
     tx_data: &TransactionData,
     tx_signatures: &[GenericSignature],
-) -> RtdResult {
+) -> Result<(), Denial> {
     deny_if_true!(
         filter_config.user_transaction_disabled(),
+        DenyRule::UserTransactionDisabled,
         "Transaction signing is temporarily disabled"
     );
 
@@ -138,14 +246,17 @@ fn check_disabled_features(
         if let GenericSignature::ZkLoginAuthenticator(z) = s {
             deny_if_true!(
                 filter_config.zklogin_sig_disabled(),
+                DenyRule::ZkLoginSigDisabled,
                 "zkLogin authenticator is temporarily disabled"
             );
+            let provider = OIDCProvider::from_iss(z.get_iss())
+                .map_err(|_| RtdErrorKind::UnexpectedMessage(z.get_iss().to_string()))?
+                .to_string();
             deny_if_true!(
-                filter_config.zklogin_disabled_providers().contains(
-                    &OIDCProvider::from_iss(z.get_iss())
-                        .map_err(|_| RtdErrorKind::UnexpectedMessage(z.get_iss().to_string()))?
-                        .to_string()
-                ),
+                filter_config
+                    .zklogin_disabled_providers()
+                    .contains(&provider),
+                DenyRule::ZkLoginProviderDisabled(provider),
                 "zkLogin OAuth provider is temporarily disabled"
             )
         }
@@ -159,17 +270,22 @@ fn check_disabled_features(
     for command in tx_data.kind().iter_commands() {
         deny_if_true!(
             filter_config.package_publish_disabled() && matches!(command, Command::Publish(..)),
+            DenyRule::PackagePublishDisabled,
             "Package publish is temporarily disabled"
         );
         deny_if_true!(
             filter_config.package_upgrade_disabled() && matches!(command, Command::Upgrade(..)),
+            DenyRule::PackageUpgradeDisabled,
             "Package upgrade is temporarily disabled"
         );
     }
     Ok(())
 }
 
-fn check_signers(filter_config: &TransactionDenyConfig, tx_data: &TransactionData) -> RtdResult {
+fn check_signers(
+    filter_config: &TransactionDenyConfig,
+    tx_data: &TransactionData,
+) -> Result<(), Denial> {
     let deny_map = filter_config.get_address_deny_set();
     if deny_map.is_empty() {
         return Ok(());
@@ -177,6 +293,7 @@ fn check_signers(filter_config: &TransactionDenyConfig, tx_data: &TransactionDat
     for signer in tx_data.required_signers() {
         deny_if_true!(
             deny_map.contains(&signer),
+            DenyRule::DeniedAddress(signer),
             format!(
                 "Access to account address {:?} is temporarily disabled",
                 signer
@@ -189,7 +306,7 @@ fn check_signers(filter_config: &TransactionDenyConfig, tx_data: &TransactionDat
 fn check_input_objects(
     filter_config: &TransactionDenyConfig,
     input_object_kinds: &[InputObjectKind],
-) -> RtdResult {
+) -> Result<(), Denial> {
     let deny_map = filter_config.get_object_deny_set();
     let shared_object_disabled = filter_config.shared_object_disabled();
     if deny_map.is_empty() && !shared_object_disabled {
@@ -200,10 +317,12 @@ fn check_input_objects(
         let id = input_object_kind.object_id();
         deny_if_true!(
             deny_map.contains(&id),
+            DenyRule::DeniedObject(id),
             format!("Access to input object {:?} is temporarily disabled", id)
         );
         deny_if_true!(
             shared_object_disabled && input_object_kind.is_shared_object(),
+            DenyRule::SharedObjectDisabled,
             "Usage of shared object in transactions is temporarily disabled"
         );
     }
@@ -214,7 +333,7 @@ fn check_package_dependencies(
     filter_config: &TransactionDenyConfig,
     tx_data: &TransactionData,
     package_store: &dyn BackingPackageStore,
-) -> RtdResult {
+) -> Result<(), Denial> {
     let deny_map = filter_config.get_package_deny_set();
     if deny_map.is_empty() {
         return Ok(());
@@ -266,6 +385,7 @@ fn check_package_dependencies(
     for dep in dependencies {
         deny_if_true!(
             deny_map.contains(&dep),
+            DenyRule::DeniedPackage(dep),
             format!("Access to package {:?} is temporarily disabled", dep)
         );
     }
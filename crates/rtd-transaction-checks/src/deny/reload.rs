@@ -0,0 +1,281 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A transaction deny config that can be replaced while the node is running, either through the
+//! admin interface or by watching the file it was loaded from.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use notify::{EventKind, RecursiveMode, Watcher};
+use rtd_config::{Config, transaction_deny_config::TransactionDenyConfig};
+use rtd_types::{
+    base_types::ObjectRef,
+    digests::TransactionDigest,
+    error::RtdResult,
+    signature::GenericSignature,
+    storage::BackingPackageStore,
+    transaction::{InputObjectKind, TransactionData, TransactionDataAPI, VerifiedTransaction},
+};
+use tracing::{error, info, warn};
+
+use super::{DenyRule, find_deny_rule};
+
+/// Number of recent denials kept for auditing.
+const RECENT_DENIALS_CAPACITY: usize = 1000;
+
+/// A deny config together with the version it was installed as. Versions start at 1 for the
+/// config the node was started with, and increase by one on every update.
+#[derive(Debug)]
+pub struct VersionedTransactionDenyConfig {
+    pub version: u64,
+    pub config: TransactionDenyConfig,
+}
+
+/// A transaction that was denied, and the rule and config version that denied it.
+#[derive(Clone, Debug)]
+pub struct DenialRecord {
+    pub digest: TransactionDigest,
+    pub rule: DenyRule,
+    pub version: u64,
+}
+
+/// What a candidate config would have denied over the recently signed transactions.
+#[derive(Debug)]
+pub struct DryRunReport {
+    /// The version of the config currently in use.
+    pub current_version: u64,
+    /// How many recent transactions were checked against the candidate config.
+    pub transactions_checked: usize,
+    /// Transactions that the candidate config would deny, and the rule that would deny them.
+    pub denied: Vec<(TransactionDigest, DenyRule)>,
+    /// Transactions that could not be checked, e.g. because a package they call is missing.
+    pub errors: usize,
+}
+
+pub struct ReloadableTransactionDenyConfig {
+    current: ArcSwap<VersionedTransactionDenyConfig>,
+    /// Serializes updates, so that versions are assigned in the order configs are installed.
+    update_lock: Mutex<()>,
+    /// The number of recent transactions to keep for dry runs, none if 0.
+    history_capacity: usize,
+    recent_transactions: Mutex<VecDeque<Arc<VerifiedTransaction>>>,
+    recent_denials: Mutex<VecDeque<DenialRecord>>,
+}
+
+impl ReloadableTransactionDenyConfig {
+    /// Keep the last `history_capacity` transactions submitted for signing, to dry run configs
+    /// against. Dry runs check nothing if it is 0.
+    pub fn new(config: TransactionDenyConfig, history_capacity: usize) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(VersionedTransactionDenyConfig { version: 1, config }),
+            update_lock: Mutex::new(()),
+            history_capacity,
+            recent_transactions: Mutex::new(VecDeque::with_capacity(history_capacity)),
+            recent_denials: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Like `new`, but starts with the config in the YAML file at `path`, as version 1.
+    pub fn from_file(path: &Path, history_capacity: usize) -> anyhow::Result<Self> {
+        let config = TransactionDenyConfig::load(path)?;
+        Ok(Self::new(config, history_capacity))
+    }
+
+    /// The config currently in use.
+    pub fn load(&self) -> Arc<VersionedTransactionDenyConfig> {
+        self.current.load_full()
+    }
+
+    /// Atomically replace the config in use, returning the version it was installed as.
+    pub fn update(&self, config: TransactionDenyConfig) -> u64 {
+        let _guard = self.update_lock.lock().unwrap();
+        let version = self.current.load().version + 1;
+        info!(version, ?config, "Installing new transaction deny config");
+        self.current
+            .store(Arc::new(VersionedTransactionDenyConfig { version, config }));
+        version
+    }
+
+    /// Load a config from a YAML file and install it, returning the version it was installed as.
+    pub fn reload_from_file(&self, path: &Path) -> anyhow::Result<u64> {
+        let config = TransactionDenyConfig::load(path)?;
+        Ok(self.update(config))
+    }
+
+    /// Check the transaction against the current config. Denials are logged and recorded with
+    /// the rule and config version that matched.
+    pub fn check_transaction_for_signing(
+        &self,
+        tx_data: &TransactionData,
+        tx_signatures: &[GenericSignature],
+        input_object_kinds: &[InputObjectKind],
+        receiving_objects: &[ObjectRef],
+        package_store: &dyn BackingPackageStore,
+    ) -> RtdResult {
+        let current = self.load();
+        let Err((rule, err)) = find_deny_rule(
+            tx_data,
+            tx_signatures,
+            input_object_kinds,
+            receiving_objects,
+            &current.config,
+            package_store,
+        )?
+        else {
+            return Ok(());
+        };
+
+        let digest = tx_data.digest();
+        warn!(
+            ?digest,
+            %rule,
+            version = current.version,
+            "Transaction denied by transaction deny config"
+        );
+        let mut denials = self.recent_denials.lock().unwrap();
+        if denials.len() == RECENT_DENIALS_CAPACITY {
+            denials.pop_front();
+        }
+        denials.push_back(DenialRecord {
+            digest,
+            rule,
+            version: current.version,
+        });
+        Err(err)
+    }
+
+    /// Remember a transaction submitted for signing, so that later configs can be dry run
+    /// against it. Only the `history_capacity` most recent transactions are kept, and nothing is
+    /// kept if it is 0.
+    pub fn record_transaction(&self, transaction: &VerifiedTransaction) {
+        if self.history_capacity == 0 {
+            return;
+        }
+        let transaction = Arc::new(transaction.clone());
+        let mut transactions = self.recent_transactions.lock().unwrap();
+        if transactions.len() == self.history_capacity {
+            transactions.pop_front();
+        }
+        transactions.push_back(transaction);
+    }
+
+    /// The most recent denials, oldest first.
+    pub fn recent_denials(&self) -> Vec<DenialRecord> {
+        self.recent_denials
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Report what `config` would have denied over the recently recorded transactions, without
+    /// installing it.
+    pub fn dry_run(
+        &self,
+        config: &TransactionDenyConfig,
+        package_store: &dyn BackingPackageStore,
+    ) -> DryRunReport {
+        // Check a snapshot, so that signing is not blocked while the dry run is in progress.
+        let transactions: Vec<_> = self
+            .recent_transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect();
+        let mut report = DryRunReport {
+            current_version: self.load().version,
+            transactions_checked: transactions.len(),
+            denied: vec![],
+            errors: 0,
+        };
+        for tx in transactions.iter() {
+            let tx_data = tx.data().transaction_data();
+            let result = tx_data.input_objects().and_then(|input_object_kinds| {
+                find_deny_rule(
+                    tx_data,
+                    tx.tx_signatures(),
+                    &input_object_kinds,
+                    &tx_data.receiving_objects(),
+                    config,
+                    package_store,
+                )
+            });
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err((rule, _))) => report.denied.push((*tx.digest(), rule)),
+                Err(_) => report.errors += 1,
+            }
+        }
+        report
+    }
+
+    /// Reload the config whenever the file at `path` changes. The directory containing the file
+    /// is watched rather than the file itself, so that files replaced by renaming over them are
+    /// picked up too. Invalid configs are logged and ignored, leaving the current one in place,
+    /// and events that leave the file's contents unchanged do not install a new version.
+    pub fn watch_file(self: &Arc<Self>, path: PathBuf) -> anyhow::Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("Not a file: {}", path.display()))?
+            .to_owned();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        let this = Arc::downgrade(self);
+        std::thread::Builder::new()
+            .name("transaction-deny-config-watcher".to_string())
+            .spawn(move || {
+                // The watcher stops when dropped, so it lives as long as this thread.
+                let _watcher = watcher;
+                let mut last_contents = std::fs::read(&path).ok();
+                for event in receiver {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(e) => {
+                            error!("Error watching transaction deny config: {e}");
+                            continue;
+                        }
+                    };
+                    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                        || !event
+                            .paths
+                            .iter()
+                            .any(|p| p.file_name() == Some(file_name.as_os_str()))
+                    {
+                        continue;
+                    }
+                    let contents = std::fs::read(&path).ok();
+                    if contents.is_none() || contents == last_contents {
+                        continue;
+                    }
+                    last_contents = contents;
+                    let Some(this) = this.upgrade() else {
+                        return;
+                    };
+                    match this.reload_from_file(&path) {
+                        Ok(version) => info!(
+                            version,
+                            "Reloaded transaction deny config from {}",
+                            path.display()
+                        ),
+                        Err(e) => error!(
+                            "Failed to reload transaction deny config from {}: {e:?}",
+                            path.display()
+                        ),
+                    }
+                }
+            })?;
+        Ok(())
+    }
+}
@@ -265,13 +265,19 @@ impl SimulatorStore for PersistedStore {
     }
 
     fn owned_objects(&self, owner: RtdAddress) -> Box<dyn Iterator<Item = Object> + '_> {
-        Box::new(self.read_write.live_objects
-            .safe_iter()
-            .map(|result| result.expect("rocksdb iteration failed"))
-            .flat_map(|(id, version)| self.get_object_at_version(&id, version))
-            .filter(
-                move |object| matches!(object.owner, Owner::AddressOwner(addr) if addr == owner),
-            ))
+        Box::new(self.live_objects().filter(
+            move |object| matches!(object.owner, Owner::AddressOwner(addr) if addr == owner),
+        ))
+    }
+
+    fn live_objects(&self) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(
+            self.read_write
+                .live_objects
+                .safe_iter()
+                .map(|result| result.expect("rocksdb iteration failed"))
+                .flat_map(|(id, version)| self.get_object_at_version(&id, version)),
+        )
     }
 
     fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint) {
@@ -3,6 +3,7 @@
 
 use serde::{Deserialize, Serialize, de::Deserializer};
 use serde_with::serde_as;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 // These values set to loosely attempt to limit
@@ -218,6 +219,97 @@ fn default_sketch_tolerance() -> f64 {
     DEFAULT_SKETCH_TOLERANCE
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TokenBucketConfig {
+    /// Number of requests per second a direct client may sustain.
+    #[serde(default = "default_client_rate")]
+    pub client_rate: f64,
+    /// Number of requests a direct client may make at once, after being idle.
+    #[serde(default = "default_client_burst")]
+    pub client_burst: u64,
+    /// Number of requests per second a proxied client may sustain.
+    #[serde(default = "default_proxied_client_rate")]
+    pub proxied_client_rate: f64,
+    /// Number of requests a proxied client may make at once, after being idle.
+    #[serde(default = "default_proxied_client_burst")]
+    pub proxied_client_burst: u64,
+}
+
+impl Default for TokenBucketConfig {
+    fn default() -> Self {
+        Self {
+            client_rate: default_client_rate(),
+            client_burst: default_client_burst(),
+            proxied_client_rate: default_proxied_client_rate(),
+            proxied_client_burst: default_proxied_client_burst(),
+        }
+    }
+}
+
+fn default_client_rate() -> f64 {
+    // As with `default_client_threshold`, a direct client may be a
+    // fullnode proxying traffic from many clients.
+    1_000_000.0
+}
+
+fn default_client_burst() -> u64 {
+    1_000_000
+}
+
+fn default_proxied_client_rate() -> f64 {
+    10.0
+}
+
+fn default_proxied_client_burst() -> u64 {
+    100
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SlidingWindowConfig {
+    /// Length of the window over which requests are counted.
+    #[serde(default = "default_window_size_secs")]
+    pub window_size_secs: u64,
+    /// Maximum number of requests a client may make within the window to the
+    /// methods not listed in `method_limits`, counted together so that clients
+    /// cannot evade the limit by varying the method. If unset, such methods are
+    /// not limited.
+    #[serde(default)]
+    pub default_limit: Option<u64>,
+    /// Maximum number of requests a client may make to the given method within
+    /// the window. Methods are the JSON-RPC method names on fullnodes (e.g.
+    /// `rtd_dryRunTransactionBlock`), and the request kinds on validators
+    /// (e.g. `transaction`, `submit_certificate`).
+    #[serde(default)]
+    pub method_limits: BTreeMap<String, u64>,
+}
+
+impl Default for SlidingWindowConfig {
+    fn default() -> Self {
+        Self {
+            window_size_secs: default_window_size_secs(),
+            default_limit: None,
+            method_limits: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CompositeConfig {
+    /// Policies to apply. A client is blocked if any of them blocks it.
+    pub policies: Vec<PolicyType>,
+    /// List of String which should all parse to type IPAddr. Clients
+    /// in this list are never blocked by this policy.
+    #[serde(default)]
+    pub allow_list: Vec<String>,
+    /// Methods whose requests are not counted against clients by this
+    /// policy, e.g. cheap reads.
+    #[serde(default)]
+    pub allowed_methods: Vec<String>,
+}
+
 // Serializable representation of policy types, used in config
 // in order to easily change in tests or to killswitch
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
@@ -232,6 +324,22 @@ pub enum PolicyType {
     #[serde(rename = "freq-threshold", alias = "FreqThreshold")]
     FreqThreshold(FreqThresholdConfig),
 
+    /// Blocks a client once it exhausts its token bucket, which holds up to
+    /// `burst` tokens and is refilled at `rate` tokens per second. Every tally
+    /// takes one token.
+    #[serde(rename = "token-bucket", alias = "TokenBucket")]
+    TokenBucket(TokenBucketConfig),
+
+    /// Blocks a client once its number of requests to a single method within
+    /// the last `window_size_secs` exceeds the limit for that method, so that
+    /// expensive methods can be limited separately from cheap ones.
+    #[serde(rename = "sliding-window", alias = "SlidingWindow")]
+    SlidingWindow(SlidingWindowConfig),
+
+    /// Combines several policies, exempting allowlisted clients and methods.
+    #[serde(rename = "composite", alias = "Composite")]
+    Composite(CompositeConfig),
+
     /* Below this point are test policies, and thus should not be used in production */
     ///
     /// Simple policy that adds connection_ip to blocklist when the same connection_ip
@@ -18,6 +18,7 @@ use std::{
 };
 
 use anyhow::{Context, anyhow, bail, ensure};
+use async_trait::async_trait;
 use bip32::DerivationPath;
 use clap::*;
 use colored::Colorize;
@@ -38,6 +39,8 @@ use prometheus::Registry;
 use serde::Serialize;
 use serde_json::{Value, json};
 use rtd_config::verifier_signing_config::VerifierSigningConfig;
+use rtd_display::v2::{Format, meter::Limits};
+use rtd_package_resolver::{Package, PackageStore, Resolver, error::Error as PackageResolverError};
 use rtd_protocol_config::{Chain, ProtocolConfig, ProtocolVersion};
 
 use shared_crypto::intent::Intent;
@@ -63,6 +66,7 @@ use rtd_sdk::{
 use rtd_types::{
     RTD_FRAMEWORK_PACKAGE_ID,
     base_types::{FullObjectID, ObjectID, ObjectRef, ObjectType, SequenceNumber, RtdAddress},
+    collection_types::{self, VecMap},
     crypto::{EmptySignInfo, SignatureScheme},
     digests::TransactionDigest,
     error::RtdErrorKind,
@@ -236,6 +240,35 @@ pub enum RtdClientCommands {
         address: Option<KeyIdentity>,
     },
 
+    /// Check a Display for a Move type before publishing it, without needing any objects of that
+    /// type: reports every field access that cannot succeed and every transform that cannot apply
+    /// for the type's layout, as well as formats that exceed the Display's metering limits.
+    /// Fails if any problems are found.
+    ///
+    /// The packages that the type's layout depends on are fetched from the active network, so
+    /// this needs a connection to it, even when the type is defined in a local package.
+    #[clap(name = "lint-display")]
+    LintDisplay {
+        /// The type being displayed. Types from the package at `--package` should use its
+        /// address (`0x0` if it has not been published).
+        #[clap(name = "type", long = "type", value_parser = parse_rtd_type_tag)]
+        type_: TypeTag,
+
+        /// Path to a JSON file containing the Display's fields, as an object mapping field names
+        /// to format strings.
+        #[clap(long)]
+        fields: PathBuf,
+
+        /// Path to directory containing a Move package that defines the type, if it has not been
+        /// published yet. Otherwise, the type's layout is resolved from on-chain packages.
+        #[clap(name = "package", long)]
+        package_path: Option<PathBuf>,
+
+        /// Package build options
+        #[clap(flatten)]
+        build_config: MoveBuildConfig,
+    },
+
     /// Merge two coin objects into one coin
     MergeCoin {
         /// The address of the coin to merge into.
@@ -1164,6 +1197,87 @@ impl RtdClientCommands {
                 }
             }
 
+            RtdClientCommands::LintDisplay {
+                type_,
+                fields,
+                package_path,
+                build_config,
+            } => {
+                let fields: BTreeMap<String, String> = serde_json::from_str(
+                    &fs::read_to_string(&fields).context("Failed to read Display fields")?,
+                )
+                .context(
+                    "Failed to parse Display fields, expected a JSON object mapping names to \
+                    format strings",
+                )?;
+
+                let display = VecMap {
+                    contents: fields
+                        .into_iter()
+                        .map(|(key, value)| collection_types::Entry { key, value })
+                        .collect(),
+                };
+
+                let client = context.get_client().await?;
+                let _ = context.cache_chain_id(&client).await?;
+
+                let local = if let Some(package_path) = package_path {
+                    let environment =
+                        find_environment(&package_path, build_config.environment.clone(), context)
+                            .await?;
+
+                    let mut root_pkg =
+                        load_root_pkg_for_publish_upgrade(context, &build_config, &package_path)
+                            .await?;
+
+                    let compiled_package = BuildConfig {
+                        config: build_config,
+                        run_bytecode_verifier: false,
+                        print_diags_to_stderr: true,
+                        environment,
+                    }
+                    .build_async_from_root_pkg(&mut root_pkg)
+                    .await?;
+
+                    Some(local_package(client.read_api(), &compiled_package).await?)
+                } else {
+                    None
+                };
+
+                let resolver = Resolver::new(LintPackageStore {
+                    client: client.clone(),
+                    local,
+                });
+
+                let layout = resolver
+                    .type_layout(type_.clone())
+                    .await
+                    .with_context(|| format!("Failed to resolve the layout of {type_}"))?;
+
+                // Limits that apply to the Display as a whole fail parsing outright, and are
+                // reported as the only problem.
+                let lints = match Format::parse(Limits::default(), &display) {
+                    Ok(format) => format.lint(&layout).iter().map(|l| l.to_string()).collect(),
+                    Err(e) => vec![e.to_string()],
+                };
+
+                let type_ = type_.to_canonical_string(/* with_prefix */ true);
+                if !lints.is_empty() {
+                    bail!(
+                        "Found {} problem{} in Display for {type_}:\n{}",
+                        lints.len(),
+                        if lints.len() != 1 { "s" } else { "" },
+                        lints
+                            .iter()
+                            .map(|lint| format!("  - {lint}"))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    );
+                }
+
+                RtdClientCommandResult::LintDisplay { type_ }
+            }
+
             RtdClientCommands::Object { id, bcs } => {
                 // Fetch the object ref
                 let client = context.get_client().await?;
@@ -2419,6 +2533,9 @@ impl Display for RtdClientCommandResult {
             RtdClientCommandResult::VerifySource => {
                 writeln!(writer, "Source verification succeeded!")?;
             }
+            RtdClientCommandResult::LintDisplay { type_ } => {
+                writeln!(writer, "No problems found in Display for {type_}")?;
+            }
             RtdClientCommandResult::VerifyBytecodeMeter {
                 success,
                 max_package_ticks,
@@ -2620,6 +2737,7 @@ impl RtdClientCommandResult {
             | RtdClientCommandResult::DevInspect(_)
             | RtdClientCommandResult::Envs(_, _)
             | RtdClientCommandResult::Gas(_)
+            | RtdClientCommandResult::LintDisplay { .. }
             | RtdClientCommandResult::NewAddress(_)
             | RtdClientCommandResult::NewEnv(_)
             | RtdClientCommandResult::NoOutput
@@ -2778,6 +2896,10 @@ pub enum RtdClientCommandResult {
     DevInspect(DevInspectResults),
     Envs(Vec<RtdEnv>, Option<String>),
     Gas(Vec<GasCoin>),
+    LintDisplay {
+        #[serde(rename = "type")]
+        type_: String,
+    },
     NewAddress(NewAddressOutput),
     NewEnv(RtdEnv),
     NoOutput,
@@ -3363,6 +3485,67 @@ fn to_package(o: RtdObjectResponse) -> anyhow::Result<MovePackage> {
     Ok(p.to_move_package(u64::MAX /* safe as this pkg comes from the network */)?)
 }
 
+/// Package store used to resolve the layouts of types whose Displays are being linted. It serves a
+/// package that was built locally, if there is one, and fetches all other packages from the
+/// network.
+struct LintPackageStore {
+    client: RtdClient,
+    local: Option<(AccountAddress, Arc<Package>)>,
+}
+
+#[async_trait]
+impl PackageStore for LintPackageStore {
+    async fn fetch(&self, id: AccountAddress) -> rtd_package_resolver::Result<Arc<Package>> {
+        if let Some((local_id, package)) = &self.local
+            && *local_id == id
+        {
+            return Ok(package.clone());
+        }
+
+        let response = self
+            .client
+            .read_api()
+            .get_object_with_options(id.into(), RtdObjectDataOptions::bcs_lossless())
+            .await
+            .map_err(|e| PackageResolverError::Store {
+                store: "RPC",
+                error: e.to_string(),
+            })?;
+
+        let package =
+            to_package(response).map_err(|_| PackageResolverError::PackageNotFound(id))?;
+        Ok(Arc::new(Package::read_from_package(&package)?))
+    }
+}
+
+/// Convert a locally compiled package into a package that types can be resolved against, linked
+/// against its published dependencies. The package is identified by the address of its modules.
+async fn local_package(
+    read_api: &ReadApi,
+    compiled_package: &CompiledPackage,
+) -> Result<(AccountAddress, Arc<Package>), anyhow::Error> {
+    let modules: Vec<_> = compiled_package.get_modules().cloned().collect();
+    ensure!(!modules.is_empty(), "Package does not contain any modules");
+
+    let objects = read_api
+        .multi_get_object_with_options(
+            compiled_package.get_dependency_storage_package_ids(),
+            RtdObjectDataOptions::bcs_lossless(),
+        )
+        .await?;
+
+    let dependencies = objects
+        .into_iter()
+        .map(to_package)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to fetch package dependencies")?;
+
+    let protocol_config = ProtocolConfig::get_for_version(ProtocolVersion::MAX, Chain::Unknown);
+    let package = MovePackage::new_initial(&modules, &protocol_config, &dependencies)?;
+    let package = Package::read_from_package(&package)?;
+    Ok((*modules[0].address(), Arc::new(package)))
+}
+
 /// Fetch move packages
 async fn fetch_move_packages(
     read_api: &ReadApi,
@@ -7,20 +7,27 @@ use anyhow::Result;
 use rtd_config::{
     transaction_deny_config::TransactionDenyConfig, verifier_signing_config::VerifierSigningConfig,
 };
+use rtd_core::authority::DEV_INSPECT_GAS_COIN_VALUE;
 use rtd_execution::Executor;
 use rtd_protocol_config::{Chain, ProtocolConfig, ProtocolVersion};
 use rtd_types::{
+    base_types::ObjectID,
     committee::{Committee, EpochId},
-    effects::TransactionEffects,
+    digests::TransactionDigest,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    error::{RtdErrorKind, RtdResult},
     execution_params::ExecutionOrEarlyError,
+    full_checkpoint_content::ObjectSet,
     gas::RtdGasStatus,
     inner_temporary_store::InnerTemporaryStore,
     metrics::{BytecodeVerifierMetrics, LimitsMetrics},
+    object::{MoveObject, OBJECT_START_VERSION, Object, Owner},
     rtd_system_state::{
         RtdSystemState, RtdSystemStateTrait,
         epoch_start_rtd_system_state::{EpochStartSystemState, EpochStartSystemStateTrait},
     },
-    transaction::{TransactionDataAPI, VerifiedTransaction},
+    transaction::{ObjectReadResult, TransactionData, TransactionDataAPI, VerifiedTransaction},
+    transaction_executor::{SimulateTransactionResult, TransactionChecks},
 };
 
 use crate::SimulatorStore;
@@ -162,4 +169,121 @@ impl EpochState {
             );
         Ok((inner_temp_store, gas_status, effects, result))
     }
+
+    /// Execute `transaction` without committing its effects, as a fullnode does to simulate a
+    /// transaction. If the transaction has no gas payment, it is paid with a mock gas coin.
+    pub fn simulate_transaction(
+        &self,
+        store: &dyn SimulatorStore,
+        deny_config: &TransactionDenyConfig,
+        verifier_signing_config: &VerifierSigningConfig,
+        mut transaction: TransactionData,
+        checks: TransactionChecks,
+    ) -> RtdResult<SimulateTransactionResult> {
+        if transaction.kind().is_system_tx() {
+            return Err(RtdErrorKind::UnsupportedFeatureError {
+                error: "simulate does not support system transactions".to_string(),
+            }
+            .into());
+        }
+        transaction.validity_check_no_gas_check(&self.protocol_config)?;
+
+        let input_object_kinds = transaction.input_objects()?;
+        let receiving_object_refs = transaction.receiving_objects();
+        rtd_transaction_checks::deny::check_transaction_for_signing(
+            &transaction,
+            &[],
+            &input_object_kinds,
+            &receiving_object_refs,
+            deny_config,
+            &store,
+        )?;
+
+        let tx_digest = transaction.digest();
+        let (mut input_objects, receiving_objects) = store.read_objects_for_synchronous_execution(
+            &tx_digest,
+            &input_object_kinds,
+            &receiving_object_refs,
+        )?;
+
+        let mock_gas_id = if transaction.gas().is_empty() {
+            let mock_gas_object = Object::new_move(
+                MoveObject::new_gas_coin(
+                    OBJECT_START_VERSION,
+                    ObjectID::MAX,
+                    DEV_INSPECT_GAS_COIN_VALUE,
+                ),
+                Owner::AddressOwner(transaction.gas_data().owner),
+                TransactionDigest::genesis_marker(),
+            );
+            transaction.gas_data_mut().payment = vec![mock_gas_object.compute_object_reference()];
+            input_objects.push(ObjectReadResult::new_from_gas_object(&mock_gas_object));
+            Some(mock_gas_object.id())
+        } else {
+            None
+        };
+
+        let (gas_status, checked_input_objects) = if checks.enabled() {
+            rtd_transaction_checks::check_transaction_input(
+                &self.protocol_config,
+                self.epoch_start_state.reference_gas_price(),
+                &transaction,
+                input_objects,
+                &receiving_objects,
+                &self.bytecode_verifier_metrics,
+                verifier_signing_config,
+            )?
+        } else {
+            let checked_input_objects = rtd_transaction_checks::check_dev_inspect_input(
+                &self.protocol_config,
+                transaction.kind(),
+                input_objects,
+                receiving_objects,
+            )?;
+            let gas_status = RtdGasStatus::new(
+                transaction.gas_budget(),
+                transaction.gas_price(),
+                self.epoch_start_state.reference_gas_price(),
+                &self.protocol_config,
+            )?;
+            (gas_status, checked_input_objects)
+        };
+
+        let (kind, signer, gas_data) = transaction.execution_parts();
+        let (inner_temp_store, _, effects, execution_result) =
+            self.executor.dev_inspect_transaction(
+                store.backing_store(),
+                &self.protocol_config,
+                self.limits_metrics.clone(),
+                false, // enable_expensive_checks
+                ExecutionOrEarlyError::Ok(()),
+                &self.epoch_start_state.epoch(),
+                self.epoch_start_state.epoch_start_timestamp_ms(),
+                checked_input_objects,
+                gas_data,
+                gas_status,
+                kind,
+                signer,
+                tx_digest,
+                checks.disabled(),
+            );
+
+        let mut objects = ObjectSet::default();
+        for object in inner_temp_store
+            .input_objects
+            .into_values()
+            .chain(inner_temp_store.written.into_values())
+        {
+            objects.insert(object);
+        }
+
+        Ok(SimulateTransactionResult {
+            events: effects.events_digest().map(|_| inner_temp_store.events),
+            effects,
+            objects,
+            execution_result,
+            mock_gas_id,
+            unchanged_loaded_runtime_objects: vec![],
+        })
+    }
 }
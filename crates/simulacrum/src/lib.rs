@@ -39,18 +39,20 @@ use rtd_types::{
     base_types::{EpochId, RtdAddress},
     committee::Committee,
     effects::TransactionEffects,
-    error::ExecutionError,
+    error::{ExecutionError, RtdResult},
     gas_coin::MIST_PER_RTD,
     inner_temporary_store::InnerTemporaryStore,
     messages_checkpoint::{EndOfEpochData, VerifiedCheckpoint},
     signature::VerifyParams,
     transaction::{Transaction, VerifiedTransaction},
+    transaction_executor::{SimulateTransactionResult, TransactionChecks},
 };
 
 use self::epoch_state::EpochState;
 pub use self::store::SimulatorStore;
 pub use self::store::in_mem_store::InMemoryStore;
 use self::store::in_mem_store::KeyStore;
+pub use self::store::persisted_store::PersistedStore;
 use rtd_core::mock_checkpoint_builder::{MockCheckpointBuilder, ValidatorKeypairProvider};
 use rtd_types::messages_checkpoint::{CheckpointContents, CheckpointSequenceNumber};
 use rtd_types::{
@@ -85,6 +87,8 @@ pub struct AdvanceEpochConfig {
 }
 
 mod epoch_state;
+mod persisted;
+mod rpc_indexes;
 pub mod store;
 
 /// A `Simulacrum` of Rtd.
@@ -202,6 +206,30 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
         }
     }
 
+    /// Create a Simulacrum instance over a `store` that already holds a chain created from
+    /// `config`, continuing from the latest checkpoint in the store.
+    pub fn resume_with_network_config_store(config: &NetworkConfig, rng: R, store: S) -> Self {
+        let keystore = KeyStore::from_network_config(config);
+        let checkpoint_builder = MockCheckpointBuilder::new(
+            store
+                .get_highest_checkpint()
+                .expect("store must hold at least the genesis checkpoint"),
+        );
+        let epoch_state = EpochState::new(store.get_system_state());
+
+        Self {
+            rng,
+            keystore,
+            genesis: config.genesis.clone(),
+            store,
+            checkpoint_builder,
+            epoch_state,
+            deny_config: TransactionDenyConfig::default(),
+            verifier_signing_config: VerifierSigningConfig::default(),
+            data_ingestion_path: None,
+        }
+    }
+
     /// Attempts to execute the provided Transaction.
     ///
     /// The provided Transaction undergoes the same types of checks that a Validator does prior to
@@ -222,6 +250,26 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
         self.execute_transaction_impl(transaction)
     }
 
+    /// Simulate the execution of `transaction` against the current state of the chain, without
+    /// committing its effects.
+    ///
+    /// As on a fullnode, the transaction does not need to be signed, and if it has no gas
+    /// payment it is paid with a mock gas coin. With `checks` disabled, the transaction is
+    /// executed in dev-inspect mode.
+    pub fn simulate_transaction(
+        &self,
+        transaction: TransactionData,
+        checks: TransactionChecks,
+    ) -> RtdResult<SimulateTransactionResult> {
+        self.epoch_state.simulate_transaction(
+            &self.store,
+            &self.deny_config,
+            &self.verifier_signing_config,
+            transaction,
+            checks,
+        )
+    }
+
     fn execute_transaction_impl(
         &mut self,
         transaction: VerifiedTransaction,
@@ -292,8 +340,8 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
         let (checkpoint, contents, _) = self
             .checkpoint_builder
             .build(&committee, self.store.get_clock().timestamp_ms());
-        self.store.insert_checkpoint(checkpoint.clone());
         self.store.insert_checkpoint_contents(contents.clone());
+        self.store.insert_checkpoint(checkpoint.clone());
         self.process_data_ingestion(checkpoint.clone(), contents)
             .unwrap();
         checkpoint
@@ -424,8 +472,21 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
             end_of_epoch_data,
         );
 
-        self.store.insert_checkpoint(checkpoint.clone());
         self.store.insert_checkpoint_contents(contents.clone());
+        self.store.insert_checkpoint(checkpoint.clone());
+
+        // The epoch that ends is closed with the start of the next one, as on a fullnode.
+        let next_epoch_info = store::epoch_info(
+            self.store.get_system_state(),
+            checkpoint.sequence_number + 1,
+        );
+        if let Some(mut epoch_info) = self.store.get_epoch_info(self.epoch_state.epoch()) {
+            epoch_info.end_timestamp_ms = next_epoch_info.start_timestamp_ms;
+            epoch_info.end_checkpoint = Some(checkpoint.sequence_number);
+            self.store.insert_epoch_info(epoch_info);
+        }
+        self.store.insert_epoch_info(next_epoch_info);
+
         self.process_data_ingestion(checkpoint, contents).unwrap();
         self.epoch_state = new_epoch_state;
     }
@@ -619,9 +680,9 @@ impl<T, V: store::SimulatorStore> ObjectStore for Simulacrum<T, V> {
 impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
     fn get_committee(
         &self,
-        _epoch: rtd_types::committee::EpochId,
+        epoch: rtd_types::committee::EpochId,
     ) -> Option<std::sync::Arc<Committee>> {
-        todo!()
+        self.store().get_committee_by_epoch(epoch).map(Arc::new)
     }
 
     fn get_latest_checkpoint(&self) -> rtd_types::storage::error::Result<VerifiedCheckpoint> {
@@ -632,16 +693,17 @@ impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
         Ok(self.epoch_state.epoch())
     }
 
+    // Every checkpoint the simulator creates is verified and synced.
     fn get_highest_verified_checkpoint(
         &self,
     ) -> rtd_types::storage::error::Result<VerifiedCheckpoint> {
-        todo!()
+        self.get_latest_checkpoint()
     }
 
     fn get_highest_synced_checkpoint(
         &self,
     ) -> rtd_types::storage::error::Result<VerifiedCheckpoint> {
-        todo!()
+        self.get_latest_checkpoint()
     }
 
     fn get_lowest_available_checkpoint(
@@ -677,9 +739,13 @@ impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
 
     fn get_checkpoint_contents_by_sequence_number(
         &self,
-        _sequence_number: rtd_types::messages_checkpoint::CheckpointSequenceNumber,
+        sequence_number: rtd_types::messages_checkpoint::CheckpointSequenceNumber,
     ) -> Option<rtd_types::messages_checkpoint::CheckpointContents> {
-        todo!()
+        let checkpoint = self
+            .store()
+            .get_checkpoint_by_sequence_number(sequence_number)?;
+        self.store()
+            .get_checkpoint_contents(&checkpoint.content_digest)
     }
 
     fn get_transaction(
@@ -720,9 +786,9 @@ impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
 
     fn get_transaction_checkpoint(
         &self,
-        _digest: &rtd_types::digests::TransactionDigest,
+        digest: &rtd_types::digests::TransactionDigest,
     ) -> Option<CheckpointSequenceNumber> {
-        None
+        self.store().get_transaction_checkpoint(digest)
     }
 }
 
@@ -746,7 +812,7 @@ impl<T: Send + Sync, V: store::SimulatorStore + Send + Sync> RpcStateReader for
     }
 
     fn indexes(&self) -> Option<&dyn rtd_types::storage::RpcIndexes> {
-        None
+        Some(self)
     }
 
     fn get_struct_layout(
@@ -910,4 +976,269 @@ mod tests {
             assert_eq!(checkpoint.network_total_transactions, 2); // genesis + 1 user txn
         };
     }
+
+    #[test]
+    fn simulate_transfer() {
+        let mut sim = Simulacrum::new();
+        let recipient = RtdAddress::random_for_testing_only();
+        let (tx, _) = sim.transfer_txn(recipient);
+        let tx_data = tx.data().transaction_data().clone();
+
+        let simulated = sim
+            .simulate_transaction(tx_data.clone(), TransactionChecks::Enabled)
+            .unwrap();
+        assert!(simulated.effects.status().is_ok());
+        assert!(simulated.mock_gas_id.is_none());
+        assert_eq!(sim.store().owned_objects(recipient).count(), 0);
+
+        // Without a gas payment, the transaction is paid with a mock gas coin.
+        let mut unpaid = tx_data;
+        unpaid.gas_data_mut().payment = vec![];
+        let simulated = sim
+            .simulate_transaction(unpaid, TransactionChecks::Disabled)
+            .unwrap();
+        assert_eq!(simulated.mock_gas_id, Some(ObjectID::MAX));
+
+        let effects = sim.execute_transaction(tx).unwrap().0;
+        assert!(effects.status().is_ok());
+        assert_eq!(sim.store().owned_objects(recipient).count(), 1);
+    }
+
+    #[test]
+    fn persisted_reopen_and_clone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain");
+        let recipient = RtdAddress::random_for_testing_only();
+
+        let rng = StdRng::from_seed([9; 32]);
+        let mut sim = Simulacrum::new_persisted(rng, ProtocolVersion::MAX, &path).unwrap();
+        sim.request_gas(recipient, MIST_PER_RTD).unwrap();
+        sim.create_checkpoint();
+        sim.advance_epoch(AdvanceEpochConfig::default());
+        let last_checkpoint = sim.store().get_highest_checkpint().unwrap();
+        drop(sim);
+
+        // The reopened chain continues in the epoch that was started last.
+        let mut sim = Simulacrum::open(StdRng::from_seed([9; 32]), &path).unwrap();
+        assert_eq!(
+            sim.store().get_highest_checkpint().unwrap().digest(),
+            last_checkpoint.digest()
+        );
+        assert_eq!(sim.store().owned_objects(recipient).count(), 1);
+        sim.advance_clock(Duration::from_millis(1));
+        let checkpoint = sim.create_checkpoint();
+        assert_eq!(
+            checkpoint.sequence_number,
+            last_checkpoint.sequence_number + 1
+        );
+        assert_eq!(checkpoint.epoch, last_checkpoint.epoch + 1);
+
+        // Copies evolve independently of the original.
+        let mut copy = sim
+            .clone_to(StdRng::from_seed([0; 32]), &dir.path().join("copy"))
+            .unwrap();
+        copy.request_gas(recipient, MIST_PER_RTD).unwrap();
+        copy.create_checkpoint();
+        assert_eq!(copy.store().owned_objects(recipient).count(), 2);
+        assert_eq!(sim.store().owned_objects(recipient).count(), 1);
+        assert_eq!(
+            sim.store().get_highest_checkpint().unwrap().digest(),
+            checkpoint.digest()
+        );
+    }
+
+    #[test]
+    fn rpc_indexes() {
+        use rtd_types::gas_coin::GAS;
+        use rtd_types::storage::RpcIndexes;
+
+        let mut sim = Simulacrum::new();
+        let recipient = RtdAddress::random_for_testing_only();
+        sim.request_gas(recipient, MIST_PER_RTD).unwrap();
+        sim.request_gas(recipient, 2 * MIST_PER_RTD).unwrap();
+        let checkpoint = sim.create_checkpoint();
+
+        // Coins are listed from the greatest balance to the least.
+        let balances: Vec<_> = sim
+            .owned_objects_iter(recipient, None, None)
+            .unwrap()
+            .map(|info| info.unwrap().balance)
+            .collect();
+        assert_eq!(balances, vec![Some(2 * MIST_PER_RTD), Some(MIST_PER_RTD)]);
+        let balance = sim.get_balance(&recipient, &GAS::type_()).unwrap().unwrap();
+        assert_eq!(balance.balance, 3 * MIST_PER_RTD);
+
+        // The previous versions of the objects a transaction writes leave the index.
+        let (tx, _) = sim.transfer_txn(recipient);
+        let sender = tx.data().transaction_data().sender();
+        sim.execute_transaction(tx).unwrap();
+        let mut owned: Vec<_> = sim
+            .owned_objects_iter(sender, None, None)
+            .unwrap()
+            .map(|info| info.unwrap().object_id)
+            .collect();
+        let mut expected: Vec<_> = sim
+            .store()
+            .owned_objects(sender)
+            .map(|object| object.id())
+            .collect();
+        owned.sort();
+        expected.sort();
+        assert_eq!(owned, expected);
+        assert_eq!(
+            sim.owned_objects_iter(recipient, None, None)
+                .unwrap()
+                .count(),
+            3
+        );
+
+        // Epochs are closed by the end of epoch checkpoint.
+        sim.advance_epoch(AdvanceEpochConfig::default());
+        let epoch = sim.get_epoch_info(0).unwrap().unwrap();
+        assert_eq!(epoch.start_checkpoint, Some(0));
+        assert_eq!(epoch.end_checkpoint, Some(checkpoint.sequence_number + 1));
+        let epoch = sim.get_epoch_info(1).unwrap().unwrap();
+        assert_eq!(epoch.start_checkpoint, Some(checkpoint.sequence_number + 2));
+        assert_eq!(epoch.end_checkpoint, None);
+        assert!(sim.get_epoch_info(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn fork_from_data_store() {
+        use rtd_data_store::{ObjectKey, ObjectStoreWriter, VersionQuery};
+        use rtd_types::{
+            RTD_CLOCK_OBJECT_ID, RTD_FRAMEWORK_PACKAGE_ID,
+            digests::TransactionDigest,
+            move_package::{MovePackage, UpgradeInfo},
+        };
+
+        fn package(id: ObjectID, version: u64, deps: &[(ObjectID, ObjectID, u64)]) -> Object {
+            let linkage_table = deps
+                .iter()
+                .map(|(original_id, upgraded_id, upgraded_version)| {
+                    let info = UpgradeInfo {
+                        upgraded_id: *upgraded_id,
+                        upgraded_version: SequenceNumber::from_u64(*upgraded_version),
+                    };
+                    (*original_id, info)
+                })
+                .collect();
+            let package = MovePackage::new(
+                id,
+                SequenceNumber::from_u64(version),
+                Default::default(),
+                u64::MAX,
+                vec![],
+                linkage_table,
+            )
+            .unwrap();
+            Object::new_from_package(package, TransactionDigest::genesis_marker())
+        }
+
+        let dep_v1 = ObjectID::from_hex_literal("0xde01").unwrap();
+        let dep_v3 = ObjectID::from_hex_literal("0xde03").unwrap();
+        let app = ObjectID::from_hex_literal("0xa44").unwrap();
+        let checkpoint = 10;
+
+        // The source holds the app package, as of the checkpoint, and the version of its
+        // dependency it links against, but none of the system packages.
+        let source =
+            rtd_data_store::stores::InMemoryStore::new(rtd_data_store::node::Node::Testnet);
+        let writes = [
+            (
+                app,
+                VersionQuery::AtCheckpoint(checkpoint),
+                package(
+                    app,
+                    1,
+                    &[
+                        (RTD_FRAMEWORK_PACKAGE_ID, RTD_FRAMEWORK_PACKAGE_ID, 1),
+                        (dep_v1, dep_v3, 3),
+                    ],
+                ),
+            ),
+            (
+                dep_v3,
+                VersionQuery::Version(3),
+                package(
+                    dep_v3,
+                    3,
+                    &[(RTD_FRAMEWORK_PACKAGE_ID, RTD_FRAMEWORK_PACKAGE_ID, 1)],
+                ),
+            ),
+            (
+                RTD_CLOCK_OBJECT_ID,
+                VersionQuery::AtCheckpoint(checkpoint),
+                Object::with_id_owner_version_for_testing(
+                    RTD_CLOCK_OBJECT_ID,
+                    SequenceNumber::from_u64(100),
+                    Owner::Immutable,
+                ),
+            ),
+        ];
+        for (object_id, version_query, object) in writes {
+            let version = object.version().value();
+            let key = ObjectKey {
+                object_id,
+                version_query,
+            };
+            source.write_object(&key, object, version).unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let sim = Simulacrum::fork_from_data_store(
+            StdRng::from_seed([9; 32]),
+            ProtocolVersion::MAX,
+            &dir.path().join("fork"),
+            &source,
+            &[app, RTD_CLOCK_OBJECT_ID, app],
+            checkpoint,
+        )
+        .unwrap();
+
+        // The app and the dependency it links against are forked.
+        let forked_app = sim.store().get_object(&app).unwrap();
+        assert_eq!(
+            forked_app
+                .data
+                .try_as_package()
+                .unwrap()
+                .linkage_table()
+                .len(),
+            2
+        );
+        assert_eq!(
+            sim.store().get_object(&dep_v3).unwrap().version(),
+            SequenceNumber::from_u64(3)
+        );
+        assert!(sim.store().get_object(&dep_v1).is_none());
+
+        // System packages and objects come from the local genesis.
+        let framework = sim.store().get_object(&RTD_FRAMEWORK_PACKAGE_ID).unwrap();
+        assert!(
+            !framework
+                .data
+                .try_as_package()
+                .unwrap()
+                .serialized_module_map()
+                .is_empty()
+        );
+        let clock = sim.store().get_object(&RTD_CLOCK_OBJECT_ID).unwrap();
+        assert!(clock.is_shared());
+        assert_ne!(clock.version(), SequenceNumber::from_u64(100));
+
+        // Dependencies missing from the source are an error.
+        let source =
+            rtd_data_store::stores::InMemoryStore::new(rtd_data_store::node::Node::Testnet);
+        let err = Simulacrum::fork_from_data_store(
+            StdRng::from_seed([9; 32]),
+            ProtocolVersion::MAX,
+            &dir.path().join("missing"),
+            &source,
+            &[app],
+            checkpoint,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+    }
 }
@@ -0,0 +1,270 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Simulacrum chains persisted on disk.
+//!
+//! A persisted chain lives in a directory holding the network config of the chain, which carries
+//! the genesis and the validator and account keys, and a [`PersistedStore`] with everything that
+//! happened since genesis.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use rtd_config::{Config, RTD_NETWORK_CONFIG};
+use rtd_core::authority::authority_store_tables::LiveObject;
+use rtd_data_store::{ObjectKey, VersionQuery};
+use rtd_protocol_config::ProtocolVersion;
+use rtd_snapshot::reader::StateSnapshotReaderV1;
+use rtd_swarm_config::network_config::NetworkConfig;
+use rtd_swarm_config::network_config_builder::ConfigBuilder;
+use rtd_types::base_types::ObjectID;
+use rtd_types::object::Object;
+use tracing::info;
+
+use crate::Simulacrum;
+use crate::store::SimulatorStore;
+use crate::store::persisted_store::PersistedStore;
+
+const STORE_DIR: &str = "store";
+
+/// Objects are written to the store in batches of this size when seeding a fork.
+const SEED_BATCH_SIZE: usize = 10_000;
+
+impl<R> Simulacrum<R, PersistedStore>
+where
+    R: rand::RngCore + rand::CryptoRng,
+{
+    /// Create a new Simulacrum instance persisted in the directory at `path`, which must not
+    /// exist yet. The chain can later be reopened with [`Simulacrum::open`].
+    pub fn new_persisted(
+        mut rng: R,
+        protocol_version: ProtocolVersion,
+        path: &Path,
+    ) -> Result<Self> {
+        if path.exists() {
+            bail!(
+                "Cannot create a chain at {}: it already exists",
+                path.display()
+            );
+        }
+        std::fs::create_dir_all(path)
+            .with_context(|| format!("Failed to create directory {}", path.display()))?;
+
+        let config = ConfigBuilder::new_with_temp_dir()
+            .rng(&mut rng)
+            .with_chain_start_timestamp_ms(1)
+            .deterministic_committee_size(NonZeroUsize::new(1).unwrap())
+            .with_protocol_version(protocol_version)
+            .build();
+        config.save(path.join(RTD_NETWORK_CONFIG))?;
+
+        let store = PersistedStore::new(&config.genesis, path.join(STORE_DIR));
+        Ok(Self::new_with_network_config_store(&config, rng, store))
+    }
+
+    /// Reopen the chain persisted in the directory at `path`, continuing from its latest
+    /// checkpoint.
+    ///
+    /// Transactions that were executed after the latest checkpoint are part of the state of the
+    /// chain, but not of any checkpoint.
+    pub fn open(rng: R, path: &Path) -> Result<Self> {
+        let config = NetworkConfig::load(path.join(RTD_NETWORK_CONFIG))?;
+        let store = PersistedStore::open(path.join(STORE_DIR))?;
+        Ok(Self::resume_with_network_config_store(&config, rng, store))
+    }
+
+    /// Copy the chain to the directory at `path`, which must not exist yet, and open the copy
+    /// with `rng` as its source of randomness.
+    pub fn clone_to<R2>(&self, rng: R2, path: &Path) -> Result<Simulacrum<R2, PersistedStore>>
+    where
+        R2: rand::RngCore + rand::CryptoRng,
+    {
+        let Some(source) = self.store.path().parent() else {
+            bail!(
+                "Store at {} is not part of a chain",
+                self.store.path().display()
+            );
+        };
+        if path.exists() {
+            bail!(
+                "Cannot copy the chain to {}: it already exists",
+                path.display()
+            );
+        }
+        std::fs::create_dir_all(path)
+            .with_context(|| format!("Failed to create directory {}", path.display()))?;
+
+        std::fs::copy(
+            source.join(RTD_NETWORK_CONFIG),
+            path.join(RTD_NETWORK_CONFIG),
+        )
+        .with_context(|| format!("Failed to copy the network config of {}", source.display()))?;
+        self.store.clone_to(path.join(STORE_DIR))?;
+        Simulacrum::open(rng, path)
+    }
+
+    /// Flush the chain to disk.
+    pub fn flush(&self) -> Result<()> {
+        self.store.flush()
+    }
+
+    /// Create a new Simulacrum instance persisted in the directory at `path`, forked from the
+    /// formal snapshot read by `reader`.
+    ///
+    /// The chain starts from a local genesis, to which the live objects of the snapshot are
+    /// added. Objects that are part of the local genesis (system packages and system objects)
+    /// are kept, so that the local validators and accounts remain in control of the chain;
+    /// everything else is taken from the snapshot as is. Transactions can then use the forked
+    /// packages and shared objects, but not the forked owned objects, whose owners' keys are
+    /// unknown.
+    pub async fn fork_from_snapshot(
+        rng: R,
+        protocol_version: ProtocolVersion,
+        path: &Path,
+        reader: &StateSnapshotReaderV1,
+    ) -> Result<Self> {
+        let mut sim = Self::new_persisted(rng, protocol_version, path)?;
+
+        let mut seeder = Seeder::new(&mut sim.store);
+        for bucket in reader.buckets()? {
+            reader
+                .for_each_object_in_bucket(bucket, |object| {
+                    if let LiveObject::Normal(object) = object {
+                        seeder.push(object);
+                    }
+                })
+                .await?;
+        }
+        let seeded = seeder.finish();
+
+        info!(
+            epoch = reader.epoch(),
+            seeded,
+            path = %path.display(),
+            "Forked chain from formal snapshot"
+        );
+        sim.flush()?;
+        Ok(sim)
+    }
+
+    /// Create a new Simulacrum instance persisted in the directory at `path`, forked from the
+    /// objects `object_ids` as of `checkpoint`, fetched from `source`.
+    ///
+    /// The packages the fetched packages depend on are fetched as well, at the versions they
+    /// are linked against. Objects are added to the local genesis as in
+    /// [`Simulacrum::fork_from_snapshot`].
+    pub fn fork_from_data_store<D: rtd_data_store::ObjectStore>(
+        rng: R,
+        protocol_version: ProtocolVersion,
+        path: &Path,
+        source: &D,
+        object_ids: &[ObjectID],
+        checkpoint: u64,
+    ) -> Result<Self> {
+        let objects = fetch_objects(source, object_ids, checkpoint)?;
+        let mut sim = Self::new_persisted(rng, protocol_version, path)?;
+
+        let mut seeder = Seeder::new(&mut sim.store);
+        objects.into_iter().for_each(|object| seeder.push(object));
+        let seeded = seeder.finish();
+
+        info!(
+            checkpoint,
+            seeded,
+            path = %path.display(),
+            "Forked chain from data store"
+        );
+        sim.flush()?;
+        Ok(sim)
+    }
+}
+
+/// Writes the objects of a fork's source to its store, skipping those already in the store.
+struct Seeder<'a, S> {
+    store: &'a mut S,
+    batch: BTreeMap<ObjectID, Object>,
+    seeded: usize,
+}
+
+impl<'a, S: SimulatorStore> Seeder<'a, S> {
+    fn new(store: &'a mut S) -> Self {
+        Self {
+            store,
+            batch: BTreeMap::new(),
+            seeded: 0,
+        }
+    }
+
+    fn push(&mut self, object: Object) {
+        if SimulatorStore::get_object(&*self.store, &object.id()).is_some() {
+            return;
+        }
+        self.batch.insert(object.id(), object);
+        if self.batch.len() >= SEED_BATCH_SIZE {
+            self.write_batch();
+        }
+    }
+
+    fn write_batch(&mut self) {
+        self.seeded += self.batch.len();
+        self.store
+            .update_objects(std::mem::take(&mut self.batch), vec![]);
+    }
+
+    /// Write the remaining objects, and return how many objects were written in total.
+    fn finish(mut self) -> usize {
+        self.write_batch();
+        self.seeded
+    }
+}
+
+/// Fetch `object_ids` as of `checkpoint` from `source`, along with the packages they depend on.
+fn fetch_objects<D: rtd_data_store::ObjectStore>(
+    source: &D,
+    object_ids: &[ObjectID],
+    checkpoint: u64,
+) -> Result<Vec<Object>> {
+    let mut objects = vec![];
+    let mut seen = BTreeSet::new();
+    let mut keys = object_ids
+        .iter()
+        .filter(|object_id| seen.insert(**object_id))
+        .map(|object_id| ObjectKey {
+            object_id: *object_id,
+            version_query: VersionQuery::AtCheckpoint(checkpoint),
+        })
+        .collect::<Vec<_>>();
+
+    while !keys.is_empty() {
+        let fetched = source.get_objects(&keys)?;
+        let mut dependencies = vec![];
+        for (key, object) in keys.iter().zip(fetched) {
+            let Some((object, _)) = object else {
+                bail!(
+                    "Object {} not found ({:?})",
+                    key.object_id,
+                    key.version_query
+                );
+            };
+            // System packages are upgraded in place, the local genesis provides them.
+            if let Some(package) = object.data.try_as_package() {
+                dependencies.extend(
+                    package
+                        .linkage_table()
+                        .values()
+                        .filter(|info| !rtd_types::is_system_package(info.upgraded_id))
+                        .filter(|info| seen.insert(info.upgraded_id))
+                        .map(|info| ObjectKey {
+                            object_id: info.upgraded_id,
+                            version_query: VersionQuery::Version(info.upgraded_version.value()),
+                        }),
+                );
+            }
+            objects.push(object);
+        }
+        keys = dependencies;
+    }
+    Ok(objects)
+}
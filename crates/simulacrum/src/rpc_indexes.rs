@@ -0,0 +1,189 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The RPC indexes of a Simulacrum chain.
+//!
+//! Objects are looked up in the indexes the store keeps as objects are written, and balances are
+//! summed from the coins in the owner index. Lookups by transaction use the store's transaction
+//! to checkpoint map.
+
+use std::collections::BTreeMap;
+
+use move_core_types::language_storage::{StructTag, TypeTag};
+use rtd_core::rpc_index::CoinIndexInfo;
+use rtd_types::base_types::{ObjectID, RtdAddress};
+use rtd_types::coin::Coin;
+use rtd_types::committee::EpochId;
+use rtd_types::digests::TransactionDigest;
+use rtd_types::full_checkpoint_content::CheckpointData;
+use rtd_types::messages_checkpoint::CheckpointSequenceNumber;
+use rtd_types::storage::error::{Error as StorageError, Result};
+use rtd_types::storage::{
+    BalanceInfo, BalanceIterator, CoinInfo, DynamicFieldKey, EpochInfo, OwnedObjectInfo, ReadStore,
+    RpcIndexes, TransactionInfo,
+};
+use typed_store::TypedStoreError;
+
+use crate::Simulacrum;
+use crate::store::SimulatorStore;
+
+impl<T: Send + Sync, V: SimulatorStore + Send + Sync> RpcIndexes for Simulacrum<T, V> {
+    fn get_epoch_info(&self, epoch: EpochId) -> Result<Option<EpochInfo>> {
+        Ok(self.store.get_epoch_info(epoch))
+    }
+
+    fn get_transaction_info(&self, digest: &TransactionDigest) -> Result<Option<TransactionInfo>> {
+        let Some(sequence_number) = self.store.get_transaction_checkpoint(digest) else {
+            return Ok(None);
+        };
+        let Some(checkpoint) = self.get_checkpoint_by_sequence_number(sequence_number) else {
+            return Ok(None);
+        };
+        let Some(contents) = self.get_checkpoint_contents_by_digest(&checkpoint.content_digest)
+        else {
+            return Ok(None);
+        };
+
+        let checkpoint: CheckpointData = self
+            .get_checkpoint_data(checkpoint, contents)
+            .map_err(StorageError::custom)?
+            .into();
+        Ok(checkpoint
+            .transactions
+            .iter()
+            .find(|tx| tx.transaction.digest() == digest)
+            .map(|tx| {
+                TransactionInfo::new(
+                    tx.transaction.transaction_data(),
+                    &tx.effects,
+                    &tx.input_objects,
+                    &tx.output_objects,
+                    sequence_number,
+                )
+            }))
+    }
+
+    fn owned_objects_iter(
+        &self,
+        owner: RtdAddress,
+        object_type: Option<StructTag>,
+        cursor: Option<OwnedObjectInfo>,
+    ) -> Result<Box<dyn Iterator<Item = Result<OwnedObjectInfo, TypedStoreError>> + '_>> {
+        Ok(Box::new(
+            self.store
+                .owned_object_infos(owner, object_type, cursor)
+                .map(Ok),
+        ))
+    }
+
+    fn dynamic_field_iter(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> Result<Box<dyn Iterator<Item = Result<DynamicFieldKey, TypedStoreError>> + '_>> {
+        Ok(Box::new(self.store.dynamic_field_ids(parent, cursor).map(
+            move |field_id| Ok(DynamicFieldKey::new(parent, field_id)),
+        )))
+    }
+
+    fn get_coin_info(&self, coin_type: &StructTag) -> Result<Option<CoinInfo>> {
+        Ok(self.store.get_coin_info(coin_type).map(
+            |CoinIndexInfo {
+                 coin_metadata_object_id,
+                 treasury_object_id,
+                 regulated_coin_metadata_object_id,
+             }| CoinInfo {
+                coin_metadata_object_id,
+                treasury_object_id,
+                regulated_coin_metadata_object_id,
+            },
+        ))
+    }
+
+    fn get_balance(
+        &self,
+        owner: &RtdAddress,
+        coin_type: &StructTag,
+    ) -> Result<Option<BalanceInfo>> {
+        let coin = Coin::type_(TypeTag::Struct(Box::new(coin_type.clone())));
+        let mut coins = self
+            .store
+            .owned_object_infos(*owner, Some(coin), None)
+            .peekable();
+        if coins.peek().is_none() {
+            return Ok(None);
+        }
+        let balance = coins
+            .filter_map(|info| info.balance)
+            .fold(0, u64::saturating_add);
+        Ok(Some(BalanceInfo { balance }))
+    }
+
+    fn balance_iter(
+        &self,
+        owner: &RtdAddress,
+        cursor: Option<(RtdAddress, StructTag)>,
+    ) -> Result<BalanceIterator<'_>> {
+        let mut balances = self.balances(*owner);
+        if let Some((_, coin_type)) = cursor {
+            balances = balances.split_off(&coin_type);
+        }
+        Ok(Box::new(balances.into_iter().map(Ok)))
+    }
+
+    fn package_versions_iter(
+        &self,
+        original_id: ObjectID,
+        cursor: Option<u64>,
+    ) -> Result<Box<dyn Iterator<Item = Result<(u64, ObjectID), TypedStoreError>> + '_>> {
+        Ok(Box::new(
+            self.store.package_versions(original_id, cursor).map(Ok),
+        ))
+    }
+
+    fn get_highest_indexed_checkpoint_seq_number(
+        &self,
+    ) -> Result<Option<CheckpointSequenceNumber>> {
+        Ok(self
+            .store
+            .get_highest_checkpint()
+            .map(|checkpoint| checkpoint.sequence_number))
+    }
+
+    // Authenticated event streams are not indexed.
+    fn authenticated_event_iter(
+        &self,
+        _stream_id: RtdAddress,
+        _start_checkpoint: u64,
+        _start_accumulator_version: Option<u64>,
+        _start_transaction_idx: Option<u32>,
+        _start_event_idx: Option<u32>,
+        _end_checkpoint: u64,
+        _limit: u32,
+    ) -> Result<
+        Box<
+            dyn Iterator<
+                    Item = Result<(u64, u64, u32, u32, rtd_types::event::Event), TypedStoreError>,
+                > + '_,
+        >,
+    > {
+        Ok(Box::new(std::iter::empty()))
+    }
+}
+
+impl<T, V: SimulatorStore> Simulacrum<T, V> {
+    /// The balance of each coin type held by `owner`, in coin objects.
+    fn balances(&self, owner: RtdAddress) -> BTreeMap<StructTag, BalanceInfo> {
+        let mut balances = BTreeMap::new();
+        for info in self.store.owned_object_infos(owner, None, None) {
+            let (Some(value), Some(TypeTag::Struct(coin_type))) =
+                (info.balance, info.object_type.type_params.first())
+            else {
+                continue;
+            };
+            let info: &mut BalanceInfo = balances.entry((**coin_type).clone()).or_default();
+            info.balance = info.balance.saturating_add(value);
+        }
+        balances
+    }
+}
@@ -4,10 +4,14 @@
 use move_binary_format::CompiledModule;
 use move_bytecode_utils::module_cache::GetModule;
 use move_core_types::{language_storage::ModuleId, resolver::ModuleResolver};
-use std::collections::{BTreeMap, HashMap};
+use move_core_types::language_storage::StructTag;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use rtd_config::genesis;
+use rtd_core::rpc_index::{CoinIndexInfo, OwnerIndexKey, PackageVersionKey};
 use rtd_types::error::RtdErrorKind;
-use rtd_types::storage::{PackageObject, get_module, load_package_object_from_object_store};
+use rtd_types::storage::{
+    EpochInfo, OwnedObjectInfo, PackageObject, get_module, load_package_object_from_object_store,
+};
 use rtd_types::{
     base_types::{AuthorityName, ObjectID, SequenceNumber, RtdAddress},
     committee::{Committee, EpochId},
@@ -25,6 +29,7 @@ use rtd_types::{
 };
 
 use super::SimulatorStore;
+use super::indexes::{self, IndexUpdate};
 
 #[derive(Debug, Default)]
 pub struct InMemoryStore {
@@ -32,6 +37,7 @@ pub struct InMemoryStore {
     checkpoints: BTreeMap<CheckpointSequenceNumber, VerifiedCheckpoint>,
     checkpoint_digest_to_sequence_number: HashMap<CheckpointDigest, CheckpointSequenceNumber>,
     checkpoint_contents: HashMap<CheckpointContentsDigest, CheckpointContents>,
+    transaction_to_checkpoint: HashMap<TransactionDigest, CheckpointSequenceNumber>,
 
     // Transaction data
     transactions: HashMap<TransactionDigest, VerifiedTransaction>,
@@ -44,6 +50,13 @@ pub struct InMemoryStore {
     // Object data
     live_objects: HashMap<ObjectID, SequenceNumber>,
     objects: HashMap<ObjectID, BTreeMap<SequenceNumber, Object>>,
+
+    // Index data
+    owner_index: BTreeMap<OwnerIndexKey, SequenceNumber>,
+    dynamic_field_index: BTreeSet<(ObjectID, ObjectID)>,
+    package_version_index: BTreeMap<PackageVersionKey, ObjectID>,
+    coin_index: HashMap<StructTag, CoinIndexInfo>,
+    epochs: BTreeMap<EpochId, EpochInfo>,
 }
 
 impl InMemoryStore {
@@ -82,6 +95,13 @@ impl InMemoryStore {
         self.checkpoint_contents.get(digest)
     }
 
+    pub fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.transaction_to_checkpoint.get(digest).copied()
+    }
+
     pub fn get_committee_by_epoch(&self, epoch: EpochId) -> Option<&Committee> {
         self.epoch_to_committee.get(epoch as usize)
     }
@@ -123,12 +143,62 @@ impl InMemoryStore {
     }
 
     pub fn owned_objects(&self, owner: RtdAddress) -> impl Iterator<Item = &Object> {
+        self.live_objects().filter(
+            move |object| matches!(object.owner, Owner::AddressOwner(addr) if addr == owner),
+        )
+    }
+
+    pub fn live_objects(&self) -> impl Iterator<Item = &Object> {
         self.live_objects
             .iter()
             .flat_map(|(id, version)| self.get_object_at_version(id, *version))
-            .filter(
-                move |object| matches!(object.owner, Owner::AddressOwner(addr) if addr == owner),
-            )
+    }
+
+    pub fn owned_object_infos(
+        &self,
+        owner: RtdAddress,
+        object_type: Option<StructTag>,
+        cursor: Option<OwnedObjectInfo>,
+    ) -> impl Iterator<Item = OwnedObjectInfo> + '_ {
+        let start = indexes::owner_lower_bound(owner, object_type.as_ref(), cursor);
+        self.owner_index
+            .range(start..)
+            .take_while(move |(key, _)| indexes::is_owned(key, owner, object_type.as_ref()))
+            .map(|(key, version)| indexes::owned_object_info(key.clone(), *version))
+    }
+
+    pub fn dynamic_field_ids(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> impl Iterator<Item = ObjectID> + '_ {
+        self.dynamic_field_index
+            .range((parent, cursor.unwrap_or(ObjectID::ZERO))..)
+            .take_while(move |(field_parent, _)| *field_parent == parent)
+            .map(|(_, field_id)| *field_id)
+    }
+
+    pub fn package_versions(
+        &self,
+        original_id: ObjectID,
+        cursor: Option<u64>,
+    ) -> impl Iterator<Item = (u64, ObjectID)> + '_ {
+        let start = PackageVersionKey {
+            original_package_id: original_id,
+            version: cursor.unwrap_or(0),
+        };
+        self.package_version_index
+            .range(start..)
+            .take_while(move |(key, _)| key.original_package_id == original_id)
+            .map(|(key, storage_id)| (key.version, *storage_id))
+    }
+
+    pub fn get_coin_info(&self, coin_type: &StructTag) -> Option<&CoinIndexInfo> {
+        self.coin_index.get(coin_type)
+    }
+
+    pub fn get_epoch_info(&self, epoch: EpochId) -> Option<&EpochInfo> {
+        self.epochs.get(&epoch)
     }
 }
 
@@ -145,6 +215,12 @@ impl InMemoryStore {
             self.insert_committee(committee);
         }
 
+        if let Some(contents) = self.checkpoint_contents.get(&checkpoint.content_digest) {
+            for tx in contents.iter() {
+                self.transaction_to_checkpoint
+                    .insert(tx.transaction, *checkpoint.sequence_number());
+            }
+        }
         self.checkpoint_digest_to_sequence_number
             .insert(*checkpoint.digest(), *checkpoint.sequence_number());
         self.checkpoints
@@ -197,11 +273,27 @@ impl InMemoryStore {
         self.events.insert(*tx_digest, events);
     }
 
+    pub fn insert_epoch_info(&mut self, epoch_info: EpochInfo) {
+        self.epochs.insert(epoch_info.epoch, epoch_info);
+    }
+
     pub fn update_objects(
         &mut self,
         written_objects: BTreeMap<ObjectID, Object>,
         deleted_objects: Vec<(ObjectID, SequenceNumber, ObjectDigest)>,
     ) {
+        let update = IndexUpdate::new(&*self, &written_objects, &deleted_objects);
+        for key in update.removed_owners {
+            self.owner_index.remove(&key);
+        }
+        for key in update.removed_dynamic_fields {
+            self.dynamic_field_index.remove(&key);
+        }
+        self.owner_index.extend(update.owners);
+        self.dynamic_field_index.extend(update.dynamic_fields);
+        self.package_version_index.extend(update.package_versions);
+        self.coin_index.extend(update.coins);
+
         for (object_id, _, _) in deleted_objects {
             self.live_objects.remove(&object_id);
         }
@@ -396,6 +488,13 @@ impl SimulatorStore for InMemoryStore {
         self.get_committee_by_epoch(epoch).cloned()
     }
 
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.get_transaction_checkpoint(digest)
+    }
+
     fn get_transaction(&self, digest: &TransactionDigest) -> Option<VerifiedTransaction> {
         self.get_transaction(digest).cloned()
     }
@@ -428,6 +527,47 @@ impl SimulatorStore for InMemoryStore {
         Box::new(self.owned_objects(owner).cloned())
     }
 
+    fn live_objects(&self) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(self.live_objects().cloned())
+    }
+
+    fn owned_object_infos(
+        &self,
+        owner: RtdAddress,
+        object_type: Option<StructTag>,
+        cursor: Option<OwnedObjectInfo>,
+    ) -> Box<dyn Iterator<Item = OwnedObjectInfo> + '_> {
+        Box::new(self.owned_object_infos(owner, object_type, cursor))
+    }
+
+    fn dynamic_field_ids(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> Box<dyn Iterator<Item = ObjectID> + '_> {
+        Box::new(self.dynamic_field_ids(parent, cursor))
+    }
+
+    fn package_versions(
+        &self,
+        original_id: ObjectID,
+        cursor: Option<u64>,
+    ) -> Box<dyn Iterator<Item = (u64, ObjectID)> + '_> {
+        Box::new(self.package_versions(original_id, cursor))
+    }
+
+    fn get_coin_info(&self, coin_type: &StructTag) -> Option<CoinIndexInfo> {
+        self.get_coin_info(coin_type).cloned()
+    }
+
+    fn get_epoch_info(&self, epoch: EpochId) -> Option<EpochInfo> {
+        self.get_epoch_info(epoch).cloned()
+    }
+
+    fn insert_epoch_info(&mut self, epoch_info: EpochInfo) {
+        self.insert_epoch_info(epoch_info)
+    }
+
     fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint) {
         self.insert_checkpoint(checkpoint)
     }
@@ -0,0 +1,221 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The RPC indexes kept by the simulator stores.
+//!
+//! Stores update their indexes as objects are written, using the keys of the RPC index of a
+//! fullnode, so that looking up the objects of an owner, the fields of a parent or the versions
+//! of a package reads only the matching entries, rather than every live object.
+
+use std::collections::BTreeMap;
+
+use move_core_types::language_storage::StructTag;
+use rtd_core::rpc_index::{CoinIndexInfo, OwnerIndexKey, PackageVersionKey};
+use rtd_types::base_types::{MoveObjectType, ObjectID, RtdAddress, SequenceNumber};
+use rtd_types::coin::{CoinMetadata, RegulatedCoinMetadata, TreasuryCap};
+use rtd_types::digests::ObjectDigest;
+use rtd_types::object::{Object, Owner};
+use rtd_types::storage::{EpochInfo, OwnedObjectInfo};
+use rtd_types::rtd_system_state::{RtdSystemState, RtdSystemStateTrait};
+
+use super::SimulatorStore;
+
+/// The changes to the indexes of a store from writing and deleting a set of objects.
+#[derive(Default)]
+pub(super) struct IndexUpdate {
+    /// Entries of the previous versions of the objects, to remove before inserting the new ones.
+    pub removed_owners: Vec<OwnerIndexKey>,
+    pub removed_dynamic_fields: Vec<(ObjectID, ObjectID)>,
+
+    pub owners: Vec<(OwnerIndexKey, SequenceNumber)>,
+    pub dynamic_fields: Vec<(ObjectID, ObjectID)>,
+    pub package_versions: Vec<(PackageVersionKey, ObjectID)>,
+    pub coins: BTreeMap<StructTag, CoinIndexInfo>,
+}
+
+impl IndexUpdate {
+    /// The changes to the indexes of `store` from writing `written_objects` and deleting
+    /// `deleted_objects`, computed before the store is updated.
+    pub fn new<S: SimulatorStore + ?Sized>(
+        store: &S,
+        written_objects: &BTreeMap<ObjectID, Object>,
+        deleted_objects: &[(ObjectID, SequenceNumber, ObjectDigest)],
+    ) -> Self {
+        let mut update = Self::default();
+        let previous_ids = deleted_objects
+            .iter()
+            .map(|(object_id, _, _)| object_id)
+            .chain(written_objects.keys());
+        for object_id in previous_ids {
+            let Some(previous) = SimulatorStore::get_object(store, object_id) else {
+                continue;
+            };
+            update.removed_owners.extend(owner_key(&previous));
+            update
+                .removed_dynamic_fields
+                .extend(dynamic_field_parent(&previous).map(|parent| (parent, *object_id)));
+        }
+
+        for (object_id, object) in written_objects {
+            update
+                .owners
+                .extend(owner_key(object).map(|key| (key, object.version())));
+            update
+                .dynamic_fields
+                .extend(dynamic_field_parent(object).map(|parent| (parent, *object_id)));
+            update.package_versions.extend(package_version_key(object));
+            record_coin_object(object, &mut update.coins, |coin_type| {
+                store.get_coin_info(coin_type)
+            });
+        }
+        update
+    }
+}
+
+/// Record `object` in the info of its coin in `coins`, if it is the metadata or treasury cap of
+/// a coin. The info of coins not in `coins` yet starts from `existing`.
+///
+/// Objects are kept in the info of their coin once created, as on a fullnode.
+pub(super) fn record_coin_object(
+    object: &Object,
+    coins: &mut BTreeMap<StructTag, CoinIndexInfo>,
+    existing: impl FnOnce(&StructTag) -> Option<CoinIndexInfo>,
+) {
+    let Some(object_type) = object.type_().and_then(MoveObjectType::other) else {
+        return;
+    };
+    type Field = fn(&mut CoinIndexInfo) -> &mut Option<ObjectID>;
+    let (coin_type, field): (_, Field) =
+        if let Some(coin_type) = CoinMetadata::is_coin_metadata_with_coin_type(object_type) {
+            (coin_type, |info| &mut info.coin_metadata_object_id)
+        } else if let Some(coin_type) = TreasuryCap::is_treasury_with_coin_type(object_type) {
+            (coin_type, |info| &mut info.treasury_object_id)
+        } else if let Some(coin_type) =
+            RegulatedCoinMetadata::is_regulated_coin_metadata_with_coin_type(object_type)
+        {
+            (coin_type, |info| {
+                &mut info.regulated_coin_metadata_object_id
+            })
+        } else {
+            return;
+        };
+
+    let info = coins.entry(coin_type.clone()).or_insert_with(|| {
+        existing(coin_type).unwrap_or(CoinIndexInfo {
+            coin_metadata_object_id: None,
+            treasury_object_id: None,
+            regulated_coin_metadata_object_id: None,
+        })
+    });
+    *field(info) = Some(object.id());
+}
+
+/// The address that owns `object`, if it is address-owned.
+fn address_owner(object: &Object) -> Option<RtdAddress> {
+    match object.owner() {
+        Owner::AddressOwner(owner) | Owner::ConsensusAddressOwner { owner, .. } => Some(*owner),
+        _ => None,
+    }
+}
+
+/// Whether `object_type` matches the type filter `filter`, whose type parameters match any if
+/// they are left out.
+fn matches_type(filter: &StructTag, object_type: &StructTag) -> bool {
+    filter.address == object_type.address
+        && filter.module == object_type.module
+        && filter.name == object_type.name
+        && (filter.type_params.is_empty() || filter.type_params == object_type.type_params)
+}
+
+/// The key of `object` in the owner index, if it is address-owned.
+pub(super) fn owner_key(object: &Object) -> Option<OwnerIndexKey> {
+    Some(OwnerIndexKey {
+        owner: address_owner(object)?,
+        object_type: object.struct_tag()?,
+        inverted_balance: object.as_coin_maybe().map(|coin| !coin.balance.value()),
+        object_id: object.id(),
+    })
+}
+
+/// The key to start reading the owner index of `owner` from: `cursor` if there is one, or else
+/// the first object of type `object_type`, or of any type.
+pub(super) fn owner_lower_bound(
+    owner: RtdAddress,
+    object_type: Option<&StructTag>,
+    cursor: Option<OwnedObjectInfo>,
+) -> OwnerIndexKey {
+    match cursor {
+        Some(cursor) => OwnerIndexKey {
+            owner: cursor.owner,
+            object_type: cursor.object_type,
+            inverted_balance: cursor.balance.map(std::ops::Not::not),
+            object_id: cursor.object_id,
+        },
+        None => OwnerIndexKey {
+            owner,
+            // No type is defined at address 0x0, so this comes before the type of any object.
+            object_type: object_type
+                .cloned()
+                .unwrap_or_else(|| "0x0::a::a".parse().unwrap()),
+            inverted_balance: None,
+            object_id: ObjectID::ZERO,
+        },
+    }
+}
+
+/// Whether `key` is in the owner index of `owner`, for an object of type `object_type` if one is
+/// given. The keys that match are contiguous in the index.
+pub(super) fn is_owned(
+    key: &OwnerIndexKey,
+    owner: RtdAddress,
+    object_type: Option<&StructTag>,
+) -> bool {
+    key.owner == owner && object_type.is_none_or(|filter| matches_type(filter, &key.object_type))
+}
+
+pub(super) fn owned_object_info(key: OwnerIndexKey, version: SequenceNumber) -> OwnedObjectInfo {
+    OwnedObjectInfo {
+        owner: key.owner,
+        object_type: key.object_type,
+        balance: key.inverted_balance.map(std::ops::Not::not),
+        object_id: key.object_id,
+        version,
+    }
+}
+
+/// The parent of `object`, if it is a dynamic field.
+pub(super) fn dynamic_field_parent(object: &Object) -> Option<ObjectID> {
+    let Owner::ObjectOwner(parent) = object.owner() else {
+        return None;
+    };
+    object
+        .data
+        .try_as_move()
+        .is_some_and(|object| object.type_().is_dynamic_field())
+        .then(|| (*parent).into())
+}
+
+/// The key of `object` in the package version index, and the ID it is stored at, if it is a
+/// package.
+pub(super) fn package_version_key(object: &Object) -> Option<(PackageVersionKey, ObjectID)> {
+    let package = object.data.try_as_package()?;
+    let key = PackageVersionKey {
+        original_package_id: package.original_package_id(),
+        version: package.version().value(),
+    };
+    Some((key, package.id()))
+}
+
+/// The info of the epoch starting at checkpoint `start_checkpoint` with `system_state`.
+pub(crate) fn epoch_info(system_state: RtdSystemState, start_checkpoint: u64) -> EpochInfo {
+    EpochInfo {
+        epoch: system_state.epoch(),
+        protocol_version: Some(system_state.protocol_version()),
+        start_timestamp_ms: Some(system_state.epoch_start_timestamp_ms()),
+        end_timestamp_ms: None,
+        start_checkpoint: Some(start_checkpoint),
+        end_checkpoint: None,
+        reference_gas_price: Some(system_state.reference_gas_price()),
+        system_state: Some(system_state),
+    }
+}
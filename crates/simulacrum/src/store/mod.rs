@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::collections::BTreeMap;
+use move_core_types::language_storage::StructTag;
 use rtd_config::genesis;
+use rtd_core::rpc_index::CoinIndexInfo;
 use rtd_types::base_types::ObjectRef;
 use rtd_types::error::UserInputError;
 use rtd_types::transaction::InputObjects;
@@ -20,10 +22,14 @@ use rtd_types::{
         VerifiedCheckpoint,
     },
     object::Object,
-    storage::{BackingStore, ChildObjectResolver, ParentSync},
+    storage::{BackingStore, ChildObjectResolver, EpochInfo, OwnedObjectInfo, ParentSync},
     transaction::{InputObjectKind, VerifiedTransaction},
 };
 pub mod in_mem_store;
+mod indexes;
+pub mod persisted_store;
+
+pub(crate) use indexes::epoch_info;
 
 pub trait SimulatorStore:
     rtd_types::storage::BackingPackageStore
@@ -32,9 +38,10 @@ pub trait SimulatorStore:
     + ChildObjectResolver
 {
     fn init_with_genesis(&mut self, genesis: &genesis::Genesis) {
-        self.insert_checkpoint(genesis.checkpoint());
         self.insert_checkpoint_contents(genesis.checkpoint_contents().clone());
+        self.insert_checkpoint(genesis.checkpoint());
         self.insert_committee(genesis.committee().unwrap());
+        self.insert_epoch_info(epoch_info(genesis.rtd_system_object(), 0));
         self.insert_transaction(VerifiedTransaction::new_unchecked(
             genesis.transaction().clone(),
         ));
@@ -86,6 +93,112 @@ pub trait SimulatorStore:
 
     fn owned_objects(&self, owner: RtdAddress) -> Box<dyn Iterator<Item = Object> + '_>;
 
+    fn live_objects(&self) -> Box<dyn Iterator<Item = Object> + '_>;
+
+    /// The checkpoint that includes the transaction with `digest`.
+    ///
+    /// By default this searches the checkpoints from the highest one down.
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        let highest = *self.get_highest_checkpint()?.sequence_number();
+        (0..=highest).rev().find(|sequence_number| {
+            self.get_checkpoint_by_sequence_number(*sequence_number)
+                .and_then(|checkpoint| self.get_checkpoint_contents(&checkpoint.content_digest))
+                .is_some_and(|contents| contents.iter().any(|tx| tx.transaction == *digest))
+        })
+    }
+
+    /// Address-owned objects of `owner`, of type `object_type` if one is given, starting from
+    /// `cursor`. Objects are ordered like in the owner index of a fullnode: by type, then coins
+    /// from the greatest balance to the least, then by ID.
+    ///
+    /// By default this scans the live objects.
+    fn owned_object_infos(
+        &self,
+        owner: RtdAddress,
+        object_type: Option<StructTag>,
+        cursor: Option<OwnedObjectInfo>,
+    ) -> Box<dyn Iterator<Item = OwnedObjectInfo> + '_> {
+        let start = indexes::owner_lower_bound(owner, object_type.as_ref(), cursor);
+        let objects: BTreeMap<_, _> = self
+            .live_objects()
+            .filter_map(|object| {
+                let key = indexes::owner_key(&object)?;
+                (key >= start && indexes::is_owned(&key, owner, object_type.as_ref()))
+                    .then(|| (key, object.version()))
+            })
+            .collect();
+        Box::new(
+            objects
+                .into_iter()
+                .map(|(key, version)| indexes::owned_object_info(key, version)),
+        )
+    }
+
+    /// IDs of the dynamic fields of `parent`, in ascending order, starting from `cursor`.
+    ///
+    /// By default this scans the live objects.
+    fn dynamic_field_ids(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> Box<dyn Iterator<Item = ObjectID> + '_> {
+        let mut fields: Vec<_> = self
+            .live_objects()
+            .filter(|object| indexes::dynamic_field_parent(object) == Some(parent))
+            .map(|object| object.id())
+            .filter(|field_id| cursor.is_none_or(|cursor| *field_id >= cursor))
+            .collect();
+        fields.sort();
+        Box::new(fields.into_iter())
+    }
+
+    /// Versions of the package originally published at `original_id`, and the IDs they are
+    /// stored at, in ascending order, starting from the version `cursor`.
+    ///
+    /// By default this scans the live objects.
+    fn package_versions(
+        &self,
+        original_id: ObjectID,
+        cursor: Option<u64>,
+    ) -> Box<dyn Iterator<Item = (u64, ObjectID)> + '_> {
+        let versions: BTreeMap<_, _> = self
+            .live_objects()
+            .filter_map(|object| indexes::package_version_key(&object))
+            .filter(|(key, _)| {
+                key.original_package_id == original_id
+                    && cursor.is_none_or(|cursor| key.version >= cursor)
+            })
+            .map(|(key, storage_id)| (key.version, storage_id))
+            .collect();
+        Box::new(versions.into_iter())
+    }
+
+    /// The metadata and treasury cap objects of the coin `coin_type`.
+    ///
+    /// By default this scans the live objects.
+    fn get_coin_info(&self, coin_type: &StructTag) -> Option<CoinIndexInfo> {
+        let mut coins = BTreeMap::new();
+        for object in self.live_objects() {
+            indexes::record_coin_object(&object, &mut coins, |_| None);
+        }
+        coins.remove(coin_type)
+    }
+
+    /// The info of `epoch`, if it has started.
+    ///
+    /// By default epochs are not indexed.
+    fn get_epoch_info(&self, _epoch: EpochId) -> Option<EpochInfo> {
+        None
+    }
+
+    /// Insert the info of an epoch, when it starts and again when it ends.
+    fn insert_epoch_info(&mut self, _epoch_info: EpochInfo) {}
+
+    /// Insert a checkpoint. Its contents are inserted first, so that stores can index the
+    /// checkpoint's transactions.
     fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint);
 
     fn insert_checkpoint_contents(&mut self, contents: CheckpointContents);
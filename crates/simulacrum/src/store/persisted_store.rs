@@ -0,0 +1,597 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{Context, Result, bail};
+use move_binary_format::CompiledModule;
+use move_bytecode_utils::module_cache::GetModule;
+use move_core_types::{
+    language_storage::{ModuleId, StructTag},
+    resolver::ModuleResolver,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use rtd_config::genesis;
+use rtd_core::rpc_index::{CoinIndexInfo, OwnerIndexKey, PackageVersionKey};
+use rtd_types::error::RtdErrorKind;
+use rtd_types::storage::{
+    EpochInfo, OwnedObjectInfo, PackageObject, get_module, load_package_object_from_object_store,
+};
+use rtd_types::{
+    base_types::{ObjectID, SequenceNumber, RtdAddress},
+    committee::{Committee, EpochId},
+    digests::{ObjectDigest, TransactionDigest},
+    effects::{TransactionEffects, TransactionEffectsAPI, TransactionEvents},
+    error::RtdError,
+    messages_checkpoint::{
+        CheckpointContents, CheckpointContentsDigest, CheckpointDigest, CheckpointSequenceNumber,
+        TrustedCheckpoint, VerifiedCheckpoint,
+    },
+    object::{Object, Owner},
+    storage::{BackingPackageStore, ChildObjectResolver, ObjectStore, ParentSync},
+    transaction::{TrustedTransaction, VerifiedTransaction},
+};
+use typed_store::{
+    DBMapUtils, Map,
+    metrics::SamplingInterval,
+    rocks::{DBMap, MetricConf},
+};
+
+use super::SimulatorStore;
+use super::indexes::{self, IndexUpdate};
+
+/// A `SimulatorStore` backed by RocksDB.
+///
+/// Everything written to the store survives the process, so that a chain can be reopened with
+/// [`PersistedStore::open`], or copied with [`PersistedStore::clone_to`] to run several scenarios
+/// from the same starting state.
+pub struct PersistedStore {
+    path: PathBuf,
+    tables: PersistedStoreTables,
+}
+
+#[derive(DBMapUtils)]
+struct PersistedStoreTables {
+    // Checkpoint data
+    checkpoints: DBMap<CheckpointSequenceNumber, TrustedCheckpoint>,
+    checkpoint_digest_to_sequence_number: DBMap<CheckpointDigest, CheckpointSequenceNumber>,
+    checkpoint_contents: DBMap<CheckpointContentsDigest, CheckpointContents>,
+    transaction_to_checkpoint: DBMap<TransactionDigest, CheckpointSequenceNumber>,
+
+    // Transaction data
+    transactions: DBMap<TransactionDigest, TrustedTransaction>,
+    effects: DBMap<TransactionDigest, TransactionEffects>,
+    events: DBMap<TransactionDigest, TransactionEvents>,
+
+    // Committee data
+    epoch_to_committee: DBMap<EpochId, Committee>,
+
+    // Object data
+    live_objects: DBMap<ObjectID, SequenceNumber>,
+    objects: DBMap<(ObjectID, SequenceNumber), Object>,
+
+    // Index data
+    owner_index: DBMap<OwnerIndexKey, SequenceNumber>,
+    dynamic_field_index: DBMap<(ObjectID, ObjectID), ()>,
+    package_version_index: DBMap<PackageVersionKey, ObjectID>,
+    coin_index: DBMap<StructTag, CoinIndexInfo>,
+    epochs: DBMap<EpochId, EpochInfo>,
+}
+
+impl PersistedStore {
+    /// Create a store at `path`, initialized with `genesis`.
+    pub fn new(genesis: &genesis::Genesis, path: PathBuf) -> Self {
+        let mut store = Self::open_tables(path);
+        store.init_with_genesis(genesis);
+        store
+    }
+
+    /// Reopen a store previously created at `path`.
+    pub fn open(path: PathBuf) -> Result<Self> {
+        if !path.is_dir() {
+            bail!("No simulator store at {}", path.display());
+        }
+        let store = Self::open_tables(path);
+        if store.get_checkpoint_by_sequence_number(0).is_none() {
+            bail!(
+                "Simulator store at {} has no genesis checkpoint",
+                store.path.display()
+            );
+        }
+        Ok(store)
+    }
+
+    fn open_tables(path: PathBuf) -> Self {
+        let sampling = SamplingInterval::new(Duration::from_secs(60), 0);
+        let tables = PersistedStoreTables::open_tables_read_write(
+            path.clone(),
+            MetricConf::new("simulacrum").with_sampling(sampling),
+            None,
+            None,
+        );
+        Self { path, tables }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush everything written so far to disk.
+    pub fn flush(&self) -> Result<()> {
+        self.tables
+            .checkpoints
+            .flush()
+            .with_context(|| format!("Failed to flush store at {}", self.path.display()))
+    }
+
+    /// Copy the store to `path`, which must not exist yet, and open the copy. The copy is
+    /// independent: writes to either store are not seen by the other.
+    pub fn clone_to(&self, path: PathBuf) -> Result<Self> {
+        self.tables
+            .checkpoints
+            .checkpoint_db(&path)
+            .with_context(|| format!("Failed to copy store to {}", path.display()))?;
+        Self::open(path)
+    }
+}
+
+impl SimulatorStore for PersistedStore {
+    fn get_checkpoint_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> Option<VerifiedCheckpoint> {
+        self.tables
+            .checkpoints
+            .get(&sequence_number)
+            .expect("Fatal: DB read failed")
+            .map(|checkpoint| checkpoint.into())
+    }
+
+    fn get_checkpoint_by_digest(&self, digest: &CheckpointDigest) -> Option<VerifiedCheckpoint> {
+        self.tables
+            .checkpoint_digest_to_sequence_number
+            .get(digest)
+            .expect("Fatal: DB read failed")
+            .and_then(|sequence_number| self.get_checkpoint_by_sequence_number(sequence_number))
+    }
+
+    fn get_highest_checkpint(&self) -> Option<VerifiedCheckpoint> {
+        self.tables
+            .checkpoints
+            .reversed_safe_iter_with_bounds(None, None)
+            .expect("Fatal: DB read failed")
+            .next()
+            .transpose()
+            .expect("Fatal: DB read failed")
+            .map(|(_, checkpoint)| checkpoint.into())
+    }
+
+    fn get_checkpoint_contents(
+        &self,
+        digest: &CheckpointContentsDigest,
+    ) -> Option<CheckpointContents> {
+        self.tables
+            .checkpoint_contents
+            .get(digest)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.tables
+            .transaction_to_checkpoint
+            .get(digest)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_committee_by_epoch(&self, epoch: EpochId) -> Option<Committee> {
+        self.tables
+            .epoch_to_committee
+            .get(&epoch)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_transaction(&self, digest: &TransactionDigest) -> Option<VerifiedTransaction> {
+        self.tables
+            .transactions
+            .get(digest)
+            .expect("Fatal: DB read failed")
+            .map(|transaction| transaction.into())
+    }
+
+    fn get_transaction_effects(&self, digest: &TransactionDigest) -> Option<TransactionEffects> {
+        self.tables
+            .effects
+            .get(digest)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_transaction_events(&self, digest: &TransactionDigest) -> Option<TransactionEvents> {
+        self.tables
+            .events
+            .get(digest)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_object(&self, id: &ObjectID) -> Option<Object> {
+        let version = self
+            .tables
+            .live_objects
+            .get(id)
+            .expect("Fatal: DB read failed")?;
+        self.get_object_at_version(id, version)
+    }
+
+    fn get_object_at_version(&self, id: &ObjectID, version: SequenceNumber) -> Option<Object> {
+        self.tables
+            .objects
+            .get(&(*id, version))
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_system_state(&self) -> rtd_types::rtd_system_state::RtdSystemState {
+        rtd_types::rtd_system_state::get_rtd_system_state(self).expect("system state must exist")
+    }
+
+    fn get_clock(&self) -> rtd_types::clock::Clock {
+        SimulatorStore::get_object(self, &rtd_types::RTD_CLOCK_OBJECT_ID)
+            .expect("clock should exist")
+            .to_rust()
+            .expect("clock object should deserialize")
+    }
+
+    fn owned_objects(&self, owner: RtdAddress) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(self.live_objects().filter(
+            move |object| matches!(object.owner, Owner::AddressOwner(addr) if addr == owner),
+        ))
+    }
+
+    fn live_objects(&self) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(
+            self.tables
+                .live_objects
+                .safe_iter()
+                .map(|result| result.expect("Fatal: DB read failed"))
+                .filter_map(|(id, version)| self.get_object_at_version(&id, version)),
+        )
+    }
+
+    fn owned_object_infos(
+        &self,
+        owner: RtdAddress,
+        object_type: Option<StructTag>,
+        cursor: Option<OwnedObjectInfo>,
+    ) -> Box<dyn Iterator<Item = OwnedObjectInfo> + '_> {
+        let start = indexes::owner_lower_bound(owner, object_type.as_ref(), cursor);
+        Box::new(
+            self.tables
+                .owner_index
+                .safe_iter_with_bounds(Some(start), None)
+                .map(|result| result.expect("Fatal: DB read failed"))
+                .take_while(move |(key, _)| indexes::is_owned(key, owner, object_type.as_ref()))
+                .map(|(key, version)| indexes::owned_object_info(key, version)),
+        )
+    }
+
+    fn dynamic_field_ids(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> Box<dyn Iterator<Item = ObjectID> + '_> {
+        let start = (parent, cursor.unwrap_or(ObjectID::ZERO));
+        Box::new(
+            self.tables
+                .dynamic_field_index
+                .safe_iter_with_bounds(Some(start), None)
+                .map(|result| result.expect("Fatal: DB read failed"))
+                .take_while(move |((field_parent, _), ())| *field_parent == parent)
+                .map(|((_, field_id), ())| field_id),
+        )
+    }
+
+    fn package_versions(
+        &self,
+        original_id: ObjectID,
+        cursor: Option<u64>,
+    ) -> Box<dyn Iterator<Item = (u64, ObjectID)> + '_> {
+        let start = PackageVersionKey {
+            original_package_id: original_id,
+            version: cursor.unwrap_or(0),
+        };
+        Box::new(
+            self.tables
+                .package_version_index
+                .safe_iter_with_bounds(Some(start), None)
+                .map(|result| result.expect("Fatal: DB read failed"))
+                .take_while(move |(key, _)| key.original_package_id == original_id)
+                .map(|(key, storage_id)| (key.version, storage_id)),
+        )
+    }
+
+    fn get_coin_info(&self, coin_type: &StructTag) -> Option<CoinIndexInfo> {
+        self.tables
+            .coin_index
+            .get(coin_type)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn get_epoch_info(&self, epoch: EpochId) -> Option<EpochInfo> {
+        self.tables
+            .epochs
+            .get(&epoch)
+            .expect("Fatal: DB read failed")
+    }
+
+    fn insert_epoch_info(&mut self, epoch_info: EpochInfo) {
+        self.tables
+            .epochs
+            .insert(&epoch_info.epoch, &epoch_info)
+            .expect("Fatal: DB write failed");
+    }
+
+    fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint) {
+        if let Some(contents) = self.get_checkpoint_contents(&checkpoint.content_digest) {
+            self.tables
+                .transaction_to_checkpoint
+                .multi_insert(
+                    contents
+                        .iter()
+                        .map(|tx| (tx.transaction, checkpoint.sequence_number())),
+                )
+                .expect("Fatal: DB write failed");
+        }
+        self.tables
+            .checkpoint_digest_to_sequence_number
+            .insert(checkpoint.digest(), checkpoint.sequence_number())
+            .expect("Fatal: DB write failed");
+        self.tables
+            .checkpoints
+            .insert(checkpoint.sequence_number(), checkpoint.serializable_ref())
+            .expect("Fatal: DB write failed");
+    }
+
+    fn insert_checkpoint_contents(&mut self, contents: CheckpointContents) {
+        self.tables
+            .checkpoint_contents
+            .insert(contents.digest(), &contents)
+            .expect("Fatal: DB write failed");
+    }
+
+    fn insert_committee(&mut self, committee: Committee) {
+        let epoch = committee.epoch;
+        if self
+            .tables
+            .epoch_to_committee
+            .contains_key(&epoch)
+            .expect("Fatal: DB read failed")
+        {
+            return;
+        }
+
+        let next_epoch = self
+            .tables
+            .epoch_to_committee
+            .reversed_safe_iter_with_bounds(None, None)
+            .expect("Fatal: DB read failed")
+            .next()
+            .transpose()
+            .expect("Fatal: DB read failed")
+            .map_or(0, |(epoch, _)| epoch + 1);
+        if epoch != next_epoch {
+            panic!("committee was inserted into EpochCommitteeMap out of order");
+        }
+        self.tables
+            .epoch_to_committee
+            .insert(&epoch, &committee)
+            .expect("Fatal: DB write failed");
+    }
+
+    fn insert_executed_transaction(
+        &mut self,
+        transaction: VerifiedTransaction,
+        effects: TransactionEffects,
+        events: TransactionEvents,
+        written_objects: BTreeMap<ObjectID, Object>,
+    ) {
+        let deleted_objects = effects.deleted();
+        let tx_digest = *effects.transaction_digest();
+        self.insert_transaction(transaction);
+        self.insert_transaction_effects(effects);
+        self.insert_events(&tx_digest, events);
+        self.update_objects(written_objects, deleted_objects);
+    }
+
+    fn insert_transaction(&mut self, transaction: VerifiedTransaction) {
+        self.tables
+            .transactions
+            .insert(transaction.digest(), transaction.serializable_ref())
+            .expect("Fatal: DB write failed");
+    }
+
+    fn insert_transaction_effects(&mut self, effects: TransactionEffects) {
+        self.tables
+            .effects
+            .insert(effects.transaction_digest(), &effects)
+            .expect("Fatal: DB write failed");
+    }
+
+    fn insert_events(&mut self, tx_digest: &TransactionDigest, events: TransactionEvents) {
+        self.tables
+            .events
+            .insert(tx_digest, &events)
+            .expect("Fatal: DB write failed");
+    }
+
+    fn update_objects(
+        &mut self,
+        written_objects: BTreeMap<ObjectID, Object>,
+        deleted_objects: Vec<(ObjectID, SequenceNumber, ObjectDigest)>,
+    ) {
+        let update = IndexUpdate::new(&*self, &written_objects, &deleted_objects);
+        let mut batch = self.tables.live_objects.batch();
+        batch
+            .delete_batch(&self.tables.owner_index, update.removed_owners)
+            .expect("Fatal: DB write failed");
+        batch
+            .delete_batch(
+                &self.tables.dynamic_field_index,
+                update.removed_dynamic_fields,
+            )
+            .expect("Fatal: DB write failed");
+        batch
+            .insert_batch(&self.tables.owner_index, update.owners)
+            .expect("Fatal: DB write failed");
+        batch
+            .insert_batch(
+                &self.tables.dynamic_field_index,
+                update.dynamic_fields.into_iter().map(|key| (key, ())),
+            )
+            .expect("Fatal: DB write failed");
+        batch
+            .insert_batch(&self.tables.package_version_index, update.package_versions)
+            .expect("Fatal: DB write failed");
+        batch
+            .insert_batch(&self.tables.coin_index, update.coins)
+            .expect("Fatal: DB write failed");
+
+        batch
+            .delete_batch(
+                &self.tables.live_objects,
+                deleted_objects
+                    .into_iter()
+                    .map(|(object_id, _, _)| object_id),
+            )
+            .expect("Fatal: DB write failed");
+        batch
+            .insert_batch(
+                &self.tables.live_objects,
+                written_objects
+                    .iter()
+                    .map(|(object_id, object)| (*object_id, object.version())),
+            )
+            .expect("Fatal: DB write failed");
+        batch
+            .insert_batch(
+                &self.tables.objects,
+                written_objects
+                    .into_iter()
+                    .map(|(object_id, object)| ((object_id, object.version()), object)),
+            )
+            .expect("Fatal: DB write failed");
+        batch.write().expect("Fatal: DB write failed");
+    }
+
+    fn backing_store(&self) -> &dyn rtd_types::storage::BackingStore {
+        self
+    }
+}
+
+impl BackingPackageStore for PersistedStore {
+    fn get_package_object(
+        &self,
+        package_id: &ObjectID,
+    ) -> rtd_types::error::RtdResult<Option<PackageObject>> {
+        load_package_object_from_object_store(self, package_id)
+    }
+}
+
+impl ChildObjectResolver for PersistedStore {
+    fn read_child_object(
+        &self,
+        parent: &ObjectID,
+        child: &ObjectID,
+        child_version_upper_bound: SequenceNumber,
+    ) -> rtd_types::error::RtdResult<Option<Object>> {
+        let child_object = match SimulatorStore::get_object(self, child) {
+            None => return Ok(None),
+            Some(obj) => obj,
+        };
+
+        let parent = *parent;
+        if child_object.owner != Owner::ObjectOwner(parent.into()) {
+            return Err(RtdErrorKind::InvalidChildObjectAccess {
+                object: *child,
+                given_parent: parent,
+                actual_owner: child_object.owner.clone(),
+            }
+            .into());
+        }
+
+        if child_object.version() > child_version_upper_bound {
+            return Err(RtdErrorKind::UnsupportedFeatureError {
+                error: "TODO PersistedStore::read_child_object does not yet support bounded reads"
+                    .to_owned(),
+            }
+            .into());
+        }
+
+        Ok(Some(child_object))
+    }
+
+    fn get_object_received_at_version(
+        &self,
+        owner: &ObjectID,
+        receiving_object_id: &ObjectID,
+        receive_object_at_version: SequenceNumber,
+        _epoch_id: EpochId,
+    ) -> rtd_types::error::RtdResult<Option<Object>> {
+        let recv_object = match SimulatorStore::get_object(self, receiving_object_id) {
+            None => return Ok(None),
+            Some(obj) => obj,
+        };
+        if recv_object.owner != Owner::AddressOwner((*owner).into()) {
+            return Ok(None);
+        }
+
+        if recv_object.version() != receive_object_at_version {
+            return Ok(None);
+        }
+        Ok(Some(recv_object))
+    }
+}
+
+impl GetModule for PersistedStore {
+    type Error = RtdError;
+    type Item = CompiledModule;
+
+    fn get_module_by_id(&self, id: &ModuleId) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self
+            .get_module(id)?
+            .map(|bytes| CompiledModule::deserialize_with_defaults(&bytes).unwrap()))
+    }
+}
+
+impl ModuleResolver for PersistedStore {
+    type Error = RtdError;
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+        get_module(self, module_id)
+    }
+}
+
+impl ObjectStore for PersistedStore {
+    fn get_object(&self, object_id: &ObjectID) -> Option<Object> {
+        SimulatorStore::get_object(self, object_id)
+    }
+
+    fn get_object_by_key(
+        &self,
+        object_id: &ObjectID,
+        version: rtd_types::base_types::VersionNumber,
+    ) -> Option<Object> {
+        self.get_object_at_version(object_id, version)
+    }
+}
+
+impl ParentSync for PersistedStore {
+    fn get_latest_parent_entry_ref_deprecated(
+        &self,
+        _object_id: ObjectID,
+    ) -> Option<rtd_types::base_types::ObjectRef> {
+        panic!("Never called in newer protocol versions")
+    }
+}
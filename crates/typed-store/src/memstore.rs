@@ -91,6 +91,15 @@ impl InMemoryDB {
         }
     }
 
+    /// Returns a copy of the current contents of all column families, unaffected by later
+    /// writes to this database.
+    pub fn snapshot(&self) -> InMemoryDB {
+        let data = self.data.read().expect("can't read data");
+        InMemoryDB {
+            data: Arc::new(RwLock::new(data.clone())),
+        }
+    }
+
     pub fn drop_cf(&self, name: &str) {
         self.data.write().expect("can't write data").remove(name);
     }
@@ -4,6 +4,7 @@ pub mod errors;
 mod options;
 mod rocks_util;
 pub(crate) mod safe_iter;
+mod snapshot;
 
 use crate::memstore::{InMemoryBatch, InMemoryDB};
 use crate::rocks::errors::typed_store_err_from_bcs_err;
@@ -12,6 +13,7 @@ pub use crate::rocks::options::{
     DBMapTableConfigMap, DBOptions, ReadWriteOptions, default_db_options, read_size_from_env,
 };
 use crate::rocks::safe_iter::{SafeIter, SafeRevIter};
+pub use crate::rocks::snapshot::{DBSnapshot, SnapshotDBMap};
 #[cfg(tidehunter)]
 use crate::tidehunter_util::{
     apply_range_bounds, transform_th_iterator, transform_th_key, typed_store_error_from_th_error,
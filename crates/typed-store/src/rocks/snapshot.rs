@@ -0,0 +1,283 @@
+// Copyright (c) LinkU Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{borrow::Borrow, ops::Bound, ops::RangeBounds};
+
+use rocksdb::{MultiThreaded, ReadOptions};
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{DBMap, Database, RocksDB, Storage, rocks_cf, rocks_util};
+use crate::memstore::InMemoryDB;
+use crate::rocks::errors::typed_store_err_from_bcs_err;
+use crate::rocks::safe_iter::{SafeIter, SafeRevIter};
+use crate::traits::Map;
+use crate::util::{be_fix_int_ser, iterator_bounds, iterator_bounds_with_range};
+use crate::{DbIterator, TypedStoreError};
+
+/// A point-in-time consistent view of all the column families of a database. Reads through
+/// a `SnapshotDBMap` see the database as it was when the snapshot was taken, regardless of
+/// writes made since.
+pub struct DBSnapshot<'a> {
+    db: &'a Database,
+    inner: SnapshotInner<'a>,
+}
+
+enum SnapshotInner<'a> {
+    Rocks(rocksdb::SnapshotWithThreadMode<'a, rocksdb::DBWithThreadMode<MultiThreaded>>),
+    InMemory(InMemoryDB),
+}
+
+impl Database {
+    /// Takes a consistent snapshot across all the column families of the database.
+    pub fn snapshot(&self) -> Result<DBSnapshot<'_>, TypedStoreError> {
+        let inner = match &self.storage {
+            Storage::Rocks(rocks) => SnapshotInner::Rocks(rocks.underlying.snapshot()),
+            Storage::InMemory(db) => SnapshotInner::InMemory(db.snapshot()),
+            #[cfg(tidehunter)]
+            Storage::TideHunter(_) => {
+                return Err(TypedStoreError::RocksDBError(
+                    "snapshots are not supported by TideHunter".to_string(),
+                ));
+            }
+        };
+        Ok(DBSnapshot { db: self, inner })
+    }
+}
+
+impl<K, V> DBMap<K, V> {
+    /// Takes a consistent snapshot of the database this map belongs to, covering every map
+    /// opened on the same database.
+    pub fn snapshot(&self) -> Result<DBSnapshot<'_>, TypedStoreError> {
+        self.db.snapshot()
+    }
+
+    /// Returns a read-only view of this map as of `snapshot`, which must have been taken of
+    /// the database this map belongs to.
+    pub fn at_snapshot<'a>(
+        &'a self,
+        snapshot: &'a DBSnapshot<'a>,
+    ) -> Result<SnapshotDBMap<'a, K, V>, TypedStoreError> {
+        if !std::ptr::eq(snapshot.db, self.db.as_ref()) {
+            return Err(TypedStoreError::RocksDBError(format!(
+                "snapshot was not taken of the database of column family {}",
+                self.cf
+            )));
+        }
+        Ok(SnapshotDBMap {
+            map: self,
+            snapshot,
+        })
+    }
+}
+
+/// A read-only view of a `DBMap` as of a `DBSnapshot`. Writes through the `Map` trait fail.
+pub struct SnapshotDBMap<'a, K, V> {
+    map: &'a DBMap<K, V>,
+    snapshot: &'a DBSnapshot<'a>,
+}
+
+impl<'a, K, V> SnapshotDBMap<'a, K, V> {
+    fn readopts(&self) -> ReadOptions {
+        let mut readopts = self.map.opts.readopts();
+        if let SnapshotInner::Rocks(snapshot) = &self.snapshot.inner {
+            readopts.set_snapshot(snapshot);
+        }
+        readopts
+    }
+
+    fn rocks(&self) -> &'a RocksDB {
+        match &self.map.db.storage {
+            Storage::Rocks(rocks) => rocks,
+            _ => unreachable!("rocks snapshot of a database with another storage"),
+        }
+    }
+
+    fn get_raw(&self, key_buf: &[u8]) -> Result<Option<Vec<u8>>, TypedStoreError> {
+        match &self.snapshot.inner {
+            SnapshotInner::Rocks(_) => Ok(self
+                .map
+                .db
+                .get(&self.map.column_family, key_buf, &self.readopts())?
+                .map(|value| value.to_vec())),
+            SnapshotInner::InMemory(db) => Ok(db.get(&self.map.cf, key_buf)),
+        }
+    }
+
+    fn rocks_iter(&self, readopts: ReadOptions) -> SafeIter<'a, K, V>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let rocks = self.rocks();
+        let db_iter = rocks
+            .underlying
+            .raw_iterator_cf_opt(&rocks_cf(rocks, &self.map.cf), readopts);
+        let (_timer, bytes_scanned, keys_scanned, _perf_ctx) = self.map.create_iter_context();
+        SafeIter::new(
+            self.map.cf.clone(),
+            db_iter,
+            _timer,
+            _perf_ctx,
+            bytes_scanned,
+            keys_scanned,
+            Some(self.map.db_metrics.clone()),
+        )
+    }
+
+    fn iter_with_raw_bounds(
+        &self,
+        lower_bound: Option<Vec<u8>>,
+        upper_bound: Option<Vec<u8>>,
+    ) -> DbIterator<'a, (K, V)>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let snapshot = self.snapshot;
+        match &snapshot.inner {
+            SnapshotInner::Rocks(_) => {
+                let readopts =
+                    rocks_util::apply_range_bounds(self.readopts(), lower_bound, upper_bound);
+                Box::new(self.rocks_iter(readopts))
+            }
+            SnapshotInner::InMemory(db) => {
+                db.iterator(&self.map.cf, lower_bound, upper_bound, false)
+            }
+        }
+    }
+
+    /// Creates a reversed iterator over the snapshot with optional bounds.
+    /// Both upper bound and lower bound are included.
+    pub fn reversed_safe_iter_with_bounds(
+        &self,
+        lower_bound: Option<K>,
+        upper_bound: Option<K>,
+    ) -> Result<DbIterator<'a, (K, V)>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let (it_lower_bound, it_upper_bound) = iterator_bounds_with_range::<K>((
+            lower_bound
+                .as_ref()
+                .map(Bound::Included)
+                .unwrap_or(Bound::Unbounded),
+            upper_bound
+                .as_ref()
+                .map(Bound::Included)
+                .unwrap_or(Bound::Unbounded),
+        ));
+        let snapshot = self.snapshot;
+        match &snapshot.inner {
+            SnapshotInner::Rocks(_) => {
+                let readopts =
+                    rocks_util::apply_range_bounds(self.readopts(), it_lower_bound, it_upper_bound);
+                let upper_bound_key = upper_bound.as_ref().map(|k| be_fix_int_ser(k));
+                Ok(Box::new(SafeRevIter::new(
+                    self.rocks_iter(readopts),
+                    upper_bound_key,
+                )))
+            }
+            SnapshotInner::InMemory(db) => {
+                Ok(db.iterator(&self.map.cf, it_lower_bound, it_upper_bound, true))
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Map<'a, K, V> for SnapshotDBMap<'_, K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    type Error = TypedStoreError;
+
+    fn contains_key(&self, key: &K) -> Result<bool, TypedStoreError> {
+        Ok(self.get_raw(&be_fix_int_ser(key))?.is_some())
+    }
+
+    fn get(&self, key: &K) -> Result<Option<V>, TypedStoreError> {
+        self.get_raw(&be_fix_int_ser(key))?
+            .map(|data| bcs::from_bytes(&data).map_err(typed_store_err_from_bcs_err))
+            .transpose()
+    }
+
+    fn insert(&self, _key: &K, _value: &V) -> Result<(), TypedStoreError> {
+        Err(read_only_error())
+    }
+
+    fn remove(&self, _key: &K) -> Result<(), TypedStoreError> {
+        Err(read_only_error())
+    }
+
+    fn schedule_delete_all(&self) -> Result<(), TypedStoreError> {
+        Err(read_only_error())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.safe_iter().next().is_none()
+    }
+
+    fn safe_iter(&'a self) -> DbIterator<'a, (K, V)> {
+        self.iter_with_raw_bounds(None, None)
+    }
+
+    fn safe_iter_with_bounds(
+        &'a self,
+        lower_bound: Option<K>,
+        upper_bound: Option<K>,
+    ) -> DbIterator<'a, (K, V)> {
+        let (lower_bound, upper_bound) = iterator_bounds(lower_bound, upper_bound);
+        self.iter_with_raw_bounds(lower_bound, upper_bound)
+    }
+
+    fn safe_range_iter(&'a self, range: impl RangeBounds<K>) -> DbIterator<'a, (K, V)> {
+        let (lower_bound, upper_bound) = iterator_bounds_with_range(range);
+        self.iter_with_raw_bounds(lower_bound, upper_bound)
+    }
+
+    /// Returns a vector of values corresponding to the keys provided, all read from the
+    /// snapshot.
+    fn multi_get<J>(
+        &self,
+        keys: impl IntoIterator<Item = J>,
+    ) -> Result<Vec<Option<V>>, TypedStoreError>
+    where
+        J: Borrow<K>,
+    {
+        let keys_bytes: Vec<_> = keys
+            .into_iter()
+            .map(|k| be_fix_int_ser(k.borrow()))
+            .collect();
+        let values: Vec<Option<Vec<u8>>> = match &self.snapshot.inner {
+            SnapshotInner::Rocks(_) => self
+                .map
+                .db
+                .multi_get(&self.map.column_family, &keys_bytes, &self.readopts())
+                .into_iter()
+                .map(|value| Ok(value?.map(|value| value.to_vec())))
+                .collect::<Result<_, TypedStoreError>>()?,
+            SnapshotInner::InMemory(db) => keys_bytes
+                .iter()
+                .map(|key| db.get(&self.map.cf, key))
+                .collect(),
+        };
+        values
+            .into_iter()
+            .map(|value| {
+                value
+                    .map(|data| bcs::from_bytes(&data).map_err(typed_store_err_from_bcs_err))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    /// A snapshot never changes, so there is nothing to catch up with.
+    fn try_catch_up_with_primary(&self) -> Result<(), TypedStoreError> {
+        Ok(())
+    }
+}
+
+fn read_only_error() -> TypedStoreError {
+    TypedStoreError::RocksDBError("cannot write through a snapshot".to_string())
+}
@@ -585,6 +585,98 @@ async fn test_checkpoint() {
     }
 }
 
+#[tokio::test]
+async fn test_snapshot_across_cf() {
+    let rocks = open_rocksdb(temp_dir(), &["First_CF", "Second_CF"]);
+    let db_cf_1: DBMap<i32, String> = DBMap::reopen(
+        &rocks,
+        Some("First_CF"),
+        &ReadWriteOptions::default(),
+        false,
+    )
+    .expect("Failed to open storage");
+    let db_cf_2: DBMap<i32, String> = DBMap::reopen(
+        &rocks,
+        Some("Second_CF"),
+        &ReadWriteOptions::default(),
+        false,
+    )
+    .expect("Failed to open storage");
+
+    db_cf_1
+        .multi_insert((0..10).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    db_cf_2
+        .multi_insert((0..10).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    let snapshot = db_cf_1.snapshot().expect("Failed to take snapshot");
+
+    // Writes to either column family after the snapshot is taken are not visible through it
+    let mut batch = db_cf_1.batch();
+    batch
+        .insert_batch(&db_cf_1, (10..20).map(|i| (i, i.to_string())))
+        .expect("Failed to batch insert");
+    batch
+        .delete_batch(&db_cf_2, 0..5)
+        .expect("Failed to batch delete");
+    batch.write().expect("Failed to execute batch");
+
+    let snapshot_cf_1 = db_cf_1
+        .at_snapshot(&snapshot)
+        .expect("Failed to read at snapshot");
+    let snapshot_cf_2 = db_cf_2
+        .at_snapshot(&snapshot)
+        .expect("Failed to read at snapshot");
+
+    assert_eq!(snapshot_cf_1.get(&15).expect("Failed to get"), None);
+    assert_eq!(
+        snapshot_cf_2.get(&3).expect("Failed to get"),
+        Some("3".to_string())
+    );
+    assert_eq!(
+        snapshot_cf_2
+            .multi_get([0, 5, 10])
+            .expect("Failed to multi get"),
+        vec![Some("0".to_string()), Some("5".to_string()), None]
+    );
+    assert_eq!(
+        snapshot_cf_1
+            .safe_iter()
+            .map(|item| item.unwrap().0)
+            .collect::<Vec<_>>(),
+        (0..10).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        snapshot_cf_2
+            .safe_iter_with_bounds(Some(2), Some(6))
+            .map(|item| item.unwrap().0)
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4, 5]
+    );
+    assert_eq!(
+        snapshot_cf_1
+            .reversed_safe_iter_with_bounds(None, Some(5))
+            .expect("Failed to create reversed iterator")
+            .map(|item| item.unwrap().0)
+            .collect::<Vec<_>>(),
+        vec![5, 4, 3, 2, 1, 0]
+    );
+    assert!(snapshot_cf_1.insert(&100, &"100".to_string()).is_err());
+
+    // The live maps see the writes
+    assert_eq!(
+        db_cf_1.get(&15).expect("Failed to get"),
+        Some("15".to_string())
+    );
+    assert_eq!(db_cf_2.get(&3).expect("Failed to get"), None);
+
+    // A snapshot of another database cannot be used
+    let other: DBMap<i32, String> = open_map(temp_dir(), Some("table"));
+    let other_snapshot = other.snapshot().expect("Failed to take snapshot");
+    assert!(db_cf_1.at_snapshot(&other_snapshot).is_err());
+}
+
 #[tokio::test]
 async fn test_multi_remove() {
     // Init a DB
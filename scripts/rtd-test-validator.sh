@@ -2,7 +2,8 @@
 # Copyright (c) LinkU Labs, Inc.
 # SPDX-License-Identifier: Apache-2.0
 
-echo "rtd-test-validator binary has been deprecated in favor of rtd start, which is a more powerful command that allows you to start the local network with more options.
+echo "The local network formerly started by rtd-test-validator is started by rtd start, which is a more powerful command that allows you to start the local network with more options.
+(The rtd-test-validator binary now runs a single-process, deterministic chain for tests instead, see crates/rtd-test-validator.)
 This script offers backward compatibility, but ideally, you should migrate to rtd start instead. Use rtd start --help to see all the flags and options. 
 
 To recreate the exact basic functionality of rtd-test-validator, you must use the following options: